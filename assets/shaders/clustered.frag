@@ -0,0 +1,121 @@
+#version 450
+
+struct Light {
+    vec4 positionRange;
+    vec4 directionType;
+    vec4 colorIntensity;
+    vec4 cone;
+};
+
+layout(std140, set = 0, binding = 0) uniform ClusterData {
+    mat4 view;
+    mat4 projection;
+    mat4 inverseProjection;
+    // View space direction the depth slices advance in.
+    vec4 forward;
+    // Near, far, log(far / near).
+    vec4 depthRange;
+    // Clusters along x, y and z, followed by the light count.
+    uvec4 gridSize;
+    // Width, height and their reciprocals.
+    vec4 screenSize;
+    vec4 cameraPosition;
+    vec4 ambientColor;
+} cluster;
+
+layout(std430, set = 0, binding = 1) readonly buffer Lights {
+    Light lights[];
+} lightBuffer;
+
+layout(std430, set = 0, binding = 2) readonly buffer LightCounts {
+    uint counts[];
+} lightCounts;
+
+layout(std430, set = 0, binding = 3) readonly buffer LightIndices {
+    uint indices[];
+} lightIndices;
+
+layout(push_constant) uniform Push {
+    mat4 model;
+    float roughness;
+    float metallic;
+} push;
+
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec3 fragWorldPosition;
+layout(location = 2) in vec3 fragViewPosition;
+layout(location = 3) in vec3 fragNormal;
+
+layout(location = 0) out vec4 outColor;
+
+const uint MAX_LIGHTS_PER_CLUSTER = 128u;
+const uint DIRECTIONAL = 0u;
+const uint SPOT = 2u;
+
+float rangeAttenuation(float distanceToLight, float range) {
+    float ratio = distanceToLight / range;
+    float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / (distanceToLight * distanceToLight + 1.0);
+}
+
+uint clusterIndex() {
+    uvec3 grid = cluster.gridSize.xyz;
+    vec2 tile = gl_FragCoord.xy * cluster.screenSize.zw * vec2(grid.xy);
+    uint x = min(uint(tile.x), grid.x - 1u);
+    uint y = min(uint(tile.y), grid.y - 1u);
+
+    float depth = dot(fragViewPosition, cluster.forward.xyz);
+    float slice = log(max(depth, cluster.depthRange.x) / cluster.depthRange.x) / cluster.depthRange.z;
+    uint z = min(uint(max(slice * float(grid.z), 0.0)), grid.z - 1u);
+
+    return x + grid.x * (y + grid.y * z);
+}
+
+void main() {
+    vec3 albedo = fragColor;
+    vec3 normal = normalize(fragNormal);
+    vec3 position = fragWorldPosition;
+    vec3 toCamera = normalize(cluster.cameraPosition.xyz - position);
+
+    float roughness = max(push.roughness, 0.04);
+    vec3 diffuseColor = albedo * (1.0 - push.metallic);
+    vec3 specularColor = mix(vec3(0.04), albedo, push.metallic);
+    float shininess = 2.0 / (roughness * roughness * roughness * roughness) - 2.0;
+
+    uint index = clusterIndex();
+    uint lightCount = lightCounts.counts[index];
+    uint firstIndex = index * MAX_LIGHTS_PER_CLUSTER;
+
+    vec3 color = cluster.ambientColor.rgb * albedo;
+    for (uint i = 0u; i < lightCount; i++) {
+        Light light = lightBuffer.lights[lightIndices.indices[firstIndex + i]];
+        uint lightType = uint(light.directionType.w);
+
+        vec3 toLight;
+        float attenuation = 1.0;
+        if (lightType == DIRECTIONAL) {
+            toLight = -light.directionType.xyz;
+        } else {
+            vec3 offset = light.positionRange.xyz - position;
+            float distanceToLight = length(offset);
+            toLight = offset / max(distanceToLight, 0.0001);
+            attenuation = rangeAttenuation(distanceToLight, light.positionRange.w);
+            if (lightType == SPOT) {
+                float cosAngle = dot(-toLight, light.directionType.xyz);
+                attenuation *= smoothstep(light.cone.y, light.cone.x, cosAngle);
+            }
+        }
+
+        float nDotL = max(dot(normal, toLight), 0.0);
+        if (nDotL <= 0.0 || attenuation <= 0.0) {
+            continue;
+        }
+
+        vec3 halfVector = normalize(toLight + toCamera);
+        float specular = pow(max(dot(normal, halfVector), 0.0), shininess) * (shininess + 8.0) / 25.13274;
+        vec3 radiance = light.colorIntensity.rgb * light.colorIntensity.a * attenuation;
+        color += (diffuseColor / 3.14159265 + specularColor * specular) * radiance * nDotL;
+    }
+
+    outColor = vec4(color, 1.0);
+}
@@ -0,0 +1,49 @@
+#version 450
+
+struct Light {
+    vec4 positionRange;
+    vec4 directionType;
+    vec4 colorIntensity;
+    vec4 cone;
+};
+
+layout(std140, set = 0, binding = 0) uniform ClusterData {
+    mat4 view;
+    mat4 projection;
+    mat4 inverseProjection;
+    // View space direction the depth slices advance in.
+    vec4 forward;
+    // Near, far, log(far / near).
+    vec4 depthRange;
+    // Clusters along x, y and z, followed by the light count.
+    uvec4 gridSize;
+    // Width, height and their reciprocals.
+    vec4 screenSize;
+    vec4 cameraPosition;
+    vec4 ambientColor;
+} cluster;
+
+layout(push_constant) uniform Push {
+    mat4 model;
+    float roughness;
+    float metallic;
+} push;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec3 inColor;
+
+layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec3 fragWorldPosition;
+layout(location = 2) out vec3 fragViewPosition;
+layout(location = 3) out vec3 fragNormal;
+
+void main() {
+    vec4 world = push.model * vec4(inPosition, 0.0, 1.0);
+    vec4 viewPosition = cluster.view * world;
+    gl_Position = cluster.projection * viewPosition;
+    fragColor = inColor;
+    fragWorldPosition = world.xyz;
+    fragViewPosition = viewPosition.xyz;
+    // The flat vertex format has no normals, treat geometry as facing the camera.
+    fragNormal = vec3(0.0, 0.0, -1.0);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+layout(set = 0, binding = 2) uniform texture3D lut;
+
+layout(push_constant) uniform Params {
+    float intensity;
+    float lutSize;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec4 color = texture(sampler2D(inputImage, inputSampler), fragUv);
+    // Sample texel centers so that the ends of the range map to the first and last entry.
+    float scale = (params.lutSize - 1.0) / params.lutSize;
+    float offset = 0.5 / params.lutSize;
+    vec3 lutCoord = clamp(color.rgb, 0.0, 1.0) * scale + offset;
+    vec3 graded = texture(sampler3D(lut, inputSampler), lutCoord).rgb;
+    outColor = vec4(mix(color.rgb, graded, params.intensity), color.a);
+}
@@ -0,0 +1,18 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 viewProjection;
+} push;
+
+// Per-line segment, see `DebugLine`.
+layout(location = 4) in vec4 lineStart;
+layout(location = 5) in vec4 lineEnd;
+layout(location = 6) in vec4 lineColor;
+
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+    vec4 position = gl_VertexIndex == 0 ? lineStart : lineEnd;
+    fragColor = lineColor;
+    gl_Position = push.viewProjection * vec4(position.xyz, 1.0);
+}
@@ -0,0 +1,144 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D albedoImage;
+layout(set = 0, binding = 1) uniform texture2D normalImage;
+layout(set = 0, binding = 2) uniform texture2D materialImage;
+layout(set = 0, binding = 3) uniform texture2D depthImage;
+layout(set = 0, binding = 4) uniform sampler gbufferSampler;
+
+struct Light {
+    vec4 positionRange;
+    vec4 directionType;
+    vec4 colorIntensity;
+    vec4 cone;
+};
+
+layout(std430, set = 1, binding = 0) readonly buffer Lights {
+    Light lights[];
+} lightBuffer;
+
+layout(set = 2, binding = 0) uniform texture2DArray shadowMaps;
+layout(set = 2, binding = 1) uniform samplerShadow shadowSampler;
+layout(std140, set = 2, binding = 2) uniform ShadowData {
+    mat4 layerMatrices[8];
+    // Texel size, PCF radius, normal bias.
+    vec4 params;
+} shadowData;
+
+layout(push_constant) uniform Push {
+    mat4 inverseViewProjection;
+    vec4 cameraPosition;
+    vec4 ambientColor;
+    uint lightCount;
+} push;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+const uint DIRECTIONAL = 0;
+const uint POINT = 1;
+const uint SPOT = 2;
+
+float rangeAttenuation(float distanceToLight, float range) {
+    float ratio = distanceToLight / range;
+    float window = clamp(1.0 - ratio * ratio * ratio * ratio, 0.0, 1.0);
+    return window * window / (distanceToLight * distanceToLight + 1.0);
+}
+
+// Returns -1 if the position lies outside of the layer.
+float sampleShadowLayer(uint layer, vec3 position) {
+    vec4 lightClip = shadowData.layerMatrices[layer] * vec4(position, 1.0);
+    vec3 coord = lightClip.xyz / lightClip.w;
+    vec2 uv = coord.xy * 0.5 + 0.5;
+    if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0))) || coord.z > 1.0) {
+        return -1.0;
+    }
+
+    float texelSize = shadowData.params.x;
+    int radius = int(shadowData.params.y);
+    float lit = 0.0;
+    for (int y = -radius; y <= radius; y++) {
+        for (int x = -radius; x <= radius; x++) {
+            vec2 offset = vec2(float(x), float(y)) * texelSize;
+            lit += texture(sampler2DArrayShadow(shadowMaps, shadowSampler), vec4(uv + offset, float(layer), coord.z));
+        }
+    }
+    float kernelSize = float(2 * radius + 1);
+    return lit / (kernelSize * kernelSize);
+}
+
+// Cascades are ordered near to far, the first one containing the position wins.
+float shadowFactor(Light light, vec3 position, vec3 normal) {
+    uint firstLayer = uint(light.cone.z);
+    uint layerCount = uint(light.cone.w);
+    vec3 offsetPosition = position + normal * shadowData.params.z;
+    for (uint layer = firstLayer; layer < firstLayer + layerCount; layer++) {
+        float lit = sampleShadowLayer(layer, offsetPosition);
+        if (lit >= 0.0) {
+            return lit;
+        }
+    }
+    return 1.0;
+}
+
+void main() {
+    float depth = texture(sampler2D(depthImage, gbufferSampler), fragUv).r;
+    if (depth >= 1.0) {
+        outColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec3 albedo = texture(sampler2D(albedoImage, gbufferSampler), fragUv).rgb;
+    vec3 normal = normalize(texture(sampler2D(normalImage, gbufferSampler), fragUv).xyz);
+    vec2 material = texture(sampler2D(materialImage, gbufferSampler), fragUv).rg;
+    float roughness = max(material.r, 0.04);
+    float metallic = material.g;
+
+    vec4 clip = vec4(fragUv * 2.0 - 1.0, depth, 1.0);
+    vec4 world = push.inverseViewProjection * clip;
+    vec3 position = world.xyz / world.w;
+    vec3 toCamera = normalize(push.cameraPosition.xyz - position);
+
+    vec3 diffuseColor = albedo * (1.0 - metallic);
+    vec3 specularColor = mix(vec3(0.04), albedo, metallic);
+    float shininess = 2.0 / (roughness * roughness * roughness * roughness) - 2.0;
+
+    vec3 color = push.ambientColor.rgb * albedo;
+    for (uint i = 0; i < push.lightCount; i++) {
+        Light light = lightBuffer.lights[i];
+        uint lightType = uint(light.directionType.w);
+
+        vec3 toLight;
+        float attenuation = 1.0;
+        if (lightType == DIRECTIONAL) {
+            toLight = -light.directionType.xyz;
+        } else {
+            vec3 offset = light.positionRange.xyz - position;
+            float distanceToLight = length(offset);
+            toLight = offset / max(distanceToLight, 0.0001);
+            attenuation = rangeAttenuation(distanceToLight, light.positionRange.w);
+            if (lightType == SPOT) {
+                float cosAngle = dot(-toLight, light.directionType.xyz);
+                attenuation *= smoothstep(light.cone.y, light.cone.x, cosAngle);
+            }
+        }
+
+        float nDotL = max(dot(normal, toLight), 0.0);
+        if (nDotL <= 0.0 || attenuation <= 0.0) {
+            continue;
+        }
+
+        attenuation *= shadowFactor(light, position, normal);
+        if (attenuation <= 0.0) {
+            continue;
+        }
+
+        vec3 halfVector = normalize(toLight + toCamera);
+        float specular = pow(max(dot(normal, halfVector), 0.0), shininess) * (shininess + 8.0) / 25.13274;
+        vec3 radiance = light.colorIntensity.rgb * light.colorIntensity.a * attenuation;
+        color += (diffuseColor / 3.14159265 + specularColor * specular) * radiance * nDotL;
+    }
+
+    outColor = vec4(color, 1.0);
+}
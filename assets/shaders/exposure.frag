@@ -0,0 +1,17 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+
+layout(push_constant) uniform Params {
+    float exposure;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec4 color = texture(sampler2D(inputImage, inputSampler), fragUv);
+    outColor = vec4(color.rgb * exp2(params.exposure), color.a);
+}
@@ -0,0 +1,49 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+
+layout(push_constant) uniform Params {
+    vec2 texelSize;
+    float spanMax;
+    float reduceMul;
+    float reduceMin;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+vec3 fetch(vec2 uv) {
+    return texture(sampler2D(inputImage, inputSampler), uv).rgb;
+}
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+// Blurs along the local edge direction found from the luma of the four diagonal neighbours.
+void main() {
+    vec2 texel = params.texelSize;
+    vec4 center = texture(sampler2D(inputImage, inputSampler), fragUv);
+
+    float lumaNW = luma(fetch(fragUv + vec2(-1.0, -1.0) * texel));
+    float lumaNE = luma(fetch(fragUv + vec2(1.0, -1.0) * texel));
+    float lumaSW = luma(fetch(fragUv + vec2(-1.0, 1.0) * texel));
+    float lumaSE = luma(fetch(fragUv + vec2(1.0, 1.0) * texel));
+    float lumaM = luma(center.rgb);
+
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    vec2 dir = vec2(-((lumaNW + lumaNE) - (lumaSW + lumaSE)), (lumaNW + lumaSW) - (lumaNE + lumaSE));
+    float dirReduce = max((lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * params.reduceMul, params.reduceMin);
+    float dirScale = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * dirScale, vec2(-params.spanMax), vec2(params.spanMax)) * texel;
+
+    vec3 colorA = 0.5 * (fetch(fragUv + dir * (1.0 / 3.0 - 0.5)) + fetch(fragUv + dir * (2.0 / 3.0 - 0.5)));
+    vec3 colorB = colorA * 0.5 + 0.25 * (fetch(fragUv - dir * 0.5) + fetch(fragUv + dir * 0.5));
+
+    float lumaB = luma(colorB);
+    outColor = vec4((lumaB < lumaMin || lumaB > lumaMax) ? colorA : colorB, center.a);
+}
@@ -0,0 +1,17 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+
+layout(push_constant) uniform Params {
+    float gamma;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec4 color = texture(sampler2D(inputImage, inputSampler), fragUv);
+    outColor = vec4(pow(max(color.rgb, vec3(0.0)), vec3(1.0 / params.gamma)), color.a);
+}
@@ -0,0 +1,20 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 transform;
+    float roughness;
+    float metallic;
+} push;
+
+layout(location = 0) in vec3 fragAlbedo;
+layout(location = 1) in vec3 fragNormal;
+
+layout(location = 0) out vec4 outAlbedo;
+layout(location = 1) out vec4 outNormal;
+layout(location = 2) out vec4 outMaterial;
+
+void main() {
+    outAlbedo = vec4(fragAlbedo, 1.0);
+    outNormal = vec4(normalize(fragNormal), 0.0);
+    outMaterial = vec4(push.roughness, push.metallic, 0.0, 0.0);
+}
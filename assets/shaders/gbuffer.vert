@@ -0,0 +1,20 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 transform;
+    float roughness;
+    float metallic;
+} push;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec3 inColor;
+
+layout(location = 0) out vec3 fragAlbedo;
+layout(location = 1) out vec3 fragNormal;
+
+void main() {
+    gl_Position = push.transform * vec4(inPosition, 0.0, 1.0);
+    fragAlbedo = inColor;
+    // The flat vertex format has no normals, treat geometry as facing the camera.
+    fragNormal = vec3(0.0, 0.0, -1.0);
+}
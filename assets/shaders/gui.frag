@@ -0,0 +1,13 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D guiTexture;
+layout(set = 0, binding = 1) uniform sampler guiSampler;
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = fragColor * texture(sampler2D(guiTexture, guiSampler), fragUv);
+}
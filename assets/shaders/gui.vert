@@ -0,0 +1,24 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    // Scale in xy and translation in zw from ImGui display coordinates to clip space.
+    vec4 scaleTranslate;
+    // Linearize vertex colors for sRGB attachments when x is set.
+    vec4 params;
+} push;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec2 inUv;
+layout(location = 2) in vec4 inColor;
+
+layout(location = 0) out vec2 fragUv;
+layout(location = 1) out vec4 fragColor;
+
+void main() {
+    fragUv = inUv;
+    fragColor = inColor;
+    if (push.params.x > 0.5) {
+        fragColor.rgb = pow(inColor.rgb, vec3(2.2));
+    }
+    gl_Position = vec4(inPosition * push.scaleTranslate.xy + push.scaleTranslate.zw, 0.0, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform IndirectData {
+    mat4 viewProjection;
+    // World space frustum planes, pointing inwards.
+    vec4 planes[6];
+    // Direction the light travels in, ambient intensity in w.
+    vec4 lightDirection;
+    // Object count, whether visible draws are compacted.
+    uvec4 counts;
+} scene;
+
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec3 fragNormal;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec3 normal = normalize(fragNormal);
+    // Two-sided, the geometry is flat.
+    float diffuse = abs(dot(normal, -normalize(scene.lightDirection.xyz)));
+    outColor = vec4(fragColor * (diffuse + scene.lightDirection.w), 1.0);
+}
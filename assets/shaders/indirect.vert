@@ -0,0 +1,37 @@
+#version 450
+
+struct Object {
+    mat4 model;
+    vec4 color;
+    // Mesh index in x.
+    uvec4 mesh;
+};
+
+layout(std140, set = 0, binding = 0) uniform IndirectData {
+    mat4 viewProjection;
+    // World space frustum planes, pointing inwards.
+    vec4 planes[6];
+    // Direction the light travels in, ambient intensity in w.
+    vec4 lightDirection;
+    // Object count, whether visible draws are compacted.
+    uvec4 counts;
+} scene;
+
+layout(std430, set = 0, binding = 1) readonly buffer Objects {
+    Object objects[];
+} objectBuffer;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec3 inColor;
+layout(location = 2) in vec3 inNormal;
+
+layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec3 fragNormal;
+
+void main() {
+    // Culling writes the object index as the first instance of its draw.
+    Object object = objectBuffer.objects[gl_InstanceIndex];
+    gl_Position = scene.viewProjection * object.model * vec4(inPosition, 0.0, 1.0);
+    fragColor = inColor * object.color.rgb;
+    fragNormal = mat3(object.model) * inNormal;
+}
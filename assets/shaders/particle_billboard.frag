@@ -0,0 +1,16 @@
+#version 450
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    // A soft disc fading out towards the edge of the quad.
+    float distance = length(fragUv * 2.0 - 1.0);
+    float alpha = 1.0 - smoothstep(0.5, 1.0, distance);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    outColor = vec4(fragColor.rgb, fragColor.a * alpha);
+}
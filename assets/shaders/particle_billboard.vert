@@ -0,0 +1,43 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 viewProjection;
+    // Camera right in xyz, pixels per world unit at distance 1 in w.
+    vec4 cameraRight;
+    vec4 cameraUp;
+} push;
+
+struct Particle {
+    vec4 positionSize;
+    vec4 velocityAge;
+    vec4 color;
+    vec4 life;
+};
+
+layout(std430, set = 0, binding = 1) readonly buffer Particles {
+    Particle particles[];
+};
+
+layout(std430, set = 0, binding = 3) readonly buffer AliveList {
+    uint aliveIndices[];
+};
+
+layout(location = 0) out vec2 fragUv;
+layout(location = 1) out vec4 fragColor;
+
+// Two triangles per instance, corners as x in bit 0 and y in bit 1.
+const int CORNERS[6] = int[6](0, 1, 2, 2, 1, 3);
+
+void main() {
+    Particle particle = particles[aliveIndices[gl_InstanceIndex]];
+    int corner = CORNERS[gl_VertexIndex];
+    vec2 weight = vec2(float(corner & 1), float(corner >> 1));
+    vec2 offset = (weight - 0.5) * particle.positionSize.w;
+    vec3 position = particle.positionSize.xyz
+        + push.cameraRight.xyz * offset.x
+        + push.cameraUp.xyz * offset.y;
+
+    fragUv = weight;
+    fragColor = particle.color;
+    gl_Position = push.viewProjection * vec4(position, 1.0);
+}
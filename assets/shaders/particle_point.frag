@@ -0,0 +1,14 @@
+#version 450
+
+layout(location = 0) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float distance = length(gl_PointCoord * 2.0 - 1.0);
+    float alpha = 1.0 - smoothstep(0.5, 1.0, distance);
+    if (alpha <= 0.0) {
+        discard;
+    }
+    outColor = vec4(fragColor.rgb, fragColor.a * alpha);
+}
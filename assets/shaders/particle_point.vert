@@ -0,0 +1,33 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 viewProjection;
+    // Camera right in xyz, pixels per world unit at distance 1 in w.
+    vec4 cameraRight;
+    vec4 cameraUp;
+} push;
+
+struct Particle {
+    vec4 positionSize;
+    vec4 velocityAge;
+    vec4 color;
+    vec4 life;
+};
+
+layout(std430, set = 0, binding = 1) readonly buffer Particles {
+    Particle particles[];
+};
+
+layout(std430, set = 0, binding = 3) readonly buffer AliveList {
+    uint aliveIndices[];
+};
+
+layout(location = 0) out vec4 fragColor;
+
+void main() {
+    Particle particle = particles[aliveIndices[gl_VertexIndex]];
+    fragColor = particle.color;
+    gl_Position = push.viewProjection * vec4(particle.positionSize.xyz, 1.0);
+    // Clamped to the device's point size range, a single pixel without `largePoints`.
+    gl_PointSize = max(particle.positionSize.w * push.cameraRight.w / gl_Position.w, 1.0);
+}
@@ -0,0 +1,132 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform SceneData {
+    mat4 viewProjection;
+    vec4 cameraPosition;
+    // Direction the light travels in, intensity in w.
+    vec4 lightDirection;
+    vec4 lightColor;
+    // Tint of the image-based lighting, highest prefiltered mip level in w.
+    vec4 ambientColor;
+} scene;
+
+layout(set = 1, binding = 0) uniform texture2D baseColorTexture;
+layout(set = 1, binding = 1) uniform texture2D normalTexture;
+layout(set = 1, binding = 2) uniform texture2D metallicRoughnessTexture;
+layout(set = 1, binding = 3) uniform texture2D occlusionTexture;
+layout(set = 1, binding = 4) uniform texture2D emissiveTexture;
+layout(set = 1, binding = 5) uniform sampler materialSampler;
+
+layout(set = 2, binding = 1) uniform textureCube irradianceTexture;
+layout(set = 2, binding = 2) uniform textureCube prefilteredTexture;
+layout(set = 2, binding = 3) uniform texture2D brdfLut;
+layout(set = 2, binding = 4) uniform sampler environmentSampler;
+
+layout(push_constant) uniform Push {
+    mat4 model;
+    vec4 baseColorFactor;
+    // Emissive color, normal scale in w.
+    vec4 emissiveFactor;
+    // Metallic, roughness, occlusion strength.
+    vec4 materialParams;
+} push;
+
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec3 fragWorldPosition;
+layout(location = 2) in vec3 fragNormal;
+layout(location = 3) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+const float PI = 3.14159265;
+
+// Tangent frame from screen space derivatives, the vertex format has no tangents.
+vec3 perturbNormal(vec3 normal, vec3 position, vec2 uv) {
+    vec3 tangentNormal = texture(sampler2D(normalTexture, materialSampler), uv).xyz * 2.0 - 1.0;
+    tangentNormal.xy *= push.emissiveFactor.w;
+
+    vec3 dpdx = dFdx(position);
+    vec3 dpdy = dFdy(position);
+    vec2 duvdx = dFdx(uv);
+    vec2 duvdy = dFdy(uv);
+    vec3 dpdyPerp = cross(dpdy, normal);
+    vec3 dpdxPerp = cross(normal, dpdx);
+    vec3 tangent = dpdyPerp * duvdx.x + dpdxPerp * duvdy.x;
+    vec3 bitangent = dpdyPerp * duvdx.y + dpdxPerp * duvdy.y;
+    float scale = inversesqrt(max(max(dot(tangent, tangent), dot(bitangent, bitangent)), 1e-12));
+
+    return normalize(mat3(tangent * scale, bitangent * scale, normal) * tangentNormal);
+}
+
+float distributionGgx(float nDotH, float alpha) {
+    float alpha2 = alpha * alpha;
+    float denominator = nDotH * nDotH * (alpha2 - 1.0) + 1.0;
+    return alpha2 / (PI * denominator * denominator);
+}
+
+float geometrySmith(float nDotV, float nDotL, float roughness) {
+    float k = (roughness + 1.0) * (roughness + 1.0) / 8.0;
+    float viewTerm = nDotV / (nDotV * (1.0 - k) + k);
+    float lightTerm = nDotL / (nDotL * (1.0 - k) + k);
+    return viewTerm * lightTerm;
+}
+
+vec3 fresnelSchlick(float cosTheta, vec3 f0) {
+    return f0 + (1.0 - f0) * pow(1.0 - cosTheta, 5.0);
+}
+
+vec3 fresnelSchlickRoughness(float cosTheta, vec3 f0, float roughness) {
+    return f0 + (max(vec3(1.0 - roughness), f0) - f0) * pow(1.0 - cosTheta, 5.0);
+}
+
+// Split sum approximation with the prefiltered environment and the BRDF lookup table.
+vec3 environmentLighting(vec3 normal, vec3 toCamera, float nDotV, vec3 baseColor, vec3 f0, float metallic, float roughness) {
+    vec3 fresnel = fresnelSchlickRoughness(nDotV, f0, roughness);
+    vec3 irradiance = texture(samplerCube(irradianceTexture, environmentSampler), normal).rgb;
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * irradiance * baseColor;
+
+    vec3 reflected = reflect(-toCamera, normal);
+    vec3 prefiltered = textureLod(samplerCube(prefilteredTexture, environmentSampler), reflected, roughness * scene.ambientColor.w).rgb;
+    vec2 brdf = texture(sampler2D(brdfLut, environmentSampler), vec2(nDotV, roughness)).rg;
+    vec3 specular = prefiltered * (fresnel * brdf.x + brdf.y);
+
+    return (diffuse + specular) * scene.ambientColor.rgb;
+}
+
+void main() {
+    vec4 baseColor = texture(sampler2D(baseColorTexture, materialSampler), fragUv)
+        * push.baseColorFactor * vec4(fragColor, 1.0);
+    vec2 metallicRoughness = texture(sampler2D(metallicRoughnessTexture, materialSampler), fragUv).bg;
+    float metallic = clamp(push.materialParams.x * metallicRoughness.x, 0.0, 1.0);
+    float roughness = clamp(push.materialParams.y * metallicRoughness.y, 0.04, 1.0);
+    float occlusion = mix(1.0, texture(sampler2D(occlusionTexture, materialSampler), fragUv).r, push.materialParams.z);
+    vec3 emissive = texture(sampler2D(emissiveTexture, materialSampler), fragUv).rgb * push.emissiveFactor.rgb;
+
+    vec3 normal = normalize(fragNormal);
+    // Flat geometry is lit from whichever side is visible.
+    if (!gl_FrontFacing) {
+        normal = -normal;
+    }
+    normal = perturbNormal(normal, fragWorldPosition, fragUv);
+
+    vec3 toCamera = normalize(scene.cameraPosition.xyz - fragWorldPosition);
+    vec3 toLight = normalize(-scene.lightDirection.xyz);
+    vec3 halfVector = normalize(toCamera + toLight);
+    float nDotV = max(dot(normal, toCamera), 0.0001);
+    float nDotL = max(dot(normal, toLight), 0.0);
+    float nDotH = max(dot(normal, halfVector), 0.0);
+
+    vec3 f0 = mix(vec3(0.04), baseColor.rgb, metallic);
+    vec3 fresnel = fresnelSchlick(max(dot(halfVector, toCamera), 0.0), f0);
+    float distribution = distributionGgx(nDotH, roughness * roughness);
+    float geometry = geometrySmith(nDotV, nDotL, roughness);
+    vec3 specular = distribution * geometry * fresnel / (4.0 * nDotV * max(nDotL, 0.0001));
+    vec3 diffuse = (1.0 - fresnel) * (1.0 - metallic) * baseColor.rgb / PI;
+
+    vec3 radiance = scene.lightColor.rgb * scene.lightDirection.w;
+    vec3 color = (diffuse + specular) * radiance * nDotL;
+    color += environmentLighting(normal, toCamera, nDotV, baseColor.rgb, f0, metallic, roughness) * occlusion;
+    color += emissive;
+
+    outColor = vec4(color, baseColor.a);
+}
@@ -0,0 +1,39 @@
+#version 450
+
+layout(std140, set = 0, binding = 0) uniform SceneData {
+    mat4 viewProjection;
+    vec4 cameraPosition;
+    // Direction the light travels in, intensity in w.
+    vec4 lightDirection;
+    vec4 lightColor;
+    // Tint of the image-based lighting, highest prefiltered mip level in w.
+    vec4 ambientColor;
+} scene;
+
+layout(push_constant) uniform Push {
+    mat4 model;
+    vec4 baseColorFactor;
+    // Emissive color, normal scale in w.
+    vec4 emissiveFactor;
+    // Metallic, roughness, occlusion strength.
+    vec4 materialParams;
+} push;
+
+layout(location = 0) in vec2 inPosition;
+layout(location = 1) in vec3 inColor;
+layout(location = 2) in vec3 inNormal;
+layout(location = 3) in vec2 inUv;
+
+layout(location = 0) out vec3 fragColor;
+layout(location = 1) out vec3 fragWorldPosition;
+layout(location = 2) out vec3 fragNormal;
+layout(location = 3) out vec2 fragUv;
+
+void main() {
+    vec4 world = push.model * vec4(inPosition, 0.0, 1.0);
+    gl_Position = scene.viewProjection * world;
+    fragColor = inColor;
+    fragWorldPosition = world.xyz;
+    fragNormal = mat3(push.model) * inNormal;
+    fragUv = inUv;
+}
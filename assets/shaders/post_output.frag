@@ -0,0 +1,58 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+
+// Matches `OutputTransferFunction`.
+layout(push_constant) uniform Params {
+    uint transferFunction;
+    // Luminance of 1.0 in nits on HDR outputs.
+    float paperWhite;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+const uint SRGB_HARDWARE = 0;
+const uint SRGB = 1;
+const uint LINEAR = 2;
+const uint PQ = 3;
+
+vec3 srgbEncode(vec3 color) {
+    color = clamp(color, 0.0, 1.0);
+    vec3 low = color * 12.92;
+    vec3 high = 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055;
+    return mix(high, low, lessThanEqual(color, vec3(0.0031308)));
+}
+
+// SMPTE ST 2084 inverse EOTF of luminance normalized to 10000 nits.
+vec3 pqEncode(vec3 color) {
+    const float m1 = 0.1593017578125;
+    const float m2 = 78.84375;
+    const float c1 = 0.8359375;
+    const float c2 = 18.8515625;
+    const float c3 = 18.6875;
+    vec3 y = pow(clamp(color, 0.0, 1.0), vec3(m1));
+    return pow((c1 + c2 * y) / (1.0 + c3 * y), vec3(m2));
+}
+
+// BT.709 primaries to BT.2020, columns first.
+const mat3 BT709_TO_BT2020 = mat3(
+    0.6274, 0.0691, 0.0164,
+    0.3293, 0.9195, 0.0880,
+    0.0433, 0.0114, 0.8956
+);
+
+void main() {
+    vec3 color = max(texture(sampler2D(inputImage, inputSampler), fragUv).rgb, vec3(0.0));
+    if (params.transferFunction == SRGB) {
+        color = srgbEncode(color);
+    } else if (params.transferFunction == LINEAR) {
+        // scRGB defines 1.0 as 80 nits.
+        color *= params.paperWhite / 80.0;
+    } else if (params.transferFunction == PQ) {
+        color = pqEncode(BT709_TO_BT2020 * color * params.paperWhite / 10000.0);
+    }
+    outColor = vec4(color, 1.0);
+}
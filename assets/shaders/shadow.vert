@@ -0,0 +1,11 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 transform;
+} push;
+
+layout(location = 0) in vec2 inPosition;
+
+void main() {
+    gl_Position = push.transform * vec4(inPosition, 0.0, 1.0);
+}
@@ -0,0 +1,23 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform textureCube environmentTexture;
+layout(set = 0, binding = 4) uniform sampler environmentSampler;
+
+layout(push_constant) uniform Push {
+    mat4 inverseViewProjection;
+    // Environment mip level, intensity.
+    vec4 params;
+} push;
+
+layout(location = 0) in vec2 fragNdc;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec4 near = push.inverseViewProjection * vec4(fragNdc, 0.0, 1.0);
+    vec4 far = push.inverseViewProjection * vec4(fragNdc, 1.0, 1.0);
+    vec3 direction = normalize(far.xyz / far.w - near.xyz / near.w);
+
+    vec3 color = textureLod(samplerCube(environmentTexture, environmentSampler), direction, push.params.x).rgb;
+    outColor = vec4(color * push.params.y, 1.0);
+}
@@ -0,0 +1,10 @@
+#version 450
+
+layout(location = 0) out vec2 fragNdc;
+
+// A fullscreen triangle on the far plane, drawn behind everything with a LESS_OR_EQUAL test.
+void main() {
+    vec2 uv = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+    fragNdc = uv * 2.0 - 1.0;
+    gl_Position = vec4(fragNdc, 1.0, 1.0);
+}
@@ -0,0 +1,31 @@
+#version 450
+
+layout(push_constant) uniform Push {
+    mat4 viewProjection;
+} push;
+
+// Per-sprite quad, see `SpriteQuad`.
+layout(location = 4) in vec4 spritePositionRotation;
+layout(location = 5) in vec4 spriteSizePivot;
+layout(location = 6) in vec4 spriteUvRect;
+layout(location = 7) in vec4 spriteColor;
+
+layout(location = 0) out vec2 fragUv;
+layout(location = 1) out vec4 fragColor;
+
+// Two triangles per instance, corners as x in bit 0 and y in bit 1.
+const int CORNERS[6] = int[6](0, 1, 2, 2, 1, 3);
+
+void main() {
+    int corner = CORNERS[gl_VertexIndex];
+    vec2 weight = vec2(float(corner & 1), float(corner >> 1));
+    // The atlas has y pointing down, the world y pointing up.
+    fragUv = mix(spriteUvRect.xy, spriteUvRect.zw, vec2(weight.x, 1.0 - weight.y));
+    fragColor = spriteColor;
+
+    vec2 local = (weight - spriteSizePivot.zw) * spriteSizePivot.xy;
+    float s = sin(spritePositionRotation.z);
+    float c = cos(spritePositionRotation.z);
+    vec2 world = spritePositionRotation.xy + vec2(c * local.x - s * local.y, s * local.x + c * local.y);
+    gl_Position = push.viewProjection * vec4(world, 0.0, 1.0);
+}
@@ -0,0 +1,14 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D glyphAtlas;
+layout(set = 0, binding = 1) uniform sampler glyphSampler;
+
+layout(location = 0) in vec2 fragUv;
+layout(location = 1) in vec4 fragColor;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    float coverage = texture(sampler2D(glyphAtlas, glyphSampler), fragUv).r;
+    outColor = vec4(fragColor.rgb, fragColor.a * coverage);
+}
@@ -0,0 +1,20 @@
+#version 450
+
+// Per-glyph quad, see `GlyphQuad`.
+layout(location = 4) in vec4 glyphRect;
+layout(location = 5) in vec4 glyphUvRect;
+layout(location = 6) in vec4 glyphColor;
+
+layout(location = 0) out vec2 fragUv;
+layout(location = 1) out vec4 fragColor;
+
+// Two triangles per instance, corners as x in bit 0 and y in bit 1.
+const int CORNERS[6] = int[6](0, 1, 2, 2, 1, 3);
+
+void main() {
+    int corner = CORNERS[gl_VertexIndex];
+    vec2 weight = vec2(float(corner & 1), float(corner >> 1));
+    fragUv = mix(glyphUvRect.xy, glyphUvRect.zw, weight);
+    fragColor = glyphColor;
+    gl_Position = vec4(mix(glyphRect.xy, glyphRect.zw, weight), 0.0, 1.0);
+}
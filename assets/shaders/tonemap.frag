@@ -0,0 +1,32 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+
+layout(push_constant) uniform Params {
+    uint tonemapOperator;
+    float whitePoint;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+const uint REINHARD = 0;
+const uint ACES = 1;
+
+vec3 reinhard(vec3 color) {
+    float white = params.whitePoint * params.whitePoint;
+    return color * (1.0 + color / white) / (1.0 + color);
+}
+
+// Narkowicz's fit of the ACES reference rendering transform.
+vec3 aces(vec3 color) {
+    return clamp((color * (2.51 * color + 0.03)) / (color * (2.43 * color + 0.59) + 0.14), 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture(sampler2D(inputImage, inputSampler), fragUv);
+    vec3 mapped = params.tonemapOperator == ACES ? aces(color.rgb) : reinhard(color.rgb);
+    outColor = vec4(mapped, color.a);
+}
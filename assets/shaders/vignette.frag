@@ -0,0 +1,21 @@
+#version 450
+
+layout(set = 0, binding = 0) uniform texture2D inputImage;
+layout(set = 0, binding = 1) uniform sampler inputSampler;
+
+layout(push_constant) uniform Params {
+    float intensity;
+    float radius;
+    float softness;
+} params;
+
+layout(location = 0) in vec2 fragUv;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    vec4 color = texture(sampler2D(inputImage, inputSampler), fragUv);
+    float distanceToCenter = length(fragUv - vec2(0.5)) * 1.41421356;
+    float falloff = smoothstep(params.radius, params.radius - params.softness, distanceToCenter);
+    outColor = vec4(color.rgb * mix(1.0, falloff, params.intensity), color.a);
+}
@@ -1,63 +1,280 @@
-use crate::vrt::device::descriptors::layout::VRTDescriptorSetLayoutBuilder;
 use crate::VRTWindow;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::process;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use erupt::vk1_0::{DescriptorType, ShaderStageFlags};
-use winit::event::{ElementState, Event, VirtualKeyCode, WindowEvent};
-use winit::event_loop::{ControlFlow, EventLoop};
+use erupt::vk1_0::{Extent2D, Format, SampleCountFlagBits};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use imgui::{Condition, TreeNodeFlags};
+use winit::event::{ElementState, Event, KeyboardInput, VirtualKeyCode, WindowEvent};
+use winit::event_loop::{ControlFlow, EventLoop, EventLoopWindowTarget};
+use winit::window::WindowId;
 
-use super::device::descriptors::layout::VRTDescriptorSetLayout;
 use super::device::device::VRTDevice;
-
+use super::device::swapchain::{
+    OutputTransferFunction, RenderPassDescription, SwapchainConfig, MAX_FRAMES_IN_FLIGHT,
+};
+
+use super::graphics::debug_draw::{DebugDraw, DebugStyle};
+use super::graphics::frame_stats::FrameLimiter;
+use super::graphics::gui::gui_render_system::GuiRenderSystem;
+use super::graphics::gui::Gui;
+use super::graphics::ibl::hdr::HdrImage;
+use super::graphics::ibl::{EnvironmentMap, IblSettings};
+use super::graphics::material::{Material, MaterialRegistry, TextureSlot};
 use super::graphics::model::Model;
-use super::graphics::renderer::VRTRenderer;
-use super::graphics::triangle_render_system::TriangleRenderSystem;
-use super::utils::result::VkResult;
+use super::graphics::pbr_render_system::{PbrRenderSystem, PbrScene};
+use super::graphics::pipeline::BlendMode;
+use super::graphics::renderer::{ClearValues, VRTRenderer};
+use super::graphics::text::text_render_system::TextRenderSystem;
+use super::graphics::text::{FontAtlas, Text, TextAlign, TextBatch};
+use super::scenes::clustered::ClusteredScene;
+use super::scenes::deferred::DeferredScene;
+use super::scenes::gpu_driven::GpuDrivenScene;
+use super::scenes::main_scene::MainScene;
+use super::scenes::particles::ParticleScene;
+use super::scenes::sprites::SpriteScene;
+use super::scenes::{Scene, SceneAssets, SceneContext};
+use super::utils::result::{VkError, VkResult};
+
+const CLEAR_COLORS: [[f32; 4]; 4] = [
+    [0.0, 0.0, 0.0, 1.0],
+    [0.1, 0.1, 0.1, 1.0],
+    [0.05, 0.07, 0.15, 1.0],
+    [0.4, 0.4, 0.45, 1.0],
+];
+/// Particles simulated when `VULKSIM_PARTICLES` is not set.
+const DEFAULT_PARTICLE_COUNT: u32 = 262_144;
+/// Index of the main scene in `VRTApp::scenes`, shown whenever no other scene is.
+const MAIN_SCENE: usize = 0;
+/// Swapchain render pass drawn over the finished frame, for text and the GUI.
+const OVERLAY_RENDER_PASS: usize = 1;
+/// Pixel size glyphs are rasterized at.
+const FONT_PIXEL_SIZE: f32 = 24.0;
+/// Tried in order when `VULKSIM_FONT` is not set.
+const SYSTEM_FONTS: [&str; 4] = [
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "C:\\Windows\\Fonts\\segoeui.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+];
 
 pub struct VRTApp {
     device: Arc<VRTDevice>,
     window: VRTWindow,
     renderer: VRTRenderer,
-    triangle_render_system: TriangleRenderSystem,
-    model: Model,
-    global_descriptor_set_layout: VRTDescriptorSetLayout,
+    /// Generation of the render passes the render systems were built against.
+    render_pass_generation: u64,
+    assets: SceneAssets,
+    scenes: Vec<Box<dyn Scene>>,
+    active_scene: usize,
+    font_atlas: Option<FontAtlas>,
+    text_render_system: Option<TextRenderSystem>,
+    text_batch: TextBatch,
+    overlay_enabled: bool,
+    debug_draw: DebugDraw,
+    debug_draw_enabled: bool,
+    gui: Gui,
+    gui_render_system: GuiRenderSystem,
+    gui_enabled: bool,
+    start_time: Instant,
+    frame_limiter: FrameLimiter,
+    inspector_windows: HashMap<WindowId, InspectorWindow>,
+}
+
+struct InspectorWindow {
+    // Dropped first, so the frames still in flight retire before the pipeline goes away.
+    renderer: VRTRenderer,
+    pbr_render_system: PbrRenderSystem,
+    render_pass_generation: u64,
+    window: VRTWindow,
+    device: Arc<VRTDevice>,
+}
+
+impl InspectorWindow {
+    fn new(
+        device: Arc<VRTDevice>,
+        target: &EventLoopWindowTarget<()>,
+        assets: &SceneAssets,
+    ) -> VkResult<Self> {
+        let window = VRTWindow::build_window(target, "Inspector", 480, 360)?;
+        let renderer = VRTRenderer::new(device.clone(), &window, SwapchainConfig::default())?;
+        let pbr_render_system = Self::create_pbr_render_system(&device, &renderer, assets)?;
+
+        Ok(Self {
+            render_pass_generation: renderer.get_render_pass_generation(),
+            renderer,
+            pbr_render_system,
+            window,
+            device,
+        })
+    }
+
+    fn create_pbr_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        assets: &SceneAssets,
+    ) -> VkResult<PbrRenderSystem> {
+        PbrRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            &assets.materials,
+            &assets.environment,
+        )
+    }
+
+    /// Draws the model, clearing like the main window so both follow the clear color presets.
+    fn draw_frame(&mut self, assets: &SceneAssets, clear_values: ClearValues) -> VkResult<()> {
+        if self.render_pass_generation != self.renderer.get_render_pass_generation() {
+            self.pbr_render_system =
+                Self::create_pbr_render_system(&self.device, &self.renderer, assets)?;
+            self.render_pass_generation = self.renderer.get_render_pass_generation();
+        }
+
+        let mut frame = match self.renderer.begin_frame(&mut self.window) {
+            Ok(frame) => frame,
+            Err(VkError::SwapChainExpired) => return Ok(()),
+            Err(err) => return Err(err),
+        };
+        frame.set_clear_values(clear_values);
+
+        let frame_index = frame.frame_index();
+        self.pbr_render_system
+            .update_scene(frame_index, &PbrScene::default(), &assets.environment);
+        let render_pass = frame.begin_swapchain_render_pass()?;
+        self.pbr_render_system.render(
+            render_pass.command_buffer(),
+            frame_index,
+            &assets.model,
+            &assets.materials,
+            &assets.environment,
+            Mat4::IDENTITY,
+        );
+        drop(render_pass);
+
+        frame.finish()
+    }
 }
 
 impl VRTApp {
     pub fn new(event_loop: &EventLoop<()>, app_name: &str, width: u32, height: u32) -> Self {
-        println!("System OS {:?}", std::env::consts::OS);
+        log::info!("System OS {:?}", std::env::consts::OS);
         let window = VRTWindow::build_window(&event_loop, app_name, width, height)
             .expect("Cannot create window.");
 
         let device = Arc::new(VRTDevice::new(&window).expect("Cannot create device"));
 
-        let renderer = VRTRenderer::new(device.clone(), &window).unwrap();
+        let mut swapchain_config = if std::env::var_os("VULKSIM_HDR").is_some() {
+            SwapchainConfig::hdr()
+        } else {
+            SwapchainConfig::default()
+        };
+        if let Some(frames_in_flight) = Self::env_var("VULKSIM_FRAMES_IN_FLIGHT") {
+            swapchain_config.frames_in_flight = frames_in_flight;
+        }
+        swapchain_config.image_count = Self::env_var("VULKSIM_SWAPCHAIN_IMAGES");
+        swapchain_config
+            .render_passes
+            .push(RenderPassDescription::overlay());
+
+        let renderer = VRTRenderer::new(device.clone(), &window, swapchain_config).unwrap();
+        log::info!(
+            "output transfer function {:?}",
+            renderer.get_output_transfer_function()
+        );
+
+        let materials = Self::create_materials(device.clone()).expect("Cannot create materials");
+        let environment =
+            Self::create_environment(device.clone()).expect("Cannot create environment map");
+        log::info!(
+            "environment cube {}px, {} prefiltered levels",
+            environment.get_settings().environment_size,
+            environment.get_settings().prefiltered_mip_levels
+        );
+        let mut model = Model::new(device.get_instance(), device.clone());
+        if let Some(material) = materials.find("checker") {
+            model.set_material(material);
+        }
+        let assets = SceneAssets {
+            model,
+            materials,
+            environment,
+        };
 
-        let triangle_render_system =
-            TriangleRenderSystem::new(device.clone(), renderer.get_swapchain_render_pass());
+        let scenes = Self::create_scenes(&device, &renderer, &assets);
 
-        let model = Model::new(device.get_instance(), device.clone());
+        let font_atlas = Self::load_font(device.clone());
+        let text_render_system = font_atlas
+            .as_ref()
+            .map(|atlas| Self::create_text_render_system(&device, &renderer, atlas));
 
-        let global_descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
-            .add_binding(
-                0,
-                DescriptorType::UNIFORM_BUFFER,
-                ShaderStageFlags::ALL_GRAPHICS,
-                None,
-            )
-            .build();
+        let mut gui = Gui::new(&window);
+        let gui_render_system = Self::create_gui_render_system(&device, &renderer, &mut gui)
+            .expect("Cannot create GUI render system");
+
+        let target_frame_rate = Self::env_var("VULKSIM_TARGET_FPS");
 
         Self {
             device,
             window,
+            render_pass_generation: renderer.get_render_pass_generation(),
             renderer,
-            triangle_render_system,
-            model,
-            global_descriptor_set_layout,
+            assets,
+            scenes,
+            active_scene: MAIN_SCENE,
+            font_atlas,
+            text_render_system,
+            text_batch: TextBatch::new(),
+            overlay_enabled: true,
+            debug_draw: DebugDraw::new(),
+            debug_draw_enabled: false,
+            gui,
+            gui_render_system,
+            gui_enabled: false,
+            start_time: Instant::now(),
+            frame_limiter: FrameLimiter::new(target_frame_rate),
+            inspector_windows: HashMap::new(),
         }
     }
 
+    /// The main scene first, then the feature demos. A demo the device cannot run is left out.
+    fn create_scenes(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        assets: &SceneAssets,
+    ) -> Vec<Box<dyn Scene>> {
+        let color_lut = std::env::var_os("VULKSIM_COLOR_LUT").map(PathBuf::from);
+        let main_scene = MainScene::new(device.clone(), renderer, assets, color_lut.as_deref())
+            .expect("Cannot create main scene");
+        let mut scenes: Vec<Box<dyn Scene>> = vec![
+            Box::new(main_scene),
+            Box::new(
+                DeferredScene::new(device.clone(), renderer)
+                    .expect("Cannot create deferred renderer"),
+            ),
+            Box::new(
+                ClusteredScene::new(device.clone(), renderer)
+                    .expect("Cannot create clustered lighting"),
+            ),
+        ];
+        match GpuDrivenScene::new(device.clone(), renderer, &assets.model) {
+            Ok(scene) => scenes.push(Box::new(scene)),
+            Err(err) => log::warn!("GPU-driven drawing unavailable: {}", err),
+        }
+        scenes.push(Box::new(
+            SpriteScene::new(device.clone(), renderer).expect("Cannot create sprite atlases"),
+        ));
+        let particle_count = Self::env_var("VULKSIM_PARTICLES").unwrap_or(DEFAULT_PARTICLE_COUNT);
+        scenes.push(Box::new(
+            ParticleScene::new(device.clone(), renderer, particle_count)
+                .expect("Cannot create particle system"),
+        ));
+        scenes
+    }
+
     // fn create_graphics_pipeline(
     //     device: &DeviceLoader,
     //     extent: &Extent2D,
@@ -171,17 +388,36 @@ impl VRTApp {
     //     Ok((pipeline_layout.result()?, graphics_pipeline))
     // }
 
-    fn process_event(&mut self, event: Event<()>, control_flow: &mut ControlFlow) -> VkResult<()> {
+    fn process_event(
+        &mut self,
+        event: Event<()>,
+        target: &EventLoopWindowTarget<()>,
+        control_flow: &mut ControlFlow,
+    ) -> VkResult<()> {
+        if let Event::WindowEvent { window_id, event } = &event {
+            if *window_id == self.window.id() {
+                self.gui.handle_event(event);
+            }
+        }
+
         match event {
+            Event::WindowEvent { window_id, event } if window_id != self.window.id() => {
+                self.process_inspector_event(window_id, event)
+            }
             Event::WindowEvent { event, .. } => match event {
                 WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                WindowEvent::KeyboardInput { input, .. } => {
-                    if let (Some(VirtualKeyCode::Escape), ElementState::Released) =
-                        (input.virtual_keycode, input.state)
-                    {
-                        *control_flow = ControlFlow::Exit;
-                    }
-                }
+                // Typing into a text field must not trigger the shortcuts below.
+                WindowEvent::KeyboardInput { .. }
+                    if self.gui_enabled && self.gui.wants_keyboard() => {}
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(key),
+                            state: ElementState::Released,
+                            ..
+                        },
+                    ..
+                } => self.process_key(key, target, control_flow)?,
                 WindowEvent::Resized(new_inner_size)
                 | WindowEvent::ScaleFactorChanged {
                     new_inner_size: &mut new_inner_size,
@@ -191,10 +427,22 @@ impl VRTApp {
                 }
                 _ => (),
             },
-            Event::MainEventsCleared => self.draw_frame()?,
-            Event::RedrawRequested(_) => self.draw_frame()?,
+            Event::MainEventsCleared if self.frame_limiter.should_render(Instant::now()) => {
+                self.draw_frame()?;
+                let clear_values = self.renderer.get_clear_values();
+                for inspector in self.inspector_windows.values_mut() {
+                    inspector.draw_frame(&self.assets, clear_values)?;
+                }
+            }
+            Event::RedrawRequested(window_id) => match self.inspector_windows.get_mut(&window_id) {
+                Some(inspector) => {
+                    inspector.draw_frame(&self.assets, self.renderer.get_clear_values())?
+                }
+                None => self.draw_frame()?,
+            },
             Event::LoopDestroyed => {
-                unsafe { self.device.get_device_ptr().device_wait_idle() }.result()?
+                unsafe { self.device.get_device_ptr().device_wait_idle() }.result()?;
+                self.inspector_windows.clear();
             }
             _ => (),
         }
@@ -202,24 +450,542 @@ impl VRTApp {
         Ok(())
     }
 
-    fn draw_frame(&mut self) -> VkResult<()> {
-        let command_buffer = self.renderer.begin_frame(&self.window).unwrap();
+    /// Scene keys toggle their scene, the shown scene gets the remaining keys before the app.
+    fn process_key(
+        &mut self,
+        key: VirtualKeyCode,
+        target: &EventLoopWindowTarget<()>,
+        control_flow: &mut ControlFlow,
+    ) -> VkResult<()> {
+        if let Some(index) = self
+            .scenes
+            .iter()
+            .position(|scene| scene.key() == Some(key))
+        {
+            self.toggle_scene(index);
+            return Ok(());
+        }
+        if self.scenes[self.active_scene].process_key(key, &self.renderer)? {
+            return Ok(());
+        }
+
+        match key {
+            VirtualKeyCode::Escape => *control_flow = ControlFlow::Exit,
+            VirtualKeyCode::M => self.cycle_msaa_samples()?,
+            VirtualKeyCode::B => self.cycle_frames_in_flight()?,
+            VirtualKeyCode::C => self.cycle_clear_color(),
+            VirtualKeyCode::R => self.cycle_model_material(),
+            VirtualKeyCode::U => self.cycle_material_roughness(),
+            VirtualKeyCode::X => self.toggle_model_texture()?,
+            VirtualKeyCode::S => {
+                self.overlay_enabled = !self.overlay_enabled;
+                log::info!("stats overlay {}", self.overlay_enabled);
+            }
+            VirtualKeyCode::V => self.toggle_debug_draw(),
+            VirtualKeyCode::E => {
+                self.gui_enabled = !self.gui_enabled;
+                log::info!("gui {}", self.gui_enabled);
+            }
+            VirtualKeyCode::F => log::info!("{}", self.renderer.get_frame_stats().summary()),
+            VirtualKeyCode::N => {
+                let inspector = InspectorWindow::new(self.device.clone(), target, &self.assets)?;
+                self.inspector_windows
+                    .insert(inspector.window.id(), inspector);
+            }
+            _ => (),
+        }
+        Ok(())
+    }
+
+    /// Shows the scene at `index`, or the main scene again if it already shows.
+    fn toggle_scene(&mut self, index: usize) {
+        self.active_scene = if self.active_scene == index {
+            MAIN_SCENE
+        } else {
+            index
+        };
+        // Shapes with a lifetime belong to the scene that queued them.
+        self.debug_draw.clear();
+        self.scenes[self.active_scene].activate();
+    }
+
+    fn process_inspector_event(&mut self, window_id: WindowId, event: WindowEvent) {
+        let inspector = match self.inspector_windows.get_mut(&window_id) {
+            Some(inspector) => inspector,
+            None => return,
+        };
+
+        match event {
+            WindowEvent::CloseRequested => {
+                self.inspector_windows.remove(&window_id);
+            }
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let (Some(VirtualKeyCode::Escape), ElementState::Released) =
+                    (input.virtual_keycode, input.state)
+                {
+                    self.inspector_windows.remove(&window_id);
+                }
+            }
+            WindowEvent::Resized(new_inner_size)
+            | WindowEvent::ScaleFactorChanged {
+                new_inner_size: &mut new_inner_size,
+                ..
+            } => {
+                inspector.window.resize_callback(new_inner_size);
+            }
+            _ => (),
+        }
+    }
+
+    /// The font in `VULKSIM_FONT` if set, otherwise the first of `SYSTEM_FONTS` that loads.
+    fn load_font(device: Arc<VRTDevice>) -> Option<FontAtlas> {
+        let paths = match std::env::var_os("VULKSIM_FONT") {
+            Some(path) => vec![PathBuf::from(path)],
+            None => SYSTEM_FONTS.iter().map(PathBuf::from).collect(),
+        };
+        for path in &paths {
+            match FontAtlas::load(device.clone(), path, FONT_PIXEL_SIZE) {
+                Ok(atlas) => {
+                    log::info!("font {}", path.display());
+                    return Some(atlas);
+                }
+                Err(err) => log::debug!("cannot load font {}: {}", path.display(), err),
+            }
+        }
+        log::warn!("no font found, set VULKSIM_FONT to show text");
+        None
+    }
+
+    fn create_gui_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        gui: &mut Gui,
+    ) -> VkResult<GuiRenderSystem> {
+        let linear_output = matches!(
+            renderer.get_output_transfer_function(),
+            OutputTransferFunction::SrgbHardware | OutputTransferFunction::Linear
+        );
+        GuiRenderSystem::new(
+            device.clone(),
+            renderer
+                .get_swapchain_render_pass_at(OVERLAY_RENDER_PASS)
+                .expect("Overlay render pass missing"),
+            renderer.get_msaa_samples(),
+            gui.context_mut(),
+            linear_output,
+        )
+    }
 
-        self.renderer.begin_swapchain_render_pass(command_buffer);
+    fn create_text_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        atlas: &FontAtlas,
+    ) -> TextRenderSystem {
+        TextRenderSystem::new(
+            device.clone(),
+            renderer
+                .get_swapchain_render_pass_at(OVERLAY_RENDER_PASS)
+                .expect("Overlay render pass missing"),
+            renderer.get_msaa_samples(),
+            atlas,
+        )
+    }
 
-        self.triangle_render_system
-            .render(self.device.clone(), command_buffer, &self.model);
+    fn env_var<T: std::str::FromStr>(name: &str) -> Option<T> {
+        std::env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+    }
 
-        self.renderer.end_swapchain_render_pass(command_buffer);
-        self.renderer.end_frame(&mut self.window, command_buffer);
+    fn cycle_frames_in_flight(&mut self) -> VkResult<()> {
+        let frames_in_flight = self.renderer.get_frames_in_flight() % MAX_FRAMES_IN_FLIGHT + 1;
+        self.renderer
+            .set_frames_in_flight(&self.window, frames_in_flight)?;
+        log::info!(
+            "frames in flight {}, swapchain images {}",
+            self.renderer.get_frames_in_flight(),
+            self.renderer.get_image_count()
+        );
         Ok(())
     }
 
-    pub fn run(&'static mut self, event_loop: EventLoop<()>) -> ! {
-        event_loop.run(move |event, _, control_flow| {
-            *control_flow = ControlFlow::Wait;
+    fn cycle_clear_color(&mut self) {
+        let current = self.renderer.get_clear_values().color;
+        let index = CLEAR_COLORS
+            .iter()
+            .position(|color| *color == current)
+            .map_or(0, |index| (index + 1) % CLEAR_COLORS.len());
+        self.renderer.set_clear_color(CLEAR_COLORS[index]);
+    }
+
+    /// A few presets to switch the model between, including a procedural checker texture.
+    fn create_materials(device: Arc<VRTDevice>) -> VkResult<MaterialRegistry> {
+        const CHECKER_SIZE: u32 = 8;
+
+        let mut materials = MaterialRegistry::new(device)?;
+        let checker_pixels = (0..CHECKER_SIZE * CHECKER_SIZE)
+            .flat_map(|texel| {
+                let (x, y) = (texel % CHECKER_SIZE, texel / CHECKER_SIZE);
+                if (x + y) % 2 == 0 {
+                    [230, 230, 230, 255]
+                } else {
+                    [40, 40, 40, 255]
+                }
+            })
+            .collect::<Vec<u8>>();
+        let checker = materials.create_texture(
+            Extent2D {
+                width: CHECKER_SIZE,
+                height: CHECKER_SIZE,
+            },
+            Format::R8G8B8A8_SRGB,
+            &checker_pixels,
+        )?;
+
+        materials.add(
+            "checker",
+            Material::new(Vec4::ONE, 0.0, 0.6).with_texture(TextureSlot::BaseColor, checker),
+        )?;
+        materials.add("plastic", Material::new(Vec4::ONE, 0.0, 0.3))?;
+        materials.add(
+            "gold",
+            Material::new(Vec4::new(1.0, 0.77, 0.34, 1.0), 1.0, 0.25),
+        )?;
+        materials.add(
+            "glow",
+            Material::new(Vec4::ONE, 0.0, 0.8).with_emissive(Vec3::new(0.6, 0.3, 0.1)),
+        )?;
+        materials.add(
+            "glass",
+            Material::new(Vec4::new(0.6, 0.8, 1.0, 0.35), 0.0, 0.05)
+                .with_blend_mode(BlendMode::Alpha),
+        )?;
+        materials.add(
+            "glass-premultiplied",
+            Material::new(Vec4::new(0.4, 0.1, 0.1, 0.5), 0.0, 0.1)
+                .with_blend_mode(BlendMode::Premultiplied),
+        )?;
+        materials.add(
+            "additive-glow",
+            Material::new(Vec4::new(0.2, 0.5, 0.2, 0.8), 0.0, 0.9)
+                .with_emissive(Vec3::new(0.1, 0.4, 0.1))
+                .with_blend_mode(BlendMode::Additive),
+        )?;
+        materials.add(
+            "tint-multiply",
+            Material::new(Vec4::new(1.0, 0.85, 0.3, 1.0), 0.0, 1.0)
+                .with_blend_mode(BlendMode::Multiply),
+        )?;
+        Ok(materials)
+    }
+
+    /// Bakes the file in `VULKSIM_ENVIRONMENT` if set, otherwise a procedural sky with a sun.
+    fn create_environment(device: Arc<VRTDevice>) -> VkResult<EnvironmentMap> {
+        let settings = IblSettings::default();
+        if let Some(path) = std::env::var_os("VULKSIM_ENVIRONMENT") {
+            return EnvironmentMap::load(device, PathBuf::from(path), &settings);
+        }
+
+        let sun = Vec3::new(0.4, 0.5, 0.6).normalize();
+        let sky = HdrImage::from_fn(256, 128, |u, v| {
+            let azimuth = (u - 0.5) * 2.0 * std::f32::consts::PI;
+            let polar = v * std::f32::consts::PI;
+            let direction = Vec3::new(
+                polar.sin() * azimuth.cos(),
+                polar.cos(),
+                polar.sin() * azimuth.sin(),
+            );
+            let color = if direction.y > 0.0 {
+                let zenith = Vec3::new(0.25, 0.45, 0.9);
+                let horizon = Vec3::new(0.9, 0.85, 0.8);
+                horizon.lerp(zenith, direction.y.sqrt())
+            } else {
+                Vec3::new(0.2, 0.18, 0.16)
+            };
+            let sun_glow = direction.dot(sun).max(0.0).powf(512.0) * 200.0;
+            (color + Vec3::splat(sun_glow)).to_array()
+        });
+        EnvironmentMap::from_image(device, &sky, &settings)
+    }
+
+    fn toggle_debug_draw(&mut self) {
+        self.debug_draw_enabled = !self.debug_draw_enabled;
+        if self.debug_draw_enabled {
+            // Flash the bounds of the model for a moment, visible through everything.
+            self.debug_draw.sphere(
+                &self.assets.model.world_bounding_sphere(Mat4::IDENTITY),
+                DebugStyle::new(Vec4::new(1.0, 0.2, 0.8, 1.0))
+                    .with_lifetime(Duration::from_secs(2))
+                    .with_depth_test(false),
+            );
+        }
+        log::info!("debug draw {}", self.debug_draw_enabled);
+    }
+
+    /// A panel of the toggles and parameters otherwise bound to keys, changed as it is used.
+    fn build_gui(&mut self) {
+        let stats = self.renderer.get_frame_stats().summary();
+        let ui = self.gui.frame(&self.window);
+        ui.window("Simulation")
+            .position([8.0, 40.0], Condition::FirstUseEver)
+            .size([320.0, 0.0], Condition::FirstUseEver)
+            .build(|| {
+                ui.text(stats);
+                ui.separator();
+
+                if ui.collapsing_header("Last frame", TreeNodeFlags::empty()) {
+                    let frame_stats = self.renderer.get_frame_stats();
+                    ui.text(format!("frame {}", frame_stats.frame_count()));
+                    for (name, stat) in [
+                        ("frame", frame_stats.cpu_frame()),
+                        ("acquire", frame_stats.acquire_wait()),
+                        ("submit", frame_stats.submit()),
+                        ("present", frame_stats.present()),
+                    ] {
+                        ui.text(format!(
+                            "{:<8} {:>7.3}ms",
+                            name,
+                            stat.last().as_secs_f64() * 1000.0
+                        ));
+                    }
+                    let culling = frame_stats.culling();
+                    ui.text(format!(
+                        "visible  {} of {}",
+                        culling.visible,
+                        culling.total()
+                    ));
+                }
+
+                if ui.collapsing_header("Rendering", TreeNodeFlags::DEFAULT_OPEN) {
+                    for (index, scene) in self.scenes.iter_mut().enumerate() {
+                        if ui.radio_button_bool(scene.name(), self.active_scene == index) {
+                            self.active_scene = index;
+                            self.debug_draw.clear();
+                            scene.activate();
+                        }
+                    }
+                    ui.checkbox("Debug shapes", &mut self.debug_draw_enabled);
+                    ui.checkbox("Stats overlay", &mut self.overlay_enabled);
+                }
+
+                self.scenes[self.active_scene].build_gui(ui);
+
+                let assets = &mut self.assets;
+                if ui.collapsing_header("Model material", TreeNodeFlags::DEFAULT_OPEN) {
+                    if let Some(material) = assets.materials.get_mut(assets.model.get_material()) {
+                        ui.slider("Metallic", 0.0, 1.0, &mut material.metallic_factor);
+                        ui.slider("Roughness", 0.0, 1.0, &mut material.roughness_factor);
+                        let mut base_color = material.base_color_factor.to_array();
+                        if ui.color_edit4("Base color", &mut base_color) {
+                            material.base_color_factor = Vec4::from(base_color);
+                        }
+                    }
+                }
+            });
+    }
+
+    fn cycle_model_material(&mut self) {
+        if self.assets.materials.is_empty() {
+            return;
+        }
+
+        let handles = self.assets.materials.handles().collect::<Vec<_>>();
+        let next = handles
+            .iter()
+            .cycle()
+            .skip_while(|handle| **handle != self.assets.model.get_material())
+            .nth(1)
+            .copied();
+        if let Some(material) = next {
+            self.assets.model.set_material(material);
+            log::info!(
+                "model material {} of {}",
+                self.assets.materials.get_name(material).unwrap_or_default(),
+                self.assets.materials.len()
+            );
+        }
+    }
+
+    /// Puts the checker texture on the model material, or takes its base color texture off.
+    fn toggle_model_texture(&mut self) -> VkResult<()> {
+        let materials = &mut self.assets.materials;
+        let handle = self.assets.model.get_material();
+        let has_texture = materials
+            .get(handle)
+            .is_some_and(|material| material.get_texture(TextureSlot::BaseColor).is_some());
+        let texture = if has_texture {
+            None
+        } else {
+            materials
+                .find("checker")
+                .and_then(|checker| materials.get(checker))
+                .and_then(|checker| checker.get_texture(TextureSlot::BaseColor))
+                .cloned()
+        };
+
+        // Inspector windows draw with the same materials.
+        unsafe { self.device.get_device_ptr().device_wait_idle() }.result()?;
+        materials.set_texture(handle, TextureSlot::BaseColor, texture);
+        log::info!("model texture {}", !has_texture);
+        Ok(())
+    }
 
-            if let Err(err) = self.process_event(event, control_flow) {
+    fn cycle_material_roughness(&mut self) {
+        if let Some(material) = self
+            .assets
+            .materials
+            .get_mut(self.assets.model.get_material())
+        {
+            material.roughness_factor = if material.roughness_factor >= 0.95 {
+                0.1
+            } else {
+                material.roughness_factor + 0.1
+            };
+            log::info!("material roughness {:.1}", material.roughness_factor);
+        }
+    }
+
+    fn cycle_msaa_samples(&mut self) -> VkResult<()> {
+        let samples = match self.renderer.get_msaa_samples() {
+            SampleCountFlagBits::_1 => SampleCountFlagBits::_2,
+            SampleCountFlagBits::_2 => SampleCountFlagBits::_4,
+            SampleCountFlagBits::_4 => SampleCountFlagBits::_8,
+            _ => SampleCountFlagBits::_1,
+        };
+
+        self.renderer.set_msaa_samples(&self.window, samples)?;
+        log::info!("msaa samples {:?}", self.renderer.get_msaa_samples());
+        Ok(())
+    }
+
+    /// Recreates every pipeline built against the swapchain render passes.
+    fn rebuild_render_systems(&mut self) -> VkResult<()> {
+        for scene in &mut self.scenes {
+            scene.rebuild(&self.renderer, &self.assets)?;
+        }
+        if let Some(atlas) = &self.font_atlas {
+            self.text_render_system = Some(Self::create_text_render_system(
+                &self.device,
+                &self.renderer,
+                atlas,
+            ));
+        }
+        self.gui_render_system =
+            Self::create_gui_render_system(&self.device, &self.renderer, &mut self.gui)?;
+        Ok(())
+    }
+
+    fn draw_frame(&mut self) -> VkResult<()> {
+        if self.render_pass_generation != self.renderer.get_render_pass_generation() {
+            self.rebuild_render_systems()?;
+            self.render_pass_generation = self.renderer.get_render_pass_generation();
+        }
+
+        let scene = &mut self.scenes[self.active_scene];
+        // The offscreen targets follow the swapchain, which may have been recreated last frame.
+        scene.resize(&self.renderer)?;
+
+        if self.debug_draw_enabled {
+            scene.queue_debug_shapes(&self.debug_draw, &self.assets);
+        }
+        // Taken even while hidden, so shapes queued meanwhile do not pile up.
+        let debug_frame = self.debug_draw.take_frame(Instant::now());
+
+        self.text_batch.clear();
+        if self.overlay_enabled {
+            let summary = self.renderer.get_frame_stats().summary();
+            if let Some(atlas) = &self.font_atlas {
+                // The active scene in the top right corner, at the size the glyphs were rasterized
+                // at, while the window is wide enough to keep it clear of the stats.
+                let name = self.scenes[self.active_scene].name();
+                let size = atlas.get_pixel_size();
+                let width = self.renderer.get_extent().width as f32;
+                if atlas.measure(&summary, 14.0).x + atlas.measure(name, size).x + 32.0 < width {
+                    self.text_batch.push(
+                        Text::new(name, Vec2::new(width - 8.0, 8.0))
+                            .with_size(size)
+                            .with_align(TextAlign::Right)
+                            .with_color(Vec4::new(1.0, 0.8, 0.3, 1.0)),
+                    );
+                }
+            }
+            self.text_batch
+                .push(Text::new(summary, Vec2::new(8.0, 8.0)).with_size(14.0));
+        }
+
+        if self.gui_enabled {
+            self.build_gui();
+        }
+
+        let mut frame = match self.renderer.begin_frame(&mut self.window) {
+            Ok(frame) => frame,
+            Err(VkError::SwapChainExpired) => {
+                if self.gui_enabled {
+                    self.gui.render();
+                }
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+
+        let extent = frame.extent();
+        let mut context = SceneContext {
+            assets: &self.assets,
+            elapsed: self.start_time.elapsed().as_secs_f32(),
+            debug_frame: self.debug_draw_enabled.then_some(&debug_frame),
+            text_batch: &mut self.text_batch,
+        };
+        self.scenes[self.active_scene].record(&mut frame, &mut context)?;
+
+        let text = match (self.font_atlas.as_ref(), self.text_render_system.as_mut()) {
+            (Some(atlas), Some(render_system)) if !self.text_batch.is_empty() => {
+                Some((atlas, render_system))
+            }
+            _ => None,
+        };
+        let gui_draw_data = if self.gui_enabled {
+            Some(self.gui.render())
+        } else {
+            None
+        };
+        if text.is_some() || gui_draw_data.is_some() {
+            let frame_index = frame.frame_index();
+            let render_pass = frame.begin_render_pass(OVERLAY_RENDER_PASS)?;
+            if let Some((atlas, render_system)) = text {
+                render_system.render(
+                    render_pass.command_buffer(),
+                    frame_index,
+                    extent,
+                    atlas,
+                    &self.text_batch,
+                );
+            }
+            if let Some(draw_data) = gui_draw_data {
+                self.gui_render_system
+                    .render(render_pass.command_buffer(), frame_index, draw_data);
+            }
+        }
+
+        frame.finish()
+    }
+
+    pub fn run(&'static mut self, event_loop: EventLoop<()>) -> ! {
+        event_loop.run(move |event, target, control_flow| {
+            // Keep rendering continuously, but sleep on events while there is nothing to show.
+            let all_minimized = self.window.is_minimized()
+                && self
+                    .inspector_windows
+                    .values()
+                    .all(|inspector| inspector.window.is_minimized());
+            *control_flow = if all_minimized {
+                ControlFlow::Wait
+            } else if self.frame_limiter.is_enabled() {
+                ControlFlow::WaitUntil(self.frame_limiter.next_frame())
+            } else {
+                ControlFlow::Poll
+            };
+
+            if let Err(err) = self.process_event(event, target, control_flow) {
                 eprintln!("Error: {:?}", color_eyre::Report::new(err));
                 process::exit(1);
             }
@@ -1,8 +1,8 @@
 use std::sync::Arc;
 
 use erupt::vk1_0::{
-    DescriptorSetLayout, DescriptorSetLayoutBinding, DescriptorSetLayoutBindingBuilder,
-    DescriptorSetLayoutCreateInfoBuilder, DescriptorType, ShaderStageFlags,
+    DescriptorSetLayout, DescriptorSetLayoutBindingBuilder, DescriptorSetLayoutCreateInfoBuilder,
+    DescriptorType, ShaderStageFlags,
 };
 
 use crate::vrt::device::device::VRTDevice;
@@ -10,6 +10,7 @@ use crate::vrt::device::device::VRTDevice;
 pub struct VRTDescriptorSetLayout {
     device: Arc<VRTDevice>,
     descriptor_set_layout: DescriptorSetLayout,
+    bindings: Vec<(u32, DescriptorType)>,
 }
 
 impl VRTDescriptorSetLayout {
@@ -22,9 +23,35 @@ impl VRTDescriptorSetLayout {
                 .create_descriptor_set_layout(&layout_info, None)
                 .unwrap()
         };
+        let bindings = bindings
+            .iter()
+            .map(|binding| (binding.binding, binding.descriptor_type))
+            .collect();
         Self {
             device,
             descriptor_set_layout,
+            bindings,
+        }
+    }
+
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout
+    }
+
+    pub fn get_descriptor_type(&self, binding: u32) -> Option<DescriptorType> {
+        self.bindings
+            .iter()
+            .find(|(index, _)| *index == binding)
+            .map(|(_, descriptor_type)| *descriptor_type)
+    }
+}
+
+impl Drop for VRTDescriptorSetLayout {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
         }
     }
 }
@@ -48,13 +75,20 @@ impl VRTDescriptorSetLayoutBuilder<'_> {
         descriptor_type: DescriptorType,
         stage_flags: ShaderStageFlags,
         count: Option<u32>,
-    ) -> &VRTDescriptorSetLayoutBuilder {
+    ) -> &mut Self {
+        assert!(
+            self.bindings
+                .iter()
+                .all(|existing| existing.binding != binding),
+            "binding {} already in use",
+            binding
+        );
         let layout_binding = DescriptorSetLayoutBindingBuilder::new()
             .binding(binding)
             .descriptor_type(descriptor_type)
             .descriptor_count(count.unwrap_or(1))
             .stage_flags(stage_flags);
-        self.bindings[binding as usize] = layout_binding;
+        self.bindings.push(layout_binding);
         self
     }
 
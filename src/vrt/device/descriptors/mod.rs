@@ -1 +1,3 @@
 pub mod layout;
+pub mod pool;
+pub mod writer;
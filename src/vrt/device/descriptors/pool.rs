@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    DescriptorPool, DescriptorPoolCreateFlags, DescriptorPoolCreateInfoBuilder,
+    DescriptorPoolResetFlags, DescriptorPoolSizeBuilder, DescriptorSet,
+    DescriptorSetAllocateInfoBuilder, DescriptorType,
+};
+
+use super::layout::VRTDescriptorSetLayout;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::utils::result::VkResult;
+
+pub struct VRTDescriptorPool {
+    device: Arc<VRTDevice>,
+    descriptor_pool: DescriptorPool,
+}
+
+impl VRTDescriptorPool {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        max_sets: u32,
+        pool_flags: DescriptorPoolCreateFlags,
+        pool_sizes: &[DescriptorPoolSizeBuilder],
+    ) -> VkResult<Self> {
+        let pool_info = DescriptorPoolCreateInfoBuilder::new()
+            .pool_sizes(pool_sizes)
+            .max_sets(max_sets)
+            .flags(pool_flags);
+
+        let descriptor_pool = unsafe {
+            device
+                .get_device_ptr()
+                .create_descriptor_pool(&pool_info, None)
+        }
+        .result()?;
+
+        Ok(Self {
+            device,
+            descriptor_pool,
+        })
+    }
+
+    pub fn allocate_descriptor(&self, layout: &VRTDescriptorSetLayout) -> VkResult<DescriptorSet> {
+        let set_layout = layout.get_descriptor_set_layout();
+        let alloc_info = DescriptorSetAllocateInfoBuilder::new()
+            .descriptor_pool(self.descriptor_pool)
+            .set_layouts(std::slice::from_ref(&set_layout));
+
+        let descriptor_sets = unsafe {
+            self.device
+                .get_device_ptr()
+                .allocate_descriptor_sets(&alloc_info)
+        }
+        .result()?;
+
+        Ok(descriptor_sets[0])
+    }
+
+    pub fn get_device(&self) -> &Arc<VRTDevice> {
+        &self.device
+    }
+
+    pub fn reset_pool(&self) -> VkResult<()> {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .reset_descriptor_pool(self.descriptor_pool, DescriptorPoolResetFlags::empty())
+        }
+        .result()?;
+        Ok(())
+    }
+}
+
+impl Drop for VRTDescriptorPool {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+        }
+    }
+}
+
+pub struct VRTDescriptorPoolBuilder<'a> {
+    device: Arc<VRTDevice>,
+    pool_sizes: Vec<DescriptorPoolSizeBuilder<'a>>,
+    max_sets: u32,
+}
+
+impl VRTDescriptorPoolBuilder<'_> {
+    pub fn new(device: Arc<VRTDevice>) -> Self {
+        Self {
+            device,
+            pool_sizes: vec![],
+            max_sets: 1000,
+        }
+    }
+
+    pub fn add_pool_size(&mut self, descriptor_type: DescriptorType, count: u32) -> &mut Self {
+        self.pool_sizes.push(
+            DescriptorPoolSizeBuilder::new()
+                ._type(descriptor_type)
+                .descriptor_count(count),
+        );
+        self
+    }
+
+    pub fn set_max_sets(&mut self, max_sets: u32) -> &mut Self {
+        self.max_sets = max_sets;
+        self
+    }
+
+    pub fn build(&self) -> VkResult<VRTDescriptorPool> {
+        VRTDescriptorPool::new(
+            self.device.clone(),
+            self.max_sets,
+            DescriptorPoolCreateFlags::empty(),
+            &self.pool_sizes,
+        )
+    }
+}
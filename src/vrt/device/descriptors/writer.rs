@@ -0,0 +1,101 @@
+use erupt::vk1_0::{
+    Buffer, DescriptorBufferInfoBuilder, DescriptorImageInfoBuilder, DescriptorSet, DescriptorType,
+    DeviceSize, ImageLayout, ImageView, Sampler, WriteDescriptorSetBuilder,
+};
+
+use super::layout::VRTDescriptorSetLayout;
+use super::pool::VRTDescriptorPool;
+use crate::vrt::utils::result::VkResult;
+
+enum DescriptorInfo {
+    Buffer(DescriptorBufferInfoBuilder<'static>),
+    Image(DescriptorImageInfoBuilder<'static>),
+}
+
+/// Collects buffer and image writes for one descriptor set of `layout`.
+pub struct VRTDescriptorWriter<'a> {
+    layout: &'a VRTDescriptorSetLayout,
+    pool: &'a VRTDescriptorPool,
+    writes: Vec<(u32, DescriptorType, DescriptorInfo)>,
+}
+
+impl<'a> VRTDescriptorWriter<'a> {
+    pub fn new(layout: &'a VRTDescriptorSetLayout, pool: &'a VRTDescriptorPool) -> Self {
+        Self {
+            layout,
+            pool,
+            writes: vec![],
+        }
+    }
+
+    pub fn write_buffer(
+        &mut self,
+        binding: u32,
+        buffer: Buffer,
+        offset: DeviceSize,
+        range: DeviceSize,
+    ) -> &mut Self {
+        let info = DescriptorBufferInfoBuilder::new()
+            .buffer(buffer)
+            .offset(offset)
+            .range(range);
+        self.push(binding, DescriptorInfo::Buffer(info))
+    }
+
+    pub fn write_image(
+        &mut self,
+        binding: u32,
+        image_view: ImageView,
+        image_layout: ImageLayout,
+    ) -> &mut Self {
+        let info = DescriptorImageInfoBuilder::new()
+            .image_view(image_view)
+            .image_layout(image_layout);
+        self.push(binding, DescriptorInfo::Image(info))
+    }
+
+    pub fn write_sampler(&mut self, binding: u32, sampler: Sampler) -> &mut Self {
+        let info = DescriptorImageInfoBuilder::new().sampler(sampler);
+        self.push(binding, DescriptorInfo::Image(info))
+    }
+
+    fn push(&mut self, binding: u32, info: DescriptorInfo) -> &mut Self {
+        let descriptor_type = self
+            .layout
+            .get_descriptor_type(binding)
+            .expect("layout does not contain the specified binding");
+        self.writes.push((binding, descriptor_type, info));
+        self
+    }
+
+    pub fn build(&self) -> VkResult<DescriptorSet> {
+        let descriptor_set = self.pool.allocate_descriptor(self.layout)?;
+        self.overwrite(descriptor_set);
+        Ok(descriptor_set)
+    }
+
+    /// Must not be used on a set that command buffers still in flight are reading.
+    pub fn overwrite(&self, descriptor_set: DescriptorSet) {
+        let writes = self
+            .writes
+            .iter()
+            .map(|(binding, descriptor_type, info)| {
+                let write = WriteDescriptorSetBuilder::new()
+                    .dst_set(descriptor_set)
+                    .dst_binding(*binding)
+                    .descriptor_type(*descriptor_type);
+                match info {
+                    DescriptorInfo::Buffer(info) => write.buffer_info(std::slice::from_ref(info)),
+                    DescriptorInfo::Image(info) => write.image_info(std::slice::from_ref(info)),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        unsafe {
+            self.pool
+                .get_device()
+                .get_device_ptr()
+                .update_descriptor_sets(&writes, &[]);
+        }
+    }
+}
@@ -9,13 +9,19 @@ use erupt::vk::{
     DeviceQueueCreateInfoBuilder, Extent2D, Framebuffer, FramebufferCreateInfoBuilder, ImageView,
     InstanceCreateInfoBuilder, PhysicalDevice, PhysicalDeviceFeaturesBuilder, PresentModeKHR,
     RenderPass, SurfaceCapabilitiesKHR, SurfaceFormatKHR, SurfaceKHR, API_VERSION_1_1,
-    KHR_GET_PHYSICAL_DEVICE_PROPERTIES_2_EXTENSION_NAME, KHR_PORTABILITY_SUBSET_EXTENSION_NAME,
-    KHR_SWAPCHAIN_EXTENSION_NAME,
+    EXT_HDR_METADATA_EXTENSION_NAME, EXT_SWAPCHAIN_COLOR_SPACE_EXTENSION_NAME,
+    KHR_DRAW_INDIRECT_COUNT_EXTENSION_NAME, KHR_GET_PHYSICAL_DEVICE_PROPERTIES_2_EXTENSION_NAME,
+    KHR_PORTABILITY_SUBSET_EXTENSION_NAME, KHR_SWAPCHAIN_EXTENSION_NAME,
 };
 use erupt::vk1_0::{
-    Buffer, BufferCreateInfoBuilder, BufferUsageFlags, CommandPoolCreateFlags, DeviceMemory,
-    DeviceSize, MemoryAllocateInfoBuilder, MemoryPropertyFlags, PhysicalDeviceProperties,
-    SharingMode,
+    AccessFlags, Buffer, BufferCreateInfoBuilder, BufferImageCopyBuilder, BufferUsageFlags,
+    CommandBuffer, CommandBufferAllocateInfoBuilder, CommandBufferBeginInfoBuilder,
+    CommandBufferLevel, CommandBufferUsageFlags, CommandPoolCreateFlags, DependencyFlags,
+    DeviceMemory, DeviceSize, Extent3D, Fence, Format, FormatFeatureFlags, Image, ImageAspectFlags,
+    ImageCreateInfoBuilder, ImageLayout, ImageMemoryBarrierBuilder, ImageSubresourceLayersBuilder,
+    ImageSubresourceRangeBuilder, ImageTiling, MemoryAllocateInfoBuilder, MemoryPropertyFlags,
+    PhysicalDeviceProperties, PipelineStageFlags, SampleCountFlagBits, SharingMode,
+    SubmitInfoBuilder, QUEUE_FAMILY_IGNORED,
 };
 use erupt::SmallVec;
 use erupt::{DeviceLoader, EntryLoader, InstanceLoader};
@@ -41,35 +47,60 @@ pub struct SwapchainSupportDetails {
     present_modes: SmallVec<PresentModeKHR>,
 }
 
+/// Optional indirect drawing features, enabled whenever the GPU supports them.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct IndirectDrawFeatures {
+    /// More than one draw per `cmd_draw_indexed_indirect`.
+    pub multi_draw_indirect: bool,
+    /// Non-zero `first_instance` in indirect draw commands.
+    pub first_instance: bool,
+    /// `cmd_draw_indexed_indirect_count_khr`, reading the draw count from a buffer.
+    pub draw_count: bool,
+}
+
+/// Optional device features and extensions, enabled whenever the GPU supports them.
+#[derive(Debug, Copy, Clone, Default)]
+struct EnabledFeatures {
+    /// `VK_EXT_hdr_metadata`, describing the mastering display of HDR output.
+    hdr_metadata: bool,
+    sample_rate_shading: bool,
+    large_points: bool,
+    indirect_draw: IndirectDrawFeatures,
+}
+
 pub struct VRTDevice {
     _queues: Queues,
     queue_family_indices: CompleteQueueFamilyIndices,
     device: Arc<DeviceLoader>,
-    surface: SurfaceKHR,
     _physical_device: PhysicalDevice,
     #[cfg(debug_assertions)]
     debug_messenger: debug::Messenger,
     instance: Arc<InstanceLoader>,
     _entry: EntryLoader,
-    swapchain_support: SwapchainSupportDetails,
     command_pool: CommandPool,
+    swapchain_colorspace_enabled: bool,
+    enabled_features: EnabledFeatures,
+    properties: PhysicalDeviceProperties,
 }
 
 impl VRTDevice {
     pub fn new(window: &VRTWindow) -> VkResult<Self> {
         let entry = EntryLoader::new()?;
-        let instance = Self::create_instance(window.get_window_ptr(), &entry)?;
+        let (instance, swapchain_colorspace_enabled) =
+            Self::create_instance(window.get_window_ptr(), &entry)?;
 
         #[cfg(debug_assertions)]
         let debug_messenger = debug::Messenger::new(&instance)?;
 
+        // Surfaces belong to the windows, this one only guides the choice of GPU and queues.
         let surface = Self::create_surface(window.get_window_ptr(), &instance)?;
 
-        let (physical_device, queue_family_indices, swapchain_support) =
-            Self::pick_physical_device(&instance, surface)?;
+        let picked = Self::pick_physical_device(&instance, surface);
+        unsafe { instance.destroy_surface_khr(surface, None) };
+        let (physical_device, queue_family_indices, _) = picked?;
         println!("physical_device {:?}", &physical_device);
 
-        let (device, queues) =
+        let (device, queues, enabled_features) =
             Self::create_logical_device(&instance, physical_device, queue_family_indices)?;
 
         let command_pool = Self::create_command_pool(&queue_family_indices, &device)?;
@@ -83,14 +114,15 @@ impl VRTDevice {
             _queues: queues,
             device,
             _physical_device: physical_device,
-            surface,
             #[cfg(debug_assertions)]
             debug_messenger,
             instance,
             _entry: entry,
             queue_family_indices,
-            swapchain_support,
             command_pool,
+            swapchain_colorspace_enabled,
+            enabled_features,
+            properties,
         })
     }
 
@@ -123,6 +155,222 @@ impl VRTDevice {
         Ok((buffer, buffer_memory))
     }
 
+    pub fn create_image_with_info(
+        &self,
+        image_info: &ImageCreateInfoBuilder,
+        properties: MemoryPropertyFlags,
+    ) -> VkResult<(Image, DeviceMemory)> {
+        let image = unsafe { self.device.create_image(image_info, None) }.result()?;
+        let memory_requirements = unsafe { self.device.get_image_memory_requirements(image) };
+
+        let alloc_info = MemoryAllocateInfoBuilder::new()
+            .allocation_size(memory_requirements.size)
+            .memory_type_index(Self::find_memory_type(
+                &self.instance,
+                self._physical_device,
+                memory_requirements.memory_type_bits,
+                properties,
+            )?);
+
+        let image_memory = unsafe { self.device.allocate_memory(&alloc_info, None) }.result()?;
+        unsafe { self.device.bind_image_memory(image, image_memory, 0) }.result()?;
+
+        Ok((image, image_memory))
+    }
+
+    pub fn begin_single_time_commands(&self) -> VkResult<CommandBuffer> {
+        let alloc_info = CommandBufferAllocateInfoBuilder::new()
+            .level(CommandBufferLevel::PRIMARY)
+            .command_pool(self.command_pool)
+            .command_buffer_count(1);
+
+        let command_buffer =
+            unsafe { self.device.allocate_command_buffers(&alloc_info) }.result()?[0];
+
+        let begin_info =
+            CommandBufferBeginInfoBuilder::new().flags(CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        unsafe {
+            self.device
+                .begin_command_buffer(command_buffer, &begin_info)
+        }
+        .result()?;
+
+        Ok(command_buffer)
+    }
+
+    /// Submits the commands and blocks until the graphics queue finished them.
+    pub fn end_single_time_commands(&self, command_buffer: CommandBuffer) -> VkResult<()> {
+        unsafe { self.device.end_command_buffer(command_buffer) }.result()?;
+
+        let submit_info =
+            SubmitInfoBuilder::new().command_buffers(std::slice::from_ref(&command_buffer));
+        unsafe {
+            self.device.queue_submit(
+                self._queues.graphics,
+                std::slice::from_ref(&submit_info),
+                Fence::null(),
+            )
+        }
+        .result()?;
+        unsafe { self.device.queue_wait_idle(self._queues.graphics) }.result()?;
+
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, std::slice::from_ref(&command_buffer))
+        };
+        Ok(())
+    }
+
+    pub fn copy_buffer_to_image(
+        &self,
+        command_buffer: CommandBuffer,
+        buffer: Buffer,
+        image: Image,
+        extent: Extent3D,
+        layer_count: u32,
+    ) {
+        let region = BufferImageCopyBuilder::new()
+            .buffer_offset(0)
+            .buffer_row_length(0)
+            .buffer_image_height(0)
+            .image_subresource(
+                *ImageSubresourceLayersBuilder::new()
+                    .aspect_mask(ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .base_array_layer(0)
+                    .layer_count(layer_count),
+            )
+            .image_extent(extent);
+
+        unsafe {
+            self.device.cmd_copy_buffer_to_image(
+                command_buffer,
+                buffer,
+                image,
+                ImageLayout::TRANSFER_DST_OPTIMAL,
+                std::slice::from_ref(&region),
+            )
+        };
+    }
+
+    /// Records a barrier for the layout changes used when uploading sampled images.
+    pub fn transition_image_layout(
+        &self,
+        command_buffer: CommandBuffer,
+        image: Image,
+        subresource_range: ImageSubresourceRangeBuilder,
+        old_layout: ImageLayout,
+        new_layout: ImageLayout,
+    ) -> VkResult<()> {
+        let (src_access_mask, dst_access_mask, src_stage, dst_stage) =
+            match (old_layout, new_layout) {
+                (ImageLayout::UNDEFINED, ImageLayout::TRANSFER_DST_OPTIMAL) => (
+                    AccessFlags::empty(),
+                    AccessFlags::TRANSFER_WRITE,
+                    PipelineStageFlags::TOP_OF_PIPE,
+                    PipelineStageFlags::TRANSFER,
+                ),
+                (ImageLayout::TRANSFER_DST_OPTIMAL, ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+                    AccessFlags::TRANSFER_WRITE,
+                    AccessFlags::SHADER_READ,
+                    PipelineStageFlags::TRANSFER,
+                    PipelineStageFlags::FRAGMENT_SHADER | PipelineStageFlags::COMPUTE_SHADER,
+                ),
+                (ImageLayout::UNDEFINED, ImageLayout::GENERAL) => (
+                    AccessFlags::empty(),
+                    AccessFlags::SHADER_READ | AccessFlags::SHADER_WRITE,
+                    PipelineStageFlags::TOP_OF_PIPE,
+                    PipelineStageFlags::COMPUTE_SHADER,
+                ),
+                (ImageLayout::GENERAL, ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+                    AccessFlags::SHADER_WRITE,
+                    AccessFlags::SHADER_READ,
+                    PipelineStageFlags::COMPUTE_SHADER,
+                    PipelineStageFlags::FRAGMENT_SHADER | PipelineStageFlags::COMPUTE_SHADER,
+                ),
+                // Mip chains generated with blits and images read back to the host.
+                (ImageLayout::GENERAL, ImageLayout::TRANSFER_SRC_OPTIMAL) => (
+                    AccessFlags::SHADER_WRITE,
+                    AccessFlags::TRANSFER_READ,
+                    PipelineStageFlags::COMPUTE_SHADER,
+                    PipelineStageFlags::TRANSFER,
+                ),
+                (ImageLayout::TRANSFER_DST_OPTIMAL, ImageLayout::TRANSFER_SRC_OPTIMAL) => (
+                    AccessFlags::TRANSFER_WRITE,
+                    AccessFlags::TRANSFER_READ,
+                    PipelineStageFlags::TRANSFER,
+                    PipelineStageFlags::TRANSFER,
+                ),
+                (ImageLayout::TRANSFER_SRC_OPTIMAL, ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+                    AccessFlags::TRANSFER_READ,
+                    AccessFlags::SHADER_READ,
+                    PipelineStageFlags::TRANSFER,
+                    PipelineStageFlags::FRAGMENT_SHADER | PipelineStageFlags::COMPUTE_SHADER,
+                ),
+                (ImageLayout::SHADER_READ_ONLY_OPTIMAL, ImageLayout::TRANSFER_SRC_OPTIMAL) => (
+                    AccessFlags::SHADER_READ,
+                    AccessFlags::TRANSFER_READ,
+                    PipelineStageFlags::FRAGMENT_SHADER | PipelineStageFlags::COMPUTE_SHADER,
+                    PipelineStageFlags::TRANSFER,
+                ),
+                // Attachments that are sampled before a pass ever renders into them.
+                (ImageLayout::UNDEFINED, ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL) => (
+                    AccessFlags::empty(),
+                    AccessFlags::SHADER_READ,
+                    PipelineStageFlags::TOP_OF_PIPE,
+                    PipelineStageFlags::FRAGMENT_SHADER | PipelineStageFlags::COMPUTE_SHADER,
+                ),
+                _ => return Err(VkError::UnsupportedLayoutTransition),
+            };
+
+        let barrier = ImageMemoryBarrierBuilder::new()
+            .old_layout(old_layout)
+            .new_layout(new_layout)
+            .src_queue_family_index(QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(QUEUE_FAMILY_IGNORED)
+            .image(image)
+            .subresource_range(*subresource_range)
+            .src_access_mask(src_access_mask)
+            .dst_access_mask(dst_access_mask);
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                command_buffer,
+                src_stage,
+                dst_stage,
+                DependencyFlags::empty(),
+                &[],
+                &[],
+                std::slice::from_ref(&barrier),
+            )
+        };
+        Ok(())
+    }
+
+    pub fn find_supported_format(
+        &self,
+        candidates: &[Format],
+        tiling: ImageTiling,
+        features: FormatFeatureFlags,
+    ) -> VkResult<Format> {
+        candidates
+            .iter()
+            .copied()
+            .find(|&format| {
+                let properties = unsafe {
+                    self.instance
+                        .get_physical_device_format_properties(self._physical_device, format)
+                };
+
+                match tiling {
+                    ImageTiling::LINEAR => properties.linear_tiling_features.contains(features),
+                    ImageTiling::OPTIMAL => properties.optimal_tiling_features.contains(features),
+                    _ => false,
+                }
+            })
+            .ok_or(VkError::NoSupportedFormat)
+    }
+
     fn find_memory_type(
         instance: &InstanceLoader,
         physical_device: PhysicalDevice,
@@ -143,8 +391,8 @@ impl VRTDevice {
             .ok_or(VkError::NoSuitableMemoryType)
     }
 
-    pub fn get_swapchain_support(&self) -> VkResult<SwapchainSupportDetails> {
-        SwapchainSupportDetails::new(&self.instance, self.surface, self._physical_device)
+    pub fn get_swapchain_support(&self, surface: SurfaceKHR) -> VkResult<SwapchainSupportDetails> {
+        SwapchainSupportDetails::new(&self.instance, surface, self._physical_device)
     }
 
     pub fn get_physical_device(&self) -> PhysicalDevice {
@@ -167,15 +415,53 @@ impl VRTDevice {
         &self._queues
     }
 
-    pub fn get_surface(&self) -> SurfaceKHR {
-        self.surface
-    }
-
     pub fn get_instance(&self) -> &InstanceLoader {
         &self.instance
     }
 
-    fn create_instance(window: &Window, entry: &EntryLoader) -> VkResult<Arc<InstanceLoader>> {
+    pub fn is_swapchain_colorspace_enabled(&self) -> bool {
+        self.swapchain_colorspace_enabled
+    }
+
+    pub fn is_hdr_metadata_enabled(&self) -> bool {
+        self.enabled_features.hdr_metadata
+    }
+
+    pub fn is_sample_rate_shading_enabled(&self) -> bool {
+        self.enabled_features.sample_rate_shading
+    }
+
+    /// Whether points can be larger than a pixel, see `PhysicalDeviceLimits::point_size_range`.
+    pub fn is_large_points_enabled(&self) -> bool {
+        self.enabled_features.large_points
+    }
+
+    pub fn get_indirect_draw_features(&self) -> IndirectDrawFeatures {
+        self.enabled_features.indirect_draw
+    }
+
+    pub fn clamp_sample_count(&self, requested: SampleCountFlagBits) -> SampleCountFlagBits {
+        let supported = self.properties.limits.framebuffer_color_sample_counts
+            & self.properties.limits.framebuffer_depth_sample_counts;
+
+        [
+            SampleCountFlagBits::_64,
+            SampleCountFlagBits::_32,
+            SampleCountFlagBits::_16,
+            SampleCountFlagBits::_8,
+            SampleCountFlagBits::_4,
+            SampleCountFlagBits::_2,
+        ]
+        .into_iter()
+        .filter(|samples| samples.0 <= requested.0)
+        .find(|samples| supported.contains(samples.bitmask()))
+        .unwrap_or(SampleCountFlagBits::_1)
+    }
+
+    fn create_instance(
+        window: &Window,
+        entry: &EntryLoader,
+    ) -> VkResult<(Arc<InstanceLoader>, bool)> {
         #[cfg(debug_assertions)]
         use erupt::ExtendableFrom;
 
@@ -189,7 +475,14 @@ impl VRTDevice {
             .engine_version(make_api_version(0, 1, 0, 0))
             .api_version(API_VERSION_1_1);
 
-        let extensions = Self::required_extensions(window)?;
+        let mut extensions = Self::required_extensions(window)?;
+
+        // Wide-gamut and HDR color spaces are only exposed through this extension.
+        let swapchain_colorspace_enabled =
+            Self::is_instance_extension_supported(entry, EXT_SWAPCHAIN_COLOR_SPACE_EXTENSION_NAME)?;
+        if swapchain_colorspace_enabled {
+            extensions.push(EXT_SWAPCHAIN_COLOR_SPACE_EXTENSION_NAME);
+        }
 
         let create_info = InstanceCreateInfoBuilder::new()
             .application_info(&app_info)
@@ -202,9 +495,42 @@ impl VRTDevice {
             .enabled_layer_names(debug::VALIDATION_LAYERS)
             .extend_from(&mut debug_create_info);
 
-        Ok(Arc::new(unsafe {
-            InstanceLoader::new(entry, &create_info)
-        }?))
+        Ok((
+            Arc::new(unsafe { InstanceLoader::new(entry, &create_info) }?),
+            swapchain_colorspace_enabled,
+        ))
+    }
+
+    fn is_instance_extension_supported(
+        entry: &EntryLoader,
+        extension: *const c_char,
+    ) -> VkResult<bool> {
+        let available_extensions =
+            unsafe { entry.enumerate_instance_extension_properties(None, None) }.result()?;
+
+        Ok(check_support(
+            available_extensions
+                .iter()
+                .map(|extension| unsafe { CStr::from_ptr(extension.extension_name.as_ptr()) }),
+            [unsafe { CStr::from_ptr(extension) }],
+        ))
+    }
+
+    fn is_device_extension_supported(
+        instance: &InstanceLoader,
+        device: PhysicalDevice,
+        extension: *const c_char,
+    ) -> VkResult<bool> {
+        let available_extensions =
+            unsafe { instance.enumerate_device_extension_properties(device, None, None) }
+                .result()?;
+
+        Ok(check_support(
+            available_extensions
+                .iter()
+                .map(|extension| unsafe { CStr::from_ptr(extension.extension_name.as_ptr()) }),
+            [unsafe { CStr::from_ptr(extension) }],
+        ))
     }
 
     fn required_extensions(window: &Window) -> VkResult<Vec<*const c_char>> {
@@ -300,7 +626,7 @@ impl VRTDevice {
         instance: &InstanceLoader,
         physical_device: PhysicalDevice,
         indices: CompleteQueueFamilyIndices,
-    ) -> VkResult<(Arc<DeviceLoader>, Queues)> {
+    ) -> VkResult<(Arc<DeviceLoader>, Queues, EnabledFeatures)> {
         let unique_queue_families =
             BTreeSet::from([indices.graphics_family(), indices.present_family()]);
 
@@ -314,19 +640,51 @@ impl VRTDevice {
             })
             .collect::<Vec<_>>();
 
-        let device_features = PhysicalDeviceFeaturesBuilder::new();
+        let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
+        let mut enabled_features = EnabledFeatures {
+            hdr_metadata: false,
+            sample_rate_shading: supported_features.sample_rate_shading != 0,
+            large_points: supported_features.large_points != 0,
+            indirect_draw: IndirectDrawFeatures {
+                multi_draw_indirect: supported_features.multi_draw_indirect != 0,
+                first_instance: supported_features.draw_indirect_first_instance != 0,
+                draw_count: false,
+            },
+        };
 
-        let device_extensions: &[*const c_char];
+        let device_features = PhysicalDeviceFeaturesBuilder::new()
+            .sample_rate_shading(enabled_features.sample_rate_shading)
+            .large_points(enabled_features.large_points)
+            .multi_draw_indirect(enabled_features.indirect_draw.multi_draw_indirect)
+            .draw_indirect_first_instance(enabled_features.indirect_draw.first_instance);
+
+        let mut device_extensions = vec![DEVICE_EXTENSIONS];
         if std::env::consts::OS.contains("macos") {
-            device_extensions = &[DEVICE_EXTENSIONS, KHR_PORTABILITY_SUBSET_EXTENSION_NAME];
-        } else {
-            device_extensions = &[DEVICE_EXTENSIONS];
+            device_extensions.push(KHR_PORTABILITY_SUBSET_EXTENSION_NAME);
+        }
+
+        enabled_features.hdr_metadata = Self::is_device_extension_supported(
+            instance,
+            physical_device,
+            EXT_HDR_METADATA_EXTENSION_NAME,
+        )?;
+        if enabled_features.hdr_metadata {
+            device_extensions.push(EXT_HDR_METADATA_EXTENSION_NAME);
+        }
+
+        enabled_features.indirect_draw.draw_count = Self::is_device_extension_supported(
+            instance,
+            physical_device,
+            KHR_DRAW_INDIRECT_COUNT_EXTENSION_NAME,
+        )?;
+        if enabled_features.indirect_draw.draw_count {
+            device_extensions.push(KHR_DRAW_INDIRECT_COUNT_EXTENSION_NAME);
         }
 
         let create_info = DeviceCreateInfoBuilder::new()
             .queue_create_infos(&queue_create_infos)
             .enabled_features(&device_features)
-            .enabled_extension_names(device_extensions);
+            .enabled_extension_names(&device_extensions);
 
         #[cfg(debug_assertions)]
         let create_info = create_info.enabled_layer_names(debug::VALIDATION_LAYERS);
@@ -339,10 +697,10 @@ impl VRTDevice {
             present: unsafe { device.get_device_queue(indices.present_family(), 0) },
         };
 
-        Ok((device, queues))
+        Ok((device, queues, enabled_features))
     }
 
-    fn create_surface(window: &Window, instance: &InstanceLoader) -> VkResult<SurfaceKHR> {
+    pub fn create_surface(window: &Window, instance: &InstanceLoader) -> VkResult<SurfaceKHR> {
         Ok(unsafe { surface::create_surface(instance, window, None) }.result()?)
     }
 
@@ -365,8 +723,6 @@ impl Drop for VRTDevice {
         unsafe {
             self.device.destroy_device(None);
 
-            self.instance.destroy_surface_khr(self.surface, None);
-
             #[cfg(debug_assertions)]
             self.debug_messenger.destroy(&self.instance);
 
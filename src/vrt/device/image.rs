@@ -0,0 +1,344 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    BorderColor, BufferUsageFlags, CompareOp, ComponentMappingBuilder, ComponentSwizzle,
+    DeviceMemory, DeviceSize, Extent2D, Extent3D, Filter, Format, Image, ImageAspectFlags,
+    ImageCreateInfoBuilder, ImageLayout, ImageSubresourceRangeBuilder, ImageTiling, ImageType,
+    ImageUsageFlags, ImageView, ImageViewCreateInfoBuilder, ImageViewType, MemoryPropertyFlags,
+    SampleCountFlagBits, Sampler, SamplerAddressMode, SamplerCreateInfoBuilder, SamplerMipmapMode,
+    SharingMode, WHOLE_SIZE,
+};
+
+use super::buffer::VRTBuffer;
+use super::device::VRTDevice;
+use crate::vrt::utils::result::{VkError, VkResult};
+
+/// An image with its memory and a view covering all of its subresources.
+pub struct VRTImage {
+    device: Arc<VRTDevice>,
+    image: Image,
+    memory: DeviceMemory,
+    image_view: ImageView,
+    format: Format,
+    extent: Extent3D,
+    mip_levels: u32,
+    array_layers: u32,
+    aspect_mask: ImageAspectFlags,
+}
+
+impl VRTImage {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        image_info: &ImageCreateInfoBuilder,
+        view_type: ImageViewType,
+        aspect_mask: ImageAspectFlags,
+    ) -> VkResult<Self> {
+        let (image, memory) =
+            device.create_image_with_info(image_info, MemoryPropertyFlags::DEVICE_LOCAL)?;
+
+        let view_info = ImageViewCreateInfoBuilder::new()
+            .image(image)
+            .view_type(view_type)
+            .format(image_info.format)
+            .components(
+                *ComponentMappingBuilder::new()
+                    .r(ComponentSwizzle::IDENTITY)
+                    .g(ComponentSwizzle::IDENTITY)
+                    .b(ComponentSwizzle::IDENTITY)
+                    .a(ComponentSwizzle::IDENTITY),
+            )
+            .subresource_range(
+                *ImageSubresourceRangeBuilder::new()
+                    .aspect_mask(aspect_mask)
+                    .base_mip_level(0)
+                    .level_count(image_info.mip_levels)
+                    .base_array_layer(0)
+                    .layer_count(image_info.array_layers),
+            );
+
+        let image_view =
+            match unsafe { device.get_device_ptr().create_image_view(&view_info, None) }.result() {
+                Ok(image_view) => image_view,
+                Err(err) => {
+                    unsafe {
+                        device.get_device_ptr().destroy_image(image, None);
+                        device.get_device_ptr().free_memory(memory, None);
+                    }
+                    return Err(VkError::Vk(err));
+                }
+            };
+
+        Ok(Self {
+            device,
+            image,
+            memory,
+            image_view,
+            format: image_info.format,
+            extent: image_info.extent,
+            mip_levels: image_info.mip_levels,
+            array_layers: image_info.array_layers,
+            aspect_mask,
+        })
+    }
+
+    /// A single-sampled 2D image meant to be rendered to and read back in later passes.
+    pub fn new_attachment(
+        device: Arc<VRTDevice>,
+        extent: Extent2D,
+        format: Format,
+        usage: ImageUsageFlags,
+        aspect_mask: ImageAspectFlags,
+    ) -> VkResult<Self> {
+        let image_info = ImageCreateInfoBuilder::new()
+            .image_type(ImageType::_2D)
+            .extent(Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(1)
+            .format(format)
+            .tiling(ImageTiling::OPTIMAL)
+            .initial_layout(ImageLayout::UNDEFINED)
+            .usage(usage)
+            .samples(SampleCountFlagBits::_1)
+            .sharing_mode(SharingMode::EXCLUSIVE);
+
+        Self::new(device, &image_info, ImageViewType::_2D, aspect_mask)
+    }
+
+    /// Uploads tightly packed texels through a staging buffer, leaving the image ready for
+    /// sampling.
+    pub fn from_pixels(
+        device: Arc<VRTDevice>,
+        image_type: ImageType,
+        view_type: ImageViewType,
+        extent: Extent3D,
+        format: Format,
+        pixels: &[u8],
+    ) -> VkResult<Self> {
+        let image_info = ImageCreateInfoBuilder::new()
+            .image_type(image_type)
+            .extent(extent)
+            .mip_levels(1)
+            .array_layers(1)
+            .format(format)
+            .tiling(ImageTiling::OPTIMAL)
+            .initial_layout(ImageLayout::UNDEFINED)
+            .usage(ImageUsageFlags::TRANSFER_DST | ImageUsageFlags::SAMPLED)
+            .samples(SampleCountFlagBits::_1)
+            .sharing_mode(SharingMode::EXCLUSIVE);
+        let image = Self::new(
+            device.clone(),
+            &image_info,
+            view_type,
+            ImageAspectFlags::COLOR,
+        )?;
+
+        let staging_buffer = VRTBuffer::new(
+            device.clone(),
+            1,
+            pixels.len() as u32,
+            BufferUsageFlags::TRANSFER_SRC,
+            MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+            None,
+        );
+        let mapped = staging_buffer.map(pixels.len() as DeviceSize, 0);
+        staging_buffer.write_to_buffer(pixels.as_ptr(), mapped, WHOLE_SIZE, 0);
+        staging_buffer.unmap();
+
+        let command_buffer = device.begin_single_time_commands()?;
+        device.transition_image_layout(
+            command_buffer,
+            image.image,
+            image.subresource_range(),
+            ImageLayout::UNDEFINED,
+            ImageLayout::TRANSFER_DST_OPTIMAL,
+        )?;
+        device.copy_buffer_to_image(
+            command_buffer,
+            staging_buffer.get_buffer(),
+            image.image,
+            extent,
+            1,
+        );
+        device.transition_image_layout(
+            command_buffer,
+            image.image,
+            image.subresource_range(),
+            ImageLayout::TRANSFER_DST_OPTIMAL,
+            ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        )?;
+        device.end_single_time_commands(command_buffer)?;
+
+        Ok(image)
+    }
+
+    pub fn subresource_range(&self) -> ImageSubresourceRangeBuilder<'static> {
+        ImageSubresourceRangeBuilder::new()
+            .aspect_mask(self.aspect_mask)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1)
+    }
+
+    /// Covers every mip level and array layer.
+    pub fn full_subresource_range(&self) -> ImageSubresourceRangeBuilder<'static> {
+        self.subresource_range()
+            .level_count(self.mip_levels)
+            .layer_count(self.array_layers)
+    }
+
+    /// A 2D view of a single array layer, e.g. to render into one layer of a shadow map array.
+    /// The caller owns the view and destroys it before the image.
+    pub fn create_layer_view(&self, layer: u32) -> VkResult<ImageView> {
+        self.create_view(ImageViewType::_2D, 0, layer, 1)
+    }
+
+    /// A view of one mip level over `layer_count` layers, e.g. a cubemap level as a 2D array
+    /// for storage writes. The caller owns the view and destroys it before the image.
+    pub fn create_view(
+        &self,
+        view_type: ImageViewType,
+        mip_level: u32,
+        base_layer: u32,
+        layer_count: u32,
+    ) -> VkResult<ImageView> {
+        let view_info = ImageViewCreateInfoBuilder::new()
+            .image(self.image)
+            .view_type(view_type)
+            .format(self.format)
+            .subresource_range(
+                *self
+                    .subresource_range()
+                    .base_mip_level(mip_level)
+                    .base_array_layer(base_layer)
+                    .layer_count(layer_count),
+            );
+
+        Ok(unsafe {
+            self.device
+                .get_device_ptr()
+                .create_image_view(&view_info, None)
+        }
+        .result()?)
+    }
+
+    pub fn get_image(&self) -> Image {
+        self.image
+    }
+
+    pub fn get_image_view(&self) -> ImageView {
+        self.image_view
+    }
+
+    pub fn get_extent(&self) -> Extent3D {
+        self.extent
+    }
+
+    pub fn get_mip_levels(&self) -> u32 {
+        self.mip_levels
+    }
+
+    pub fn get_array_layers(&self) -> u32 {
+        self.array_layers
+    }
+}
+
+impl Drop for VRTImage {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_image_view(self.image_view, None);
+            self.device.get_device_ptr().destroy_image(self.image, None);
+            self.device.get_device_ptr().free_memory(self.memory, None);
+        }
+    }
+}
+
+pub struct VRTSampler {
+    device: Arc<VRTDevice>,
+    sampler: Sampler,
+}
+
+impl VRTSampler {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        filter: Filter,
+        address_mode: SamplerAddressMode,
+    ) -> VkResult<Self> {
+        Self::new_mipmapped(device, filter, address_mode, 0.0)
+    }
+
+    /// Filters between mip levels up to `max_lod`, e.g. for prefiltered environment maps.
+    pub fn new_mipmapped(
+        device: Arc<VRTDevice>,
+        filter: Filter,
+        address_mode: SamplerAddressMode,
+        max_lod: f32,
+    ) -> VkResult<Self> {
+        let sampler_info = SamplerCreateInfoBuilder::new()
+            .mag_filter(filter)
+            .min_filter(filter)
+            .address_mode_u(address_mode)
+            .address_mode_v(address_mode)
+            .address_mode_w(address_mode)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(BorderColor::FLOAT_OPAQUE_WHITE)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(CompareOp::ALWAYS)
+            .mipmap_mode(SamplerMipmapMode::LINEAR)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(max_lod);
+
+        let sampler =
+            unsafe { device.get_device_ptr().create_sampler(&sampler_info, None) }.result()?;
+
+        Ok(Self { device, sampler })
+    }
+
+    /// Depth comparison sampler for `samplerShadow`, texels outside the map count as lit.
+    pub fn new_shadow(device: Arc<VRTDevice>) -> VkResult<Self> {
+        let sampler_info = SamplerCreateInfoBuilder::new()
+            .mag_filter(Filter::LINEAR)
+            .min_filter(Filter::LINEAR)
+            .address_mode_u(SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_v(SamplerAddressMode::CLAMP_TO_BORDER)
+            .address_mode_w(SamplerAddressMode::CLAMP_TO_BORDER)
+            .anisotropy_enable(false)
+            .max_anisotropy(1.0)
+            .border_color(BorderColor::FLOAT_OPAQUE_WHITE)
+            .unnormalized_coordinates(false)
+            .compare_enable(true)
+            .compare_op(CompareOp::LESS_OR_EQUAL)
+            .mipmap_mode(SamplerMipmapMode::NEAREST)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(0.0);
+
+        let sampler =
+            unsafe { device.get_device_ptr().create_sampler(&sampler_info, None) }.result()?;
+
+        Ok(Self { device, sampler })
+    }
+
+    pub fn get_sampler(&self) -> Sampler {
+        self.sampler
+    }
+}
+
+impl Drop for VRTSampler {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_sampler(self.sampler, None);
+        }
+    }
+}
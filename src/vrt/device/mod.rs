@@ -1,6 +1,8 @@
 pub mod buffer;
 pub mod descriptors;
 pub mod device;
+pub mod image;
 pub mod queue;
+pub mod surface;
 pub mod swapchain;
 pub mod sync;
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use erupt::vk::SurfaceKHR;
+
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::utils::result::{VkError, VkResult};
+use crate::vrt::window::VRTWindow;
+
+pub struct VRTSurface {
+    device: Arc<VRTDevice>,
+    surface: SurfaceKHR,
+}
+
+impl VRTSurface {
+    pub fn new(device: Arc<VRTDevice>, window: &VRTWindow) -> VkResult<Self> {
+        let surface = VRTDevice::create_surface(window.get_window_ptr(), device.get_instance())?;
+
+        let supported = unsafe {
+            device
+                .get_instance()
+                .get_physical_device_surface_support_khr(
+                    device.get_physical_device(),
+                    device.get_queue_family_indices().present_family(),
+                    surface,
+                )
+        }
+        .result();
+
+        if supported != Ok(true) {
+            unsafe { device.get_instance().destroy_surface_khr(surface, None) };
+            return Err(supported
+                .err()
+                .map_or(VkError::UnsupportedSurface, VkError::Vk));
+        }
+
+        Ok(Self { device, surface })
+    }
+
+    pub fn get_surface_khr(&self) -> SurfaceKHR {
+        self.surface
+    }
+}
+
+impl Drop for VRTSurface {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_instance()
+                .destroy_surface_khr(self.surface, None);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::surface::VRTSurface;
 use crate::vrt::device::sync::SyncObjects;
 use crate::vrt::utils::result::{VkError, VkResult};
 use erupt::vk::CommandBuffer;
@@ -7,67 +8,307 @@ use erupt::vk::PresentInfoKHRBuilder;
 use erupt::vk::RenderPass;
 use erupt::vk::SwapchainCreateInfoKHRBuilder;
 use erupt::vk::{
-    AttachmentDescriptionBuilder, AttachmentLoadOp, AttachmentReferenceBuilder, AttachmentStoreOp,
-    ColorSpaceKHR, ComponentMappingBuilder, ComponentSwizzle, CompositeAlphaFlagBitsKHR, Extent2D,
-    Extent2DBuilder, FenceCreateFlags, FenceCreateInfoBuilder, Format,
-    FramebufferCreateInfoBuilder, Image, ImageAspectFlags, ImageLayout,
-    ImageSubresourceRangeBuilder, ImageUsageFlags, ImageView, ImageViewCreateInfoBuilder,
-    ImageViewType, PhysicalDevice, PipelineBindPoint, PipelineStageFlags, PresentModeKHR,
-    RenderPassCreateInfoBuilder, SampleCountFlagBits, SemaphoreCreateInfoBuilder, SharingMode,
-    SubmitInfoBuilder, SubpassDescriptionBuilder, SurfaceCapabilitiesKHR, SurfaceFormatKHR,
-    SurfaceKHR, SwapchainKHR,
+    AccessFlags, AttachmentDescriptionBuilder, AttachmentLoadOp, AttachmentReferenceBuilder,
+    AttachmentStoreOp, ColorSpaceKHR, ComponentMappingBuilder, ComponentSwizzle,
+    CompositeAlphaFlagBitsKHR, DeviceMemory, Extent2D, Extent2DBuilder, Extent3D, FenceCreateFlags,
+    FenceCreateInfoBuilder, Format, FormatFeatureFlags, FramebufferCreateInfoBuilder,
+    HdrMetadataEXT, Image, ImageAspectFlags, ImageCreateInfoBuilder, ImageLayout,
+    ImageSubresourceRangeBuilder, ImageTiling, ImageType, ImageUsageFlags, ImageView,
+    ImageViewCreateInfoBuilder, ImageViewType, MemoryPropertyFlags, PipelineBindPoint,
+    PipelineStageFlags, PresentModeKHR, RenderPassCreateInfoBuilder, SampleCountFlagBits,
+    SemaphoreCreateInfoBuilder, SharingMode, SubmitInfoBuilder, SubpassDependencyBuilder,
+    SubpassDescriptionBuilder, SurfaceCapabilitiesKHR, SurfaceFormatKHR, SwapchainKHR,
+    XYColorEXTBuilder, SUBPASS_EXTERNAL,
 };
 use erupt::DeviceLoader;
-use erupt::{InstanceLoader, SmallVec};
+use erupt::SmallVec;
 use std::sync::Arc;
 
-pub const MAX_FRAMES_IN_FLIGHT: usize = 2;
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 2;
+pub const MAX_FRAMES_IN_FLIGHT: usize = 4;
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+#[repr(u32)]
+pub enum OutputTransferFunction {
+    /// `*_SRGB` image format, the attachment encodes on write.
+    SrgbHardware = 0,
+    /// UNORM image in the sRGB color space, shaders must apply the sRGB curve.
+    Srgb = 1,
+    /// Extended linear sRGB (scRGB), shaders write linear values.
+    Linear = 2,
+    /// HDR10 / SMPTE ST 2084, shaders must apply the PQ curve.
+    Pq = 3,
+}
+
+#[derive(Debug, Clone)]
+pub struct SwapchainConfig {
+    pub preferred_surface_formats: Vec<SurfaceFormatKHR>,
+    pub hdr_metadata: Option<HdrMetadataEXT>,
+    pub stencil: bool,
+    pub msaa_samples: SampleCountFlagBits,
+    pub frames_in_flight: usize,
+    /// Requested number of swapchain images, e.g. 2 for double or 3 for triple buffering.
+    /// `None` asks for one more than the surface minimum.
+    pub image_count: Option<u32>,
+    /// Passes that render into the swapchain framebuffers, the first is the main pass.
+    pub render_passes: Vec<RenderPassDescription>,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct AttachmentOps {
+    pub load_op: AttachmentLoadOp,
+    pub store_op: AttachmentStoreOp,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct RenderPassDescription {
+    pub color: AttachmentOps,
+    pub depth: AttachmentOps,
+    pub stencil: AttachmentOps,
+}
 
 #[derive(Clone, Debug)]
 pub struct Swapchain {
     image_views: Vec<ImageView>,
     extent: Extent2D,
     image_format: Format,
+    color_space: ColorSpaceKHR,
     images: SmallVec<Image>,
+    depth_format: Format,
+    depth_images: Vec<Image>,
+    depth_image_memories: Vec<DeviceMemory>,
+    depth_image_views: Vec<ImageView>,
+    msaa_samples: SampleCountFlagBits,
+    color_images: Vec<Image>,
+    color_image_memories: Vec<DeviceMemory>,
+    color_image_views: Vec<ImageView>,
     swapchain: SwapchainKHR,
-    render_pass: RenderPass,
+    render_passes: Vec<RenderPass>,
     framebuffers: Vec<Framebuffer>,
-    sync: SyncObjects<MAX_FRAMES_IN_FLIGHT>,
+    sync: SyncObjects,
     device: Arc<DeviceLoader>,
 }
 
-#[derive(Debug, Clone)]
-pub struct SwapchainSupportDetails {
-    capabilities: SurfaceCapabilitiesKHR,
-    formats: SmallVec<SurfaceFormatKHR>,
-    present_modes: SmallVec<PresentModeKHR>,
+impl OutputTransferFunction {
+    pub fn from_surface_format(surface_format: SurfaceFormatKHR) -> Self {
+        match surface_format.color_space {
+            ColorSpaceKHR::HDR10_ST2084_EXT => Self::Pq,
+            ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT => Self::Linear,
+            _ => match surface_format.format {
+                Format::B8G8R8A8_SRGB | Format::R8G8B8A8_SRGB | Format::A8B8G8R8_SRGB_PACK32 => {
+                    Self::SrgbHardware
+                }
+                _ => Self::Srgb,
+            },
+        }
+    }
+}
+
+impl AttachmentOps {
+    pub const fn new(load_op: AttachmentLoadOp, store_op: AttachmentStoreOp) -> Self {
+        Self { load_op, store_op }
+    }
+}
+
+impl RenderPassDescription {
+    /// Starts from cleared attachments, the usual first pass of a frame.
+    pub const fn clear() -> Self {
+        Self {
+            color: AttachmentOps::new(AttachmentLoadOp::CLEAR, AttachmentStoreOp::STORE),
+            depth: AttachmentOps::new(AttachmentLoadOp::CLEAR, AttachmentStoreOp::DONT_CARE),
+            stencil: AttachmentOps::new(AttachmentLoadOp::CLEAR, AttachmentStoreOp::DONT_CARE),
+        }
+    }
+
+    /// Keeps the color of earlier passes, e.g. for overlays drawn on top of the scene.
+    pub const fn overlay() -> Self {
+        Self {
+            color: AttachmentOps::new(AttachmentLoadOp::LOAD, AttachmentStoreOp::STORE),
+            depth: AttachmentOps::new(AttachmentLoadOp::CLEAR, AttachmentStoreOp::DONT_CARE),
+            stencil: AttachmentOps::new(AttachmentLoadOp::CLEAR, AttachmentStoreOp::DONT_CARE),
+        }
+    }
+}
+
+impl Default for RenderPassDescription {
+    fn default() -> Self {
+        Self::clear()
+    }
+}
+
+impl SwapchainConfig {
+    pub fn hdr() -> Self {
+        let surface_format = |format, color_space| SurfaceFormatKHR {
+            format,
+            color_space,
+        };
+
+        // Rec. 2020 primaries with a D65 white point, the usual HDR10 mastering display.
+        let hdr_metadata = HdrMetadataEXT {
+            display_primary_red: *XYColorEXTBuilder::new().x(0.708).y(0.292),
+            display_primary_green: *XYColorEXTBuilder::new().x(0.170).y(0.797),
+            display_primary_blue: *XYColorEXTBuilder::new().x(0.131).y(0.046),
+            white_point: *XYColorEXTBuilder::new().x(0.3127).y(0.3290),
+            max_luminance: 1000.0,
+            min_luminance: 0.001,
+            max_content_light_level: 1000.0,
+            max_frame_average_light_level: 400.0,
+            ..Default::default()
+        };
+
+        Self {
+            preferred_surface_formats: vec![
+                surface_format(
+                    Format::A2B10G10R10_UNORM_PACK32,
+                    ColorSpaceKHR::HDR10_ST2084_EXT,
+                ),
+                surface_format(
+                    Format::R16G16B16A16_SFLOAT,
+                    ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+                ),
+                surface_format(
+                    Format::A2B10G10R10_UNORM_PACK32,
+                    ColorSpaceKHR::SRGB_NONLINEAR_KHR,
+                ),
+                surface_format(Format::B8G8R8A8_SRGB, ColorSpaceKHR::SRGB_NONLINEAR_KHR),
+            ],
+            hdr_metadata: Some(hdr_metadata),
+            stencil: false,
+            msaa_samples: SampleCountFlagBits::_1,
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            image_count: None,
+            render_passes: vec![RenderPassDescription::clear()],
+        }
+    }
+}
+
+impl Default for SwapchainConfig {
+    fn default() -> Self {
+        Self {
+            preferred_surface_formats: vec![SurfaceFormatKHR {
+                format: Format::B8G8R8A8_SRGB,
+                color_space: ColorSpaceKHR::SRGB_NONLINEAR_KHR,
+            }],
+            hdr_metadata: None,
+            stencil: false,
+            msaa_samples: SampleCountFlagBits::_1,
+            frames_in_flight: DEFAULT_FRAMES_IN_FLIGHT,
+            image_count: None,
+            render_passes: vec![RenderPassDescription::clear()],
+        }
+    }
 }
 
 impl Swapchain {
     pub fn new(
         device: &VRTDevice,
+        surface: &VRTSurface,
         extent: Extent2D,
         old_swapchain: std::option::Option<SwapchainKHR>,
+        config: &SwapchainConfig,
     ) -> VkResult<Self> {
-        let (extent, image_format, images, swapchain) =
-            Self::create_swapchain(extent, device, old_swapchain)?;
+        // Framebuffers are built against the main pass.
+        if config.render_passes.is_empty() {
+            return Err(VkError::NoRenderPass);
+        }
 
-        let image_views = Self::create_image_views(device, &images, image_format)?;
+        let (extent, surface_format, images, swapchain) =
+            Self::create_swapchain(extent, device, surface, old_swapchain, config)?;
+        let image_format = surface_format.format;
 
-        let render_pass = Self::create_render_pass(&device.get_device_ptr(), image_format)?;
+        let image_views = Self::create_image_views(device, &images, image_format)?;
 
-        let framebuffers = Self::create_framebuffers(device, &image_views, &extent, render_pass)?;
+        let msaa_samples = device.clamp_sample_count(config.msaa_samples);
 
-        let sync = Self::create_sync_objects(device, &images)?;
+        let depth_format = Self::find_depth_format(device, config.stencil)?;
+        let depth_aspect_mask = if Self::has_stencil_component(depth_format) {
+            ImageAspectFlags::DEPTH | ImageAspectFlags::STENCIL
+        } else {
+            ImageAspectFlags::DEPTH
+        };
+        let (depth_images, depth_image_memories, depth_image_views) =
+            Self::create_attachment_images(
+                device,
+                images.len(),
+                depth_format,
+                extent,
+                ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+                depth_aspect_mask,
+                msaa_samples,
+            )?;
+
+        // Samples only live for the frame unless a later pass loads them, e.g. an overlay.
+        let color_loaded = config
+            .render_passes
+            .iter()
+            .any(|description| description.color.load_op == AttachmentLoadOp::LOAD);
+        let color_usage = if color_loaded {
+            ImageUsageFlags::COLOR_ATTACHMENT
+        } else {
+            ImageUsageFlags::TRANSIENT_ATTACHMENT | ImageUsageFlags::COLOR_ATTACHMENT
+        };
+        let (color_images, color_image_memories, color_image_views) =
+            if msaa_samples == SampleCountFlagBits::_1 {
+                (vec![], vec![], vec![])
+            } else {
+                Self::create_attachment_images(
+                    device,
+                    images.len(),
+                    image_format,
+                    extent,
+                    color_usage,
+                    ImageAspectFlags::COLOR,
+                    msaa_samples,
+                )?
+            };
+
+        let render_passes = config
+            .render_passes
+            .iter()
+            .map(|description| {
+                Self::create_render_pass(
+                    &device.get_device_ptr(),
+                    image_format,
+                    depth_format,
+                    msaa_samples,
+                    description,
+                )
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+
+        // Passes only differ in load and store ops, so they share compatible framebuffers.
+        let framebuffers = Self::create_framebuffers(
+            device,
+            &image_views,
+            &depth_image_views,
+            &color_image_views,
+            &extent,
+            render_passes[0],
+        )?;
+
+        let sync = Self::create_sync_objects(
+            device,
+            &images,
+            config.frames_in_flight.clamp(1, MAX_FRAMES_IN_FLIGHT),
+        )?;
 
         Ok(Self {
             swapchain,
             images,
+            depth_format,
+            depth_images,
+            depth_image_memories,
+            depth_image_views,
+            msaa_samples,
+            color_images,
+            color_image_memories,
+            color_image_views,
             extent,
             image_format,
+            color_space: surface_format.color_space,
             sync,
             framebuffers,
-            render_pass,
+            render_passes,
             image_views,
             device: device.get_device_ptr(),
         })
@@ -95,13 +336,22 @@ impl Swapchain {
         .result()
     }
 
+    pub fn wait_for_frames_in_flight(&self) -> VkResult<()> {
+        unsafe {
+            self.device
+                .wait_for_fences(&self.sync.in_flight_fences, true, u64::MAX)
+        }
+        .result()?;
+        Ok(())
+    }
+
     pub fn submit_command_buffer(
         &mut self,
         device: &VRTDevice,
         command_buffers: &CommandBuffer,
         image_index: &u32,
-    ) -> VkResult<erupt::utils::VulkanResult<()>> {
-        if self.sync.images_in_flight[*image_index as usize] != None {
+    ) -> VkResult<()> {
+        if self.sync.images_in_flight[*image_index as usize].is_some() {
             unsafe {
                 self.device.wait_for_fences(
                     std::slice::from_ref(
@@ -148,6 +398,14 @@ impl Swapchain {
         .result()
         .unwrap();
 
+        Ok(())
+    }
+
+    pub fn present(
+        &mut self,
+        device: &VRTDevice,
+        image_index: &u32,
+    ) -> erupt::utils::VulkanResult<()> {
         let present_info = PresentInfoKHRBuilder::new()
             .wait_semaphores(std::slice::from_ref(
                 &self.sync.render_finished_semaphores[self.sync.current_frame],
@@ -160,23 +418,32 @@ impl Swapchain {
                 .queue_present_khr(device.get_queues().present, &present_info)
         };
 
-        self.sync.current_frame = (self.sync.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        self.sync.current_frame = (self.sync.current_frame + 1) % self.sync.frames_in_flight();
 
-        Ok(result)
+        result
     }
 
     fn create_swapchain(
         extent: Extent2D,
         device: &VRTDevice,
+        surface: &VRTSurface,
         old_swapchain: std::option::Option<SwapchainKHR>,
-    ) -> VkResult<(Extent2D, Format, SmallVec<Image>, SwapchainKHR)> {
-        let swapchain_support = device.get_swapchain_support()?;
-
-        let surface_format = Self::choose_swap_surface_format(swapchain_support.formats());
+        config: &SwapchainConfig,
+    ) -> VkResult<(Extent2D, SurfaceFormatKHR, SmallVec<Image>, SwapchainKHR)> {
+        let swapchain_support = device.get_swapchain_support(surface.get_surface_khr())?;
+
+        let surface_format = Self::choose_swap_surface_format(
+            swapchain_support.formats(),
+            &config.preferred_surface_formats,
+            device.is_swapchain_colorspace_enabled(),
+        );
         let present_mode = Self::choose_swap_present_mode(swapchain_support.present_modes());
         let extent = Self::choose_swap_extent(extent, swapchain_support.capabilities());
 
-        let mut image_count = swapchain_support.capabilities().min_image_count + 1;
+        let mut image_count = config
+            .image_count
+            .unwrap_or(swapchain_support.capabilities().min_image_count + 1)
+            .max(swapchain_support.capabilities().min_image_count);
         if swapchain_support.capabilities().max_image_count > 0
             && image_count > swapchain_support.capabilities().max_image_count
         {
@@ -199,7 +466,7 @@ impl Swapchain {
         };
 
         let create_info = SwapchainCreateInfoKHRBuilder::new()
-            .surface(device.get_surface())
+            .surface(surface.get_surface_khr())
             .min_image_count(image_count)
             .image_format(surface_format.format)
             .image_color_space(surface_format.color_space)
@@ -227,9 +494,21 @@ impl Swapchain {
                 .get_swapchain_images_khr(swapchain, None)
         }
         .result()?;
-        let image_format = surface_format.format;
 
-        Ok((extent, image_format, images, swapchain))
+        if let Some(hdr_metadata) = config.hdr_metadata {
+            if device.is_hdr_metadata_enabled()
+                && surface_format.color_space != ColorSpaceKHR::SRGB_NONLINEAR_KHR
+            {
+                unsafe {
+                    device.get_device_ptr().set_hdr_metadata_ext(
+                        std::slice::from_ref(&swapchain),
+                        std::slice::from_ref(&hdr_metadata.into_builder()),
+                    )
+                };
+            }
+        }
+
+        Ok((extent, surface_format, images, swapchain))
     }
 
     fn create_image_views(
@@ -240,73 +519,249 @@ impl Swapchain {
         images
             .iter()
             .map(|image| {
-                let create_info = ImageViewCreateInfoBuilder::new()
-                    .image(*image)
-                    .view_type(ImageViewType::_2D)
-                    .format(image_format)
-                    .components(
-                        *ComponentMappingBuilder::new()
-                            .r(ComponentSwizzle::IDENTITY)
-                            .g(ComponentSwizzle::IDENTITY)
-                            .b(ComponentSwizzle::IDENTITY)
-                            .a(ComponentSwizzle::IDENTITY),
-                    )
-                    .subresource_range(
-                        *ImageSubresourceRangeBuilder::new()
-                            .aspect_mask(ImageAspectFlags::COLOR)
-                            .base_mip_level(0)
-                            .level_count(1)
-                            .base_array_layer(0)
-                            .layer_count(1),
-                    );
-                unsafe {
-                    device
-                        .get_device_ptr()
-                        .create_image_view(&create_info, None)
-                }
-                .map_err(VkError::Vk)
+                Self::create_image_view(device, *image, image_format, ImageAspectFlags::COLOR)
             })
             .collect()
     }
 
-    fn create_render_pass(device: &DeviceLoader, image_format: Format) -> VkResult<RenderPass> {
+    fn create_image_view(
+        device: &VRTDevice,
+        image: Image,
+        format: Format,
+        aspect_mask: ImageAspectFlags,
+    ) -> VkResult<ImageView> {
+        let create_info = ImageViewCreateInfoBuilder::new()
+            .image(image)
+            .view_type(ImageViewType::_2D)
+            .format(format)
+            .components(
+                *ComponentMappingBuilder::new()
+                    .r(ComponentSwizzle::IDENTITY)
+                    .g(ComponentSwizzle::IDENTITY)
+                    .b(ComponentSwizzle::IDENTITY)
+                    .a(ComponentSwizzle::IDENTITY),
+            )
+            .subresource_range(
+                *ImageSubresourceRangeBuilder::new()
+                    .aspect_mask(aspect_mask)
+                    .base_mip_level(0)
+                    .level_count(1)
+                    .base_array_layer(0)
+                    .layer_count(1),
+            );
+        unsafe {
+            device
+                .get_device_ptr()
+                .create_image_view(&create_info, None)
+        }
+        .map_err(VkError::Vk)
+    }
+
+    fn find_depth_format(device: &VRTDevice, stencil: bool) -> VkResult<Format> {
+        let candidates: &[Format] = if stencil {
+            &[Format::D32_SFLOAT_S8_UINT, Format::D24_UNORM_S8_UINT]
+        } else {
+            &[
+                Format::D32_SFLOAT,
+                Format::D32_SFLOAT_S8_UINT,
+                Format::D24_UNORM_S8_UINT,
+            ]
+        };
+
+        device.find_supported_format(
+            candidates,
+            ImageTiling::OPTIMAL,
+            FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT,
+        )
+    }
+
+    fn has_stencil_component(format: Format) -> bool {
+        matches!(
+            format,
+            Format::D32_SFLOAT_S8_UINT | Format::D24_UNORM_S8_UINT | Format::D16_UNORM_S8_UINT
+        )
+    }
+
+    fn create_attachment_images(
+        device: &VRTDevice,
+        image_count: usize,
+        format: Format,
+        extent: Extent2D,
+        usage: ImageUsageFlags,
+        aspect_mask: ImageAspectFlags,
+        samples: SampleCountFlagBits,
+    ) -> VkResult<(Vec<Image>, Vec<DeviceMemory>, Vec<ImageView>)> {
+        let mut images = Vec::with_capacity(image_count);
+        let mut image_memories = Vec::with_capacity(image_count);
+        let mut image_views = Vec::with_capacity(image_count);
+
+        for _ in 0..image_count {
+            let image_info = ImageCreateInfoBuilder::new()
+                .image_type(ImageType::_2D)
+                .extent(Extent3D {
+                    width: extent.width,
+                    height: extent.height,
+                    depth: 1,
+                })
+                .mip_levels(1)
+                .array_layers(1)
+                .format(format)
+                .tiling(ImageTiling::OPTIMAL)
+                .initial_layout(ImageLayout::UNDEFINED)
+                .usage(usage)
+                .samples(samples)
+                .sharing_mode(SharingMode::EXCLUSIVE);
+
+            let (image, memory) =
+                device.create_image_with_info(&image_info, MemoryPropertyFlags::DEVICE_LOCAL)?;
+
+            images.push(image);
+            image_memories.push(memory);
+            image_views.push(Self::create_image_view(device, image, format, aspect_mask)?);
+        }
+
+        Ok((images, image_memories, image_views))
+    }
+
+    fn create_render_pass(
+        device: &DeviceLoader,
+        image_format: Format,
+        depth_format: Format,
+        msaa_samples: SampleCountFlagBits,
+        description: &RenderPassDescription,
+    ) -> VkResult<RenderPass> {
+        let multisampled = msaa_samples != SampleCountFlagBits::_1;
+
+        // With MSAA the swapchain image only receives the resolved result.
+        let color_final_layout = if multisampled {
+            ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+        } else {
+            ImageLayout::PRESENT_SRC_KHR
+        };
         let color_attachment = AttachmentDescriptionBuilder::new()
+            .format(image_format)
+            .samples(msaa_samples)
+            .load_op(description.color.load_op)
+            .store_op(description.color.store_op)
+            .stencil_load_op(AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(AttachmentStoreOp::DONT_CARE)
+            .initial_layout(Self::initial_layout(
+                description.color.load_op,
+                color_final_layout,
+            ))
+            .final_layout(color_final_layout);
+
+        let resolve_attachment = AttachmentDescriptionBuilder::new()
             .format(image_format)
             .samples(SampleCountFlagBits::_1)
-            .load_op(AttachmentLoadOp::CLEAR)
+            .load_op(AttachmentLoadOp::DONT_CARE)
             .store_op(AttachmentStoreOp::STORE)
             .stencil_load_op(AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(AttachmentStoreOp::DONT_CARE)
             .initial_layout(ImageLayout::UNDEFINED)
             .final_layout(ImageLayout::PRESENT_SRC_KHR);
 
+        let depth_loaded = description.depth.load_op == AttachmentLoadOp::LOAD
+            || description.stencil.load_op == AttachmentLoadOp::LOAD;
+        let depth_attachment = AttachmentDescriptionBuilder::new()
+            .format(depth_format)
+            .samples(msaa_samples)
+            .load_op(description.depth.load_op)
+            .store_op(description.depth.store_op)
+            .stencil_load_op(description.stencil.load_op)
+            .stencil_store_op(description.stencil.store_op)
+            .initial_layout(if depth_loaded {
+                ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+            } else {
+                ImageLayout::UNDEFINED
+            })
+            .final_layout(ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
         let color_attachment_ref = AttachmentReferenceBuilder::new()
             .attachment(0)
             .layout(ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
 
+        let depth_attachment_ref = AttachmentReferenceBuilder::new()
+            .attachment(1)
+            .layout(ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+        let resolve_attachment_ref = AttachmentReferenceBuilder::new()
+            .attachment(2)
+            .layout(ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
         let subpass = SubpassDescriptionBuilder::new()
             .pipeline_bind_point(PipelineBindPoint::GRAPHICS)
-            .color_attachments(std::slice::from_ref(&color_attachment_ref));
+            .color_attachments(std::slice::from_ref(&color_attachment_ref))
+            .depth_stencil_attachment(&depth_attachment_ref);
+        let subpass = if multisampled {
+            subpass.resolve_attachments(std::slice::from_ref(&resolve_attachment_ref))
+        } else {
+            subpass
+        };
 
+        let dependency = SubpassDependencyBuilder::new()
+            .src_subpass(SUBPASS_EXTERNAL)
+            .dst_subpass(0)
+            .src_stage_mask(
+                PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                    | PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            )
+            .src_access_mask(
+                AccessFlags::COLOR_ATTACHMENT_WRITE | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            )
+            .dst_stage_mask(
+                PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                    | PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            )
+            .dst_access_mask(
+                AccessFlags::COLOR_ATTACHMENT_READ
+                    | AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            );
+
+        let attachments = [color_attachment, depth_attachment, resolve_attachment];
+        let attachment_count = if multisampled { 3 } else { 2 };
         let render_pass_info = RenderPassCreateInfoBuilder::new()
-            .attachments(std::slice::from_ref(&color_attachment))
-            .subpasses(std::slice::from_ref(&subpass));
+            .attachments(&attachments[..attachment_count])
+            .subpasses(std::slice::from_ref(&subpass))
+            .dependencies(std::slice::from_ref(&dependency));
 
         Ok(unsafe { device.create_render_pass(&render_pass_info, None) }.result()?)
     }
 
+    // Loaded attachments were left in their final layout by an earlier pass of the frame.
+    fn initial_layout(load_op: AttachmentLoadOp, final_layout: ImageLayout) -> ImageLayout {
+        if load_op == AttachmentLoadOp::LOAD {
+            final_layout
+        } else {
+            ImageLayout::UNDEFINED
+        }
+    }
+
     fn create_framebuffers(
         device: &VRTDevice,
-        image_views: &Vec<ImageView>,
+        image_views: &[ImageView],
+        depth_image_views: &[ImageView],
+        color_image_views: &[ImageView],
         extent: &Extent2D,
         render_pass: RenderPass,
     ) -> VkResult<Vec<Framebuffer>> {
         image_views
             .iter()
-            .map(|image_view| {
+            .zip(depth_image_views)
+            .enumerate()
+            .map(|(i, (image_view, depth_image_view))| {
+                let attachments = match color_image_views.get(i) {
+                    Some(color_image_view) => {
+                        vec![*color_image_view, *depth_image_view, *image_view]
+                    }
+                    None => vec![*image_view, *depth_image_view],
+                };
                 let framebuffer_info = FramebufferCreateInfoBuilder::new()
                     .render_pass(render_pass)
-                    .attachments(std::slice::from_ref(image_view))
+                    .attachments(&attachments)
                     .width(extent.width)
                     .height(extent.height)
                     .layers(1);
@@ -323,18 +778,13 @@ impl Swapchain {
     fn create_sync_objects(
         device: &VRTDevice,
         images: &[Image],
-    ) -> VkResult<SyncObjects<MAX_FRAMES_IN_FLIGHT>> {
+        frames_in_flight: usize,
+    ) -> VkResult<SyncObjects> {
         fn create_objects<T>(
+            count: usize,
             create_fn: impl Fn() -> VkResult<T>,
-        ) -> VkResult<[T; MAX_FRAMES_IN_FLIGHT]>
-        where
-            T: Default + Copy,
-        {
-            let mut objects = [T::default(); MAX_FRAMES_IN_FLIGHT];
-            for object in &mut objects {
-                *object = create_fn()?;
-            }
-            Ok(objects)
+        ) -> VkResult<Vec<T>> {
+            (0..count).map(|_| create_fn()).collect()
         }
 
         let semaphore_info = SemaphoreCreateInfoBuilder::new();
@@ -353,20 +803,29 @@ impl Swapchain {
         };
 
         Ok(SyncObjects {
-            image_available_semaphores: create_objects(create_semaphore)?,
-            render_finished_semaphores: create_objects(create_semaphore)?,
-            in_flight_fences: create_objects(create_fence)?,
+            image_available_semaphores: create_objects(frames_in_flight, create_semaphore)?,
+            render_finished_semaphores: create_objects(frames_in_flight, create_semaphore)?,
+            in_flight_fences: create_objects(frames_in_flight, create_fence)?,
             images_in_flight: vec![None; images.len()],
             current_frame: 0,
         })
     }
 
-    fn choose_swap_surface_format(available_formats: &[SurfaceFormatKHR]) -> SurfaceFormatKHR {
-        *available_formats
+    fn choose_swap_surface_format(
+        available_formats: &[SurfaceFormatKHR],
+        preferred_formats: &[SurfaceFormatKHR],
+        swapchain_colorspace_enabled: bool,
+    ) -> SurfaceFormatKHR {
+        *preferred_formats
             .iter()
-            .find(|format| {
-                format.format == Format::B8G8R8A8_SRGB
-                    && format.color_space == ColorSpaceKHR::SRGB_NONLINEAR_KHR
+            .filter(|preferred| {
+                swapchain_colorspace_enabled
+                    || preferred.color_space == ColorSpaceKHR::SRGB_NONLINEAR_KHR
+            })
+            .find_map(|preferred| {
+                available_formats.iter().find(|format| {
+                    format.format == preferred.format && format.color_space == preferred.color_space
+                })
             })
             .unwrap_or(&available_formats[0])
     }
@@ -395,7 +854,11 @@ impl Swapchain {
     }
 
     pub fn get_render_pass(&self) -> RenderPass {
-        self.render_pass
+        self.render_passes[0]
+    }
+
+    pub fn get_render_pass_at(&self, index: usize) -> Option<RenderPass> {
+        self.render_passes.get(index).copied()
     }
 
     pub fn get_frame_buffer(&self) -> &Vec<Framebuffer> {
@@ -406,6 +869,33 @@ impl Swapchain {
         self.extent
     }
 
+    pub fn get_surface_format(&self) -> SurfaceFormatKHR {
+        SurfaceFormatKHR {
+            format: self.image_format,
+            color_space: self.color_space,
+        }
+    }
+
+    pub fn get_output_transfer_function(&self) -> OutputTransferFunction {
+        OutputTransferFunction::from_surface_format(self.get_surface_format())
+    }
+
+    pub fn get_depth_format(&self) -> Format {
+        self.depth_format
+    }
+
+    pub fn get_msaa_samples(&self) -> SampleCountFlagBits {
+        self.msaa_samples
+    }
+
+    pub fn get_frames_in_flight(&self) -> usize {
+        self.sync.frames_in_flight()
+    }
+
+    pub fn get_image_count(&self) -> usize {
+        self.images.len()
+    }
+
     pub fn get_swapchain_khr(&self) -> SwapchainKHR {
         self.swapchain
     }
@@ -443,15 +933,33 @@ impl Drop for Swapchain {
                 self.device.destroy_image_view(*image_view, None);
             }
 
+            for ((image_view, image), memory) in self
+                .depth_image_views
+                .iter()
+                .chain(&self.color_image_views)
+                .zip(self.depth_images.iter().chain(&self.color_images))
+                .zip(
+                    self.depth_image_memories
+                        .iter()
+                        .chain(&self.color_image_memories),
+                )
+            {
+                self.device.destroy_image_view(*image_view, None);
+                self.device.destroy_image(*image, None);
+                self.device.free_memory(*memory, None);
+            }
+
             self.device.destroy_swapchain_khr(self.swapchain, None);
 
             for framebuffer in &self.framebuffers {
                 self.device.destroy_framebuffer(*framebuffer, None);
             }
 
-            self.device.destroy_render_pass(self.render_pass, None);
+            for render_pass in &self.render_passes {
+                self.device.destroy_render_pass(*render_pass, None);
+            }
 
-            for i in 0..MAX_FRAMES_IN_FLIGHT {
+            for i in 0..self.sync.frames_in_flight() {
                 self.device
                     .destroy_fence(self.sync.in_flight_fences[i], None);
                 self.device
@@ -462,46 +970,3 @@ impl Drop for Swapchain {
         }
     }
 }
-
-impl SwapchainSupportDetails {
-    pub fn new(
-        instance: &InstanceLoader,
-        surface: SurfaceKHR,
-        device: PhysicalDevice,
-    ) -> VkResult<Self> {
-        let capabilities =
-            unsafe { instance.get_physical_device_surface_capabilities_khr(device, surface) }
-                .result()?;
-
-        let formats =
-            unsafe { instance.get_physical_device_surface_formats_khr(device, surface, None) }
-                .result()?;
-
-        let present_modes = unsafe {
-            instance.get_physical_device_surface_present_modes_khr(device, surface, None)
-        }
-        .result()?;
-
-        Ok(Self {
-            capabilities,
-            formats,
-            present_modes,
-        })
-    }
-
-    pub fn is_adequate(&self) -> bool {
-        !self.formats.is_empty() && !self.present_modes.is_empty()
-    }
-
-    pub fn capabilities(&self) -> &SurfaceCapabilitiesKHR {
-        &self.capabilities
-    }
-
-    pub fn formats(&self) -> &[SurfaceFormatKHR] {
-        &self.formats
-    }
-
-    pub fn present_modes(&self) -> &[PresentModeKHR] {
-        &self.present_modes
-    }
-}
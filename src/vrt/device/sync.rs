@@ -1,10 +1,16 @@
 use erupt::vk::{Fence, Semaphore};
 
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
-pub struct SyncObjects<const N: usize> {
+pub struct SyncObjects {
     pub current_frame: usize,
     pub images_in_flight: Vec<Option<Fence>>,
-    pub in_flight_fences: [Fence; N],
-    pub render_finished_semaphores: [Semaphore; N],
-    pub image_available_semaphores: [Semaphore; N],
+    pub in_flight_fences: Vec<Fence>,
+    pub render_finished_semaphores: Vec<Semaphore>,
+    pub image_available_semaphores: Vec<Semaphore>,
+}
+
+impl SyncObjects {
+    pub fn frames_in_flight(&self) -> usize {
+        self.in_flight_fences.len()
+    }
 }
@@ -0,0 +1,279 @@
+use glam::{Mat4, Vec3, Vec4};
+
+/// Axis aligned bounding box.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn new(min: Vec3, max: Vec3) -> Self {
+        Self { min, max }
+    }
+
+    /// Returns `None` without points.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(Self::new(first, first), |aabb, point| Self {
+            min: aabb.min.min(point),
+            max: aabb.max.max(point),
+        }))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn half_extents(&self) -> Vec3 {
+        (self.max - self.min) * 0.5
+    }
+
+    /// The box around the transformed box, larger than a tight fit under rotations.
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        let center = transform.transform_point3(self.center());
+        let half_extents = self.half_extents();
+        let extent = transform.x_axis.truncate().abs() * half_extents.x
+            + transform.y_axis.truncate().abs() * half_extents.y
+            + transform.z_axis.truncate().abs() * half_extents.z;
+        Self::new(center - extent, center + extent)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Vec3, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    /// Centered on the box of the points, not the smallest sphere but close for most meshes.
+    pub fn from_points(points: impl IntoIterator<Item = Vec3> + Clone) -> Option<Self> {
+        let center = Aabb::from_points(points.clone())?.center();
+        let radius = points
+            .into_iter()
+            .map(|point| point.distance(center))
+            .fold(0.0, f32::max);
+        Some(Self::new(center, radius))
+    }
+
+    /// Scales the radius by the largest axis scale of `transform`.
+    pub fn transformed(&self, transform: Mat4) -> Self {
+        let scale = transform
+            .x_axis
+            .truncate()
+            .length()
+            .max(transform.y_axis.truncate().length())
+            .max(transform.z_axis.truncate().length());
+        Self::new(transform.transform_point3(self.center), self.radius * scale)
+    }
+}
+
+/// The six planes of a view projection with 0..1 depth, normals pointing inwards.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Frustum {
+    planes: [Vec4; 6],
+}
+
+impl Frustum {
+    pub fn from_view_projection(view_projection: Mat4) -> Self {
+        let rows = [
+            view_projection.row(0),
+            view_projection.row(1),
+            view_projection.row(2),
+            view_projection.row(3),
+        ];
+        let planes = [
+            rows[3] + rows[0],
+            rows[3] - rows[0],
+            rows[3] + rows[1],
+            rows[3] - rows[1],
+            rows[2],
+            rows[3] - rows[2],
+        ]
+        .map(|plane| plane / plane.truncate().length().max(f32::EPSILON));
+        Self { planes }
+    }
+
+    pub fn planes(&self) -> &[Vec4; 6] {
+        &self.planes
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.truncate().dot(sphere.center) + plane.w >= -sphere.radius)
+    }
+
+    /// Conservative, boxes outside near a frustum corner may still pass.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        let center = aabb.center();
+        let half_extents = aabb.half_extents();
+        self.planes.iter().all(|plane| {
+            let normal = plane.truncate();
+            let radius = normal.abs().dot(half_extents);
+            normal.dot(center) + plane.w >= -radius
+        })
+    }
+}
+
+/// Objects a culling pass let through and rejected.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct CullingStats {
+    pub visible: u32,
+    pub culled: u32,
+}
+
+impl CullingStats {
+    pub fn record(&mut self, visible: bool) {
+        if visible {
+            self.visible += 1;
+        } else {
+            self.culled += 1;
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.visible + self.culled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Quat;
+
+    /// Looks down -Z from the origin, with a 90 degree field of view and depth from 0.1 to 10.
+    fn frustum() -> Frustum {
+        Frustum::from_view_projection(Mat4::perspective_rh(
+            std::f32::consts::FRAC_PI_2,
+            1.0,
+            0.1,
+            10.0,
+        ))
+    }
+
+    #[test]
+    fn aabb_from_points() {
+        let aabb = Aabb::from_points([
+            Vec3::new(1.0, -2.0, 0.0),
+            Vec3::new(-1.0, 3.0, 2.0),
+            Vec3::new(0.0, 0.0, -4.0),
+        ])
+        .unwrap();
+
+        assert_eq!(aabb.min, Vec3::new(-1.0, -2.0, -4.0));
+        assert_eq!(aabb.max, Vec3::new(1.0, 3.0, 2.0));
+        assert_eq!(aabb.center(), Vec3::new(0.0, 0.5, -1.0));
+        assert!(Aabb::from_points([]).is_none());
+    }
+
+    #[test]
+    fn transformed_aabb_contains_the_rotated_box() {
+        let aabb = Aabb::new(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let transform = Mat4::from_translation(Vec3::new(5.0, 0.0, 0.0))
+            * Mat4::from_rotation_z(std::f32::consts::FRAC_PI_4);
+        let transformed = aabb.transformed(transform);
+
+        let half_diagonal = std::f32::consts::SQRT_2;
+        assert!((transformed.center() - Vec3::new(5.0, 0.0, 0.0)).length() < 1e-5);
+        assert!(
+            (transformed.half_extents() - Vec3::new(half_diagonal, half_diagonal, 1.0))
+                .abs()
+                .max_element()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn sphere_from_points_reaches_the_farthest_point() {
+        let sphere =
+            BoundingSphere::from_points([Vec3::new(-1.0, 0.0, 0.0), Vec3::new(3.0, 0.0, 0.0)])
+                .unwrap();
+
+        assert_eq!(sphere.center, Vec3::new(1.0, 0.0, 0.0));
+        assert_eq!(sphere.radius, 2.0);
+        assert!(BoundingSphere::from_points(Vec::<Vec3>::new()).is_none());
+    }
+
+    #[test]
+    fn transformed_sphere_scales_by_the_largest_axis() {
+        let sphere =
+            BoundingSphere::new(Vec3::X, 1.0).transformed(Mat4::from_scale_rotation_translation(
+                Vec3::new(1.0, 3.0, 2.0),
+                Quat::IDENTITY,
+                Vec3::Z,
+            ));
+
+        assert_eq!(sphere.center, Vec3::new(1.0, 0.0, 1.0));
+        assert_eq!(sphere.radius, 3.0);
+    }
+
+    #[test]
+    fn frustum_planes_point_inwards() {
+        let inside = Vec3::new(0.0, 0.0, -5.0);
+        for plane in frustum().planes() {
+            assert!((plane.truncate().length() - 1.0).abs() < 1e-5);
+            assert!(plane.truncate().dot(inside) + plane.w > 0.0);
+        }
+    }
+
+    #[test]
+    fn frustum_culls_spheres() {
+        let frustum = frustum();
+        let visible = |center: Vec3, radius: f32| {
+            frustum.intersects_sphere(&BoundingSphere::new(center, radius))
+        };
+
+        assert!(visible(Vec3::new(0.0, 0.0, -5.0), 0.5));
+        // Behind the camera, beyond the far plane and off to the side.
+        assert!(!visible(Vec3::new(0.0, 0.0, 5.0), 0.5));
+        assert!(!visible(Vec3::new(0.0, 0.0, -12.0), 0.5));
+        assert!(!visible(Vec3::new(8.0, 0.0, -5.0), 0.5));
+        // Straddling the near, far and right planes.
+        assert!(visible(Vec3::new(0.0, 0.0, 0.2), 0.5));
+        assert!(visible(Vec3::new(0.0, 0.0, -10.3), 0.5));
+        assert!(visible(Vec3::new(5.5, 0.0, -5.0), 1.0));
+    }
+
+    #[test]
+    fn frustum_culls_boxes() {
+        let frustum = frustum();
+        let visible = |center: Vec3, half_extent: f32| {
+            frustum.intersects_aabb(&Aabb::new(
+                center - Vec3::splat(half_extent),
+                center + Vec3::splat(half_extent),
+            ))
+        };
+
+        assert!(visible(Vec3::new(0.0, 0.0, -5.0), 0.5));
+        assert!(!visible(Vec3::new(0.0, 0.0, 5.0), 0.5));
+        assert!(!visible(Vec3::new(0.0, 0.0, -12.0), 0.5));
+        assert!(!visible(Vec3::new(0.0, -8.0, -5.0), 0.5));
+        // A box around the camera is never culled.
+        assert!(visible(Vec3::ZERO, 20.0));
+        assert!(visible(Vec3::new(0.0, 5.5, -5.0), 1.0));
+    }
+
+    #[test]
+    fn culling_stats_count_both_outcomes() {
+        let mut stats = CullingStats::default();
+        for visible in [true, false, true] {
+            stats.record(visible);
+        }
+
+        assert_eq!(
+            stats,
+            CullingStats {
+                visible: 2,
+                culled: 1
+            }
+        );
+        assert_eq!(stats.total(), 3);
+    }
+}
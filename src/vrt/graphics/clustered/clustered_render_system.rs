@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, DescriptorSet, DescriptorSetLayout, RenderPass, SampleCountFlagBits,
+    ShaderStageFlags,
+};
+use glam::Mat4;
+
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::model::Model;
+use crate::vrt::graphics::pipeline::VRTPipeline;
+
+const VERTEX_SHADER: &str = "./assets/shaders/clustered_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/clustered_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ClusteredPush {
+    model: Mat4,
+    roughness: f32,
+    metallic: f32,
+}
+
+/// Forward shades models with the lights binned by `ClusteredLighting`.
+pub struct ClusteredRenderSystem {
+    pipeline: VRTPipeline,
+    device: Arc<VRTDevice>,
+}
+
+impl ClusteredRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        cluster_layout: DescriptorSetLayout,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info.set_descriptor_set_layouts(&[cluster_layout]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            0,
+            std::mem::size_of::<ClusteredPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        Self { pipeline, device }
+    }
+
+    /// `cluster_set` is the descriptor set of the frame from `ClusteredLighting`.
+    pub fn render(
+        &self,
+        command_buffer: CommandBuffer,
+        cluster_set: DescriptorSet,
+        model: &Model,
+        transform: Mat4,
+        roughness: f32,
+        metallic: f32,
+    ) {
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[cluster_set]);
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            &ClusteredPush {
+                model: transform,
+                roughness,
+                metallic,
+            },
+        );
+        model.bind(self.device.clone(), command_buffer);
+        model.draw(self.device.clone(), command_buffer)
+    }
+}
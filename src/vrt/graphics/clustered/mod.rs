@@ -0,0 +1,304 @@
+pub mod clustered_render_system;
+
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    AccessFlags, BufferUsageFlags, CommandBuffer, DependencyFlags, DescriptorSet,
+    DescriptorSetLayout, DescriptorType, DeviceSize, Extent2D, MemoryBarrierBuilder,
+    MemoryPropertyFlags, PipelineStageFlags, ShaderStageFlags, WHOLE_SIZE,
+};
+use glam::{Mat4, Vec3};
+
+use super::compute_pipeline::VRTComputePipeline;
+use super::light::{GpuLight, Light, MAX_LIGHTS};
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::utils::result::VkResult;
+
+const CULLING_SHADER: &str = "./assets/shaders/cluster_culling_comp.spirv";
+const WORKGROUP_SIZE: u32 = 64;
+
+/// Froxels along the screen width, height and the exponentially sliced depth.
+pub const CLUSTER_GRID: [u32; 3] = [16, 9, 24];
+/// Matches `MAX_LIGHTS_PER_CLUSTER` in the clustered shaders, further lights are dropped.
+pub const MAX_LIGHTS_PER_CLUSTER: u32 = 128;
+
+const CLUSTER_COUNT: u32 = CLUSTER_GRID[0] * CLUSTER_GRID[1] * CLUSTER_GRID[2];
+
+/// Matches the `ClusterData` uniform block of the clustered shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ClusterUniform {
+    view: Mat4,
+    projection: Mat4,
+    inverse_projection: Mat4,
+    forward: [f32; 4],
+    depth_range: [f32; 4],
+    grid_size: [u32; 4],
+    screen_size: [f32; 4],
+    camera_position: [f32; 4],
+    ambient_color: [f32; 4],
+}
+
+/// Bins lights into a froxel grid with a compute pass, so that forward shaders only iterate
+/// the lights of their cluster.
+///
+/// The descriptor set of a frame holds the camera uniform, the lights, the light count of every
+/// cluster and their light indices. Forward pipelines bind it as set 0.
+pub struct ClusteredLighting {
+    pipeline: VRTComputePipeline,
+    descriptor_sets: Vec<DescriptorSet>,
+    uniform_buffers: Vec<VRTBuffer>,
+    light_buffers: Vec<VRTBuffer>,
+    _light_count_buffers: Vec<VRTBuffer>,
+    _light_index_buffers: Vec<VRTBuffer>,
+    _descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    lights: Vec<Light>,
+    view: Mat4,
+    projection: Mat4,
+    depth_range: (f32, f32),
+    ambient_color: Vec3,
+    device: Arc<VRTDevice>,
+}
+
+impl ClusteredLighting {
+    pub fn new(device: Arc<VRTDevice>) -> VkResult<Self> {
+        let stages =
+            ShaderStageFlags::COMPUTE | ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT;
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(0, DescriptorType::UNIFORM_BUFFER, stages, None)
+            .add_binding(1, DescriptorType::STORAGE_BUFFER, stages, None)
+            .add_binding(2, DescriptorType::STORAGE_BUFFER, stages, None)
+            .add_binding(3, DescriptorType::STORAGE_BUFFER, stages, None)
+            .build();
+
+        let frame_count = MAX_FRAMES_IN_FLIGHT as u32;
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(frame_count)
+            .add_pool_size(DescriptorType::UNIFORM_BUFFER, frame_count)
+            .add_pool_size(DescriptorType::STORAGE_BUFFER, 3 * frame_count)
+            .build()?;
+
+        let create_buffers = |instance_size: usize,
+                              count: u32,
+                              usage: BufferUsageFlags,
+                              properties: MemoryPropertyFlags| {
+            (0..MAX_FRAMES_IN_FLIGHT)
+                .map(|_| {
+                    VRTBuffer::new(
+                        device.clone(),
+                        instance_size as DeviceSize,
+                        count,
+                        usage,
+                        properties,
+                        None,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let host_visible = MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT;
+
+        // Everything is per frame in flight, so culling never races the shading of another frame.
+        let uniform_buffers = create_buffers(
+            mem::size_of::<ClusterUniform>(),
+            1,
+            BufferUsageFlags::UNIFORM_BUFFER,
+            host_visible,
+        );
+        let light_buffers = create_buffers(
+            mem::size_of::<GpuLight>(),
+            MAX_LIGHTS as u32,
+            BufferUsageFlags::STORAGE_BUFFER,
+            host_visible,
+        );
+        let light_count_buffers = create_buffers(
+            mem::size_of::<u32>(),
+            CLUSTER_COUNT,
+            BufferUsageFlags::STORAGE_BUFFER,
+            MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+        let light_index_buffers = create_buffers(
+            mem::size_of::<u32>(),
+            CLUSTER_COUNT * MAX_LIGHTS_PER_CLUSTER,
+            BufferUsageFlags::STORAGE_BUFFER,
+            MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+
+        let descriptor_sets = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|frame_index| {
+                VRTDescriptorWriter::new(&descriptor_set_layout, &descriptor_pool)
+                    .write_buffer(0, uniform_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(1, light_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(
+                        2,
+                        light_count_buffers[frame_index].get_buffer(),
+                        0,
+                        WHOLE_SIZE,
+                    )
+                    .write_buffer(
+                        3,
+                        light_index_buffers[frame_index].get_buffer(),
+                        0,
+                        WHOLE_SIZE,
+                    )
+                    .build()
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+
+        let pipeline = VRTComputePipeline::new(
+            device.clone(),
+            CULLING_SHADER,
+            &[descriptor_set_layout.get_descriptor_set_layout()],
+            0,
+        )?;
+
+        Ok(Self {
+            pipeline,
+            descriptor_sets,
+            uniform_buffers,
+            light_buffers,
+            _light_count_buffers: light_count_buffers,
+            _light_index_buffers: light_index_buffers,
+            _descriptor_pool: descriptor_pool,
+            descriptor_set_layout,
+            lights: vec![],
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            depth_range: (0.01, 1.0),
+            ambient_color: Vec3::splat(0.03),
+            device,
+        })
+    }
+
+    /// Forward pipelines that shade with the cluster lights use this layout for set 0.
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self, frame_index: usize) -> DescriptorSet {
+        self.descriptor_sets[frame_index]
+    }
+
+    pub fn set_camera(&mut self, view: Mat4, projection: Mat4) {
+        self.view = view;
+        self.projection = projection;
+    }
+
+    /// View space distances of the near and far planes, the range the depth slices cover.
+    pub fn set_depth_range(&mut self, near: f32, far: f32) {
+        self.depth_range = (near, far);
+    }
+
+    pub fn get_view_projection(&self) -> Mat4 {
+        self.projection * self.view
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn lights_mut(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
+    }
+
+    pub fn get_ambient_color(&self) -> Vec3 {
+        self.ambient_color
+    }
+
+    pub fn set_ambient_color(&mut self, ambient_color: Vec3) {
+        self.ambient_color = ambient_color;
+    }
+
+    /// Uploads the lights and camera of `frame_index` and bins the lights, outside of any render
+    /// pass. `extent` is the size of the target the forward pass renders to. Returns the number
+    /// of lights that fit into the buffer.
+    pub fn cull_lights(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        extent: Extent2D,
+    ) -> usize {
+        let gpu_lights = self
+            .lights
+            .iter()
+            .take(MAX_LIGHTS)
+            .map(|light| light.to_gpu())
+            .collect::<Vec<_>>();
+
+        let light_buffer = &self.light_buffers[frame_index];
+        if !gpu_lights.is_empty() {
+            let mapped = light_buffer.map(WHOLE_SIZE, 0);
+            light_buffer.write_to_buffer(
+                gpu_lights.as_ptr(),
+                mapped,
+                gpu_lights.len() as DeviceSize,
+                0,
+            );
+            light_buffer.unmap();
+        }
+
+        let uniform = self.cluster_uniform(gpu_lights.len() as u32, extent);
+        let uniform_buffer = &self.uniform_buffers[frame_index];
+        let mapped = uniform_buffer.map(WHOLE_SIZE, 0);
+        uniform_buffer.write_to_buffer(&uniform as *const ClusterUniform, mapped, 1, 0);
+        uniform_buffer.unmap();
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[self.descriptor_sets[frame_index]]);
+        self.pipeline
+            .dispatch(command_buffer, CLUSTER_COUNT.div_ceil(WORKGROUP_SIZE), 1, 1);
+
+        let barrier = MemoryBarrierBuilder::new()
+            .src_access_mask(AccessFlags::SHADER_WRITE)
+            .dst_access_mask(AccessFlags::SHADER_READ);
+        unsafe {
+            self.device.get_device_ptr().cmd_pipeline_barrier(
+                command_buffer,
+                PipelineStageFlags::COMPUTE_SHADER,
+                PipelineStageFlags::FRAGMENT_SHADER,
+                DependencyFlags::empty(),
+                std::slice::from_ref(&barrier),
+                &[],
+                &[],
+            );
+        }
+        gpu_lights.len()
+    }
+
+    fn cluster_uniform(&self, light_count: u32, extent: Extent2D) -> ClusterUniform {
+        let (near, far) = self.depth_range;
+        let inverse_projection = self.projection.inverse();
+        // The direction view depth grows in, -z for the usual right-handed projections.
+        let forward = (inverse_projection.project_point3(Vec3::Z)
+            - inverse_projection.project_point3(Vec3::ZERO))
+        .normalize_or_zero();
+        let width = extent.width.max(1) as f32;
+        let height = extent.height.max(1) as f32;
+
+        ClusterUniform {
+            view: self.view,
+            projection: self.projection,
+            inverse_projection,
+            forward: forward.extend(0.0).to_array(),
+            depth_range: [near, far, (far / near).ln(), 0.0],
+            grid_size: [
+                CLUSTER_GRID[0],
+                CLUSTER_GRID[1],
+                CLUSTER_GRID[2],
+                light_count,
+            ],
+            screen_size: [width, height, 1.0 / width, 1.0 / height],
+            camera_position: self.view.inverse().w_axis.to_array(),
+            ambient_color: self.ambient_color.extend(1.0).to_array(),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::ffi::CString;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, ComputePipelineCreateInfoBuilder, DescriptorSet, DescriptorSetLayout, Pipeline,
+    PipelineBindPoint, PipelineCache, PipelineLayout, PipelineLayoutCreateInfoBuilder,
+    PipelineShaderStageCreateInfoBuilder, PushConstantRangeBuilder, ShaderStageFlagBits,
+    ShaderStageFlags,
+};
+
+use super::pipeline::VRTPipeline;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::utils::result::VkResult;
+
+pub struct VRTComputePipeline {
+    compute_pipeline: Pipeline,
+    pipeline_layout: PipelineLayout,
+    device: Arc<VRTDevice>,
+}
+
+impl VRTComputePipeline {
+    /// A `push_constant_size` of 0 creates a layout without push constants.
+    pub fn new(
+        device: Arc<VRTDevice>,
+        shader_path: &str,
+        descriptor_set_layouts: &[DescriptorSetLayout],
+        push_constant_size: u32,
+    ) -> VkResult<Self> {
+        let push_constant_ranges = if push_constant_size > 0 {
+            vec![PushConstantRangeBuilder::new()
+                .stage_flags(ShaderStageFlags::COMPUTE)
+                .offset(0)
+                .size(push_constant_size)]
+        } else {
+            vec![]
+        };
+        let pipeline_layout_info = PipelineLayoutCreateInfoBuilder::new()
+            .set_layouts(descriptor_set_layouts)
+            .push_constant_ranges(&push_constant_ranges);
+        let pipeline_layout = unsafe {
+            device
+                .get_device_ptr()
+                .create_pipeline_layout(&pipeline_layout_info, None)
+        }
+        .result()?;
+
+        let shader_module = VRTPipeline::create_shader_module(
+            device.clone(),
+            &VRTPipeline::read_file(shader_path),
+        )?;
+        let name = CString::new("main").unwrap();
+        let stage_info = PipelineShaderStageCreateInfoBuilder::new()
+            .stage(ShaderStageFlagBits::COMPUTE)
+            .module(shader_module)
+            .name(&name);
+
+        let pipeline_info = ComputePipelineCreateInfoBuilder::new()
+            .stage(*stage_info)
+            .layout(pipeline_layout)
+            .base_pipeline_index(-1);
+
+        let compute_pipeline = unsafe {
+            device.get_device_ptr().create_compute_pipelines(
+                PipelineCache::null(),
+                std::slice::from_ref(&pipeline_info),
+                None,
+            )
+        }
+        .result();
+
+        unsafe {
+            device
+                .get_device_ptr()
+                .destroy_shader_module(shader_module, None)
+        };
+
+        let compute_pipeline = match compute_pipeline {
+            Ok(pipelines) => pipelines[0],
+            Err(err) => {
+                unsafe {
+                    device
+                        .get_device_ptr()
+                        .destroy_pipeline_layout(pipeline_layout, None)
+                };
+                return Err(err.into());
+            }
+        };
+
+        Ok(Self {
+            compute_pipeline,
+            pipeline_layout,
+            device,
+        })
+    }
+
+    pub fn bind(&self, command_buffer: CommandBuffer) {
+        unsafe {
+            self.device.get_device_ptr().cmd_bind_pipeline(
+                command_buffer,
+                PipelineBindPoint::COMPUTE,
+                self.compute_pipeline,
+            );
+        }
+    }
+
+    pub fn bind_descriptor_sets(
+        &self,
+        command_buffer: CommandBuffer,
+        first_set: u32,
+        descriptor_sets: &[DescriptorSet],
+    ) {
+        unsafe {
+            self.device.get_device_ptr().cmd_bind_descriptor_sets(
+                command_buffer,
+                PipelineBindPoint::COMPUTE,
+                self.pipeline_layout,
+                first_set,
+                descriptor_sets,
+                &[],
+            );
+        }
+    }
+
+    /// `T` has to match the `push_constant` block layout of the shader.
+    pub fn push_constants<T: Copy>(&self, command_buffer: CommandBuffer, constants: &T) {
+        unsafe {
+            self.device.get_device_ptr().cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                ShaderStageFlags::COMPUTE,
+                0,
+                std::mem::size_of::<T>() as u32,
+                (constants as *const T).cast(),
+            );
+        }
+    }
+
+    pub fn dispatch(&self, command_buffer: CommandBuffer, x: u32, y: u32, z: u32) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .cmd_dispatch(command_buffer, x, y, z);
+        }
+    }
+}
+
+impl Drop for VRTComputePipeline {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_pipeline(self.compute_pipeline, None);
+            self.device
+                .get_device_ptr()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+    }
+}
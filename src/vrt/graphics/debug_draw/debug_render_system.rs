@@ -0,0 +1,107 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, CompareOp, CullModeFlags, PrimitiveTopology, RenderPass, SampleCountFlagBits,
+    ShaderStageFlags,
+};
+use glam::Mat4;
+
+use super::{DebugFrame, DebugLine};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::instance::InstanceBuffer;
+use crate::vrt::graphics::pipeline::{BlendMode, VRTPipeline};
+
+const VERTEX_SHADER: &str = "./assets/shaders/debug_line_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/debug_line_frag.spirv";
+const INITIAL_LINE_CAPACITY: u32 = 4096;
+
+/// Draws the lines of a `DebugFrame` as a line list, one instance per segment. Depth tested
+/// lines never write depth, overlay lines are drawn after them without testing it.
+pub struct DebugRenderSystem {
+    depth_pipeline: VRTPipeline,
+    overlay_pipeline: VRTPipeline,
+    lines: InstanceBuffer<DebugLine>,
+    device: Arc<VRTDevice>,
+}
+
+impl DebugRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.add_instance_input::<DebugLine>();
+        config_info.set_topology(PrimitiveTopology::LINE_LIST);
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_blend_mode(BlendMode::Alpha);
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX,
+            0,
+            mem::size_of::<Mat4>() as u32,
+        );
+
+        config_info.set_depth_test(true, false, CompareOp::LESS_OR_EQUAL);
+        let depth_pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+        config_info.set_depth_test(false, false, CompareOp::ALWAYS);
+        let overlay_pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+        let lines = InstanceBuffer::new(device.clone(), INITIAL_LINE_CAPACITY);
+
+        Self {
+            depth_pipeline,
+            overlay_pipeline,
+            lines,
+            device,
+        }
+    }
+
+    pub fn render(
+        &mut self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        view_projection: Mat4,
+        frame: &DebugFrame,
+    ) {
+        if frame.is_empty() {
+            return;
+        }
+        self.lines.update(
+            frame_index,
+            &[&frame.depth_tested[..], &frame.overlay[..]].concat(),
+        );
+        self.lines.bind(command_buffer, frame_index);
+
+        let depth_tested = frame.depth_tested.len() as u32;
+        let overlay = frame.overlay.len() as u32;
+        for (pipeline, count, first) in [
+            (&self.depth_pipeline, depth_tested, 0),
+            (&self.overlay_pipeline, overlay, depth_tested),
+        ] {
+            if count == 0 {
+                continue;
+            }
+            pipeline.bind(command_buffer);
+            pipeline.push_constants(command_buffer, ShaderStageFlags::VERTEX, &view_projection);
+            unsafe {
+                self.device
+                    .get_device_ptr()
+                    .cmd_draw(command_buffer, 2, count, 0, first);
+            }
+        }
+    }
+}
@@ -0,0 +1,280 @@
+pub mod debug_render_system;
+
+use std::f32::consts::TAU;
+use std::mem;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use erupt::vk1_0::{Format, VertexInputAttributeDescriptionBuilder};
+use glam::{BVec3, Mat4, Vec3, Vec4};
+
+use super::bounds::{Aabb, BoundingSphere};
+use super::instance::{InstanceData, FIRST_INSTANCE_LOCATION, INSTANCE_BINDING};
+
+/// Segments of the circles making up debug spheres.
+const CIRCLE_SEGMENTS: u32 = 32;
+
+/// Which coordinates of box corner `index` lie on the max side, one bit per axis.
+fn corner_mask(index: usize) -> BVec3 {
+    BVec3::new(index & 1 != 0, index & 2 != 0, index & 4 != 0)
+}
+
+/// A world space line segment, read per instance by `debug_line.vert`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DebugLine {
+    pub start: Vec4,
+    pub end: Vec4,
+    pub color: Vec4,
+}
+
+impl DebugLine {
+    pub fn new(start: Vec3, end: Vec3, color: Vec4) -> Self {
+        Self {
+            start: start.extend(1.0),
+            end: end.extend(1.0),
+            color,
+        }
+    }
+}
+
+impl InstanceData for DebugLine {
+    fn attribute_descriptions() -> Vec<VertexInputAttributeDescriptionBuilder<'static>> {
+        let size = mem::size_of::<Vec4>() as u32;
+        (0..3)
+            .map(|index| {
+                VertexInputAttributeDescriptionBuilder::new()
+                    .binding(INSTANCE_BINDING)
+                    .location(FIRST_INSTANCE_LOCATION + index)
+                    .format(Format::R32G32B32A32_SFLOAT)
+                    .offset(index * size)
+            })
+            .collect()
+    }
+}
+
+/// Color, lifetime and depth testing of queued shapes. Shapes without a lifetime are drawn in
+/// the next frame only.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DebugStyle {
+    pub color: Vec4,
+    pub lifetime: Option<Duration>,
+    /// Hidden behind scene geometry when set, otherwise drawn on top of it.
+    pub depth_test: bool,
+}
+
+impl DebugStyle {
+    pub fn new(color: Vec4) -> Self {
+        Self {
+            color,
+            lifetime: None,
+            depth_test: true,
+        }
+    }
+
+    pub fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = Some(lifetime);
+        self
+    }
+
+    pub fn with_depth_test(mut self, depth_test: bool) -> Self {
+        self.depth_test = depth_test;
+        self
+    }
+}
+
+impl Default for DebugStyle {
+    fn default() -> Self {
+        Self::new(Vec4::ONE)
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+struct QueuedLine {
+    line: DebugLine,
+    depth_test: bool,
+    expires: Option<Instant>,
+}
+
+/// Lines of a frame split by depth testing, see `DebugDraw::take_frame`.
+#[derive(Debug, Clone, Default)]
+pub struct DebugFrame {
+    pub depth_tested: Vec<DebugLine>,
+    pub overlay: Vec<DebugLine>,
+}
+
+impl DebugFrame {
+    pub fn is_empty(&self) -> bool {
+        self.depth_tested.is_empty() && self.overlay.is_empty()
+    }
+}
+
+/// Immediate mode debug shapes in world space.
+///
+/// A cheap handle to a shared queue: clone it into whatever needs to draw, every clone queues
+/// into the same frame. The renderer takes the queue once per frame with `take_frame`.
+#[derive(Debug, Clone, Default)]
+pub struct DebugDraw {
+    lines: Arc<Mutex<Vec<QueuedLine>>>,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn push(&self, lines: impl IntoIterator<Item = DebugLine>, style: DebugStyle) {
+        let expires = style.lifetime.map(|lifetime| Instant::now() + lifetime);
+        self.lines
+            .lock()
+            .unwrap()
+            .extend(lines.into_iter().map(|line| QueuedLine {
+                line,
+                depth_test: style.depth_test,
+                expires,
+            }));
+    }
+
+    pub fn line(&self, start: Vec3, end: Vec3, style: DebugStyle) {
+        self.push([DebugLine::new(start, end, style.color)], style);
+    }
+
+    /// A line with a head of a fifth of its length at `end`.
+    pub fn arrow(&self, start: Vec3, end: Vec3, style: DebugStyle) {
+        let direction = end - start;
+        let length = direction.length();
+        if length <= f32::EPSILON {
+            return;
+        }
+        let direction = direction / length;
+        let side = direction.any_orthonormal_vector();
+        let up = direction.cross(side);
+        let base = end - direction * length * 0.2;
+        let head = length * 0.08;
+
+        let color = style.color;
+        self.push(
+            [
+                DebugLine::new(start, end, color),
+                DebugLine::new(end, base + side * head, color),
+                DebugLine::new(end, base - side * head, color),
+                DebugLine::new(end, base + up * head, color),
+                DebugLine::new(end, base - up * head, color),
+            ],
+            style,
+        );
+    }
+
+    pub fn aabb(&self, aabb: &Aabb, style: DebugStyle) {
+        let corner = |index: usize| Vec3::select(corner_mask(index), aabb.max, aabb.min);
+        self.box_edges(corner, style);
+    }
+
+    /// Three great circles around the sphere.
+    pub fn sphere(&self, sphere: &BoundingSphere, style: DebugStyle) {
+        for (u, v) in [(Vec3::X, Vec3::Y), (Vec3::Y, Vec3::Z), (Vec3::Z, Vec3::X)] {
+            self.circle(sphere.center, u * sphere.radius, v * sphere.radius, style);
+        }
+    }
+
+    /// The ellipse through `center + u` and `center + v`.
+    pub fn circle(&self, center: Vec3, u: Vec3, v: Vec3, style: DebugStyle) {
+        let point = |segment: u32| {
+            let angle = TAU * segment as f32 / CIRCLE_SEGMENTS as f32;
+            center + u * angle.cos() + v * angle.sin()
+        };
+        self.push(
+            (0..CIRCLE_SEGMENTS)
+                .map(|segment| DebugLine::new(point(segment), point(segment + 1), style.color)),
+            style,
+        );
+    }
+
+    /// `cells` by `cells` squares of `cell_size` centered on `center`, spanned by the unit
+    /// vectors `u` and `v`.
+    pub fn grid(
+        &self,
+        center: Vec3,
+        u: Vec3,
+        v: Vec3,
+        cells: u32,
+        cell_size: f32,
+        style: DebugStyle,
+    ) {
+        let half = cells as f32 * cell_size * 0.5;
+        let lines = (0..=cells).flat_map(|index| {
+            let offset = index as f32 * cell_size - half;
+            [
+                DebugLine::new(
+                    center + u * offset - v * half,
+                    center + u * offset + v * half,
+                    style.color,
+                ),
+                DebugLine::new(
+                    center + v * offset - u * half,
+                    center + v * offset + u * half,
+                    style.color,
+                ),
+            ]
+        });
+        self.push(lines, style);
+    }
+
+    /// The x, y and z axes of `transform` in red, green and blue, `length` long. Only the
+    /// lifetime and depth testing of `style` are used.
+    pub fn axes(&self, transform: Mat4, length: f32, style: DebugStyle) {
+        let origin = transform.transform_point3(Vec3::ZERO);
+        for (axis, color) in [
+            (Vec3::X, Vec4::new(1.0, 0.0, 0.0, 1.0)),
+            (Vec3::Y, Vec4::new(0.0, 1.0, 0.0, 1.0)),
+            (Vec3::Z, Vec4::new(0.0, 0.0, 1.0, 1.0)),
+        ] {
+            let end = transform.transform_point3(axis * length);
+            self.arrow(origin, end, DebugStyle { color, ..style });
+        }
+    }
+
+    /// The edges of the volume seen by `view_projection`, with 0..1 depth.
+    pub fn frustum(&self, view_projection: Mat4, style: DebugStyle) {
+        let inverse = view_projection.inverse();
+        let corner = |index: usize| {
+            let ndc = Vec3::select(corner_mask(index), Vec3::ONE, Vec3::new(-1.0, -1.0, 0.0));
+            inverse.project_point3(ndc)
+        };
+        self.box_edges(corner, style);
+    }
+
+    /// The twelve edges between corners differing in one bit of their index.
+    fn box_edges(&self, corner: impl Fn(usize) -> Vec3, style: DebugStyle) {
+        let edges = (0..8usize).flat_map(|index| {
+            [1, 2, 4]
+                .into_iter()
+                .filter(move |bit| index & bit == 0)
+                .map(move |bit| (index, index | bit))
+        });
+        let corner = &corner;
+        self.push(
+            edges.map(|(from, to)| DebugLine::new(corner(from), corner(to), style.color)),
+            style,
+        );
+    }
+
+    /// Removes every shape of the frame and returns their lines. Shapes whose lifetime has not
+    /// run out by `now` stay queued for the next frames.
+    pub fn take_frame(&self, now: Instant) -> DebugFrame {
+        let mut frame = DebugFrame::default();
+        self.lines.lock().unwrap().retain(|queued| {
+            if queued.depth_test {
+                frame.depth_tested.push(queued.line);
+            } else {
+                frame.overlay.push(queued.line);
+            }
+            queued.expires.is_some_and(|expires| expires > now)
+        });
+        frame
+    }
+
+    pub fn clear(&self) {
+        self.lines.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{CommandBuffer, RenderPass, ShaderStageFlags};
+use glam::Mat4;
+
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::model::Model;
+use crate::vrt::graphics::pipeline::VRTPipeline;
+
+use super::GBUFFER_FORMATS;
+
+const VERTEX_SHADER: &str = "./assets/shaders/gbuffer_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/gbuffer_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GeometryPush {
+    transform: Mat4,
+    roughness: f32,
+    metallic: f32,
+}
+
+/// Writes models into the G-buffer of a `DeferredRenderer`.
+pub struct GeometryRenderSystem {
+    pipeline: VRTPipeline,
+    device: Arc<VRTDevice>,
+}
+
+impl GeometryRenderSystem {
+    pub fn new(device: Arc<VRTDevice>, render_pass: RenderPass) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.set_color_attachment_count(GBUFFER_FORMATS.len());
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            0,
+            std::mem::size_of::<GeometryPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        Self { pipeline, device }
+    }
+
+    pub fn render(
+        &self,
+        command_buffer: CommandBuffer,
+        model: &Model,
+        transform: Mat4,
+        roughness: f32,
+        metallic: f32,
+    ) {
+        self.pipeline.bind(command_buffer);
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            &GeometryPush {
+                transform,
+                roughness,
+                metallic,
+            },
+        );
+        model.bind(self.device.clone(), command_buffer);
+        model.draw(self.device.clone(), command_buffer)
+    }
+}
@@ -0,0 +1,221 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    BufferUsageFlags, CommandBuffer, CompareOp, CullModeFlags, DescriptorSet, DescriptorSetLayout,
+    DescriptorType, DeviceSize, ImageLayout, ImageView, MemoryPropertyFlags, RenderPass, Sampler,
+    ShaderStageFlags, WHOLE_SIZE,
+};
+use glam::{Mat4, Vec3};
+
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::graphics::light::{GpuLight, MAX_LIGHTS};
+use crate::vrt::graphics::pipeline::VRTPipeline;
+use crate::vrt::graphics::post::fullscreen::FULLSCREEN_VERTEX_SHADER;
+use crate::vrt::utils::result::VkResult;
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/deferred_lighting_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct LightingPush {
+    inverse_view_projection: Mat4,
+    camera_position: [f32; 4],
+    ambient_color: [f32; 4],
+    light_count: u32,
+}
+
+/// Accumulates all lights over the G-buffer in one fullscreen pass.
+///
+/// Set 0 holds the G-buffer, set 1 the light storage buffer of the frame in flight and set 2
+/// the shadow maps.
+pub struct LightingPass {
+    pipeline: VRTPipeline,
+    gbuffer_set: DescriptorSet,
+    light_sets: Vec<DescriptorSet>,
+    light_buffers: Vec<VRTBuffer>,
+    descriptor_pool: VRTDescriptorPool,
+    gbuffer_layout: VRTDescriptorSetLayout,
+    _light_layout: VRTDescriptorSetLayout,
+    sampler: Sampler,
+}
+
+impl LightingPass {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        sampler: Sampler,
+        gbuffer_views: &[ImageView],
+        shadow_layout: DescriptorSetLayout,
+    ) -> VkResult<Self> {
+        let mut gbuffer_layout_builder = VRTDescriptorSetLayoutBuilder::new(device.clone());
+        for binding in 0..gbuffer_views.len() as u32 {
+            gbuffer_layout_builder.add_binding(
+                binding,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            );
+        }
+        gbuffer_layout_builder.add_binding(
+            gbuffer_views.len() as u32,
+            DescriptorType::SAMPLER,
+            ShaderStageFlags::FRAGMENT,
+            None,
+        );
+        let gbuffer_layout = gbuffer_layout_builder.build();
+
+        let light_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::STORAGE_BUFFER,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .build();
+
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(1 + MAX_FRAMES_IN_FLIGHT as u32)
+            .add_pool_size(DescriptorType::SAMPLED_IMAGE, gbuffer_views.len() as u32)
+            .add_pool_size(DescriptorType::SAMPLER, 1)
+            .add_pool_size(DescriptorType::STORAGE_BUFFER, MAX_FRAMES_IN_FLIGHT as u32)
+            .build()?;
+
+        // One buffer per possible frame in flight, so a frame never overwrites lights in use.
+        let light_buffers = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| {
+                VRTBuffer::new(
+                    device.clone(),
+                    mem::size_of::<GpuLight>() as DeviceSize,
+                    MAX_LIGHTS as u32,
+                    BufferUsageFlags::STORAGE_BUFFER,
+                    MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let light_sets = light_buffers
+            .iter()
+            .map(|buffer| {
+                VRTDescriptorWriter::new(&light_layout, &descriptor_pool)
+                    .write_buffer(0, buffer.get_buffer(), 0, WHOLE_SIZE)
+                    .build()
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+
+        let gbuffer_set = descriptor_pool.allocate_descriptor(&gbuffer_layout)?;
+
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_depth_test(false, false, CompareOp::ALWAYS);
+        config_info.set_descriptor_set_layouts(&[
+            gbuffer_layout.get_descriptor_set_layout(),
+            light_layout.get_descriptor_set_layout(),
+            shadow_layout,
+        ]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::FRAGMENT,
+            0,
+            mem::size_of::<LightingPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device,
+            FULLSCREEN_VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        let pass = Self {
+            pipeline,
+            gbuffer_set,
+            light_sets,
+            light_buffers,
+            descriptor_pool,
+            gbuffer_layout,
+            _light_layout: light_layout,
+            sampler,
+        };
+        pass.set_gbuffer(gbuffer_views);
+        Ok(pass)
+    }
+
+    /// Rewrites the G-buffer descriptors, no frame using them may still be in flight.
+    pub fn set_gbuffer(&self, gbuffer_views: &[ImageView]) {
+        let mut writer = VRTDescriptorWriter::new(&self.gbuffer_layout, &self.descriptor_pool);
+        for (binding, view) in gbuffer_views.iter().enumerate() {
+            let layout = if binding + 1 == gbuffer_views.len() {
+                ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL
+            } else {
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL
+            };
+            writer.write_image(binding as u32, *view, layout);
+        }
+        writer
+            .write_sampler(gbuffer_views.len() as u32, self.sampler)
+            .overwrite(self.gbuffer_set);
+    }
+
+    /// Uploads the lights of `frame_index` and records the pass, returns the number of lights
+    /// that fit into the buffer.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        lights: &[GpuLight],
+        shadow_set: DescriptorSet,
+        inverse_view_projection: Mat4,
+        camera_position: Vec3,
+        ambient_color: Vec3,
+    ) -> usize {
+        let gpu_lights = &lights[..lights.len().min(MAX_LIGHTS)];
+
+        let light_buffer = &self.light_buffers[frame_index];
+        if !gpu_lights.is_empty() {
+            let mapped = light_buffer.map(WHOLE_SIZE, 0);
+            light_buffer.write_to_buffer(
+                gpu_lights.as_ptr(),
+                mapped,
+                gpu_lights.len() as DeviceSize,
+                0,
+            );
+            light_buffer.unmap();
+        }
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline.bind_descriptor_sets(
+            command_buffer,
+            0,
+            &[self.gbuffer_set, self.light_sets[frame_index], shadow_set],
+        );
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::FRAGMENT,
+            &LightingPush {
+                inverse_view_projection,
+                camera_position: camera_position.extend(1.0).to_array(),
+                ambient_color: ambient_color.extend(1.0).to_array(),
+                light_count: gpu_lights.len() as u32,
+            },
+        );
+
+        unsafe {
+            self.descriptor_pool
+                .get_device()
+                .get_device_ptr()
+                .cmd_draw(command_buffer, 3, 1, 0, 0);
+        }
+        gpu_lights.len()
+    }
+}
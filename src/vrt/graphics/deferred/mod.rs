@@ -0,0 +1,290 @@
+pub mod geometry_render_system;
+pub mod lighting;
+
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, Extent2D, Filter, Format, ImageView, RenderPass, SampleCountFlagBits,
+    SamplerAddressMode,
+};
+use glam::{Mat4, Vec3};
+
+use self::lighting::LightingPass;
+use super::light::{Light, MAX_LIGHTS};
+use super::post::fullscreen::FullscreenPass;
+use super::post::HDR_FORMAT;
+use super::render_target::{RenderTargetPass, VRTRenderTarget};
+use super::renderer::ClearValues;
+use super::shadow::{ShadowMaps, ShadowSettings};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::VRTSampler;
+use crate::vrt::device::swapchain::RenderPassDescription;
+use crate::vrt::utils::result::VkResult;
+
+/// Albedo, world space normal and material parameters (roughness, metallic), followed by depth.
+pub const GBUFFER_FORMATS: [Format; 3] = [
+    Format::R8G8B8A8_UNORM,
+    Format::R16G16B16A16_SFLOAT,
+    Format::R8G8B8A8_UNORM,
+];
+
+const COMPOSITION_FRAGMENT_SHADER: &str = "./assets/shaders/deferred_composition_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct CompositionParams {
+    exposure: f32,
+}
+
+/// Renders geometry into a G-buffer, lights it in a single fullscreen pass and composes the
+/// result into the swapchain image.
+pub struct DeferredRenderer {
+    lighting_pass: LightingPass,
+    composition_pass: FullscreenPass,
+    gbuffer: VRTRenderTarget,
+    lighting_target: VRTRenderTarget,
+    shadow_maps: ShadowMaps,
+    sampler: VRTSampler,
+    lights: Vec<Light>,
+    view: Mat4,
+    projection: Mat4,
+    depth_range: (f32, f32),
+    ambient_color: Vec3,
+    composition_params: CompositionParams,
+    device: Arc<VRTDevice>,
+}
+
+impl DeferredRenderer {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        extent: Extent2D,
+        output_render_pass: RenderPass,
+        output_samples: SampleCountFlagBits,
+    ) -> VkResult<Self> {
+        let depth_format = VRTRenderTarget::find_sampled_depth_format(&device)?;
+        let gbuffer = VRTRenderTarget::new(
+            device.clone(),
+            extent,
+            &GBUFFER_FORMATS,
+            Some(depth_format),
+            &RenderPassDescription::clear(),
+        )?;
+        let lighting_target = VRTRenderTarget::new(
+            device.clone(),
+            extent,
+            &[HDR_FORMAT],
+            None,
+            &RenderPassDescription::clear(),
+        )?;
+        let sampler = VRTSampler::new(
+            device.clone(),
+            Filter::NEAREST,
+            SamplerAddressMode::CLAMP_TO_EDGE,
+        )?;
+
+        let shadow_maps = ShadowMaps::new(device.clone(), ShadowSettings::default())?;
+
+        let lighting_pass = LightingPass::new(
+            device.clone(),
+            lighting_target.get_render_pass(),
+            sampler.get_sampler(),
+            &Self::gbuffer_views(&gbuffer),
+            shadow_maps.get_descriptor_set_layout(),
+        )?;
+        let composition_pass = Self::create_composition_pass(
+            &device,
+            output_render_pass,
+            output_samples,
+            &sampler,
+            &lighting_target,
+        )?;
+
+        Ok(Self {
+            lighting_pass,
+            composition_pass,
+            gbuffer,
+            lighting_target,
+            shadow_maps,
+            sampler,
+            lights: vec![],
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            depth_range: (0.01, 1.0),
+            ambient_color: Vec3::splat(0.03),
+            composition_params: CompositionParams { exposure: 0.0 },
+            device,
+        })
+    }
+
+    fn gbuffer_views(gbuffer: &VRTRenderTarget) -> Vec<ImageView> {
+        (0..GBUFFER_FORMATS.len())
+            .map(|index| gbuffer.get_color_view(index))
+            .chain(gbuffer.get_depth_view())
+            .collect()
+    }
+
+    fn create_composition_pass(
+        device: &Arc<VRTDevice>,
+        render_pass: RenderPass,
+        samples: SampleCountFlagBits,
+        sampler: &VRTSampler,
+        lighting_target: &VRTRenderTarget,
+    ) -> VkResult<FullscreenPass> {
+        FullscreenPass::new(
+            device.clone(),
+            render_pass,
+            samples,
+            COMPOSITION_FRAGMENT_SHADER,
+            std::mem::size_of::<CompositionParams>() as u32,
+            sampler.get_sampler(),
+            &[],
+            &[lighting_target.get_color_view(0)],
+        )
+    }
+
+    /// Geometry pipelines have to be built against this render pass.
+    pub fn get_geometry_render_pass(&self) -> RenderPass {
+        self.gbuffer.get_render_pass()
+    }
+
+    pub fn get_extent(&self) -> Extent2D {
+        self.gbuffer.get_extent()
+    }
+
+    /// Recreates the G-buffer, no frame using it may still be in flight.
+    pub fn resize(&mut self, extent: Extent2D) -> VkResult<()> {
+        if extent == self.get_extent() {
+            return Ok(());
+        }
+
+        self.gbuffer.resize(extent)?;
+        self.lighting_target.resize(extent)?;
+        self.lighting_pass
+            .set_gbuffer(&Self::gbuffer_views(&self.gbuffer));
+        self.composition_pass
+            .set_inputs(&[self.lighting_target.get_color_view(0)])
+    }
+
+    pub fn set_output_render_pass(
+        &mut self,
+        render_pass: RenderPass,
+        samples: SampleCountFlagBits,
+    ) -> VkResult<()> {
+        self.composition_pass = Self::create_composition_pass(
+            &self.device,
+            render_pass,
+            samples,
+            &self.sampler,
+            &self.lighting_target,
+        )?;
+        Ok(())
+    }
+
+    pub fn set_camera(&mut self, view: Mat4, projection: Mat4) {
+        self.view = view;
+        self.projection = projection;
+    }
+
+    /// View space distances of the near and far planes, the range shadow cascades split.
+    pub fn set_camera_depth_range(&mut self, near: f32, far: f32) {
+        self.depth_range = (near, far);
+    }
+
+    pub fn get_view_projection(&self) -> Mat4 {
+        self.projection * self.view
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn lights_mut(&mut self) -> &mut Vec<Light> {
+        &mut self.lights
+    }
+
+    pub fn get_ambient_color(&self) -> Vec3 {
+        self.ambient_color
+    }
+
+    pub fn set_ambient_color(&mut self, ambient_color: Vec3) {
+        self.ambient_color = ambient_color;
+    }
+
+    pub fn get_exposure(&self) -> f32 {
+        self.composition_params.exposure
+    }
+
+    /// Exposure in stops applied before the composition tonemaps to the swapchain.
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.composition_params.exposure = exposure;
+    }
+
+    /// Depth-only render systems draw the layers of these maps after `update_shadows`.
+    pub fn shadow_maps(&self) -> &ShadowMaps {
+        &self.shadow_maps
+    }
+
+    pub fn get_shadow_settings(&self) -> ShadowSettings {
+        self.shadow_maps.get_settings()
+    }
+
+    /// No frame using the shadow maps may still be in flight.
+    pub fn set_shadow_settings(&mut self, settings: ShadowSettings) -> VkResult<()> {
+        self.shadow_maps.set_settings(settings)
+    }
+
+    /// Fits the shadow maps of the current lights to the camera, before their layers are drawn.
+    pub fn update_shadows(&mut self, frame_index: usize) {
+        let (near, far) = self.depth_range;
+        let inverse_view_projection = self.get_view_projection().inverse();
+        self.shadow_maps.update(
+            frame_index,
+            &self.lights,
+            inverse_view_projection,
+            near,
+            far,
+        );
+    }
+
+    /// Clears the G-buffer, geometry render systems draw inside the returned pass.
+    pub fn begin_geometry(&self, command_buffer: CommandBuffer) -> RenderTargetPass<'_> {
+        let clear_values = ClearValues {
+            color: [0.0, 0.0, 0.0, 0.0],
+            ..ClearValues::default()
+        };
+        self.gbuffer.begin(command_buffer, &clear_values)
+    }
+
+    /// Lights the G-buffer, outside of any render pass.
+    pub fn render_lighting(&self, command_buffer: CommandBuffer, frame_index: usize) {
+        let view_projection = self.get_view_projection();
+        let camera_position = self.view.inverse().w_axis.truncate();
+
+        let mut gpu_lights = self
+            .lights
+            .iter()
+            .take(MAX_LIGHTS)
+            .map(|light| light.to_gpu())
+            .collect::<Vec<_>>();
+        self.shadow_maps.apply_to(&mut gpu_lights);
+
+        let pass = self
+            .lighting_target
+            .begin(command_buffer, &ClearValues::default());
+        self.lighting_pass.draw(
+            pass.command_buffer(),
+            frame_index,
+            &gpu_lights,
+            self.shadow_maps.get_descriptor_set(frame_index),
+            view_projection.inverse(),
+            camera_position,
+            self.ambient_color,
+        );
+    }
+
+    /// Tonemaps the lit image, inside the swapchain render pass.
+    pub fn draw_composition(&self, command_buffer: CommandBuffer) {
+        self.composition_pass
+            .draw(command_buffer, 0, &self.composition_params);
+    }
+}
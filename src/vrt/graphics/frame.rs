@@ -0,0 +1,110 @@
+use std::marker::PhantomData;
+
+use erupt::vk::{CommandBuffer, Extent2D};
+
+use super::bounds::CullingStats;
+use super::renderer::{ClearValues, VRTRenderer};
+use crate::vrt::utils::result::VkResult;
+use crate::VRTWindow;
+
+/// A frame being recorded. Dropping it submits and presents, `finish` does the same but
+/// reports the result.
+pub struct Frame<'a> {
+    renderer: &'a mut VRTRenderer,
+    window: &'a mut VRTWindow,
+    command_buffer: CommandBuffer,
+    is_finished: bool,
+}
+
+/// A swapchain render pass of a frame, ended when dropped.
+pub struct FrameRenderPass<'f> {
+    renderer: &'f VRTRenderer,
+    command_buffer: CommandBuffer,
+    _frame: PhantomData<&'f mut ()>,
+}
+
+impl<'a> Frame<'a> {
+    pub(super) fn new(
+        renderer: &'a mut VRTRenderer,
+        window: &'a mut VRTWindow,
+        command_buffer: CommandBuffer,
+    ) -> Self {
+        Self {
+            renderer,
+            window,
+            command_buffer,
+            is_finished: false,
+        }
+    }
+
+    pub fn command_buffer(&self) -> CommandBuffer {
+        self.command_buffer
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.renderer.get_current_frame_index()
+    }
+
+    pub fn extent(&self) -> Extent2D {
+        self.renderer.get_extent()
+    }
+
+    pub fn renderer(&self) -> &VRTRenderer {
+        self.renderer
+    }
+
+    /// Adds culling counts to the stats of this frame.
+    pub fn record_culling(&mut self, stats: CullingStats) {
+        self.renderer.get_frame_stats_mut().record_culling(stats);
+    }
+
+    /// Clear values used by render passes begun after this call.
+    pub fn set_clear_values(&mut self, clear_values: ClearValues) {
+        self.renderer.set_clear_values(clear_values);
+    }
+
+    pub fn begin_swapchain_render_pass(&mut self) -> VkResult<FrameRenderPass<'_>> {
+        self.begin_render_pass(0)
+    }
+
+    /// Begins the swapchain render pass at `index` of `SwapchainConfig::render_passes`.
+    pub fn begin_render_pass(&mut self, index: usize) -> VkResult<FrameRenderPass<'_>> {
+        self.renderer
+            .begin_swapchain_render_pass(self.command_buffer, index)?;
+
+        Ok(FrameRenderPass {
+            renderer: self.renderer,
+            command_buffer: self.command_buffer,
+            _frame: PhantomData,
+        })
+    }
+
+    pub fn finish(mut self) -> VkResult<()> {
+        self.is_finished = true;
+        self.renderer.end_frame(self.window, self.command_buffer)
+    }
+}
+
+impl Drop for Frame<'_> {
+    fn drop(&mut self) {
+        if self.is_finished {
+            return;
+        }
+
+        if let Err(err) = self.renderer.end_frame(self.window, self.command_buffer) {
+            log::error!("failed to submit frame: {}", err);
+        }
+    }
+}
+
+impl FrameRenderPass<'_> {
+    pub fn command_buffer(&self) -> CommandBuffer {
+        self.command_buffer
+    }
+}
+
+impl Drop for FrameRenderPass<'_> {
+    fn drop(&mut self) {
+        self.renderer.end_swapchain_render_pass(self.command_buffer);
+    }
+}
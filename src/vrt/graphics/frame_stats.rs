@@ -0,0 +1,343 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::bounds::CullingStats;
+
+const DEFAULT_SAMPLE_COUNT: usize = 240;
+
+#[derive(Debug, Clone)]
+pub struct RollingStat {
+    samples: VecDeque<Duration>,
+    capacity: usize,
+}
+
+impl RollingStat {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, sample: Duration) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn last(&self) -> Duration {
+        self.samples.back().copied().unwrap_or_default()
+    }
+
+    pub fn min(&self) -> Duration {
+        self.samples.iter().min().copied().unwrap_or_default()
+    }
+
+    pub fn max(&self) -> Duration {
+        self.samples.iter().max().copied().unwrap_or_default()
+    }
+
+    pub fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let mut sorted = self.samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_unstable();
+
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+        sorted[rank.round() as usize]
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    cpu_frame: RollingStat,
+    acquire_wait: RollingStat,
+    submit: RollingStat,
+    present: RollingStat,
+    last_frame_start: Option<Instant>,
+    frame_count: u64,
+    culling: CullingStats,
+    current_culling: CullingStats,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            cpu_frame: RollingStat::new(DEFAULT_SAMPLE_COUNT),
+            acquire_wait: RollingStat::new(DEFAULT_SAMPLE_COUNT),
+            submit: RollingStat::new(DEFAULT_SAMPLE_COUNT),
+            present: RollingStat::new(DEFAULT_SAMPLE_COUNT),
+            last_frame_start: None,
+            frame_count: 0,
+            culling: CullingStats::default(),
+            current_culling: CullingStats::default(),
+        }
+    }
+
+    pub fn begin_frame(&mut self, now: Instant) {
+        if let Some(last_frame_start) = self.last_frame_start {
+            self.cpu_frame.push(now - last_frame_start);
+        }
+        self.last_frame_start = Some(now);
+        self.frame_count += 1;
+        self.culling = self.current_culling;
+        self.current_culling = CullingStats::default();
+    }
+
+    /// Adds the objects culled by a render system to the counts of the current frame.
+    pub fn record_culling(&mut self, stats: CullingStats) {
+        self.current_culling.visible += stats.visible;
+        self.current_culling.culled += stats.culled;
+    }
+
+    pub fn record_acquire_wait(&mut self, duration: Duration) {
+        self.acquire_wait.push(duration);
+    }
+
+    pub fn record_submit(&mut self, duration: Duration) {
+        self.submit.push(duration);
+    }
+
+    pub fn record_present(&mut self, duration: Duration) {
+        self.present.push(duration);
+    }
+
+    pub fn cpu_frame(&self) -> &RollingStat {
+        &self.cpu_frame
+    }
+
+    pub fn acquire_wait(&self) -> &RollingStat {
+        &self.acquire_wait
+    }
+
+    pub fn submit(&self) -> &RollingStat {
+        &self.submit
+    }
+
+    pub fn present(&self) -> &RollingStat {
+        &self.present
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Culling counts of the last completed frame.
+    pub fn culling(&self) -> CullingStats {
+        self.culling
+    }
+
+    pub fn fps(&self) -> f64 {
+        let average = self.cpu_frame.average();
+        if average.is_zero() {
+            0.0
+        } else {
+            1.0 / average.as_secs_f64()
+        }
+    }
+
+    pub fn summary(&self) -> String {
+        let line = |name: &str, stat: &RollingStat| {
+            format!(
+                "{:<8} min {:>7.3}ms avg {:>7.3}ms p99 {:>7.3}ms max {:>7.3}ms",
+                name,
+                stat.min().as_secs_f64() * 1000.0,
+                stat.average().as_secs_f64() * 1000.0,
+                stat.percentile(99.0).as_secs_f64() * 1000.0,
+                stat.max().as_secs_f64() * 1000.0,
+            )
+        };
+
+        format!(
+            "{:.1} fps\n{}\n{}\n{}\n{}\nculling  visible {} culled {}",
+            self.fps(),
+            line("frame", &self.cpu_frame),
+            line("acquire", &self.acquire_wait),
+            line("submit", &self.submit),
+            line("present", &self.present),
+            self.culling.visible,
+            self.culling.culled,
+        )
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct FrameLimiter {
+    target_frame_time: Option<Duration>,
+    next_frame: Instant,
+}
+
+impl FrameLimiter {
+    pub fn new(target_frame_rate: Option<f64>) -> Self {
+        let mut limiter = Self {
+            target_frame_time: None,
+            next_frame: Instant::now(),
+        };
+        limiter.set_target_frame_rate(target_frame_rate);
+        limiter
+    }
+
+    pub fn set_target_frame_rate(&mut self, target_frame_rate: Option<f64>) {
+        self.target_frame_time = target_frame_rate
+            .filter(|rate| *rate > 0.0)
+            .map(|rate| Duration::from_secs_f64(1.0 / rate));
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.target_frame_time.is_some()
+    }
+
+    /// Returns true when the next frame is due and schedules the one after it.
+    pub fn should_render(&mut self, now: Instant) -> bool {
+        let target_frame_time = match self.target_frame_time {
+            Some(target_frame_time) => target_frame_time,
+            None => return true,
+        };
+
+        if now < self.next_frame {
+            return false;
+        }
+
+        self.next_frame += target_frame_time;
+        // Do not try to catch up on frames missed after a long stall.
+        if self.next_frame < now {
+            self.next_frame = now + target_frame_time;
+        }
+        true
+    }
+
+    pub fn next_frame(&self) -> Instant {
+        self.next_frame
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(stat: &RollingStat, percentile: f64) -> u128 {
+        stat.percentile(percentile).as_millis()
+    }
+
+    #[test]
+    fn percentiles_pick_the_nearest_rank() {
+        let mut stat = RollingStat::new(8);
+        for ms in [5, 1, 4, 2, 3] {
+            stat.push(Duration::from_millis(ms));
+        }
+
+        assert_eq!(millis(&stat, 0.0), 1);
+        assert_eq!(millis(&stat, 50.0), 3);
+        assert_eq!(millis(&stat, 99.0), 5);
+        assert_eq!(millis(&stat, 100.0), 5);
+        // Out of range percentiles are clamped.
+        assert_eq!(millis(&stat, -10.0), 1);
+        assert_eq!(millis(&stat, 250.0), 5);
+    }
+
+    #[test]
+    fn rolling_stat_drops_the_oldest_sample() {
+        let mut stat = RollingStat::new(3);
+        for ms in [10, 1, 2, 3] {
+            stat.push(Duration::from_millis(ms));
+        }
+
+        assert_eq!(stat.max(), Duration::from_millis(3));
+        assert_eq!(stat.min(), Duration::from_millis(1));
+        assert_eq!(stat.average(), Duration::from_millis(2));
+        assert_eq!(stat.last(), Duration::from_millis(3));
+    }
+
+    #[test]
+    fn empty_stats_are_zero() {
+        let stats = FrameStats::new();
+
+        assert_eq!(stats.cpu_frame().percentile(99.0), Duration::ZERO);
+        assert_eq!(stats.cpu_frame().average(), Duration::ZERO);
+        assert_eq!(stats.fps(), 0.0);
+    }
+
+    #[test]
+    fn frame_times_are_measured_between_frame_starts() {
+        let start = Instant::now();
+        let mut stats = FrameStats::new();
+        for frame in 0..5 {
+            stats.begin_frame(start + Duration::from_millis(frame * 20));
+        }
+
+        assert_eq!(stats.frame_count(), 5);
+        assert_eq!(stats.cpu_frame().average(), Duration::from_millis(20));
+        assert!((stats.fps() - 50.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn culling_counts_show_after_the_frame() {
+        let mut stats = FrameStats::new();
+        stats.begin_frame(Instant::now());
+        stats.record_culling(CullingStats {
+            visible: 3,
+            culled: 1,
+        });
+        stats.record_culling(CullingStats {
+            visible: 2,
+            culled: 4,
+        });
+        assert_eq!(stats.culling().visible, 0);
+
+        stats.begin_frame(Instant::now());
+        assert_eq!(stats.culling().visible, 5);
+        assert_eq!(stats.culling().culled, 5);
+    }
+
+    #[test]
+    fn unlimited_limiter_always_renders() {
+        let mut limiter = FrameLimiter::new(None);
+        let now = Instant::now();
+
+        assert!(!limiter.is_enabled());
+        assert!(limiter.should_render(now));
+        assert!(limiter.should_render(now));
+        // Rates that are not positive disable the limiter too.
+        limiter.set_target_frame_rate(Some(0.0));
+        assert!(!limiter.is_enabled());
+    }
+
+    #[test]
+    fn limiter_waits_for_the_next_frame() {
+        let mut limiter = FrameLimiter::new(Some(100.0));
+        let start = limiter.next_frame();
+
+        assert!(limiter.should_render(start));
+        assert_eq!(limiter.next_frame(), start + Duration::from_millis(10));
+        assert!(!limiter.should_render(start + Duration::from_millis(5)));
+        assert!(limiter.should_render(start + Duration::from_millis(10)));
+        assert_eq!(limiter.next_frame(), start + Duration::from_millis(20));
+    }
+
+    #[test]
+    fn limiter_does_not_catch_up_after_a_stall() {
+        let mut limiter = FrameLimiter::new(Some(100.0));
+        let start = limiter.next_frame();
+        let stalled = start + Duration::from_secs(1);
+
+        assert!(limiter.should_render(stalled));
+        assert_eq!(limiter.next_frame(), stalled + Duration::from_millis(10));
+        assert!(!limiter.should_render(stalled + Duration::from_millis(1)));
+    }
+}
@@ -0,0 +1,353 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    BufferUsageFlags, CommandBuffer, CompareOp, CullModeFlags, DescriptorSet, DescriptorType,
+    DeviceSize, Extent2D, Extent3D, Filter, Format, ImageLayout, ImageType, ImageViewType,
+    IndexType, MemoryPropertyFlags, Offset2D, Rect2DBuilder, RenderPass, SampleCountFlagBits,
+    SamplerAddressMode, ShaderStageFlags, VertexInputAttributeDescriptionBuilder,
+    VertexInputBindingDescriptionBuilder, VertexInputRate, WHOLE_SIZE,
+};
+use imgui::{Context, DrawCmd, DrawCmdParams, DrawData, DrawIdx, DrawVert, TextureId};
+
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::graphics::pipeline::{BlendMode, VRTPipeline};
+use crate::vrt::graphics::render_target::set_viewport_and_scissor;
+use crate::vrt::utils::result::VkResult;
+
+const VERTEX_SHADER: &str = "./assets/shaders/gui_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/gui_frag.spirv";
+/// Texture id of the font atlas, the only texture the system knows.
+const FONT_TEXTURE_ID: usize = 0;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GuiPush {
+    scale_translate: [f32; 4],
+    params: [f32; 4],
+}
+
+/// Host visible geometry of one frame in flight, grown when a frame does not fit.
+struct GeometryBuffers {
+    vertices: VRTBuffer,
+    vertex_capacity: usize,
+    indices: VRTBuffer,
+    index_capacity: usize,
+}
+
+/// Draws ImGui draw data: uploads the vertices and indices of all draw lists each frame, binds
+/// the font atlas and clips every command to its scissor rectangle.
+///
+/// ImGui's raw callbacks are not supported and skipped.
+pub struct GuiRenderSystem {
+    pipeline: VRTPipeline,
+    geometry: Vec<Option<GeometryBuffers>>,
+    linear_output: bool,
+    font_set: DescriptorSet,
+    _font_texture: VRTImage,
+    _sampler: VRTSampler,
+    _descriptor_pool: VRTDescriptorPool,
+    _descriptor_set_layout: VRTDescriptorSetLayout,
+    device: Arc<VRTDevice>,
+}
+
+impl GuiRenderSystem {
+    /// Builds the font atlas of `context`. `linear_output` linearizes ImGui's sRGB colors for
+    /// render passes that encode sRGB in hardware.
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        context: &mut Context,
+        linear_output: bool,
+    ) -> VkResult<Self> {
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .add_binding(1, DescriptorType::SAMPLER, ShaderStageFlags::FRAGMENT, None)
+            .build();
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(1)
+            .add_pool_size(DescriptorType::SAMPLED_IMAGE, 1)
+            .add_pool_size(DescriptorType::SAMPLER, 1)
+            .build()?;
+
+        let fonts = context.fonts();
+        let atlas = fonts.build_rgba32_texture();
+        let font_texture = VRTImage::from_pixels(
+            device.clone(),
+            ImageType::_2D,
+            ImageViewType::_2D,
+            Extent3D {
+                width: atlas.width,
+                height: atlas.height,
+                depth: 1,
+            },
+            Format::R8G8B8A8_UNORM,
+            atlas.data,
+        )?;
+        fonts.tex_id = TextureId::new(FONT_TEXTURE_ID);
+
+        let sampler = VRTSampler::new(
+            device.clone(),
+            Filter::LINEAR,
+            SamplerAddressMode::CLAMP_TO_EDGE,
+        )?;
+        let font_set = VRTDescriptorWriter::new(&descriptor_set_layout, &descriptor_pool)
+            .write_image(
+                0,
+                font_texture.get_image_view(),
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )
+            .write_sampler(1, sampler.get_sampler())
+            .build()?;
+
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.set_vertex_input(
+            vec![VertexInputBindingDescriptionBuilder::new()
+                .binding(0)
+                .stride(mem::size_of::<DrawVert>() as u32)
+                .input_rate(VertexInputRate::VERTEX)],
+            [
+                (Format::R32G32_SFLOAT, 0),
+                (Format::R32G32_SFLOAT, 8),
+                (Format::R8G8B8A8_UNORM, 16),
+            ]
+            .into_iter()
+            .enumerate()
+            .map(|(location, (format, offset))| {
+                VertexInputAttributeDescriptionBuilder::new()
+                    .binding(0)
+                    .location(location as u32)
+                    .format(format)
+                    .offset(offset)
+            })
+            .collect(),
+        );
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_depth_test(false, false, CompareOp::ALWAYS);
+        config_info.set_blend_mode(BlendMode::Alpha);
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info
+            .set_descriptor_set_layouts(&[descriptor_set_layout.get_descriptor_set_layout()]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX,
+            0,
+            mem::size_of::<GuiPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        Ok(Self {
+            pipeline,
+            geometry: (0..MAX_FRAMES_IN_FLIGHT).map(|_| None).collect(),
+            linear_output,
+            font_set,
+            _font_texture: font_texture,
+            _sampler: sampler,
+            _descriptor_pool: descriptor_pool,
+            _descriptor_set_layout: descriptor_set_layout,
+            device,
+        })
+    }
+
+    /// Records `draw_data` into a render pass over the whole framebuffer, leaving the viewport
+    /// and scissor covering it again afterwards.
+    pub fn render(
+        &mut self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        draw_data: &DrawData,
+    ) {
+        let framebuffer = Extent2D {
+            width: (draw_data.display_size[0] * draw_data.framebuffer_scale[0]) as u32,
+            height: (draw_data.display_size[1] * draw_data.framebuffer_scale[1]) as u32,
+        };
+        if framebuffer.width == 0 || framebuffer.height == 0 || draw_data.total_vtx_count == 0 {
+            return;
+        }
+
+        self.upload(frame_index, draw_data);
+        let geometry = match &self.geometry[frame_index] {
+            Some(geometry) => geometry,
+            None => return,
+        };
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[self.font_set]);
+        let scale = [
+            2.0 / draw_data.display_size[0],
+            2.0 / draw_data.display_size[1],
+        ];
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::VERTEX,
+            &GuiPush {
+                scale_translate: [
+                    scale[0],
+                    scale[1],
+                    -1.0 - draw_data.display_pos[0] * scale[0],
+                    -1.0 - draw_data.display_pos[1] * scale[1],
+                ],
+                params: [self.linear_output as u32 as f32, 0.0, 0.0, 0.0],
+            },
+        );
+        unsafe {
+            let device = self.device.get_device_ptr();
+            device.cmd_bind_vertex_buffers(
+                command_buffer,
+                0,
+                &[geometry.vertices.get_buffer()],
+                &[0],
+            );
+            device.cmd_bind_index_buffer(
+                command_buffer,
+                geometry.indices.get_buffer(),
+                0,
+                IndexType::UINT16,
+            );
+        }
+
+        let (mut vertex_base, mut index_base) = (0, 0);
+        for draw_list in draw_data.draw_lists() {
+            for command in draw_list.commands() {
+                if let DrawCmd::Elements {
+                    count,
+                    cmd_params:
+                        DrawCmdParams {
+                            clip_rect,
+                            vtx_offset,
+                            idx_offset,
+                            ..
+                        },
+                } = command
+                {
+                    let scissor = match Self::scissor(draw_data, clip_rect, framebuffer) {
+                        Some(scissor) => scissor,
+                        None => continue,
+                    };
+                    unsafe {
+                        let device = self.device.get_device_ptr();
+                        device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+                        device.cmd_draw_indexed(
+                            command_buffer,
+                            count as u32,
+                            1,
+                            (index_base + idx_offset) as u32,
+                            (vertex_base + vtx_offset) as i32,
+                            0,
+                        );
+                    }
+                }
+            }
+            vertex_base += draw_list.vtx_buffer().len();
+            index_base += draw_list.idx_buffer().len();
+        }
+
+        set_viewport_and_scissor(&self.device, command_buffer, framebuffer);
+    }
+
+    /// The clip rectangle in framebuffer pixels, `None` when nothing of it is visible.
+    fn scissor(
+        draw_data: &DrawData,
+        clip_rect: [f32; 4],
+        framebuffer: Extent2D,
+    ) -> Option<Rect2DBuilder<'static>> {
+        let [scale_x, scale_y] = draw_data.framebuffer_scale;
+        let [offset_x, offset_y] = draw_data.display_pos;
+        let min_x = ((clip_rect[0] - offset_x) * scale_x).max(0.0);
+        let min_y = ((clip_rect[1] - offset_y) * scale_y).max(0.0);
+        let max_x = ((clip_rect[2] - offset_x) * scale_x).min(framebuffer.width as f32);
+        let max_y = ((clip_rect[3] - offset_y) * scale_y).min(framebuffer.height as f32);
+        if max_x <= min_x || max_y <= min_y {
+            return None;
+        }
+
+        Some(
+            Rect2DBuilder::new()
+                .offset(Offset2D {
+                    x: min_x as i32,
+                    y: min_y as i32,
+                })
+                .extent(Extent2D {
+                    width: (max_x - min_x) as u32,
+                    height: (max_y - min_y) as u32,
+                }),
+        )
+    }
+
+    /// Copies all draw lists back to back into the buffers of `frame_index`.
+    fn upload(&mut self, frame_index: usize, draw_data: &DrawData) {
+        let vertex_count = draw_data.total_vtx_count as usize;
+        let index_count = draw_data.total_idx_count as usize;
+        let fits = self.geometry[frame_index].as_ref().is_some_and(|geometry| {
+            geometry.vertex_capacity >= vertex_count && geometry.index_capacity >= index_count
+        });
+        if !fits {
+            let vertex_capacity = vertex_count.next_power_of_two();
+            let index_capacity = index_count.next_power_of_two();
+            self.geometry[frame_index] = Some(GeometryBuffers {
+                vertices: self
+                    .create_buffer::<DrawVert>(vertex_capacity, BufferUsageFlags::VERTEX_BUFFER),
+                vertex_capacity,
+                indices: self
+                    .create_buffer::<DrawIdx>(index_capacity, BufferUsageFlags::INDEX_BUFFER),
+                index_capacity,
+            });
+        }
+
+        let geometry = self.geometry[frame_index].as_ref().unwrap();
+        let vertices = geometry.vertices.map(WHOLE_SIZE, 0);
+        let indices = geometry.indices.map(WHOLE_SIZE, 0);
+        let (mut vertex_offset, mut index_offset) = (0, 0);
+        for draw_list in draw_data.draw_lists() {
+            let (vertex_data, index_data) = (draw_list.vtx_buffer(), draw_list.idx_buffer());
+            geometry.vertices.write_to_buffer(
+                vertex_data.as_ptr(),
+                vertices,
+                vertex_data.len() as DeviceSize,
+                vertex_offset,
+            );
+            geometry.indices.write_to_buffer(
+                index_data.as_ptr(),
+                indices,
+                index_data.len() as DeviceSize,
+                index_offset,
+            );
+            vertex_offset += mem::size_of_val(vertex_data) as DeviceSize;
+            index_offset += mem::size_of_val(index_data) as DeviceSize;
+        }
+        geometry.vertices.unmap();
+        geometry.indices.unmap();
+    }
+
+    fn create_buffer<T>(&self, capacity: usize, usage: BufferUsageFlags) -> VRTBuffer {
+        VRTBuffer::new(
+            self.device.clone(),
+            mem::size_of::<T>() as DeviceSize,
+            capacity as u32,
+            usage,
+            MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+            None,
+        )
+    }
+}
@@ -0,0 +1,71 @@
+pub mod gui_render_system;
+pub mod platform;
+
+use std::time::Instant;
+
+use imgui::{Context, DrawData, FontConfig, FontSource, Ui};
+use winit::event::WindowEvent;
+
+use self::platform::GuiPlatform;
+use crate::VRTWindow;
+
+/// The Dear ImGui context of a window and its platform adapter.
+///
+/// Events go in through `handle_event`, a frame's widgets are built on the `Ui` of `frame` and
+/// `render` ends the frame with its draw data for `GuiRenderSystem`.
+pub struct Gui {
+    context: Context,
+    platform: GuiPlatform,
+    last_frame: Instant,
+}
+
+impl Gui {
+    pub fn new(window: &VRTWindow) -> Self {
+        let mut context = Context::create();
+        context.set_ini_filename(None);
+
+        let platform = GuiPlatform::new(context.io_mut(), window.get_window_ptr());
+        let scale_factor = window.get_window_ptr().scale_factor() as f32;
+        context.fonts().add_font(&[FontSource::DefaultFontData {
+            config: Some(FontConfig {
+                size_pixels: (13.0 * scale_factor).round(),
+                ..FontConfig::default()
+            }),
+        }]);
+        // Rasterized for the framebuffer, drawn in logical pixels.
+        context.io_mut().font_global_scale = 1.0 / scale_factor;
+
+        Self {
+            context,
+            platform,
+            last_frame: Instant::now(),
+        }
+    }
+
+    pub fn context_mut(&mut self) -> &mut Context {
+        &mut self.context
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        self.platform.handle_event(self.context.io_mut(), event);
+    }
+
+    /// Whether a text field has focus, so the app should ignore key presses.
+    pub fn wants_keyboard(&self) -> bool {
+        self.context.io().want_capture_keyboard
+    }
+
+    /// Starts a frame, `render` must end it before the next one.
+    pub fn frame(&mut self, window: &VRTWindow) -> &mut Ui {
+        let now = Instant::now();
+        let io = self.context.io_mut();
+        io.update_delta_time(now - self.last_frame);
+        self.platform.prepare_frame(io, window.get_window_ptr());
+        self.last_frame = now;
+        self.context.new_frame()
+    }
+
+    pub fn render(&mut self) -> &DrawData {
+        self.context.render()
+    }
+}
@@ -0,0 +1,125 @@
+use imgui::{Io, Key, MouseButton as GuiMouseButton};
+use winit::event::{
+    ElementState, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+use winit::window::Window;
+
+/// Feeds winit window events into the ImGui IO, in logical pixels scaled by the window's scale
+/// factor.
+#[derive(Debug, Clone)]
+pub struct GuiPlatform {
+    scale_factor: f64,
+}
+
+impl GuiPlatform {
+    pub fn new(io: &mut Io, window: &Window) -> Self {
+        let platform = Self {
+            scale_factor: window.scale_factor(),
+        };
+        platform.prepare_frame(io, window);
+        platform
+    }
+
+    /// Updates the display size before a new ImGui frame.
+    pub fn prepare_frame(&self, io: &mut Io, window: &Window) {
+        let size = window.inner_size().to_logical::<f32>(self.scale_factor);
+        io.display_size = [size.width, size.height];
+        io.display_framebuffer_scale = [self.scale_factor as f32; 2];
+    }
+
+    pub fn handle_event(&mut self, io: &mut Io, event: &WindowEvent) {
+        match *event {
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                self.scale_factor = scale_factor;
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = position.to_logical::<f32>(self.scale_factor);
+                io.add_mouse_pos_event([position.x, position.y]);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                io.add_mouse_pos_event([f32::MAX, f32::MAX]);
+            }
+            WindowEvent::MouseInput { state, button, .. } => {
+                let button = match button {
+                    MouseButton::Left => GuiMouseButton::Left,
+                    MouseButton::Right => GuiMouseButton::Right,
+                    MouseButton::Middle => GuiMouseButton::Middle,
+                    MouseButton::Other(0) => GuiMouseButton::Extra1,
+                    MouseButton::Other(1) => GuiMouseButton::Extra2,
+                    MouseButton::Other(_) => return,
+                };
+                io.add_mouse_button_event(button, state == ElementState::Pressed);
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                let wheel = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [x, y],
+                    // Touchpads report pixels, ImGui expects about one unit per notch.
+                    MouseScrollDelta::PixelDelta(position) => {
+                        let position = position.to_logical::<f32>(self.scale_factor);
+                        [position.x / 20.0, position.y / 20.0]
+                    }
+                };
+                io.add_mouse_wheel_event(wheel);
+            }
+            WindowEvent::ModifiersChanged(modifiers) => Self::set_modifiers(io, modifiers),
+            WindowEvent::KeyboardInput { input, .. } => {
+                if let Some(key) = input.virtual_keycode.and_then(Self::key) {
+                    io.add_key_event(key, input.state == ElementState::Pressed);
+                }
+            }
+            WindowEvent::ReceivedCharacter(character) if !character.is_control() => {
+                io.add_input_character(character);
+            }
+            WindowEvent::Focused(false) => Self::set_modifiers(io, ModifiersState::empty()),
+            _ => (),
+        }
+    }
+
+    fn set_modifiers(io: &mut Io, modifiers: ModifiersState) {
+        io.add_key_event(Key::ModCtrl, modifiers.ctrl());
+        io.add_key_event(Key::ModShift, modifiers.shift());
+        io.add_key_event(Key::ModAlt, modifiers.alt());
+        io.add_key_event(Key::ModSuper, modifiers.logo());
+    }
+
+    /// Keys used by ImGui's text editing, navigation and shortcuts.
+    fn key(key: VirtualKeyCode) -> Option<Key> {
+        use VirtualKeyCode as Code;
+
+        Some(match key {
+            Code::Tab => Key::Tab,
+            Code::Left => Key::LeftArrow,
+            Code::Right => Key::RightArrow,
+            Code::Up => Key::UpArrow,
+            Code::Down => Key::DownArrow,
+            Code::PageUp => Key::PageUp,
+            Code::PageDown => Key::PageDown,
+            Code::Home => Key::Home,
+            Code::End => Key::End,
+            Code::Insert => Key::Insert,
+            Code::Delete => Key::Delete,
+            Code::Back => Key::Backspace,
+            Code::Space => Key::Space,
+            Code::Return => Key::Enter,
+            Code::NumpadEnter => Key::KeypadEnter,
+            Code::Escape => Key::Escape,
+            Code::LControl => Key::LeftCtrl,
+            Code::RControl => Key::RightCtrl,
+            Code::LShift => Key::LeftShift,
+            Code::RShift => Key::RightShift,
+            Code::LAlt => Key::LeftAlt,
+            Code::RAlt => Key::RightAlt,
+            Code::LWin => Key::LeftSuper,
+            Code::RWin => Key::RightSuper,
+            Code::Minus => Key::Minus,
+            Code::Period => Key::Period,
+            Code::A => Key::A,
+            Code::C => Key::C,
+            Code::V => Key::V,
+            Code::X => Key::X,
+            Code::Y => Key::Y,
+            Code::Z => Key::Z,
+            _ => return None,
+        })
+    }
+}
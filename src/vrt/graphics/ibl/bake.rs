@@ -0,0 +1,317 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, DescriptorSet, DescriptorType, Extent3D, Filter, ImageAspectFlags,
+    ImageBlitBuilder, ImageLayout, ImageSubresourceLayersBuilder, ImageType, ImageView,
+    ImageViewType, Offset3D, ShaderStageFlags,
+};
+
+use super::hdr::HdrImage;
+use super::{IblImages, IblSettings, ENVIRONMENT_FORMAT};
+use crate::vrt::device::descriptors::layout::VRTDescriptorSetLayoutBuilder;
+use crate::vrt::device::descriptors::pool::VRTDescriptorPoolBuilder;
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::graphics::compute_pipeline::VRTComputePipeline;
+use crate::vrt::utils::result::VkResult;
+
+const EQUIRECT_SHADER: &str = "./assets/shaders/ibl_equirect_comp.spirv";
+const IRRADIANCE_SHADER: &str = "./assets/shaders/ibl_irradiance_comp.spirv";
+const PREFILTER_SHADER: &str = "./assets/shaders/ibl_prefilter_comp.spirv";
+const BRDF_SHADER: &str = "./assets/shaders/ibl_brdf_comp.spirv";
+const WORKGROUP_SIZE: u32 = 8;
+
+/// Matches the `Push` block of the IBL compute shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct BakePush {
+    params: [f32; 4],
+}
+
+/// Storage views of single mip levels, destroyed once baking finished or failed.
+struct LevelViews {
+    device: Arc<VRTDevice>,
+    views: Vec<ImageView>,
+}
+
+impl LevelViews {
+    fn create(
+        &mut self,
+        image: &VRTImage,
+        view_type: ImageViewType,
+        mip_level: u32,
+    ) -> VkResult<ImageView> {
+        let view = image.create_view(view_type, mip_level, 0, image.get_array_layers())?;
+        self.views.push(view);
+        Ok(view)
+    }
+}
+
+impl Drop for LevelViews {
+    fn drop(&mut self) {
+        for view in self.views.drain(..) {
+            unsafe { self.device.get_device_ptr().destroy_image_view(view, None) };
+        }
+    }
+}
+
+/// Converts `source` into the environment cubemap and precomputes the irradiance map, the
+/// prefiltered specular mip chain and the BRDF lookup table. Blocks until the GPU finished and
+/// leaves every image ready for sampling.
+pub(super) fn bake(
+    device: &Arc<VRTDevice>,
+    source: &HdrImage,
+    settings: &IblSettings,
+    images: &IblImages,
+    sampler: &VRTSampler,
+) -> VkResult<()> {
+    let equirect = VRTImage::from_pixels(
+        device.clone(),
+        ImageType::_2D,
+        ImageViewType::_2D,
+        Extent3D {
+            width: source.get_width(),
+            height: source.get_height(),
+            depth: 1,
+        },
+        ENVIRONMENT_FORMAT,
+        &source.to_rgba16f_bytes(),
+    )?;
+
+    let layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+        .add_binding(
+            0,
+            DescriptorType::SAMPLED_IMAGE,
+            ShaderStageFlags::COMPUTE,
+            None,
+        )
+        .add_binding(1, DescriptorType::SAMPLER, ShaderStageFlags::COMPUTE, None)
+        .add_binding(
+            2,
+            DescriptorType::STORAGE_IMAGE,
+            ShaderStageFlags::COMPUTE,
+            None,
+        )
+        .build();
+    let set_count = 3 + settings.prefiltered_mip_levels;
+    let pool = VRTDescriptorPoolBuilder::new(device.clone())
+        .set_max_sets(set_count)
+        .add_pool_size(DescriptorType::SAMPLED_IMAGE, set_count)
+        .add_pool_size(DescriptorType::SAMPLER, set_count)
+        .add_pool_size(DescriptorType::STORAGE_IMAGE, set_count)
+        .build()?;
+    let write_set = |input: Option<ImageView>, output: ImageView| -> VkResult<DescriptorSet> {
+        let mut writer = VRTDescriptorWriter::new(&layout, &pool);
+        if let Some(input) = input {
+            writer
+                .write_image(0, input, ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                .write_sampler(1, sampler.get_sampler());
+        }
+        writer.write_image(2, output, ImageLayout::GENERAL).build()
+    };
+
+    let mut views = LevelViews {
+        device: device.clone(),
+        views: vec![],
+    };
+    let environment = &images.environment;
+    let environment_set = write_set(
+        Some(equirect.get_image_view()),
+        views.create(environment, ImageViewType::_2D_ARRAY, 0)?,
+    )?;
+    let irradiance_set = write_set(
+        Some(environment.get_image_view()),
+        views.create(&images.irradiance, ImageViewType::_2D_ARRAY, 0)?,
+    )?;
+    let prefiltered_sets = (0..settings.prefiltered_mip_levels)
+        .map(|mip_level| {
+            write_set(
+                Some(environment.get_image_view()),
+                views.create(&images.prefiltered, ImageViewType::_2D_ARRAY, mip_level)?,
+            )
+        })
+        .collect::<VkResult<Vec<_>>>()?;
+    let brdf_set = write_set(None, images.brdf_lut.get_image_view())?;
+
+    let create_pipeline = |shader: &str| {
+        VRTComputePipeline::new(
+            device.clone(),
+            shader,
+            &[layout.get_descriptor_set_layout()],
+            mem::size_of::<BakePush>() as u32,
+        )
+    };
+    let equirect_pipeline = create_pipeline(EQUIRECT_SHADER)?;
+    let irradiance_pipeline = create_pipeline(IRRADIANCE_SHADER)?;
+    let prefilter_pipeline = create_pipeline(PREFILTER_SHADER)?;
+    let brdf_pipeline = create_pipeline(BRDF_SHADER)?;
+
+    let environment_size = settings.environment_size as f32;
+    let sample_count = settings.sample_count as f32;
+    let command_buffer = device.begin_single_time_commands()?;
+
+    device.transition_image_layout(
+        command_buffer,
+        environment.get_image(),
+        environment.subresource_range().layer_count(6),
+        ImageLayout::UNDEFINED,
+        ImageLayout::GENERAL,
+    )?;
+    if environment.get_mip_levels() > 1 {
+        device.transition_image_layout(
+            command_buffer,
+            environment.get_image(),
+            environment
+                .full_subresource_range()
+                .base_mip_level(1)
+                .level_count(environment.get_mip_levels() - 1),
+            ImageLayout::UNDEFINED,
+            ImageLayout::TRANSFER_DST_OPTIMAL,
+        )?;
+    }
+    dispatch(
+        &equirect_pipeline,
+        command_buffer,
+        environment_set,
+        [0.0; 4],
+        settings.environment_size,
+        6,
+    );
+    generate_mips(device, command_buffer, environment)?;
+
+    for image in [&images.irradiance, &images.prefiltered, &images.brdf_lut] {
+        device.transition_image_layout(
+            command_buffer,
+            image.get_image(),
+            image.full_subresource_range(),
+            ImageLayout::UNDEFINED,
+            ImageLayout::GENERAL,
+        )?;
+    }
+    dispatch(
+        &irradiance_pipeline,
+        command_buffer,
+        irradiance_set,
+        [0.0, sample_count, environment_size, 0.0],
+        settings.irradiance_size,
+        6,
+    );
+    let roughness_step = 1.0 / (settings.prefiltered_mip_levels - 1).max(1) as f32;
+    for (mip_level, set) in prefiltered_sets.into_iter().enumerate() {
+        dispatch(
+            &prefilter_pipeline,
+            command_buffer,
+            set,
+            [
+                mip_level as f32 * roughness_step,
+                sample_count,
+                environment_size,
+                0.0,
+            ],
+            (settings.prefiltered_size >> mip_level).max(1),
+            6,
+        );
+    }
+    dispatch(
+        &brdf_pipeline,
+        command_buffer,
+        brdf_set,
+        [0.0, sample_count, 0.0, 0.0],
+        settings.brdf_lut_size,
+        1,
+    );
+    for image in [&images.irradiance, &images.prefiltered, &images.brdf_lut] {
+        device.transition_image_layout(
+            command_buffer,
+            image.get_image(),
+            image.full_subresource_range(),
+            ImageLayout::GENERAL,
+            ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        )?;
+    }
+
+    device.end_single_time_commands(command_buffer)
+}
+
+fn dispatch(
+    pipeline: &VRTComputePipeline,
+    command_buffer: CommandBuffer,
+    descriptor_set: DescriptorSet,
+    params: [f32; 4],
+    size: u32,
+    layers: u32,
+) {
+    let groups = size.div_ceil(WORKGROUP_SIZE);
+    pipeline.bind(command_buffer);
+    pipeline.bind_descriptor_sets(command_buffer, 0, &[descriptor_set]);
+    pipeline.push_constants(command_buffer, &BakePush { params });
+    pipeline.dispatch(command_buffer, groups, groups, layers);
+}
+
+/// Downsamples level 0, written by a compute pass in `GENERAL`, into the remaining levels that
+/// start out in `TRANSFER_DST_OPTIMAL`.
+fn generate_mips(
+    device: &VRTDevice,
+    command_buffer: CommandBuffer,
+    image: &VRTImage,
+) -> VkResult<()> {
+    let layers = image.get_array_layers();
+    device.transition_image_layout(
+        command_buffer,
+        image.get_image(),
+        image.subresource_range().layer_count(layers),
+        ImageLayout::GENERAL,
+        ImageLayout::TRANSFER_SRC_OPTIMAL,
+    )?;
+
+    let level_offset = |level: u32| Offset3D {
+        x: (image.get_extent().width >> level).max(1) as i32,
+        y: (image.get_extent().height >> level).max(1) as i32,
+        z: 1,
+    };
+    let level_layers = |level: u32| {
+        *ImageSubresourceLayersBuilder::new()
+            .aspect_mask(ImageAspectFlags::COLOR)
+            .mip_level(level)
+            .base_array_layer(0)
+            .layer_count(layers)
+    };
+    for level in 1..image.get_mip_levels() {
+        let blit = ImageBlitBuilder::new()
+            .src_subresource(level_layers(level - 1))
+            .src_offsets([Offset3D::default(), level_offset(level - 1)])
+            .dst_subresource(level_layers(level))
+            .dst_offsets([Offset3D::default(), level_offset(level)]);
+        unsafe {
+            device.get_device_ptr().cmd_blit_image(
+                command_buffer,
+                image.get_image(),
+                ImageLayout::TRANSFER_SRC_OPTIMAL,
+                image.get_image(),
+                ImageLayout::TRANSFER_DST_OPTIMAL,
+                std::slice::from_ref(&blit),
+                Filter::LINEAR,
+            );
+        }
+        device.transition_image_layout(
+            command_buffer,
+            image.get_image(),
+            image
+                .subresource_range()
+                .base_mip_level(level)
+                .layer_count(layers),
+            ImageLayout::TRANSFER_DST_OPTIMAL,
+            ImageLayout::TRANSFER_SRC_OPTIMAL,
+        )?;
+    }
+
+    device.transition_image_layout(
+        command_buffer,
+        image.get_image(),
+        image.full_subresource_range(),
+        ImageLayout::TRANSFER_SRC_OPTIMAL,
+        ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+    )
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::IblSettings;
+
+const MAGIC: &[u8; 8] = b"VRTIBL01";
+
+/// Cache file of a source image, named after it and a hash of its contents and the settings, so
+/// that edited sources or changed settings never pick up stale data.
+pub(super) fn cache_path(
+    cache_dir: &Path,
+    source: &Path,
+    bytes: &[u8],
+    settings: &IblSettings,
+) -> PathBuf {
+    let stem = source
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("environment");
+    let mut hash = fnv1a(0xcbf2_9ce4_8422_2325, bytes);
+    hash = fnv1a(hash, &header(settings));
+    cache_dir.join(format!("{}-{:016x}.ibl", stem, hash))
+}
+
+/// The baked texels, or `None` if the file is missing or does not match the settings.
+pub(super) fn read(path: &Path, settings: &IblSettings, expected_size: usize) -> Option<Vec<u8>> {
+    let bytes = fs::read(path).ok()?;
+    let header = header(settings);
+    let data = bytes.strip_prefix(header.as_slice())?;
+    (data.len() == expected_size).then(|| data.to_vec())
+}
+
+pub(super) fn write(path: &Path, settings: &IblSettings, data: &[u8]) -> io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    let mut bytes = header(settings);
+    bytes.extend_from_slice(data);
+    fs::write(path, bytes)
+}
+
+fn header(settings: &IblSettings) -> Vec<u8> {
+    let mut header = MAGIC.to_vec();
+    for value in [
+        settings.environment_size,
+        settings.irradiance_size,
+        settings.prefiltered_size,
+        settings.prefiltered_mip_levels,
+        settings.brdf_lut_size,
+        settings.sample_count,
+    ] {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header
+}
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
@@ -0,0 +1,681 @@
+use std::fs;
+use std::path::Path;
+
+use crate::vrt::utils::result::{VkError, VkResult};
+
+/// Largest image accepted from a file, 16k by 8k, so a corrupt header cannot claim gigabytes.
+const MAX_PIXELS: usize = 16384 * 8192;
+
+/// Linear RGB texels of an equirectangular environment, rows from the top.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HdrImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<[f32; 3]>,
+}
+
+impl HdrImage {
+    /// Reads a Radiance `.hdr` or an uncompressed OpenEXR `.exr` file, picked by extension.
+    pub fn load(path: impl AsRef<Path>) -> VkResult<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("exr") => Self::parse_exr(&bytes),
+            _ => Self::parse_hdr(&bytes),
+        }
+    }
+
+    /// Fills the image from `texel(u, v)`, with `u` and `v` at texel centers in [0, 1].
+    pub fn from_fn(width: u32, height: u32, texel: impl Fn(f32, f32) -> [f32; 3]) -> Self {
+        let pixels = (0..width * height)
+            .map(|index| {
+                let u = ((index % width) as f32 + 0.5) / width as f32;
+                let v = ((index / width) as f32 + 0.5) / height as f32;
+                texel(u, v)
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[[f32; 3]] {
+        &self.pixels
+    }
+
+    /// Texels as `R16G16B16A16_SFLOAT`, which unlike 32 bit floats is always linearly filterable.
+    pub fn to_rgba16f_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|[r, g, b]| [*r, *g, *b, 1.0])
+            .flat_map(|channel| f32_to_f16(channel).to_le_bytes())
+            .collect()
+    }
+
+    /// Radiance RGBE with flat, old style run length or adaptive run length encoded scanlines.
+    pub fn parse_hdr(bytes: &[u8]) -> VkResult<Self> {
+        let mut reader = ByteReader::new(bytes);
+        let magic = reader.line()?;
+        if !magic.starts_with("#?") {
+            return Err(invalid("missing Radiance header"));
+        }
+        loop {
+            let line = reader.line()?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some(format) = line.strip_prefix("FORMAT=") {
+                if format != "32-bit_rle_rgbe" {
+                    return Err(invalid(&format!("unsupported Radiance format {}", format)));
+                }
+            }
+        }
+
+        let resolution = reader.line()?;
+        let (height, width) = match resolution.split_whitespace().collect::<Vec<_>>()[..] {
+            ["-Y", height, "+X", width] => (height.parse::<u32>(), width.parse::<u32>()),
+            _ => return Err(invalid(&format!("unsupported orientation {}", resolution))),
+        };
+        let (width, height) = match (width, height) {
+            (Ok(width), Ok(height)) if width > 0 && height > 0 => (width, height),
+            _ => return Err(invalid("invalid Radiance resolution")),
+        };
+
+        let mut pixels = Vec::with_capacity(pixel_count(width, height)?);
+        let mut scanline = vec![[0u8; 4]; width as usize];
+        for _ in 0..height {
+            reader.rgbe_scanline(&mut scanline)?;
+            pixels.extend(scanline.iter().map(|&rgbe| rgbe_to_rgb(rgbe)));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Scanline OpenEXR without compression, reading the half, float or uint R, G and B channels.
+    pub fn parse_exr(bytes: &[u8]) -> VkResult<Self> {
+        const MAGIC: u32 = 20000630;
+
+        let mut reader = ByteReader::new(bytes);
+        if reader.u32()? != MAGIC {
+            return Err(invalid("missing OpenEXR magic number"));
+        }
+        let version = reader.u32()?;
+        if version & 0x1a00 != 0 {
+            return Err(invalid(
+                "tiled, deep or multi-part OpenEXR files are not supported",
+            ));
+        }
+
+        let mut channels = vec![];
+        let mut data_window = None;
+        let mut compression = None;
+        loop {
+            let name = reader.null_terminated()?;
+            if name.is_empty() {
+                break;
+            }
+            let _attribute_type = reader.null_terminated()?;
+            let size = reader.u32()? as usize;
+            let value = reader.take(size)?;
+            match name.as_str() {
+                "channels" => channels = parse_exr_channels(value)?,
+                "compression" => compression = value.first().copied(),
+                "dataWindow" => {
+                    let mut window = ByteReader::new(value);
+                    data_window = Some([
+                        window.u32()? as i32,
+                        window.u32()? as i32,
+                        window.u32()? as i32,
+                        window.u32()? as i32,
+                    ]);
+                }
+                _ => {}
+            }
+        }
+
+        if compression != Some(0) {
+            return Err(invalid(
+                "compressed OpenEXR files are not supported, save without compression",
+            ));
+        }
+        let [min_x, min_y, max_x, max_y] =
+            data_window.ok_or_else(|| invalid("OpenEXR data window missing"))?;
+        if max_x < min_x || max_y < min_y {
+            return Err(invalid("empty OpenEXR data window"));
+        }
+        // Computed wider than the i32 bounds, a window spanning them overflows otherwise.
+        let extent = |min: i32, max: i32| u32::try_from(i64::from(max) - i64::from(min) + 1);
+        let (width, height) = match (extent(min_x, max_x), extent(min_y, max_y)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => return Err(invalid("OpenEXR data window is too large")),
+        };
+        let pixel_count = pixel_count(width, height)?;
+        let rgb = ["R", "G", "B"].map(|name| channels.iter().position(|(n, _)| n == name));
+        if rgb.iter().all(Option::is_none) {
+            return Err(invalid("OpenEXR file has no R, G or B channel"));
+        }
+
+        // The offset table is skipped, uncompressed chunks hold a single scanline in file order.
+        reader.take(height as usize * 8)?;
+        let mut pixels = vec![[0.0; 3]; pixel_count];
+        for _ in 0..height {
+            let y = i64::from(reader.u32()? as i32) - i64::from(min_y);
+            let _size = reader.u32()?;
+            let row = usize::try_from(y)
+                .ok()
+                .and_then(|y| pixels.chunks_exact_mut(width as usize).nth(y))
+                .ok_or_else(|| invalid("OpenEXR scanline outside of the data window"))?;
+            for (channel, (_, pixel_type)) in channels.iter().enumerate() {
+                let target = rgb.iter().position(|index| *index == Some(channel));
+                for texel in row.iter_mut() {
+                    let value = match pixel_type {
+                        0 => reader.u32()? as f32,
+                        1 => f16_to_f32(reader.u16()?),
+                        2 => f32::from_bits(reader.u32()?),
+                        _ => return Err(invalid("unknown OpenEXR pixel type")),
+                    };
+                    if let Some(target) = target {
+                        texel[target] = value;
+                    }
+                }
+            }
+        }
+
+        // Single channel files are treated as luminance.
+        if rgb[1].is_none() && rgb[2].is_none() {
+            pixels.iter_mut().for_each(|texel| *texel = [texel[0]; 3]);
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+fn invalid(reason: &str) -> VkError {
+    VkError::InvalidAsset(reason.to_string())
+}
+
+fn pixel_count(width: u32, height: u32) -> VkResult<usize> {
+    (width as usize)
+        .checked_mul(height as usize)
+        .filter(|count| *count <= MAX_PIXELS)
+        .ok_or_else(|| invalid(&format!("image of {}x{} is too large", width, height)))
+}
+
+fn parse_exr_channels(bytes: &[u8]) -> VkResult<Vec<(String, u32)>> {
+    let mut reader = ByteReader::new(bytes);
+    let mut channels = vec![];
+    loop {
+        let name = reader.null_terminated()?;
+        if name.is_empty() {
+            return Ok(channels);
+        }
+        let pixel_type = reader.u32()?;
+        // pLinear, reserved bytes and the x and y sampling.
+        reader.take(12)?;
+        channels.push((name, pixel_type));
+    }
+}
+
+fn rgbe_to_rgb([r, g, b, e]: [u8; 4]) -> [f32; 3] {
+    if e == 0 {
+        return [0.0; 3];
+    }
+    let scale = 2f32.powi(e as i32 - 136);
+    [r as f32 * scale, g as f32 * scale, b as f32 * scale]
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half as u32) & 0x8000) << 16;
+    let exponent = ((half >> 10) & 0x1f) as u32;
+    let mantissa = (half & 0x3ff) as u32;
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        (0, _) => {
+            // Subnormal, renormalized for the wider exponent.
+            let shift = mantissa.leading_zeros() - 21;
+            sign | ((113 - shift) << 23) | ((mantissa << shift) & 0x3ff) << 13
+        }
+        (0x1f, _) => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// Rounds to nearest, overflowing to infinity and flushing values below the half range to zero.
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let half_exponent = exponent - 112;
+    if half_exponent >= 0x1f {
+        return sign | 0x7c00;
+    }
+    if half_exponent <= 0 {
+        if half_exponent < -10 {
+            return sign;
+        }
+        let mantissa = (mantissa | 0x80_0000) >> (1 - half_exponent);
+        return sign | ((mantissa + 0x1000) >> 13) as u16;
+    }
+    let rounded = ((half_exponent as u32) << 10 | mantissa >> 13) + ((mantissa >> 12) & 1);
+    sign | rounded.min(0x7c00) as u16
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+
+    fn take(&mut self, count: usize) -> VkResult<&'a [u8]> {
+        let end = self
+            .position
+            .checked_add(count)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| invalid("unexpected end of image data"))?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> VkResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> VkResult<u16> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> VkResult<u32> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn line(&mut self) -> VkResult<String> {
+        let remaining = &self.bytes[self.position..];
+        let length = remaining
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or_else(|| invalid("unexpected end of header"))?;
+        let line = String::from_utf8_lossy(self.take(length)?)
+            .trim()
+            .to_string();
+        self.take(1)?;
+        Ok(line)
+    }
+
+    fn null_terminated(&mut self) -> VkResult<String> {
+        let remaining = &self.bytes[self.position..];
+        let length = remaining
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| invalid("unterminated string"))?;
+        let string = String::from_utf8_lossy(self.take(length)?).to_string();
+        self.take(1)?;
+        Ok(string)
+    }
+
+    fn rgbe(&mut self) -> VkResult<[u8; 4]> {
+        let bytes = self.take(4)?;
+        Ok([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    fn rgbe_scanline(&mut self, scanline: &mut [[u8; 4]]) -> VkResult<()> {
+        let width = scanline.len();
+        let start = self.position;
+        let first = self.rgbe()?;
+        let adaptive = (8..0x8000).contains(&width)
+            && first[0] == 2
+            && first[1] == 2
+            && ((first[2] as usize) << 8 | first[3] as usize) == width;
+
+        if !adaptive {
+            self.position = start;
+            return self.flat_scanline(scanline);
+        }
+
+        // Every channel is encoded separately as runs (> 128) and literal spans.
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = self.u8()? as usize;
+                if count > 128 {
+                    let count = count - 128;
+                    let value = self.u8()?;
+                    if x + count > width {
+                        return Err(invalid("Radiance run exceeds the scanline"));
+                    }
+                    scanline[x..x + count]
+                        .iter_mut()
+                        .for_each(|texel| texel[channel] = value);
+                    x += count;
+                } else {
+                    if count == 0 || x + count > width {
+                        return Err(invalid("invalid Radiance span"));
+                    }
+                    for (texel, value) in scanline[x..x + count].iter_mut().zip(self.take(count)?) {
+                        texel[channel] = *value;
+                    }
+                    x += count;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flat_scanline(&mut self, scanline: &mut [[u8; 4]]) -> VkResult<()> {
+        let mut x = 0;
+        let mut shift = 0;
+        while x < scanline.len() {
+            let texel = self.rgbe()?;
+            // Old style runs repeat the previous texel, consecutive runs multiply by 256.
+            if texel[0] == 1 && texel[1] == 1 && texel[2] == 1 && x > 0 {
+                // Four runs in a row already count past any scanline width.
+                if shift > 24 {
+                    return Err(invalid("Radiance run exceeds the scanline"));
+                }
+                let count = (texel[3] as usize) << shift;
+                if x + count > scanline.len() {
+                    return Err(invalid("Radiance run exceeds the scanline"));
+                }
+                let previous = scanline[x - 1];
+                scanline[x..x + count].fill(previous);
+                x += count;
+                shift += 8;
+            } else {
+                scanline[x] = texel;
+                x += 1;
+                shift = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn radiance(width: u32, height: u32, data: &[u8]) -> Vec<u8> {
+        let mut bytes = format!(
+            "#?RADIANCE\n# made by hand\nFORMAT=32-bit_rle_rgbe\n\n-Y {} +X {}\n",
+            height, width
+        )
+        .into_bytes();
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    /// An OpenEXR header with `channels` as name and pixel type, in file order.
+    fn openexr_header(channels: &[(&str, u32)], compression: u8, data_window: [i32; 4]) -> Vec<u8> {
+        fn attribute(bytes: &mut Vec<u8>, name: &str, attribute_type: &str, value: &[u8]) {
+            for string in [name, attribute_type] {
+                bytes.extend(string.as_bytes());
+                bytes.push(0);
+            }
+            bytes.extend((value.len() as u32).to_le_bytes());
+            bytes.extend(value);
+        }
+
+        let mut channel_list = vec![];
+        for (name, pixel_type) in channels {
+            channel_list.extend(name.as_bytes());
+            channel_list.push(0);
+            channel_list.extend(pixel_type.to_le_bytes());
+            channel_list.extend([0; 4]);
+            channel_list.extend([1u32, 1].iter().flat_map(|sampling| sampling.to_le_bytes()));
+        }
+        channel_list.push(0);
+        let window = data_window
+            .iter()
+            .flat_map(|bound| bound.to_le_bytes())
+            .collect::<Vec<_>>();
+
+        let mut bytes = [20000630u32, 2]
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect::<Vec<_>>();
+        attribute(&mut bytes, "channels", "chlist", &channel_list);
+        attribute(&mut bytes, "compression", "compression", &[compression]);
+        attribute(&mut bytes, "dataWindow", "box2i", &window);
+        bytes.push(0);
+        bytes
+    }
+
+    /// An uncompressed OpenEXR file with one `(y, channel data)` chunk per scanline.
+    fn openexr(
+        channels: &[(&str, u32)],
+        data_window: [i32; 4],
+        rows: &[(i32, Vec<u8>)],
+    ) -> Vec<u8> {
+        let mut bytes = openexr_header(channels, 0, data_window);
+        bytes.extend(vec![0; rows.len() * 8]);
+        for (y, data) in rows {
+            bytes.extend(y.to_le_bytes());
+            bytes.extend((data.len() as u32).to_le_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+
+    fn halves(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| f32_to_f16(*value).to_le_bytes())
+            .collect()
+    }
+
+    fn floats(values: &[f32]) -> Vec<u8> {
+        values
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect()
+    }
+
+    fn assert_invalid(result: VkResult<HdrImage>) {
+        assert!(matches!(result, Err(VkError::InvalidAsset(_))));
+    }
+
+    #[test]
+    fn reads_flat_scanlines() {
+        let image = HdrImage::parse_hdr(&radiance(
+            2,
+            2,
+            &[
+                128, 64, 0, 129, 0, 0, 0, 0, 128, 128, 128, 130, 0, 0, 128, 128,
+            ],
+        ))
+        .unwrap();
+
+        assert_eq!(image.width, 2);
+        assert_eq!(image.height, 2);
+        assert_eq!(
+            image.pixels,
+            &[[1.0, 0.5, 0.0], [0.0; 3], [2.0, 2.0, 2.0], [0.0, 0.0, 0.5]]
+        );
+    }
+
+    #[test]
+    fn repeats_old_style_runs() {
+        let image = HdrImage::parse_hdr(&radiance(4, 1, &[128, 64, 0, 129, 1, 1, 1, 3])).unwrap();
+
+        assert_eq!(image.pixels, &[[1.0, 0.5, 0.0]; 4]);
+    }
+
+    #[test]
+    fn decodes_adaptive_run_length_scanlines() {
+        #[rustfmt::skip]
+        let data = [
+            2, 2, 0, 8,
+            // Red as one run, green as a literal span and a run, blue and the exponent as runs.
+            136, 128,
+            3, 32, 64, 96, 133, 128,
+            136, 0,
+            136, 129,
+        ];
+        let image = HdrImage::parse_hdr(&radiance(8, 1, &data)).unwrap();
+
+        let green = image.pixels.iter().map(|[_, g, _]| *g).collect::<Vec<_>>();
+        assert_eq!(green, [0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0, 1.0]);
+        assert!(image.pixels.iter().all(|[r, _, b]| *r == 1.0 && *b == 0.0));
+    }
+
+    #[test]
+    fn rejects_invalid_headers() {
+        assert_invalid(HdrImage::parse_hdr(b"P6\n2 2\n255\n"));
+        assert_invalid(HdrImage::parse_hdr(
+            b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0",
+        ));
+        assert_invalid(HdrImage::parse_hdr(b"#?RADIANCE\n\n+Y 1 +X 1\n\0\0\0\0"));
+        assert_invalid(HdrImage::parse_hdr(b"#?RADIANCE\n\n-Y 0 +X 1\n"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_overlong_scanlines() {
+        assert_invalid(HdrImage::parse_hdr(&radiance(2, 2, &[128, 64, 0, 129])));
+        assert_invalid(HdrImage::parse_hdr(&radiance(
+            2,
+            1,
+            &[128, 64, 0, 129, 1, 1, 1, 2],
+        )));
+        assert_invalid(HdrImage::parse_hdr(&radiance(
+            8,
+            1,
+            &[2, 2, 0, 8, 137, 128],
+        )));
+    }
+
+    #[test]
+    fn rejects_oversized_images() {
+        assert_invalid(HdrImage::parse_hdr(&radiance(100_000, 100_000, &[])));
+        assert_invalid(HdrImage::parse_hdr(&radiance(u32::MAX, u32::MAX, &[])));
+    }
+
+    #[test]
+    fn rejects_endless_old_style_runs() {
+        let mut data = vec![128, 64, 0, 129];
+        for _ in 0..16 {
+            data.extend([1, 1, 1, 0]);
+        }
+        assert_invalid(HdrImage::parse_hdr(&radiance(4, 1, &data)));
+    }
+
+    #[test]
+    fn reads_uncompressed_openexr_scanlines() {
+        let channels = [("B", 1), ("G", 1), ("R", 1)];
+        let row = [
+            halves(&[0.5, 0.0]),
+            halves(&[1.0, 0.25]),
+            halves(&[2.0, 1.0]),
+        ]
+        .concat();
+        let image = HdrImage::parse_exr(&openexr(&channels, [0, 0, 1, 0], &[(0, row)])).unwrap();
+
+        assert_eq!(image.width, 2);
+        assert_eq!(image.height, 1);
+        assert_eq!(image.pixels, &[[2.0, 1.0, 0.5], [1.0, 0.25, 0.0]]);
+    }
+
+    #[test]
+    fn places_openexr_scanlines_in_the_data_window() {
+        let rows = [(11, floats(&[3.0])), (10, floats(&[0.5]))];
+        let image = HdrImage::parse_exr(&openexr(&[("R", 2)], [-4, 10, -4, 11], &rows)).unwrap();
+
+        assert_eq!(image.pixels, &[[0.5; 3], [3.0; 3]]);
+    }
+
+    #[test]
+    fn rejects_invalid_openexr_headers() {
+        let rgb = [("B", 1), ("G", 1), ("R", 1)];
+        assert_invalid(HdrImage::parse_exr(b"#?RADIANCE\n"));
+        let mut tiled = openexr(&rgb, [0, 0, 0, 0], &[]);
+        tiled[4..8].copy_from_slice(&0x202u32.to_le_bytes());
+        assert_invalid(HdrImage::parse_exr(&tiled));
+        assert_invalid(HdrImage::parse_exr(&openexr_header(&rgb, 3, [0, 0, 0, 0])));
+        assert_invalid(HdrImage::parse_exr(&openexr(&rgb, [0, 0, -1, 0], &[])));
+        assert_invalid(HdrImage::parse_exr(&openexr(
+            &[("A", 1)],
+            [0, 0, 0, 0],
+            &[],
+        )));
+        assert_invalid(HdrImage::parse_exr(
+            &openexr(&rgb[..2], [0, 0, 0, 0], &[])[..40],
+        ));
+    }
+
+    #[test]
+    fn rejects_oversized_openexr_data_windows() {
+        let rgb = [("B", 1), ("G", 1), ("R", 1)];
+        let windows = [
+            [i32::MIN, 0, i32::MAX, 0],
+            [0, i32::MIN, 0, i32::MAX],
+            [0, 0, 99_999, 99_999],
+        ];
+        for window in windows {
+            assert_invalid(HdrImage::parse_exr(&openexr(&rgb, window, &[])));
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_openexr_scanlines() {
+        let window = [0, 0, 1, 0];
+        assert_invalid(HdrImage::parse_exr(&openexr(
+            &[("R", 2)],
+            window,
+            &[(1, floats(&[1.0, 2.0]))],
+        )));
+        assert_invalid(HdrImage::parse_exr(&openexr(
+            &[("R", 2)],
+            window,
+            &[(i32::MIN, floats(&[1.0, 2.0]))],
+        )));
+        assert_invalid(HdrImage::parse_exr(&openexr(
+            &[("R", 7)],
+            window,
+            &[(0, floats(&[1.0, 2.0]))],
+        )));
+        assert_invalid(HdrImage::parse_exr(&openexr(
+            &[("R", 2)],
+            window,
+            &[(0, floats(&[1.0]))],
+        )));
+    }
+
+    #[test]
+    fn converts_to_half_floats() {
+        for value in [0.0, 1.0, -2.5, 0.333, 65504.0, 1e-5] {
+            let half = f32_to_f16(value);
+            assert!((f16_to_f32(half) - value).abs() <= value.abs() * 1e-3 + 1e-7);
+        }
+        assert_eq!(f32_to_f16(1e6), 0x7c00);
+        assert_eq!(f32_to_f16(1e-9), 0);
+    }
+}
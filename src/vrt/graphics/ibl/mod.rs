@@ -0,0 +1,474 @@
+mod bake;
+mod cache;
+pub mod hdr;
+pub mod skybox_render_system;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    AccessFlags, BufferImageCopyBuilder, BufferUsageFlags, DependencyFlags, DescriptorSet,
+    DescriptorSetLayout, DescriptorType, DeviceSize, Extent3D, Filter, Format, ImageAspectFlags,
+    ImageCreateFlags, ImageCreateInfoBuilder, ImageLayout, ImageSubresourceLayersBuilder,
+    ImageTiling, ImageType, ImageUsageFlags, ImageViewType, MemoryBarrierBuilder,
+    MemoryPropertyFlags, PipelineStageFlags, SampleCountFlagBits, SamplerAddressMode,
+    ShaderStageFlags, SharingMode, WHOLE_SIZE,
+};
+
+use self::hdr::HdrImage;
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::utils::result::VkResult;
+
+/// Format of every baked image, also used to upload the equirectangular source.
+pub const ENVIRONMENT_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+const TEXEL_SIZE: DeviceSize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IblSettings {
+    /// Face size of the environment cubemap, which gets a full mip chain.
+    pub environment_size: u32,
+    pub irradiance_size: u32,
+    /// Face size of the first prefiltered level, roughness 0.
+    pub prefiltered_size: u32,
+    /// Levels between roughness 0 and 1.
+    pub prefiltered_mip_levels: u32,
+    pub brdf_lut_size: u32,
+    /// Samples per texel of the irradiance, prefilter and BRDF integrations.
+    pub sample_count: u32,
+    /// Baked images of loaded files are stored here, `None` disables the cache.
+    pub cache_dir: Option<PathBuf>,
+}
+
+impl Default for IblSettings {
+    fn default() -> Self {
+        Self {
+            environment_size: 512,
+            irradiance_size: 32,
+            prefiltered_size: 128,
+            prefiltered_mip_levels: 5,
+            brdf_lut_size: 256,
+            sample_count: 1024,
+            cache_dir: Some(PathBuf::from("./cache/ibl")),
+        }
+    }
+}
+
+impl IblSettings {
+    pub fn clamped(&self) -> Self {
+        let prefiltered_size = self.prefiltered_size.max(1);
+        Self {
+            environment_size: self.environment_size.max(1),
+            irradiance_size: self.irradiance_size.max(1),
+            prefiltered_size,
+            prefiltered_mip_levels: self
+                .prefiltered_mip_levels
+                .clamp(1, full_mip_levels(prefiltered_size)),
+            brdf_lut_size: self.brdf_lut_size.max(1),
+            sample_count: self.sample_count.max(1),
+            cache_dir: self.cache_dir.clone(),
+        }
+    }
+
+    pub fn environment_mip_levels(&self) -> u32 {
+        full_mip_levels(self.environment_size)
+    }
+}
+
+fn full_mip_levels(size: u32) -> u32 {
+    u32::BITS - size.max(1).leading_zeros()
+}
+
+/// The baked images, in the order they are laid out in cache files.
+struct IblImages {
+    environment: VRTImage,
+    irradiance: VRTImage,
+    prefiltered: VRTImage,
+    brdf_lut: VRTImage,
+}
+
+impl IblImages {
+    fn new(device: &Arc<VRTDevice>, settings: &IblSettings) -> VkResult<Self> {
+        let create = |size: u32, mip_levels: u32, cube: bool| {
+            let (flags, layers, view_type) = if cube {
+                (ImageCreateFlags::CUBE_COMPATIBLE, 6, ImageViewType::CUBE)
+            } else {
+                (ImageCreateFlags::empty(), 1, ImageViewType::_2D)
+            };
+            let image_info = ImageCreateInfoBuilder::new()
+                .flags(flags)
+                .image_type(ImageType::_2D)
+                .extent(Extent3D {
+                    width: size,
+                    height: size,
+                    depth: 1,
+                })
+                .mip_levels(mip_levels)
+                .array_layers(layers)
+                .format(ENVIRONMENT_FORMAT)
+                .tiling(ImageTiling::OPTIMAL)
+                .initial_layout(ImageLayout::UNDEFINED)
+                .usage(
+                    ImageUsageFlags::STORAGE
+                        | ImageUsageFlags::SAMPLED
+                        | ImageUsageFlags::TRANSFER_SRC
+                        | ImageUsageFlags::TRANSFER_DST,
+                )
+                .samples(SampleCountFlagBits::_1)
+                .sharing_mode(SharingMode::EXCLUSIVE);
+            VRTImage::new(
+                device.clone(),
+                &image_info,
+                view_type,
+                ImageAspectFlags::COLOR,
+            )
+        };
+
+        Ok(Self {
+            environment: create(
+                settings.environment_size,
+                settings.environment_mip_levels(),
+                true,
+            )?,
+            irradiance: create(settings.irradiance_size, 1, true)?,
+            prefiltered: create(
+                settings.prefiltered_size,
+                settings.prefiltered_mip_levels,
+                true,
+            )?,
+            brdf_lut: create(settings.brdf_lut_size, 1, false)?,
+        })
+    }
+
+    fn all(&self) -> [&VRTImage; 4] {
+        [
+            &self.environment,
+            &self.irradiance,
+            &self.prefiltered,
+            &self.brdf_lut,
+        ]
+    }
+
+    /// Tightly packed copies of every level and layer, starting at `offset` in a buffer.
+    fn copy_regions(
+        image: &VRTImage,
+        offset: &mut DeviceSize,
+    ) -> Vec<BufferImageCopyBuilder<'static>> {
+        (0..image.get_mip_levels())
+            .map(|level| {
+                let width = (image.get_extent().width >> level).max(1);
+                let height = (image.get_extent().height >> level).max(1);
+                let region = BufferImageCopyBuilder::new()
+                    .buffer_offset(*offset)
+                    .buffer_row_length(0)
+                    .buffer_image_height(0)
+                    .image_subresource(
+                        *ImageSubresourceLayersBuilder::new()
+                            .aspect_mask(ImageAspectFlags::COLOR)
+                            .mip_level(level)
+                            .base_array_layer(0)
+                            .layer_count(image.get_array_layers()),
+                    )
+                    .image_extent(Extent3D {
+                        width,
+                        height,
+                        depth: 1,
+                    });
+                *offset += (width * height * image.get_array_layers()) as DeviceSize * TEXEL_SIZE;
+                region
+            })
+            .collect()
+    }
+
+    fn byte_size(&self) -> DeviceSize {
+        let mut size = 0;
+        for image in self.all() {
+            Self::copy_regions(image, &mut size);
+        }
+        size
+    }
+
+    /// Fills every image from cache data, leaving them ready for sampling.
+    fn upload(&self, device: &Arc<VRTDevice>, data: &[u8]) -> VkResult<()> {
+        let staging_buffer = VRTBuffer::new(
+            device.clone(),
+            1,
+            data.len() as u32,
+            BufferUsageFlags::TRANSFER_SRC,
+            MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+            None,
+        );
+        let mapped = staging_buffer.map(WHOLE_SIZE, 0);
+        staging_buffer.write_to_buffer(data.as_ptr(), mapped, data.len() as DeviceSize, 0);
+        staging_buffer.unmap();
+
+        let command_buffer = device.begin_single_time_commands()?;
+        let mut offset = 0;
+        for image in self.all() {
+            device.transition_image_layout(
+                command_buffer,
+                image.get_image(),
+                image.full_subresource_range(),
+                ImageLayout::UNDEFINED,
+                ImageLayout::TRANSFER_DST_OPTIMAL,
+            )?;
+            let regions = Self::copy_regions(image, &mut offset);
+            unsafe {
+                device.get_device_ptr().cmd_copy_buffer_to_image(
+                    command_buffer,
+                    staging_buffer.get_buffer(),
+                    image.get_image(),
+                    ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &regions,
+                );
+            }
+            device.transition_image_layout(
+                command_buffer,
+                image.get_image(),
+                image.full_subresource_range(),
+                ImageLayout::TRANSFER_DST_OPTIMAL,
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )?;
+        }
+        device.end_single_time_commands(command_buffer)
+    }
+
+    /// Copies every image back to the host in cache layout.
+    fn read_back(&self, device: &Arc<VRTDevice>) -> VkResult<Vec<u8>> {
+        let size = self.byte_size();
+        let readback_buffer = VRTBuffer::new(
+            device.clone(),
+            1,
+            size as u32,
+            BufferUsageFlags::TRANSFER_DST,
+            MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+            None,
+        );
+
+        let command_buffer = device.begin_single_time_commands()?;
+        let mut offset = 0;
+        for image in self.all() {
+            device.transition_image_layout(
+                command_buffer,
+                image.get_image(),
+                image.full_subresource_range(),
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                ImageLayout::TRANSFER_SRC_OPTIMAL,
+            )?;
+            let regions = Self::copy_regions(image, &mut offset);
+            unsafe {
+                device.get_device_ptr().cmd_copy_image_to_buffer(
+                    command_buffer,
+                    image.get_image(),
+                    ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    readback_buffer.get_buffer(),
+                    &regions,
+                );
+            }
+            device.transition_image_layout(
+                command_buffer,
+                image.get_image(),
+                image.full_subresource_range(),
+                ImageLayout::TRANSFER_SRC_OPTIMAL,
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )?;
+        }
+        let barrier = MemoryBarrierBuilder::new()
+            .src_access_mask(AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(AccessFlags::HOST_READ);
+        unsafe {
+            device.get_device_ptr().cmd_pipeline_barrier(
+                command_buffer,
+                PipelineStageFlags::TRANSFER,
+                PipelineStageFlags::HOST,
+                DependencyFlags::empty(),
+                std::slice::from_ref(&barrier),
+                &[],
+                &[],
+            );
+        }
+        device.end_single_time_commands(command_buffer)?;
+
+        let mapped = readback_buffer.map(WHOLE_SIZE, 0);
+        let data =
+            unsafe { std::slice::from_raw_parts(mapped as *const u8, size as usize) }.to_vec();
+        readback_buffer.unmap();
+        Ok(data)
+    }
+}
+
+/// Image-based lighting baked from an equirectangular HDR environment.
+///
+/// The descriptor set holds the environment cubemap at binding 0, the diffuse irradiance map at
+/// 1, the prefiltered specular map at 2, the BRDF lookup table at 3 and a trilinear sampler
+/// at 4.
+pub struct EnvironmentMap {
+    descriptor_set: DescriptorSet,
+    _descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    sampler: VRTSampler,
+    images: IblImages,
+    settings: IblSettings,
+}
+
+impl EnvironmentMap {
+    /// Loads a `.hdr` or `.exr` environment, reusing baked images from the cache directory when
+    /// the file and settings did not change since they were written.
+    pub fn load(
+        device: Arc<VRTDevice>,
+        path: impl AsRef<Path>,
+        settings: &IblSettings,
+    ) -> VkResult<Self> {
+        let path = path.as_ref();
+        let settings = settings.clamped();
+        let cache_path = match &settings.cache_dir {
+            Some(cache_dir) => Some(cache::cache_path(
+                cache_dir,
+                path,
+                &std::fs::read(path)?,
+                &settings,
+            )),
+            None => None,
+        };
+
+        let images = IblImages::new(&device, &settings)?;
+        if let Some(cache_path) = &cache_path {
+            if let Some(data) = cache::read(cache_path, &settings, images.byte_size() as usize) {
+                images.upload(&device, &data)?;
+                log::info!("loaded baked environment {}", cache_path.display());
+                return Self::with_images(device, settings, images);
+            }
+        }
+
+        let source = HdrImage::load(path)?;
+        let peak = source
+            .pixels()
+            .iter()
+            .flatten()
+            .fold(0.0f32, |peak, channel| peak.max(*channel));
+        log::info!(
+            "baking {}, {}x{} with a peak of {:.1}",
+            path.display(),
+            source.get_width(),
+            source.get_height(),
+            peak
+        );
+        let environment = Self::bake(device.clone(), &source, settings, images)?;
+        if let Some(cache_path) = &cache_path {
+            let written = environment
+                .images
+                .read_back(&device)
+                .and_then(|data| Ok(cache::write(cache_path, &environment.settings, &data)?));
+            match written {
+                Ok(()) => log::info!("cached baked environment {}", cache_path.display()),
+                Err(err) => log::warn!("cannot cache baked environment: {}", err),
+            }
+        }
+        Ok(environment)
+    }
+
+    /// Bakes an environment generated in memory, bypassing the cache.
+    pub fn from_image(
+        device: Arc<VRTDevice>,
+        source: &HdrImage,
+        settings: &IblSettings,
+    ) -> VkResult<Self> {
+        let settings = settings.clamped();
+        let images = IblImages::new(&device, &settings)?;
+        Self::bake(device, source, settings, images)
+    }
+
+    fn bake(
+        device: Arc<VRTDevice>,
+        source: &HdrImage,
+        settings: IblSettings,
+        images: IblImages,
+    ) -> VkResult<Self> {
+        let environment = Self::with_images(device.clone(), settings, images)?;
+        bake::bake(
+            &device,
+            source,
+            &environment.settings,
+            &environment.images,
+            &environment.sampler,
+        )?;
+        Ok(environment)
+    }
+
+    fn with_images(
+        device: Arc<VRTDevice>,
+        settings: IblSettings,
+        images: IblImages,
+    ) -> VkResult<Self> {
+        let max_lod = settings
+            .environment_mip_levels()
+            .max(settings.prefiltered_mip_levels) as f32;
+        let sampler = VRTSampler::new_mipmapped(
+            device.clone(),
+            Filter::LINEAR,
+            SamplerAddressMode::CLAMP_TO_EDGE,
+            max_lod,
+        )?;
+
+        let mut layout_builder = VRTDescriptorSetLayoutBuilder::new(device.clone());
+        for binding in 0..4 {
+            layout_builder.add_binding(
+                binding,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            );
+        }
+        let descriptor_set_layout = layout_builder
+            .add_binding(4, DescriptorType::SAMPLER, ShaderStageFlags::FRAGMENT, None)
+            .build();
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device)
+            .set_max_sets(1)
+            .add_pool_size(DescriptorType::SAMPLED_IMAGE, 4)
+            .add_pool_size(DescriptorType::SAMPLER, 1)
+            .build()?;
+
+        let mut writer = VRTDescriptorWriter::new(&descriptor_set_layout, &descriptor_pool);
+        for (binding, image) in images.all().into_iter().enumerate() {
+            writer.write_image(
+                binding as u32,
+                image.get_image_view(),
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            );
+        }
+        let descriptor_set = writer.write_sampler(4, sampler.get_sampler()).build()?;
+
+        Ok(Self {
+            descriptor_set,
+            _descriptor_pool: descriptor_pool,
+            descriptor_set_layout,
+            sampler,
+            images,
+            settings,
+        })
+    }
+
+    pub fn get_settings(&self) -> &IblSettings {
+        &self.settings
+    }
+
+    /// Pipelines that sample the environment use this layout, the PBR pipeline as set 2.
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self) -> DescriptorSet {
+        self.descriptor_set
+    }
+
+    /// Prefiltered level sampled for roughness 1.
+    pub fn max_reflection_lod(&self) -> f32 {
+        (self.settings.prefiltered_mip_levels - 1) as f32
+    }
+}
@@ -0,0 +1,86 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, CompareOp, CullModeFlags, DescriptorSetLayout, RenderPass, SampleCountFlagBits,
+    ShaderStageFlags,
+};
+use glam::Mat4;
+
+use super::EnvironmentMap;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::pipeline::VRTPipeline;
+
+const VERTEX_SHADER: &str = "./assets/shaders/skybox_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/skybox_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct SkyboxPush {
+    inverse_view_projection: Mat4,
+    params: [f32; 4],
+}
+
+/// Draws the environment cubemap behind all geometry, on the far plane without writing depth.
+pub struct SkyboxRenderSystem {
+    pipeline: VRTPipeline,
+    device: Arc<VRTDevice>,
+}
+
+impl SkyboxRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        environment_layout: DescriptorSetLayout,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_depth_test(true, false, CompareOp::LESS_OR_EQUAL);
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info.set_descriptor_set_layouts(&[environment_layout]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::FRAGMENT,
+            0,
+            std::mem::size_of::<SkyboxPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        Self { pipeline, device }
+    }
+
+    /// `lod` blurs the sky by sampling smaller environment mips, `intensity` scales its radiance.
+    pub fn render(
+        &self,
+        command_buffer: CommandBuffer,
+        environment: &EnvironmentMap,
+        view_projection: Mat4,
+        lod: f32,
+        intensity: f32,
+    ) {
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[environment.get_descriptor_set()]);
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::FRAGMENT,
+            &SkyboxPush {
+                inverse_view_projection: view_projection.inverse(),
+                params: [lod, intensity, 0.0, 0.0],
+            },
+        );
+
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .cmd_draw(command_buffer, 3, 1, 0, 0);
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{CommandBuffer, RenderPass, SampleCountFlagBits};
+
+use super::IndirectScene;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::model::Model;
+use crate::vrt::graphics::pipeline::VRTPipeline;
+
+const VERTEX_SHADER: &str = "./assets/shaders/indirect_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/indirect_frag.spirv";
+
+/// Draws the objects `IndirectScene` culled on the GPU, with a single diffuse light.
+pub struct IndirectRenderSystem {
+    pipeline: VRTPipeline,
+    device: Arc<VRTDevice>,
+}
+
+impl IndirectRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        scene: &IndirectScene,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info.set_descriptor_set_layouts(&[scene.get_descriptor_set_layout()]);
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        Self { pipeline, device }
+    }
+
+    /// `model` holds the index and vertex data every mesh of `scene` refers to. Call
+    /// `IndirectScene::cull` for the frame before its render pass began.
+    pub fn render(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        scene: &IndirectScene,
+        model: &Model,
+    ) {
+        self.pipeline.bind(command_buffer);
+        self.pipeline.bind_descriptor_sets(
+            command_buffer,
+            0,
+            &[scene.get_descriptor_set(frame_index)],
+        );
+        model.bind(self.device.clone(), command_buffer);
+        scene.draw(command_buffer, frame_index);
+    }
+}
@@ -0,0 +1,450 @@
+pub mod indirect_render_system;
+
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    AccessFlags, BufferUsageFlags, CommandBuffer, DependencyFlags, DescriptorSet,
+    DescriptorSetLayout, DescriptorType, DeviceSize, DrawIndexedIndirectCommand,
+    MemoryBarrierBuilder, MemoryPropertyFlags, PipelineStageFlags, ShaderStageFlags, WHOLE_SIZE,
+};
+use glam::{Mat4, Vec3, Vec4};
+
+use super::bounds::Frustum;
+use super::compute_pipeline::VRTComputePipeline;
+use super::model::Model;
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::{IndirectDrawFeatures, VRTDevice};
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::utils::result::{VkError, VkResult};
+
+const CULLING_SHADER: &str = "./assets/shaders/indirect_culling_comp.spirv";
+
+/// Meshes an `IndirectScene` holds, they are few and shared by many objects.
+pub const MAX_MESHES: usize = 64;
+
+/// Matches the `Object` struct of the indirect shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GpuObject {
+    model: Mat4,
+    color: Vec4,
+    mesh: [u32; 4],
+}
+
+/// Matches the `Mesh` struct of the indirect shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GpuMesh {
+    sphere: Vec4,
+    draw: [u32; 4],
+}
+
+/// Matches the `IndirectData` uniform block of the indirect shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct IndirectUniform {
+    view_projection: Mat4,
+    planes: [Vec4; 6],
+    light_direction: Vec4,
+    counts: [u32; 4],
+}
+
+/// A range of the bound index and vertex buffers with a model space bounding sphere.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IndirectMesh {
+    pub index_count: u32,
+    pub first_index: u32,
+    pub vertex_offset: i32,
+    pub bounds_center: Vec3,
+    pub bounds_radius: f32,
+}
+
+impl IndirectMesh {
+    /// All indices of `model`, with its bounding sphere.
+    pub fn from_model(model: &Model) -> Self {
+        let sphere = model.get_bounding_sphere();
+        Self {
+            index_count: model.get_index_count(),
+            first_index: 0,
+            vertex_offset: 0,
+            bounds_center: sphere.center,
+            bounds_radius: sphere.radius,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct IndirectMeshId(u32);
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct IndirectObject {
+    pub transform: Mat4,
+    pub color: Vec4,
+    pub mesh: IndirectMeshId,
+}
+
+impl IndirectObject {
+    pub fn new(mesh: IndirectMeshId, transform: Mat4) -> Self {
+        Self {
+            transform,
+            color: Vec4::ONE,
+            mesh,
+        }
+    }
+
+    pub fn with_color(mut self, color: Vec4) -> Self {
+        self.color = color;
+        self
+    }
+}
+
+/// Keeps every object of a scene in storage buffers and lets a compute pass frustum cull them
+/// into `VkDrawIndexedIndirectCommand`s, so drawing costs the CPU the same for any object count.
+///
+/// Visible draws are compacted and counted when the device supports
+/// `VK_KHR_draw_indirect_count`. Otherwise every object keeps a command, culled ones with no
+/// instances, and they are issued as one multi-draw or one draw per object.
+///
+/// The descriptor set of a frame holds the camera uniform, the objects, the meshes, the
+/// commands and the draw count. Graphics pipelines bind it as set 0 and select their object by
+/// the instance index, which requires `drawIndirectFirstInstance`.
+pub struct IndirectScene {
+    pipeline: VRTComputePipeline,
+    descriptor_sets: Vec<DescriptorSet>,
+    uniform_buffers: Vec<VRTBuffer>,
+    object_buffers: Vec<VRTBuffer>,
+    mesh_buffers: Vec<VRTBuffer>,
+    command_buffers: Vec<VRTBuffer>,
+    count_buffers: Vec<VRTBuffer>,
+    _descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    meshes: Vec<IndirectMesh>,
+    objects: Vec<IndirectObject>,
+    // Whether the objects and meshes of a frame's buffers are outdated.
+    dirty: Vec<bool>,
+    max_objects: u32,
+    view_projection: Mat4,
+    light_direction: Vec3,
+    ambient: f32,
+    features: IndirectDrawFeatures,
+    device: Arc<VRTDevice>,
+}
+
+impl IndirectScene {
+    pub fn new(device: Arc<VRTDevice>, max_objects: u32) -> VkResult<Self> {
+        let features = device.get_indirect_draw_features();
+        if !features.first_instance {
+            return Err(VkError::UnsupportedFeature("drawIndirectFirstInstance"));
+        }
+        let max_objects = max_objects.max(1);
+
+        let stages =
+            ShaderStageFlags::COMPUTE | ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT;
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(0, DescriptorType::UNIFORM_BUFFER, stages, None)
+            .add_binding(1, DescriptorType::STORAGE_BUFFER, stages, None)
+            .add_binding(
+                2,
+                DescriptorType::STORAGE_BUFFER,
+                ShaderStageFlags::COMPUTE,
+                None,
+            )
+            .add_binding(
+                3,
+                DescriptorType::STORAGE_BUFFER,
+                ShaderStageFlags::COMPUTE,
+                None,
+            )
+            .add_binding(
+                4,
+                DescriptorType::STORAGE_BUFFER,
+                ShaderStageFlags::COMPUTE,
+                None,
+            )
+            .build();
+
+        let frame_count = MAX_FRAMES_IN_FLIGHT as u32;
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(frame_count)
+            .add_pool_size(DescriptorType::UNIFORM_BUFFER, frame_count)
+            .add_pool_size(DescriptorType::STORAGE_BUFFER, 4 * frame_count)
+            .build()?;
+
+        let create_buffers = |instance_size: usize,
+                              count: u32,
+                              usage: BufferUsageFlags,
+                              properties: MemoryPropertyFlags| {
+            (0..MAX_FRAMES_IN_FLIGHT)
+                .map(|_| {
+                    VRTBuffer::new(
+                        device.clone(),
+                        instance_size as DeviceSize,
+                        count,
+                        usage,
+                        properties,
+                        None,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let host_visible = MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT;
+        let indirect_storage = BufferUsageFlags::STORAGE_BUFFER | BufferUsageFlags::INDIRECT_BUFFER;
+
+        let uniform_buffers = create_buffers(
+            mem::size_of::<IndirectUniform>(),
+            1,
+            BufferUsageFlags::UNIFORM_BUFFER,
+            host_visible,
+        );
+        let object_buffers = create_buffers(
+            mem::size_of::<GpuObject>(),
+            max_objects,
+            BufferUsageFlags::STORAGE_BUFFER,
+            host_visible,
+        );
+        let mesh_buffers = create_buffers(
+            mem::size_of::<GpuMesh>(),
+            MAX_MESHES as u32,
+            BufferUsageFlags::STORAGE_BUFFER,
+            host_visible,
+        );
+        let command_buffers = create_buffers(
+            mem::size_of::<DrawIndexedIndirectCommand>(),
+            max_objects,
+            indirect_storage,
+            MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+        let count_buffers = create_buffers(
+            mem::size_of::<u32>(),
+            1,
+            indirect_storage,
+            MemoryPropertyFlags::DEVICE_LOCAL,
+        );
+
+        let descriptor_sets = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|frame_index| {
+                VRTDescriptorWriter::new(&descriptor_set_layout, &descriptor_pool)
+                    .write_buffer(0, uniform_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(1, object_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(2, mesh_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(3, command_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(4, count_buffers[frame_index].get_buffer(), 0, WHOLE_SIZE)
+                    .build()
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+
+        let pipeline = VRTComputePipeline::new(
+            device.clone(),
+            CULLING_SHADER,
+            &[descriptor_set_layout.get_descriptor_set_layout()],
+            0,
+        )?;
+
+        Ok(Self {
+            pipeline,
+            descriptor_sets,
+            uniform_buffers,
+            object_buffers,
+            mesh_buffers,
+            command_buffers,
+            count_buffers,
+            _descriptor_pool: descriptor_pool,
+            descriptor_set_layout,
+            meshes: vec![],
+            objects: vec![],
+            dirty: vec![true; MAX_FRAMES_IN_FLIGHT],
+            max_objects,
+            view_projection: Mat4::IDENTITY,
+            light_direction: Vec3::new(0.3, 0.5, 1.0),
+            ambient: 0.2,
+            features,
+            device,
+        })
+    }
+
+    /// Graphics pipelines that draw the culled commands use this layout for set 0.
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self, frame_index: usize) -> DescriptorSet {
+        self.descriptor_sets[frame_index]
+    }
+
+    /// Returns `None` once `MAX_MESHES` meshes were added.
+    pub fn add_mesh(&mut self, mesh: IndirectMesh) -> Option<IndirectMeshId> {
+        if self.meshes.len() >= MAX_MESHES {
+            return None;
+        }
+        self.meshes.push(mesh);
+        self.mark_dirty();
+        Some(IndirectMeshId(self.meshes.len() as u32 - 1))
+    }
+
+    pub fn objects(&self) -> &[IndirectObject] {
+        &self.objects
+    }
+
+    /// Objects past `get_max_objects` are ignored.
+    pub fn objects_mut(&mut self) -> &mut Vec<IndirectObject> {
+        self.mark_dirty();
+        &mut self.objects
+    }
+
+    pub fn get_max_objects(&self) -> u32 {
+        self.max_objects
+    }
+
+    pub fn set_camera(&mut self, view_projection: Mat4) {
+        self.view_projection = view_projection;
+    }
+
+    /// `direction` is the way the light travels, `ambient` the light every surface receives.
+    pub fn set_light(&mut self, direction: Vec3, ambient: f32) {
+        self.light_direction = direction;
+        self.ambient = ambient;
+    }
+
+    /// Whether culled draws are compacted and issued with a GPU-side draw count.
+    pub fn is_compacting(&self) -> bool {
+        self.features.draw_count
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty.iter_mut().for_each(|dirty| *dirty = true);
+    }
+
+    fn object_count(&self) -> u32 {
+        (self.objects.len() as u32).min(self.max_objects)
+    }
+
+    /// Uploads the camera and any changed objects of `frame_index` and culls them into draw
+    /// commands, outside of any render pass.
+    pub fn cull(&mut self, command_buffer: CommandBuffer, frame_index: usize) {
+        let object_count = self.object_count();
+        if self.dirty[frame_index] {
+            self.upload_objects(frame_index, object_count);
+            self.dirty[frame_index] = false;
+        }
+
+        let uniform = IndirectUniform {
+            view_projection: self.view_projection,
+            planes: *Frustum::from_view_projection(self.view_projection).planes(),
+            light_direction: self.light_direction.extend(self.ambient),
+            counts: [object_count, self.features.draw_count as u32, 0, 0],
+        };
+        let uniform_buffer = &self.uniform_buffers[frame_index];
+        let mapped = uniform_buffer.map(WHOLE_SIZE, 0);
+        uniform_buffer.write_to_buffer(&uniform as *const IndirectUniform, mapped, 1, 0);
+        uniform_buffer.unmap();
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[self.descriptor_sets[frame_index]]);
+        // A single workgroup, compaction needs the running count across all objects.
+        self.pipeline.dispatch(command_buffer, 1, 1, 1);
+
+        let barrier = MemoryBarrierBuilder::new()
+            .src_access_mask(AccessFlags::SHADER_WRITE)
+            .dst_access_mask(AccessFlags::INDIRECT_COMMAND_READ);
+        unsafe {
+            self.device.get_device_ptr().cmd_pipeline_barrier(
+                command_buffer,
+                PipelineStageFlags::COMPUTE_SHADER,
+                PipelineStageFlags::DRAW_INDIRECT,
+                DependencyFlags::empty(),
+                std::slice::from_ref(&barrier),
+                &[],
+                &[],
+            );
+        }
+    }
+
+    /// Issues the commands culled for `frame_index`, with the pipeline, descriptor set and the
+    /// index and vertex buffers of the meshes bound.
+    pub fn draw(&self, command_buffer: CommandBuffer, frame_index: usize) {
+        let object_count = self.object_count();
+        if object_count == 0 {
+            return;
+        }
+        let device = self.device.get_device_ptr();
+        let commands = self.command_buffers[frame_index].get_buffer();
+        let stride = mem::size_of::<DrawIndexedIndirectCommand>() as u32;
+
+        unsafe {
+            if self.features.draw_count {
+                device.cmd_draw_indexed_indirect_count_khr(
+                    command_buffer,
+                    commands,
+                    0,
+                    self.count_buffers[frame_index].get_buffer(),
+                    0,
+                    object_count,
+                    stride,
+                );
+            } else if self.features.multi_draw_indirect {
+                device.cmd_draw_indexed_indirect(command_buffer, commands, 0, object_count, stride);
+            } else {
+                for index in 0..object_count {
+                    let offset = index as DeviceSize * stride as DeviceSize;
+                    device.cmd_draw_indexed_indirect(command_buffer, commands, offset, 1, stride);
+                }
+            }
+        }
+    }
+
+    fn upload_objects(&self, frame_index: usize, object_count: u32) {
+        let gpu_meshes = self
+            .meshes
+            .iter()
+            .map(|mesh| GpuMesh {
+                sphere: mesh.bounds_center.extend(mesh.bounds_radius),
+                draw: [
+                    mesh.index_count,
+                    mesh.first_index,
+                    mesh.vertex_offset as u32,
+                    0,
+                ],
+            })
+            .collect::<Vec<_>>();
+        let gpu_objects = self
+            .objects
+            .iter()
+            .take(object_count as usize)
+            .map(|object| GpuObject {
+                model: object.transform,
+                color: object.color,
+                mesh: [object.mesh.0, 0, 0, 0],
+            })
+            .collect::<Vec<_>>();
+
+        if !gpu_meshes.is_empty() {
+            let mesh_buffer = &self.mesh_buffers[frame_index];
+            let mapped = mesh_buffer.map(WHOLE_SIZE, 0);
+            mesh_buffer.write_to_buffer(
+                gpu_meshes.as_ptr(),
+                mapped,
+                gpu_meshes.len() as DeviceSize,
+                0,
+            );
+            mesh_buffer.unmap();
+        }
+        if !gpu_objects.is_empty() {
+            let object_buffer = &self.object_buffers[frame_index];
+            let mapped = object_buffer.map(WHOLE_SIZE, 0);
+            object_buffer.write_to_buffer(
+                gpu_objects.as_ptr(),
+                mapped,
+                gpu_objects.len() as DeviceSize,
+                0,
+            );
+            object_buffer.unmap();
+        }
+    }
+}
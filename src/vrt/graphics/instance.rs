@@ -0,0 +1,172 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    BufferUsageFlags, CommandBuffer, DeviceSize, Format, MemoryPropertyFlags,
+    VertexInputAttributeDescriptionBuilder, VertexInputBindingDescriptionBuilder, VertexInputRate,
+    WHOLE_SIZE,
+};
+use glam::{Mat4, Vec4};
+
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+
+/// Vertex buffer binding of per-instance data, binding 0 holds the `Vertex` data.
+pub const INSTANCE_BINDING: u32 = 1;
+/// First shader location of per-instance attributes, after the `Vertex` attributes.
+pub const FIRST_INSTANCE_LOCATION: u32 = 4;
+
+/// Per-instance vertex input, added to a pipeline with `PipelineConfigInfo::add_instance_input`.
+pub trait InstanceData: Copy {
+    /// Attributes read from `INSTANCE_BINDING`, from `FIRST_INSTANCE_LOCATION` onwards.
+    fn attribute_descriptions() -> Vec<VertexInputAttributeDescriptionBuilder<'static>>;
+
+    fn binding_description() -> VertexInputBindingDescriptionBuilder<'static> {
+        VertexInputBindingDescriptionBuilder::new()
+            .binding(INSTANCE_BINDING)
+            .stride(mem::size_of::<Self>() as u32)
+            .input_rate(VertexInputRate::INSTANCE)
+    }
+}
+
+/// A model matrix and a color multiplied with the vertex color, read by `pbr_instanced.vert`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InstanceTransform {
+    pub model: Mat4,
+    pub color: Vec4,
+}
+
+impl Default for InstanceTransform {
+    fn default() -> Self {
+        Self {
+            model: Mat4::IDENTITY,
+            color: Vec4::ONE,
+        }
+    }
+}
+
+impl InstanceTransform {
+    pub fn new(model: Mat4, color: Vec4) -> Self {
+        Self { model, color }
+    }
+}
+
+impl InstanceData for InstanceTransform {
+    fn attribute_descriptions() -> Vec<VertexInputAttributeDescriptionBuilder<'static>> {
+        let column_size = mem::size_of::<Vec4>() as u32;
+        // A matrix takes one location per column.
+        (0..5)
+            .map(|index| {
+                VertexInputAttributeDescriptionBuilder::new()
+                    .binding(INSTANCE_BINDING)
+                    .location(FIRST_INSTANCE_LOCATION + index)
+                    .format(Format::R32G32B32A32_SFLOAT)
+                    .offset(index * column_size)
+            })
+            .collect()
+    }
+}
+
+/// A range of the instances of a frame, drawn with one level of detail of the model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstanceBatch {
+    pub range: Range<u32>,
+    pub lod: usize,
+}
+
+impl InstanceBatch {
+    pub fn new(range: Range<u32>) -> Self {
+        Self { range, lod: 0 }
+    }
+
+    pub fn with_lod(mut self, lod: usize) -> Self {
+        self.lod = lod;
+        self
+    }
+}
+
+/// Host visible per-instance data with one buffer per frame in flight, so it can be rewritten
+/// every frame while earlier frames still draw from theirs.
+pub struct InstanceBuffer<T: InstanceData> {
+    buffers: Vec<VRTBuffer>,
+    capacities: Vec<u32>,
+    counts: Vec<u32>,
+    device: Arc<VRTDevice>,
+    _instance: PhantomData<T>,
+}
+
+impl<T: InstanceData> InstanceBuffer<T> {
+    pub fn new(device: Arc<VRTDevice>, capacity: u32) -> Self {
+        let capacity = capacity.max(1);
+        let buffers = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| Self::create_buffer(&device, capacity))
+            .collect();
+
+        Self {
+            buffers,
+            capacities: vec![capacity; MAX_FRAMES_IN_FLIGHT],
+            counts: vec![0; MAX_FRAMES_IN_FLIGHT],
+            device,
+            _instance: PhantomData,
+        }
+    }
+
+    fn create_buffer(device: &Arc<VRTDevice>, capacity: u32) -> VRTBuffer {
+        VRTBuffer::new(
+            device.clone(),
+            mem::size_of::<T>() as DeviceSize,
+            capacity,
+            BufferUsageFlags::VERTEX_BUFFER,
+            MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+            None,
+        )
+    }
+
+    /// Replaces the instances of `frame_index`, growing its buffer if they do not fit. Only call
+    /// this once the frame has begun, when the previous use of the buffer finished.
+    pub fn update(&mut self, frame_index: usize, instances: &[T]) {
+        let count = instances.len() as u32;
+        if count > self.capacities[frame_index] {
+            let capacity = count.next_power_of_two();
+            self.buffers[frame_index] = Self::create_buffer(&self.device, capacity);
+            self.capacities[frame_index] = capacity;
+        }
+
+        if count > 0 {
+            let buffer = &self.buffers[frame_index];
+            let mapped = buffer.map(WHOLE_SIZE, 0);
+            buffer.write_to_buffer(instances.as_ptr(), mapped, count as DeviceSize, 0);
+            buffer.unmap();
+        }
+        self.counts[frame_index] = count;
+    }
+
+    /// Binds the instances of `frame_index` to `INSTANCE_BINDING`.
+    pub fn bind(&self, command_buffer: CommandBuffer, frame_index: usize) {
+        unsafe {
+            self.device.get_device_ptr().cmd_bind_vertex_buffers(
+                command_buffer,
+                INSTANCE_BINDING,
+                std::slice::from_ref(&self.buffers[frame_index].get_buffer()),
+                &[0],
+            );
+        }
+    }
+
+    /// Instances written by the last `update` of `frame_index`.
+    pub fn len(&self, frame_index: usize) -> u32 {
+        self.counts[frame_index]
+    }
+
+    pub fn is_empty(&self, frame_index: usize) -> bool {
+        self.counts[frame_index] == 0
+    }
+
+    pub fn get_capacity(&self, frame_index: usize) -> u32 {
+        self.capacities[frame_index]
+    }
+}
@@ -0,0 +1,119 @@
+use glam::Vec3;
+
+/// Capacity of the light storage buffers of the lighting passes.
+pub const MAX_LIGHTS: usize = 1024;
+
+/// Matches the light type constants in the lighting shaders.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LightKind {
+    Directional = 0,
+    Point = 1,
+    Spot = 2,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Light {
+    pub kind: LightKind,
+    pub position: Vec3,
+    /// Direction the light travels in, unused by point lights.
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+    /// Distance at which point and spot lights fade out completely.
+    pub range: f32,
+    /// Cone half angles in radians, the falloff happens between them.
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    /// Directional and spot lights render shadow maps when set, point lights ignore it.
+    pub cast_shadows: bool,
+}
+
+/// Shader layout of a light, four `vec4`s in a std430 storage buffer.
+///
+/// `cone.zw` hold the first shadow map layer and the layer count, zero layers means unshadowed.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default)]
+pub struct GpuLight {
+    pub position_range: [f32; 4],
+    pub direction_kind: [f32; 4],
+    pub color_intensity: [f32; 4],
+    pub cone: [f32; 4],
+}
+
+impl Light {
+    pub fn directional(direction: Vec3, color: Vec3, intensity: f32) -> Self {
+        Self {
+            kind: LightKind::Directional,
+            position: Vec3::ZERO,
+            direction: direction.normalize_or_zero(),
+            color,
+            intensity,
+            range: f32::INFINITY,
+            inner_angle: 0.0,
+            outer_angle: 0.0,
+            cast_shadows: false,
+        }
+    }
+
+    pub fn point(position: Vec3, color: Vec3, intensity: f32, range: f32) -> Self {
+        Self {
+            kind: LightKind::Point,
+            position,
+            direction: Vec3::ZERO,
+            color,
+            intensity,
+            range,
+            inner_angle: 0.0,
+            outer_angle: 0.0,
+            cast_shadows: false,
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn spot(
+        position: Vec3,
+        direction: Vec3,
+        color: Vec3,
+        intensity: f32,
+        range: f32,
+        inner_angle: f32,
+        outer_angle: f32,
+    ) -> Self {
+        Self {
+            kind: LightKind::Spot,
+            position,
+            direction: direction.normalize_or_zero(),
+            color,
+            intensity,
+            range,
+            inner_angle: inner_angle.min(outer_angle),
+            outer_angle,
+            cast_shadows: false,
+        }
+    }
+
+    pub fn with_shadows(mut self) -> Self {
+        self.cast_shadows = true;
+        self
+    }
+
+    pub fn to_gpu(self) -> GpuLight {
+        GpuLight {
+            position_range: [
+                self.position.x,
+                self.position.y,
+                self.position.z,
+                self.range.min(f32::MAX),
+            ],
+            direction_kind: [
+                self.direction.x,
+                self.direction.y,
+                self.direction.z,
+                self.kind as u32 as f32,
+            ],
+            color_intensity: [self.color.x, self.color.y, self.color.z, self.intensity],
+            cone: [self.inner_angle.cos(), self.outer_angle.cos(), 0.0, 0.0],
+        }
+    }
+}
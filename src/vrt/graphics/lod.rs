@@ -0,0 +1,280 @@
+use std::collections::{HashMap, HashSet};
+
+use glam::Mat4;
+
+use super::bounds::{Aabb, BoundingSphere};
+use super::vertex::Vertex;
+
+/// A range of a model's index buffer holding one level of detail.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LodLevel {
+    pub first_index: u32,
+    pub index_count: u32,
+}
+
+/// Simplifies a triangle list by vertex clustering: vertices in the same grid cell of
+/// `cell_size` merge into the first of them and collapsed triangles are dropped. Only indices
+/// change, so all levels share the vertex buffer.
+pub fn simplify(vertices: &[Vertex], indices: &[u32], cell_size: f32) -> Vec<u32> {
+    if cell_size <= 0.0 {
+        return indices.to_vec();
+    }
+
+    let mut representatives = HashMap::new();
+    let remap = vertices
+        .iter()
+        .enumerate()
+        .map(|(index, vertex)| {
+            let cell = (vertex.get_position() / cell_size).floor().as_ivec3();
+            *representatives.entry(cell).or_insert(index as u32)
+        })
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    indices
+        .chunks_exact(3)
+        .map(|triangle| [0, 1, 2].map(|corner| remap[triangle[corner] as usize]))
+        .filter(|[a, b, c]| a != b && b != c && a != c)
+        .filter(|triangle| {
+            // Merged triangles often coincide, keep one of each winding.
+            let rotation = (0..3).min_by_key(|&i| triangle[i]).unwrap();
+            seen.insert([0, 1, 2].map(|i| triangle[(rotation + i) % 3]))
+        })
+        .flatten()
+        .collect()
+}
+
+/// `levels` index lists, the full `indices` first, then simplified with cells doubling in size
+/// from a sixteenth of the mesh extent. Levels that would lose every triangle are left out.
+pub fn generate_lods(vertices: &[Vertex], indices: &[u32], levels: usize) -> Vec<Vec<u32>> {
+    let extent = Aabb::from_points(vertices.iter().map(Vertex::get_position))
+        .map(|aabb| (aabb.max - aabb.min).max_element())
+        .unwrap_or(0.0);
+
+    let mut lods = vec![indices.to_vec()];
+    let mut cell_size = extent / 16.0;
+    while lods.len() < levels.max(1) && cell_size > 0.0 {
+        let lod = simplify(vertices, indices, cell_size);
+        if lod.is_empty() {
+            break;
+        }
+        if lod.len() < lods.last().unwrap().len() {
+            lods.push(lod);
+        }
+        cell_size *= 2.0;
+    }
+    lods
+}
+
+/// Fraction of the viewport height covered by the diameter of the world space `sphere`.
+pub fn projected_size(sphere: &BoundingSphere, view: Mat4, projection: Mat4) -> f32 {
+    let depth = projection
+        .mul_vec4(view.transform_point3(sphere.center).extend(1.0))
+        .w;
+    sphere.radius * projection.y_axis.y.abs() / depth.max(f32::EPSILON)
+}
+
+/// Picks a level per object from its projected size, remembering the levels of the last frame.
+/// A level only changes once the size passed its threshold by the hysteresis fraction, so
+/// objects hovering around a threshold do not pop between levels.
+#[derive(Debug, Clone)]
+pub struct LodSelector {
+    thresholds: Vec<f32>,
+    hysteresis: f32,
+    levels: Vec<usize>,
+}
+
+impl LodSelector {
+    /// Level `i + 1` is used below `thresholds[i]`, which must be descending.
+    pub fn new(thresholds: Vec<f32>, hysteresis: f32) -> Self {
+        Self {
+            thresholds,
+            hysteresis: hysteresis.clamp(0.0, 0.9),
+            levels: vec![],
+        }
+    }
+
+    pub fn get_level_count(&self) -> usize {
+        self.thresholds.len() + 1
+    }
+
+    pub fn get_hysteresis(&self) -> f32 {
+        self.hysteresis
+    }
+
+    pub fn set_hysteresis(&mut self, hysteresis: f32) {
+        self.hysteresis = hysteresis.clamp(0.0, 0.9);
+    }
+
+    /// The level of `object`, which is kept from the last call when within the hysteresis band.
+    pub fn select(&mut self, object: usize, screen_size: f32) -> usize {
+        if object >= self.levels.len() {
+            self.levels.resize(object + 1, 0);
+        }
+
+        let mut level = self.levels[object].min(self.thresholds.len());
+        while level < self.thresholds.len()
+            && screen_size < self.thresholds[level] * (1.0 - self.hysteresis)
+        {
+            level += 1;
+        }
+        while level > 0 && screen_size > self.thresholds[level - 1] * (1.0 + self.hysteresis) {
+            level -= 1;
+        }
+        self.levels[object] = level;
+        level
+    }
+
+    /// Forgets the levels of all objects, for when they are replaced.
+    pub fn reset(&mut self) {
+        self.levels.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::{Vec2, Vec3};
+
+    /// A flat grid of `size` by `size` quads over the unit square.
+    fn grid(size: u32) -> (Vec<Vertex>, Vec<u32>) {
+        let vertices = (0..(size + 1) * (size + 1))
+            .map(|index| {
+                let position = Vec2::new(
+                    (index % (size + 1)) as f32 / size as f32,
+                    (index / (size + 1)) as f32 / size as f32,
+                );
+                Vertex::new(position, Vec3::ONE)
+            })
+            .collect();
+        let indices = (0..size * size)
+            .flat_map(|quad| {
+                let corner = quad / size * (size + 1) + quad % size;
+                let above = corner + size + 1;
+                [corner, corner + 1, above, above, corner + 1, above + 1]
+            })
+            .collect();
+        (vertices, indices)
+    }
+
+    fn assert_valid_triangles(indices: &[u32], vertex_count: usize) {
+        assert_eq!(indices.len() % 3, 0);
+        for triangle in indices.chunks_exact(3) {
+            assert!(triangle
+                .iter()
+                .all(|index| (*index as usize) < vertex_count));
+            assert!(triangle[0] != triangle[1] && triangle[1] != triangle[2]);
+            assert_ne!(triangle[0], triangle[2]);
+        }
+    }
+
+    #[test]
+    fn simplify_without_cells_keeps_the_mesh() {
+        let (vertices, indices) = grid(4);
+
+        assert_eq!(simplify(&vertices, &indices, 0.0), indices);
+    }
+
+    #[test]
+    fn simplify_merges_vertices_of_a_cell() {
+        let (vertices, indices) = grid(8);
+        let simplified = simplify(&vertices, &indices, 0.3);
+
+        assert!(!simplified.is_empty());
+        assert!(simplified.len() < indices.len());
+        assert_valid_triangles(&simplified, vertices.len());
+    }
+
+    #[test]
+    fn simplify_drops_coinciding_triangles() {
+        let vertices = [
+            Vertex::new(Vec2::new(0.0, 0.0), Vec3::ONE),
+            Vertex::new(Vec2::new(1.0, 0.0), Vec3::ONE),
+            Vertex::new(Vec2::new(0.0, 1.0), Vec3::ONE),
+            Vertex::new(Vec2::new(0.01, 0.0), Vec3::ONE),
+        ];
+        // The second triangle is the first after merging vertex 3 into vertex 0, rotated.
+        let simplified = simplify(&vertices, &[0, 1, 2, 2, 3, 1], 0.5);
+
+        assert_eq!(simplified, [0, 1, 2]);
+    }
+
+    #[test]
+    fn lods_shrink_from_the_full_mesh() {
+        let (vertices, indices) = grid(16);
+        let lods = generate_lods(&vertices, &indices, 4);
+
+        assert_eq!(lods[0], indices);
+        assert!(lods.len() > 1 && lods.len() <= 4);
+        for pair in lods.windows(2) {
+            assert!(pair[1].len() < pair[0].len());
+        }
+        for lod in &lods {
+            assert_valid_triangles(lod, vertices.len());
+        }
+    }
+
+    #[test]
+    fn lods_always_include_the_full_mesh() {
+        let (vertices, indices) = grid(2);
+
+        assert_eq!(
+            generate_lods(&vertices, &indices, 0),
+            std::slice::from_ref(&indices)
+        );
+        assert_eq!(generate_lods(&[], &[], 4), [Vec::<u32>::new()]);
+    }
+
+    #[test]
+    fn projected_size_falls_off_with_distance() {
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 100.0);
+        let size = |distance: f32| {
+            projected_size(
+                &BoundingSphere::new(Vec3::new(0.0, 0.0, -distance), 1.0),
+                Mat4::IDENTITY,
+                projection,
+            )
+        };
+
+        assert!((size(10.0) - 0.1).abs() < 1e-5);
+        assert!((size(20.0) - 0.05).abs() < 1e-5);
+    }
+
+    #[test]
+    fn selection_holds_the_level_within_the_hysteresis_band() {
+        let mut selector = LodSelector::new(vec![0.5, 0.2], 0.1);
+        assert_eq!(selector.get_level_count(), 3);
+
+        assert_eq!(selector.select(0, 1.0), 0);
+        // Switches down only below 0.45 and back up only above 0.55.
+        assert_eq!(selector.select(0, 0.46), 0);
+        assert_eq!(selector.select(0, 0.44), 1);
+        assert_eq!(selector.select(0, 0.54), 1);
+        assert_eq!(selector.select(0, 0.56), 0);
+        // Several levels at once.
+        assert_eq!(selector.select(0, 0.01), 2);
+    }
+
+    #[test]
+    fn selection_is_tracked_per_object() {
+        let mut selector = LodSelector::new(vec![0.5], 0.1);
+
+        assert_eq!(selector.select(0, 0.3), 1);
+        assert_eq!(selector.select(3, 0.52), 0);
+        assert_eq!(selector.select(0, 0.52), 1);
+    }
+
+    #[test]
+    fn reset_and_hysteresis_changes_apply_to_the_next_selection() {
+        let mut selector = LodSelector::new(vec![0.5], 0.1);
+        assert_eq!(selector.select(0, 0.3), 1);
+
+        selector.reset();
+        assert_eq!(selector.select(0, 0.52), 0);
+
+        selector.set_hysteresis(2.0);
+        assert_eq!(selector.get_hysteresis(), 0.9);
+        assert_eq!(selector.select(0, 0.06), 0);
+        assert_eq!(selector.select(0, 0.04), 1);
+    }
+}
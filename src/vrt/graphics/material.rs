@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    DescriptorSet, DescriptorSetLayout, DescriptorType, Extent2D, Extent3D, Filter, Format,
+    ImageLayout, ImageType, ImageViewType, SamplerAddressMode, ShaderStageFlags,
+};
+use glam::{Vec3, Vec4};
+
+use super::pipeline::BlendMode;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::utils::result::VkResult;
+
+pub const MAX_MATERIALS: u32 = 256;
+
+/// Texture bindings of the material descriptor set, the sampler follows at `TEXTURE_SLOT_COUNT`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum TextureSlot {
+    BaseColor = 0,
+    Normal = 1,
+    MetallicRoughness = 2,
+    Occlusion = 3,
+    Emissive = 4,
+}
+
+pub const TEXTURE_SLOT_COUNT: usize = 5;
+
+impl TextureSlot {
+    pub const ALL: [TextureSlot; TEXTURE_SLOT_COUNT] = [
+        TextureSlot::BaseColor,
+        TextureSlot::Normal,
+        TextureSlot::MetallicRoughness,
+        TextureSlot::Occlusion,
+        TextureSlot::Emissive,
+    ];
+}
+
+/// Metallic-roughness PBR parameters. Texture values are multiplied with the factors, empty
+/// slots sample a neutral texture.
+#[derive(Clone)]
+pub struct Material {
+    pub base_color_factor: Vec4,
+    pub metallic_factor: f32,
+    pub roughness_factor: f32,
+    pub emissive_factor: Vec3,
+    pub normal_scale: f32,
+    pub occlusion_strength: f32,
+    /// Transparent materials are drawn after opaque geometry, sorted back to front.
+    pub blend_mode: BlendMode,
+    textures: [Option<Arc<VRTImage>>; TEXTURE_SLOT_COUNT],
+}
+
+/// Push constant layout of the material factors in the PBR shaders, after the model matrix.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct MaterialFactors {
+    pub base_color: [f32; 4],
+    pub emissive_normal_scale: [f32; 4],
+    pub metallic_roughness_occlusion: [f32; 4],
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color_factor: Vec4::ONE,
+            metallic_factor: 0.0,
+            roughness_factor: 0.5,
+            emissive_factor: Vec3::ZERO,
+            normal_scale: 1.0,
+            occlusion_strength: 1.0,
+            blend_mode: BlendMode::Opaque,
+            textures: Default::default(),
+        }
+    }
+}
+
+impl Material {
+    pub fn new(base_color_factor: Vec4, metallic_factor: f32, roughness_factor: f32) -> Self {
+        Self {
+            base_color_factor,
+            metallic_factor,
+            roughness_factor,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_emissive(mut self, emissive_factor: Vec3) -> Self {
+        self.emissive_factor = emissive_factor;
+        self
+    }
+
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    pub fn with_texture(mut self, slot: TextureSlot, texture: Arc<VRTImage>) -> Self {
+        self.textures[slot as usize] = Some(texture);
+        self
+    }
+
+    pub fn get_texture(&self, slot: TextureSlot) -> Option<&Arc<VRTImage>> {
+        self.textures[slot as usize].as_ref()
+    }
+
+    pub fn factors(&self) -> MaterialFactors {
+        MaterialFactors {
+            base_color: self.base_color_factor.to_array(),
+            emissive_normal_scale: self.emissive_factor.extend(self.normal_scale).to_array(),
+            metallic_roughness_occlusion: [
+                self.metallic_factor,
+                self.roughness_factor,
+                self.occlusion_strength,
+                0.0,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default)]
+pub struct MaterialHandle(usize);
+
+impl MaterialHandle {
+    /// Always registered, used by meshes without a material of their own.
+    pub const DEFAULT: Self = Self(0);
+}
+
+struct MaterialEntry {
+    name: String,
+    material: Material,
+    descriptor_set: DescriptorSet,
+}
+
+/// Owns all materials and their texture descriptor sets.
+///
+/// Factors are pushed per draw, so they can be edited at any time through `get_mut`. Texture
+/// changes rewrite the descriptor set and go through `set_texture`.
+pub struct MaterialRegistry {
+    entries: Vec<MaterialEntry>,
+    names: HashMap<String, MaterialHandle>,
+    white_texture: Arc<VRTImage>,
+    flat_normal_texture: Arc<VRTImage>,
+    sampler: VRTSampler,
+    descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    device: Arc<VRTDevice>,
+}
+
+impl MaterialRegistry {
+    pub fn new(device: Arc<VRTDevice>) -> VkResult<Self> {
+        let mut layout_builder = VRTDescriptorSetLayoutBuilder::new(device.clone());
+        for slot in TextureSlot::ALL {
+            layout_builder.add_binding(
+                slot as u32,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            );
+        }
+        let descriptor_set_layout = layout_builder
+            .add_binding(
+                TEXTURE_SLOT_COUNT as u32,
+                DescriptorType::SAMPLER,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .build();
+
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(MAX_MATERIALS)
+            .add_pool_size(
+                DescriptorType::SAMPLED_IMAGE,
+                MAX_MATERIALS * TEXTURE_SLOT_COUNT as u32,
+            )
+            .add_pool_size(DescriptorType::SAMPLER, MAX_MATERIALS)
+            .build()?;
+
+        let sampler = VRTSampler::new(device.clone(), Filter::LINEAR, SamplerAddressMode::REPEAT)?;
+        let white_texture = Arc::new(Self::solid_texture(&device, [255, 255, 255, 255])?);
+        let flat_normal_texture = Arc::new(Self::solid_texture(&device, [128, 128, 255, 255])?);
+
+        let mut registry = Self {
+            entries: vec![],
+            names: HashMap::new(),
+            white_texture,
+            flat_normal_texture,
+            sampler,
+            descriptor_pool,
+            descriptor_set_layout,
+            device,
+        };
+        registry.add("default", Material::default())?;
+        Ok(registry)
+    }
+
+    fn solid_texture(device: &Arc<VRTDevice>, texel: [u8; 4]) -> VkResult<VRTImage> {
+        VRTImage::from_pixels(
+            device.clone(),
+            ImageType::_2D,
+            ImageViewType::_2D,
+            Extent3D {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+            Format::R8G8B8A8_UNORM,
+            &texel,
+        )
+    }
+
+    /// Uploads tightly packed texels. Color textures (base color, emissive) should use an
+    /// `*_SRGB` format, data textures a `*_UNORM` one.
+    pub fn create_texture(
+        &self,
+        extent: Extent2D,
+        format: Format,
+        pixels: &[u8],
+    ) -> VkResult<Arc<VRTImage>> {
+        Ok(Arc::new(VRTImage::from_pixels(
+            self.device.clone(),
+            ImageType::_2D,
+            ImageViewType::_2D,
+            Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            },
+            format,
+            pixels,
+        )?))
+    }
+
+    /// Registers `material` under `name`, replacing the material of an existing name in place.
+    /// Replacing rewrites the descriptor set, no frame using it may still be in flight.
+    pub fn add(&mut self, name: &str, material: Material) -> VkResult<MaterialHandle> {
+        if let Some(handle) = self.find(name) {
+            self.entries[handle.0].material = material;
+            self.write_descriptor_set(handle);
+            return Ok(handle);
+        }
+
+        let handle = MaterialHandle(self.entries.len());
+        let descriptor_set = self
+            .descriptor_pool
+            .allocate_descriptor(&self.descriptor_set_layout)?;
+        self.entries.push(MaterialEntry {
+            name: name.to_string(),
+            material,
+            descriptor_set,
+        });
+        self.names.insert(name.to_string(), handle);
+        self.write_descriptor_set(handle);
+        Ok(handle)
+    }
+
+    fn write_descriptor_set(&self, handle: MaterialHandle) {
+        let entry = &self.entries[handle.0];
+        let mut writer =
+            VRTDescriptorWriter::new(&self.descriptor_set_layout, &self.descriptor_pool);
+        for slot in TextureSlot::ALL {
+            let texture = entry.material.get_texture(slot).unwrap_or(match slot {
+                TextureSlot::Normal => &self.flat_normal_texture,
+                _ => &self.white_texture,
+            });
+            writer.write_image(
+                slot as u32,
+                texture.get_image_view(),
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            );
+        }
+        writer
+            .write_sampler(TEXTURE_SLOT_COUNT as u32, self.sampler.get_sampler())
+            .overwrite(entry.descriptor_set);
+    }
+
+    pub fn find(&self, name: &str) -> Option<MaterialHandle> {
+        self.names.get(name).copied()
+    }
+
+    pub fn get(&self, handle: MaterialHandle) -> Option<&Material> {
+        self.entries.get(handle.0).map(|entry| &entry.material)
+    }
+
+    /// Factors can be changed freely, textures only through `set_texture`.
+    pub fn get_mut(&mut self, handle: MaterialHandle) -> Option<&mut Material> {
+        self.entries
+            .get_mut(handle.0)
+            .map(|entry| &mut entry.material)
+    }
+
+    pub fn get_name(&self, handle: MaterialHandle) -> Option<&str> {
+        self.entries.get(handle.0).map(|entry| entry.name.as_str())
+    }
+
+    /// No frame using the material may still be in flight.
+    pub fn set_texture(
+        &mut self,
+        handle: MaterialHandle,
+        slot: TextureSlot,
+        texture: Option<Arc<VRTImage>>,
+    ) {
+        if let Some(entry) = self.entries.get_mut(handle.0) {
+            entry.material.textures[slot as usize] = texture;
+            self.write_descriptor_set(handle);
+        }
+    }
+
+    pub fn handles(&self) -> impl Iterator<Item = MaterialHandle> {
+        (0..self.entries.len()).map(MaterialHandle)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// PBR pipelines bind material descriptor sets as set 1.
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    /// Unknown handles fall back to the default material.
+    pub fn get_descriptor_set(&self, handle: MaterialHandle) -> DescriptorSet {
+        self.entries
+            .get(handle.0)
+            .unwrap_or(&self.entries[MaterialHandle::DEFAULT.0])
+            .descriptor_set
+    }
+
+    /// The material to draw `handle` with, falling back to the default material.
+    pub fn resolve(&self, handle: MaterialHandle) -> &Material {
+        self.get(handle)
+            .unwrap_or(&self.entries[MaterialHandle::DEFAULT.0].material)
+    }
+}
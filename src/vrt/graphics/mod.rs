@@ -1,6 +1,27 @@
+pub mod bounds;
+pub mod clustered;
+pub mod compute_pipeline;
+pub mod debug_draw;
+pub mod deferred;
+pub mod frame;
+pub mod frame_stats;
+pub mod gui;
+pub mod ibl;
+pub mod indirect;
+pub mod instance;
+pub mod light;
+pub mod lod;
+pub mod material;
 pub mod model;
+pub mod particles;
+pub mod pbr_render_system;
 pub mod pipeline;
+pub mod post;
+pub mod render_target;
 pub mod renderer;
 pub mod shader;
-pub mod triangle_render_system;
+pub mod shadow;
+pub mod sprite;
+pub mod text;
+pub mod transparency;
 pub mod vertex;
@@ -1,36 +1,139 @@
-use std::{convert::TryInto, ffi::c_void, mem, ptr::copy_nonoverlapping, sync::Arc};
+use std::{ffi::c_void, mem, ops::Range, ptr::copy_nonoverlapping, sync::Arc};
 
 use color_eyre::owo_colors::OwoColorize;
 use erupt::{
     vk1_0::{
         Buffer, BufferCopyBuilder, BufferCreateInfoBuilder, BufferUsageFlags, CommandBuffer,
         CommandBufferAllocateInfoBuilder, CommandBufferBeginInfoBuilder, CommandBufferLevel,
-        CommandBufferUsageFlags, CommandPool, DeviceMemory, DeviceSize, Fence,
+        CommandBufferUsageFlags, CommandPool, DeviceMemory, DeviceSize, Fence, IndexType,
         MemoryAllocateInfoBuilder, MemoryMapFlags, MemoryPropertyFlags, PhysicalDevice, Queue,
         SharingMode, SubmitInfoBuilder, WHOLE_SIZE,
     },
     DeviceLoader, InstanceLoader,
 };
 
+use glam::Mat4;
+
 use crate::vrt::{
     device::{buffer::VRTBuffer, device::VRTDevice},
     utils::result::{VkError, VkResult},
 };
 
+use super::bounds::{Aabb, BoundingSphere, Frustum};
+use super::lod::LodLevel;
+use super::material::MaterialHandle;
 use super::vertex::Vertex;
 
 pub struct Model {
     device: Arc<VRTDevice>,
     vertex_buffer: VRTBuffer,
+    index_buffer: VRTBuffer,
+    lods: Vec<LodLevel>,
+    material: MaterialHandle,
+    aabb: Aabb,
+    bounding_sphere: BoundingSphere,
 }
 
 impl Model {
-    pub fn new(instance: &InstanceLoader, device: Arc<VRTDevice>) -> Self {
-        let vertex_buffer = Self::create_vertex_buffer(instance, device.clone()).unwrap();
-        Self {
+    pub fn new(_instance: &InstanceLoader, device: Arc<VRTDevice>) -> Self {
+        Self::from_mesh(device, &Vertex::VERTICES, &[Vertex::INDICES.to_vec()]).unwrap()
+    }
+
+    /// A model of `vertices` with one index list per level of detail, the most detailed first.
+    /// Levels can be authored or made with `lod::generate_lods`, they share the vertices.
+    pub fn from_mesh(
+        device: Arc<VRTDevice>,
+        vertices: &[Vertex],
+        lods: &[Vec<u32>],
+    ) -> VkResult<Self> {
+        let positions = vertices.iter().map(Vertex::get_position);
+        let (aabb, bounding_sphere) = Aabb::from_points(positions.clone())
+            .zip(BoundingSphere::from_points(positions))
+            .ok_or_else(|| VkError::InvalidAsset("model without vertices".to_string()))?;
+        if lods.iter().all(|indices| indices.is_empty()) {
+            return Err(VkError::InvalidAsset("model without indices".to_string()));
+        }
+
+        let mut first_index = 0;
+        let levels = lods
+            .iter()
+            .filter(|indices| !indices.is_empty())
+            .map(|indices| {
+                let level = LodLevel {
+                    first_index,
+                    index_count: indices.len() as u32,
+                };
+                first_index += level.index_count;
+                level
+            })
+            .collect();
+        let indices = lods.concat();
+
+        let vertex_buffer = Self::create_device_local_buffer(
+            device.clone(),
+            vertices,
+            BufferUsageFlags::VERTEX_BUFFER,
+        )?;
+        let index_buffer = Self::create_device_local_buffer(
+            device.clone(),
+            &indices,
+            BufferUsageFlags::INDEX_BUFFER,
+        )?;
+
+        Ok(Self {
             device,
             vertex_buffer,
-        }
+            index_buffer,
+            lods: levels,
+            material: MaterialHandle::DEFAULT,
+            aabb,
+            bounding_sphere,
+        })
+    }
+
+    pub fn get_material(&self) -> MaterialHandle {
+        self.material
+    }
+
+    pub fn set_material(&mut self, material: MaterialHandle) {
+        self.material = material;
+    }
+
+    /// Model space bounds of the vertices.
+    pub fn get_aabb(&self) -> Aabb {
+        self.aabb
+    }
+
+    pub fn get_bounding_sphere(&self) -> BoundingSphere {
+        self.bounding_sphere
+    }
+
+    pub fn world_aabb(&self, transform: Mat4) -> Aabb {
+        self.aabb.transformed(transform)
+    }
+
+    pub fn world_bounding_sphere(&self, transform: Mat4) -> BoundingSphere {
+        self.bounding_sphere.transformed(transform)
+    }
+
+    /// Whether the model drawn with `transform` may be inside `frustum`.
+    pub fn is_visible(&self, frustum: &Frustum, transform: Mat4) -> bool {
+        frustum.intersects_sphere(&self.world_bounding_sphere(transform))
+            && frustum.intersects_aabb(&self.world_aabb(transform))
+    }
+
+    /// Indices of the most detailed level.
+    pub fn get_index_count(&self) -> u32 {
+        self.lods[0].index_count
+    }
+
+    pub fn get_lod_count(&self) -> usize {
+        self.lods.len()
+    }
+
+    /// Levels past the last one fall back to the last.
+    pub fn get_lod(&self, lod: usize) -> LodLevel {
+        self.lods[lod.min(self.lods.len() - 1)]
     }
 
     pub fn bind(&self, device: Arc<VRTDevice>, command_buffer: CommandBuffer) {
@@ -41,101 +144,83 @@ impl Model {
                 std::slice::from_ref(&self.vertex_buffer.get_buffer()),
                 &[0],
             );
+            device.get_device_ptr().cmd_bind_index_buffer(
+                command_buffer,
+                self.index_buffer.get_buffer(),
+                0,
+                IndexType::UINT32,
+            );
         }
     }
 
     pub fn draw(&self, device: Arc<VRTDevice>, command_buffer: CommandBuffer) {
+        self.draw_instanced(device, command_buffer, 0, 0..1)
+    }
+
+    /// Draws level `lod` for the instances in `instances`, with per-instance data bound to
+    /// `INSTANCE_BINDING`.
+    pub fn draw_instanced(
+        &self,
+        device: Arc<VRTDevice>,
+        command_buffer: CommandBuffer,
+        lod: usize,
+        instances: Range<u32>,
+    ) {
+        if instances.is_empty() {
+            return;
+        }
+        let level = self.get_lod(lod);
         unsafe {
-            device.get_device_ptr().cmd_draw(
+            device.get_device_ptr().cmd_draw_indexed(
                 command_buffer,
-                Vertex::VERTICES.len().try_into().unwrap(),
-                1,
-                0,
+                level.index_count,
+                instances.end - instances.start,
+                level.first_index,
                 0,
+                instances.start,
             );
         }
     }
 
-    fn create_vertex_buffer(
-        instance: &InstanceLoader,
+    /// Copies `data` into a new device local buffer through a staging buffer.
+    fn create_device_local_buffer<T>(
         device: Arc<VRTDevice>,
+        data: &[T],
+        usage: BufferUsageFlags,
     ) -> VkResult<VRTBuffer> {
-        let buffer_size = (mem::size_of::<Vertex>() * Vertex::VERTICES.len()) as DeviceSize;
+        let buffer_size = mem::size_of_val(data) as DeviceSize;
 
         let staging_buffer = VRTBuffer::new(
             device.clone(),
-            mem::size_of::<Vertex>().try_into().unwrap(),
-            Vertex::VERTICES.len().try_into().unwrap(),
+            mem::size_of::<T>() as DeviceSize,
+            data.len() as u32,
             BufferUsageFlags::TRANSFER_SRC,
             MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
             None,
         );
-
-        // let (staging_buffer, staging_buffer_memory) = Self::create_buffer(
-        //     instance,
-        //     device.get_physical_device(),
-        //     &device.get_device_ptr(),
-        //     buffer_size,
-        //     BufferUsageFlags::TRANSFER_SRC,
-        //     MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
-        // )?;
-
         let mapped = staging_buffer.map(buffer_size, 0);
-
-        staging_buffer.write_to_buffer(
-            Vertex::VERTICES.as_ptr(),
-            mapped,
-            Vertex::VERTICES.len() as DeviceSize,
-            0,
-        );
+        staging_buffer.write_to_buffer(data.as_ptr(), mapped, data.len() as DeviceSize, 0);
         staging_buffer.unmap();
 
-        // unsafe {
-        //     let memory = device
-        //         .get_device_ptr()
-        //         .map_memory(
-        //             staging_buffer_memory,
-        //             0,
-        //             buffer_size,
-        //             MemoryMapFlags::empty(),
-        //         )
-        //         .result()?;
-        //     copy_nonoverlapping(
-        //         Vertex::VERTICES.as_ptr(),
-        //         memory.cast(),
-        //         Vertex::VERTICES.len(),
-        //     );
-        //     device.get_device_ptr().unmap_memory(staging_buffer_memory);
-        // }
-
-        let vertex_buffer = VRTBuffer::new(
+        let buffer = VRTBuffer::new(
             device.clone(),
-            mem::size_of::<Vertex>().try_into().unwrap(),
-            Vertex::VERTICES.len().try_into().unwrap(),
-            BufferUsageFlags::TRANSFER_DST | BufferUsageFlags::VERTEX_BUFFER,
+            mem::size_of::<T>() as DeviceSize,
+            data.len() as u32,
+            BufferUsageFlags::TRANSFER_DST | usage,
             MemoryPropertyFlags::DEVICE_LOCAL,
             None,
         );
 
-        // let (vertex_buffer, vertex_buffer_memory) = Self::create_buffer(
-        //     instance,
-        //     device.get_physical_device(),
-        //     &device.get_device_ptr(),
-        //     buffer_size,
-        //     BufferUsageFlags::TRANSFER_DST | BufferUsageFlags::VERTEX_BUFFER,
-        //     MemoryPropertyFlags::DEVICE_LOCAL,
-        // )?;
-
         Self::copy_buffer(
             &device.get_device_ptr(),
             device.get_queues().graphics,
             device.get_command_pool(),
             staging_buffer.get_buffer(),
-            vertex_buffer.get_buffer(),
+            buffer.get_buffer(),
             buffer_size,
         )?;
 
-        Ok(vertex_buffer)
+        Ok(buffer)
     }
 
     fn find_memory_type(
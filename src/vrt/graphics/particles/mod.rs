@@ -0,0 +1,551 @@
+pub mod particle_render_system;
+
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    AccessFlags, Buffer, BufferCopyBuilder, BufferUsageFlags, CommandBuffer, DependencyFlags,
+    DescriptorSet, DescriptorSetLayout, DescriptorType, DeviceSize, DrawIndirectCommand,
+    MemoryBarrierBuilder, MemoryPropertyFlags, PipelineStageFlags, ShaderStageFlags, WHOLE_SIZE,
+};
+use glam::{Vec3, Vec4};
+
+use super::compute_pipeline::VRTComputePipeline;
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::utils::result::VkResult;
+
+const RESET_SHADER: &str = "./assets/shaders/particle_reset_comp.spirv";
+const EMIT_SHADER: &str = "./assets/shaders/particle_emit_comp.spirv";
+const SIMULATE_SHADER: &str = "./assets/shaders/particle_simulate_comp.spirv";
+/// `local_size_x` of the reset and simulate shaders.
+const SIMULATE_GROUP_SIZE: u32 = 256;
+/// `local_size_x` of the emit shader.
+const EMIT_GROUP_SIZE: u32 = 64;
+/// Colliders of each kind the simulation considers.
+pub const MAX_COLLIDERS: usize = 8;
+/// Longest time step simulated at once, so a stalled frame does not tunnel through colliders.
+const MAX_TIME_STEP: f32 = 1.0 / 20.0;
+
+/// Matches the `Particle` struct of the particle shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GpuParticle {
+    position_size: Vec4,
+    velocity_age: Vec4,
+    color: Vec4,
+    life: Vec4,
+}
+
+/// Matches the `ParticleData` uniform block of the particle shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ParticleUniform {
+    emitter_position_radius: Vec4,
+    emitter_velocity_spread: Vec4,
+    lifetime_size: Vec4,
+    color_start: Vec4,
+    color_end: Vec4,
+    gravity_drag: Vec4,
+    curl_time_bounce: Vec4,
+    step_friction: Vec4,
+    counts: [u32; 4],
+    collider_counts: [u32; 4],
+    planes: [Vec4; MAX_COLLIDERS],
+    spheres: [Vec4; MAX_COLLIDERS],
+}
+
+/// Where and how particles are born. Position and velocity are randomized within a sphere of
+/// `radius` and `velocity_spread` around their base values.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ParticleEmitter {
+    pub position: Vec3,
+    pub radius: f32,
+    pub velocity: Vec3,
+    pub velocity_spread: f32,
+    /// Particles per second.
+    pub rate: f32,
+    /// Seconds, each particle picks a lifetime between the two.
+    pub lifetime: (f32, f32),
+    /// Linearly interpolated over the lifetime of a particle.
+    pub color_start: Vec4,
+    pub color_end: Vec4,
+    /// World units, interpolated like the color.
+    pub size_start: f32,
+    pub size_end: f32,
+}
+
+impl Default for ParticleEmitter {
+    fn default() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            radius: 0.1,
+            velocity: Vec3::ZERO,
+            velocity_spread: 1.0,
+            rate: 1000.0,
+            lifetime: (1.0, 2.0),
+            color_start: Vec4::ONE,
+            color_end: Vec4::new(1.0, 1.0, 1.0, 0.0),
+            size_start: 0.05,
+            size_end: 0.05,
+        }
+    }
+}
+
+/// Forces integrated every step.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ParticleForces {
+    pub gravity: Vec3,
+    /// Fraction of the velocity lost per second, as an exponential decay rate.
+    pub drag: f32,
+    /// Acceleration of the curl noise flow, 0 disables it.
+    pub curl_strength: f32,
+    /// Frequency of the curl noise, higher values make smaller swirls.
+    pub curl_scale: f32,
+    /// Fraction of the velocity into a collider that bounces back.
+    pub restitution: f32,
+    /// Fraction of the velocity along a collider lost on contact.
+    pub friction: f32,
+}
+
+impl Default for ParticleForces {
+    fn default() -> Self {
+        Self {
+            gravity: Vec3::ZERO,
+            drag: 0.0,
+            curl_strength: 0.0,
+            curl_scale: 1.0,
+            restitution: 0.5,
+            friction: 0.1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParticleCollider {
+    /// Particles stay on the side `normal` points to, `distance` is the offset of the plane
+    /// from the origin along `normal`.
+    Plane { normal: Vec3, distance: f32 },
+    /// Particles stay outside.
+    Sphere { center: Vec3, radius: f32 },
+}
+
+/// Particles living entirely on the GPU. Compute passes emit, integrate and retire them, so the
+/// CPU cost does not depend on the particle count.
+///
+/// Dead particles are kept on a stack the emit pass pops from and the simulate pass pushes to.
+/// The simulate pass also appends every living particle to an alive list and counts it in the
+/// instance count of an indirect draw, which `ParticleRenderSystem` issues.
+///
+/// The descriptor set of a frame holds the settings uniform, the particles, the dead list, the
+/// alive list and the draw commands. Graphics pipelines bind it as set 0.
+pub struct ParticleSystem {
+    reset_pipeline: VRTComputePipeline,
+    emit_pipeline: VRTComputePipeline,
+    simulate_pipeline: VRTComputePipeline,
+    descriptor_sets: Vec<DescriptorSet>,
+    uniform_buffers: Vec<VRTBuffer>,
+    _particle_buffer: VRTBuffer,
+    _dead_list_buffer: VRTBuffer,
+    _alive_list_buffer: VRTBuffer,
+    draw_buffer: VRTBuffer,
+    _descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    emitter: ParticleEmitter,
+    forces: ParticleForces,
+    colliders: Vec<ParticleCollider>,
+    capacity: u32,
+    // Fractional particles left over from the last step.
+    emit_carry: f32,
+    time: f32,
+    step: u32,
+    needs_reset: bool,
+    device: Arc<VRTDevice>,
+}
+
+impl ParticleSystem {
+    /// Allocates room for `capacity` particles, 64 bytes each.
+    pub fn new(device: Arc<VRTDevice>, capacity: u32) -> VkResult<Self> {
+        let capacity = capacity.max(1);
+
+        let stages = ShaderStageFlags::COMPUTE | ShaderStageFlags::VERTEX;
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::UNIFORM_BUFFER,
+                ShaderStageFlags::COMPUTE,
+                None,
+            )
+            .add_binding(1, DescriptorType::STORAGE_BUFFER, stages, None)
+            .add_binding(
+                2,
+                DescriptorType::STORAGE_BUFFER,
+                ShaderStageFlags::COMPUTE,
+                None,
+            )
+            .add_binding(3, DescriptorType::STORAGE_BUFFER, stages, None)
+            .add_binding(
+                4,
+                DescriptorType::STORAGE_BUFFER,
+                ShaderStageFlags::COMPUTE,
+                None,
+            )
+            .build();
+
+        let frame_count = MAX_FRAMES_IN_FLIGHT as u32;
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(frame_count)
+            .add_pool_size(DescriptorType::UNIFORM_BUFFER, frame_count)
+            .add_pool_size(DescriptorType::STORAGE_BUFFER, 4 * frame_count)
+            .build()?;
+
+        // Settings change every step, the particles themselves are only touched by the GPU.
+        let uniform_buffers = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| {
+                VRTBuffer::new(
+                    device.clone(),
+                    mem::size_of::<ParticleUniform>() as DeviceSize,
+                    1,
+                    BufferUsageFlags::UNIFORM_BUFFER,
+                    MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+        let create_buffer = |instance_size: usize, count: u32, usage: BufferUsageFlags| {
+            VRTBuffer::new(
+                device.clone(),
+                instance_size as DeviceSize,
+                count,
+                usage,
+                MemoryPropertyFlags::DEVICE_LOCAL,
+                None,
+            )
+        };
+        let particle_buffer = create_buffer(
+            mem::size_of::<GpuParticle>(),
+            capacity,
+            BufferUsageFlags::STORAGE_BUFFER,
+        );
+        // The count, then one index per particle.
+        let dead_list_buffer = create_buffer(
+            mem::size_of::<u32>(),
+            capacity + 1,
+            BufferUsageFlags::STORAGE_BUFFER,
+        );
+        let alive_list_buffer = create_buffer(
+            mem::size_of::<u32>(),
+            capacity,
+            BufferUsageFlags::STORAGE_BUFFER,
+        );
+        // One draw per `ParticleStyle`.
+        let draw_buffer = create_buffer(
+            mem::size_of::<DrawIndirectCommand>(),
+            2,
+            BufferUsageFlags::STORAGE_BUFFER
+                | BufferUsageFlags::INDIRECT_BUFFER
+                | BufferUsageFlags::TRANSFER_SRC
+                | BufferUsageFlags::TRANSFER_DST,
+        );
+
+        let descriptor_sets = uniform_buffers
+            .iter()
+            .map(|uniform_buffer| {
+                VRTDescriptorWriter::new(&descriptor_set_layout, &descriptor_pool)
+                    .write_buffer(0, uniform_buffer.get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(1, particle_buffer.get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(2, dead_list_buffer.get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(3, alive_list_buffer.get_buffer(), 0, WHOLE_SIZE)
+                    .write_buffer(
+                        4,
+                        draw_buffer.get_buffer(),
+                        0,
+                        mem::size_of::<DrawIndirectCommand>() as DeviceSize,
+                    )
+                    .build()
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+
+        let layouts = [descriptor_set_layout.get_descriptor_set_layout()];
+        let reset_pipeline = VRTComputePipeline::new(device.clone(), RESET_SHADER, &layouts, 0)?;
+        let emit_pipeline = VRTComputePipeline::new(device.clone(), EMIT_SHADER, &layouts, 0)?;
+        let simulate_pipeline =
+            VRTComputePipeline::new(device.clone(), SIMULATE_SHADER, &layouts, 0)?;
+
+        Ok(Self {
+            reset_pipeline,
+            emit_pipeline,
+            simulate_pipeline,
+            descriptor_sets,
+            uniform_buffers,
+            _particle_buffer: particle_buffer,
+            _dead_list_buffer: dead_list_buffer,
+            _alive_list_buffer: alive_list_buffer,
+            draw_buffer,
+            _descriptor_pool: descriptor_pool,
+            descriptor_set_layout,
+            emitter: ParticleEmitter::default(),
+            forces: ParticleForces::default(),
+            colliders: vec![],
+            capacity,
+            emit_carry: 0.0,
+            time: 0.0,
+            step: 0,
+            needs_reset: true,
+            device,
+        })
+    }
+
+    /// Graphics pipelines that draw the particles use this layout for set 0.
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self, frame_index: usize) -> DescriptorSet {
+        self.descriptor_sets[frame_index]
+    }
+
+    pub fn get_capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn emitter_mut(&mut self) -> &mut ParticleEmitter {
+        &mut self.emitter
+    }
+
+    pub fn forces_mut(&mut self) -> &mut ParticleForces {
+        &mut self.forces
+    }
+
+    pub fn colliders(&self) -> &[ParticleCollider] {
+        &self.colliders
+    }
+
+    /// Colliders past `MAX_COLLIDERS` of each kind are ignored.
+    pub fn colliders_mut(&mut self) -> &mut Vec<ParticleCollider> {
+        &mut self.colliders
+    }
+
+    /// Kills all particles at the next `update`.
+    pub fn reset(&mut self) {
+        self.needs_reset = true;
+    }
+
+    /// Advances the simulation by `dt` seconds, outside of any render pass. Particles are then
+    /// ready to be drawn with the descriptor set of `frame_index`.
+    pub fn update(&mut self, command_buffer: CommandBuffer, frame_index: usize, dt: f32) {
+        let dt = dt.clamp(0.0, MAX_TIME_STEP);
+        self.time += dt;
+        self.step = self.step.wrapping_add(1);
+        let emitted = self.emitter.rate.max(0.0) * dt + self.emit_carry;
+        let emit_count = (emitted.floor() as u32).min(self.capacity);
+        self.emit_carry = (emitted - emit_count as f32).min(1.0);
+
+        self.write_uniform(frame_index, dt, emit_count);
+
+        // The previous frame may still read the particles, the alive list and the draws.
+        self.barrier(
+            command_buffer,
+            PipelineStageFlags::COMPUTE_SHADER
+                | PipelineStageFlags::VERTEX_SHADER
+                | PipelineStageFlags::DRAW_INDIRECT,
+            AccessFlags::SHADER_WRITE,
+            PipelineStageFlags::TRANSFER | PipelineStageFlags::COMPUTE_SHADER,
+            AccessFlags::TRANSFER_WRITE | AccessFlags::SHADER_WRITE,
+        );
+        let draws = [
+            DrawIndirectCommand {
+                vertex_count: 6,
+                instance_count: 0,
+                first_vertex: 0,
+                first_instance: 0,
+            },
+            DrawIndirectCommand {
+                vertex_count: 0,
+                instance_count: 1,
+                first_vertex: 0,
+                first_instance: 0,
+            },
+        ];
+        unsafe {
+            self.device.get_device_ptr().cmd_update_buffer(
+                command_buffer,
+                self.draw_buffer.get_buffer(),
+                0,
+                mem::size_of_val(&draws) as DeviceSize,
+                draws.as_ptr().cast(),
+            );
+        }
+        self.barrier(
+            command_buffer,
+            PipelineStageFlags::TRANSFER,
+            AccessFlags::TRANSFER_WRITE,
+            PipelineStageFlags::COMPUTE_SHADER,
+            AccessFlags::SHADER_READ | AccessFlags::SHADER_WRITE,
+        );
+
+        let descriptor_set = self.descriptor_sets[frame_index];
+        let simulate_groups = self.capacity.div_ceil(SIMULATE_GROUP_SIZE);
+        if self.needs_reset {
+            self.reset_pipeline.bind(command_buffer);
+            self.reset_pipeline
+                .bind_descriptor_sets(command_buffer, 0, &[descriptor_set]);
+            self.reset_pipeline
+                .dispatch(command_buffer, simulate_groups, 1, 1);
+            self.compute_barrier(command_buffer);
+            self.needs_reset = false;
+        }
+        if emit_count > 0 {
+            self.emit_pipeline.bind(command_buffer);
+            self.emit_pipeline
+                .bind_descriptor_sets(command_buffer, 0, &[descriptor_set]);
+            self.emit_pipeline
+                .dispatch(command_buffer, emit_count.div_ceil(EMIT_GROUP_SIZE), 1, 1);
+            self.compute_barrier(command_buffer);
+        }
+        self.simulate_pipeline.bind(command_buffer);
+        self.simulate_pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[descriptor_set]);
+        self.simulate_pipeline
+            .dispatch(command_buffer, simulate_groups, 1, 1);
+
+        // The point draw takes the alive count as its vertex count.
+        self.barrier(
+            command_buffer,
+            PipelineStageFlags::COMPUTE_SHADER,
+            AccessFlags::SHADER_WRITE,
+            PipelineStageFlags::TRANSFER
+                | PipelineStageFlags::VERTEX_SHADER
+                | PipelineStageFlags::DRAW_INDIRECT,
+            AccessFlags::TRANSFER_READ
+                | AccessFlags::SHADER_READ
+                | AccessFlags::INDIRECT_COMMAND_READ,
+        );
+        let command_size = mem::size_of::<DrawIndirectCommand>() as DeviceSize;
+        let copy = BufferCopyBuilder::new()
+            .src_offset(mem::size_of::<u32>() as DeviceSize)
+            .dst_offset(command_size)
+            .size(mem::size_of::<u32>() as DeviceSize);
+        unsafe {
+            self.device.get_device_ptr().cmd_copy_buffer(
+                command_buffer,
+                self.draw_buffer.get_buffer(),
+                self.draw_buffer.get_buffer(),
+                std::slice::from_ref(&copy),
+            );
+        }
+        self.barrier(
+            command_buffer,
+            PipelineStageFlags::TRANSFER,
+            AccessFlags::TRANSFER_WRITE,
+            PipelineStageFlags::DRAW_INDIRECT,
+            AccessFlags::INDIRECT_COMMAND_READ,
+        );
+    }
+
+    fn write_uniform(&self, frame_index: usize, dt: f32, emit_count: u32) {
+        let emitter = &self.emitter;
+        let forces = &self.forces;
+        let mut planes = [Vec4::ZERO; MAX_COLLIDERS];
+        let mut spheres = [Vec4::ZERO; MAX_COLLIDERS];
+        let (mut plane_count, mut sphere_count) = (0, 0);
+        for collider in &self.colliders {
+            match *collider {
+                ParticleCollider::Plane { normal, distance } if plane_count < MAX_COLLIDERS => {
+                    planes[plane_count] = normal.normalize_or_zero().extend(distance);
+                    plane_count += 1;
+                }
+                ParticleCollider::Sphere { center, radius } if sphere_count < MAX_COLLIDERS => {
+                    spheres[sphere_count] = center.extend(radius);
+                    sphere_count += 1;
+                }
+                _ => (),
+            }
+        }
+
+        let uniform = ParticleUniform {
+            emitter_position_radius: emitter.position.extend(emitter.radius),
+            emitter_velocity_spread: emitter.velocity.extend(emitter.velocity_spread),
+            lifetime_size: Vec4::new(
+                emitter.lifetime.0,
+                emitter.lifetime.1,
+                emitter.size_start,
+                emitter.size_end,
+            ),
+            color_start: emitter.color_start,
+            color_end: emitter.color_end,
+            gravity_drag: forces.gravity.extend(forces.drag),
+            curl_time_bounce: Vec4::new(
+                forces.curl_strength,
+                forces.curl_scale,
+                self.time,
+                forces.restitution,
+            ),
+            step_friction: Vec4::new(dt, forces.friction, 0.0, 0.0),
+            counts: [emit_count, self.capacity, self.step, 0],
+            collider_counts: [plane_count as u32, sphere_count as u32, 0, 0],
+            planes,
+            spheres,
+        };
+        let uniform_buffer = &self.uniform_buffers[frame_index];
+        let mapped = uniform_buffer.map(WHOLE_SIZE, 0);
+        uniform_buffer.write_to_buffer(&uniform as *const ParticleUniform, mapped, 1, 0);
+        uniform_buffer.unmap();
+    }
+
+    fn compute_barrier(&self, command_buffer: CommandBuffer) {
+        self.barrier(
+            command_buffer,
+            PipelineStageFlags::COMPUTE_SHADER,
+            AccessFlags::SHADER_WRITE,
+            PipelineStageFlags::COMPUTE_SHADER,
+            AccessFlags::SHADER_READ | AccessFlags::SHADER_WRITE,
+        );
+    }
+
+    fn barrier(
+        &self,
+        command_buffer: CommandBuffer,
+        src_stage: PipelineStageFlags,
+        src_access: AccessFlags,
+        dst_stage: PipelineStageFlags,
+        dst_access: AccessFlags,
+    ) {
+        let barrier = MemoryBarrierBuilder::new()
+            .src_access_mask(src_access)
+            .dst_access_mask(dst_access);
+        unsafe {
+            self.device.get_device_ptr().cmd_pipeline_barrier(
+                command_buffer,
+                src_stage,
+                dst_stage,
+                DependencyFlags::empty(),
+                std::slice::from_ref(&barrier),
+                &[],
+                &[],
+            );
+        }
+    }
+
+    /// The indirect draw of `style`, counted by the last `update`.
+    fn draw_command(&self, style: ParticleStyle) -> (Buffer, DeviceSize) {
+        let offset = style as DeviceSize * mem::size_of::<DrawIndirectCommand>() as DeviceSize;
+        (self.draw_buffer.get_buffer(), offset)
+    }
+}
+
+/// How `ParticleRenderSystem` draws particles, each style has its own indirect draw.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ParticleStyle {
+    /// Camera facing quads, six vertices per instance and one instance per living particle.
+    Billboard = 0,
+    /// One point per living particle. Without the `largePoints` feature every point is a
+    /// single pixel.
+    Point = 1,
+}
@@ -0,0 +1,136 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, CompareOp, CullModeFlags, DrawIndirectCommand, Extent2D, PrimitiveTopology,
+    RenderPass, SampleCountFlagBits, ShaderStageFlags,
+};
+use glam::{Mat4, Vec4};
+
+use super::{ParticleStyle, ParticleSystem};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::pipeline::{BlendMode, VRTPipeline};
+
+const BILLBOARD_VERTEX_SHADER: &str = "./assets/shaders/particle_billboard_vert.spirv";
+const BILLBOARD_FRAGMENT_SHADER: &str = "./assets/shaders/particle_billboard_frag.spirv";
+const POINT_VERTEX_SHADER: &str = "./assets/shaders/particle_point_vert.spirv";
+const POINT_FRAGMENT_SHADER: &str = "./assets/shaders/particle_point_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ParticlePush {
+    view_projection: Mat4,
+    /// Pixels per world unit at distance 1 in w, points use it for their size.
+    camera_right: Vec4,
+    camera_up: Vec4,
+}
+
+/// Draws the living particles of a `ParticleSystem` with a single indirect draw. Blended
+/// particles are depth tested against the scene without writing depth. With
+/// `BlendMode::Opaque` alpha becomes MSAA coverage instead and particles write depth, so they
+/// occlude each other correctly without sorting.
+pub struct ParticleRenderSystem {
+    pipeline: VRTPipeline,
+    style: ParticleStyle,
+    blend_mode: BlendMode,
+    device: Arc<VRTDevice>,
+}
+
+impl ParticleRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        particles: &ParticleSystem,
+        style: ParticleStyle,
+        blend_mode: BlendMode,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        let alpha_to_coverage = blend_mode == BlendMode::Opaque;
+        config_info.set_depth_test(true, alpha_to_coverage, CompareOp::LESS);
+        config_info.set_blend_mode(blend_mode);
+        config_info.set_multisampling(msaa_samples, None, alpha_to_coverage);
+        config_info.set_descriptor_set_layouts(&[particles.get_descriptor_set_layout()]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX,
+            0,
+            mem::size_of::<ParticlePush>() as u32,
+        );
+        let (vertex_shader, fragment_shader) = match style {
+            ParticleStyle::Billboard => (BILLBOARD_VERTEX_SHADER, BILLBOARD_FRAGMENT_SHADER),
+            ParticleStyle::Point => {
+                if !device.is_large_points_enabled() {
+                    log::warn!("largePoints is not supported, particles are drawn as pixels");
+                }
+                config_info.set_topology(PrimitiveTopology::POINT_LIST);
+                (POINT_VERTEX_SHADER, POINT_FRAGMENT_SHADER)
+            }
+        };
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            vertex_shader,
+            fragment_shader,
+            &mut config_info,
+            render_pass,
+        );
+
+        Self {
+            pipeline,
+            style,
+            blend_mode,
+            device,
+        }
+    }
+
+    pub fn get_style(&self) -> ParticleStyle {
+        self.style
+    }
+
+    pub fn get_blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Draws the particles `particles.update` simulated for `frame_index`, seen from `view`
+    /// through `projection` onto a target of `extent`.
+    pub fn render(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        particles: &ParticleSystem,
+        view: Mat4,
+        projection: Mat4,
+        extent: Extent2D,
+    ) {
+        // Rows of the view rotation are the camera axes in world space.
+        let camera_right = view.row(0).truncate();
+        let camera_up = view.row(1).truncate();
+        let pixel_scale = extent.height as f32 * projection.y_axis.y.abs() * 0.5;
+        let push = ParticlePush {
+            view_projection: projection * view,
+            camera_right: camera_right.extend(pixel_scale),
+            camera_up: camera_up.extend(0.0),
+        };
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .push_constants(command_buffer, ShaderStageFlags::VERTEX, &push);
+        self.pipeline.bind_descriptor_sets(
+            command_buffer,
+            0,
+            &[particles.get_descriptor_set(frame_index)],
+        );
+        let (buffer, offset) = particles.draw_command(self.style);
+        unsafe {
+            self.device.get_device_ptr().cmd_draw_indirect(
+                command_buffer,
+                buffer,
+                offset,
+                1,
+                mem::size_of::<DrawIndirectCommand>() as u32,
+            );
+        }
+    }
+}
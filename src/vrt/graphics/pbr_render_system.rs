@@ -0,0 +1,340 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    BufferUsageFlags, CommandBuffer, CompareOp, CullModeFlags, DescriptorSet, DescriptorType,
+    DeviceSize, MemoryPropertyFlags, RenderPass, SampleCountFlagBits, ShaderStageFlags, WHOLE_SIZE,
+};
+use glam::{Mat4, Vec3};
+
+use super::ibl::EnvironmentMap;
+use super::instance::{InstanceBatch, InstanceBuffer, InstanceTransform};
+use super::material::{MaterialFactors, MaterialHandle, MaterialRegistry};
+use super::model::Model;
+use super::pipeline::{BlendMode, VRTPipeline};
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::utils::result::VkResult;
+
+const VERTEX_SHADER: &str = "./assets/shaders/pbr_vert.spirv";
+const INSTANCED_VERTEX_SHADER: &str = "./assets/shaders/pbr_instanced_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/pbr_frag.spirv";
+/// Fraction of the MSAA samples shaded individually, against specular aliasing inside of
+/// triangles that plain MSAA does not touch.
+const MIN_SAMPLE_SHADING: f32 = 0.5;
+
+/// Camera, a single directional light and the tint of the image-based ambient light, shared by
+/// all PBR draws of a frame.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PbrScene {
+    pub view_projection: Mat4,
+    pub camera_position: Vec3,
+    /// Direction the light travels in.
+    pub light_direction: Vec3,
+    pub light_color: Vec3,
+    pub light_intensity: f32,
+    pub ambient_color: Vec3,
+}
+
+impl Default for PbrScene {
+    fn default() -> Self {
+        Self {
+            view_projection: Mat4::IDENTITY,
+            camera_position: Vec3::new(0.0, 0.0, -1.0),
+            light_direction: Vec3::new(0.3, 0.5, 1.0).normalize(),
+            light_color: Vec3::ONE,
+            light_intensity: 3.0,
+            ambient_color: Vec3::ONE,
+        }
+    }
+}
+
+/// Matches the `SceneData` uniform block of the PBR shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct SceneUniform {
+    view_projection: Mat4,
+    camera_position: [f32; 4],
+    light_direction: [f32; 4],
+    light_color: [f32; 4],
+    ambient_color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct PbrPush {
+    model: Mat4,
+    factors: MaterialFactors,
+}
+
+/// Draws models with the metallic-roughness PBR shaders.
+///
+/// Set 0 holds the scene uniform of the frame in flight, set 1 the material of the model and set
+/// 2 the environment lighting it. Instanced draws read an `InstanceTransform` per instance.
+/// Materials with a transparent blend mode get a pipeline that tests depth without writing it.
+pub struct PbrRenderSystem {
+    pipeline: VRTPipeline,
+    instanced_pipeline: VRTPipeline,
+    transparent_pipelines: Vec<(BlendMode, VRTPipeline)>,
+    scene_sets: Vec<DescriptorSet>,
+    scene_buffers: Vec<VRTBuffer>,
+    _descriptor_pool: VRTDescriptorPool,
+    _scene_layout: VRTDescriptorSetLayout,
+    device: Arc<VRTDevice>,
+}
+
+impl PbrRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        materials: &MaterialRegistry,
+        environment: &EnvironmentMap,
+    ) -> VkResult<Self> {
+        let scene_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::UNIFORM_BUFFER,
+                ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .build();
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(MAX_FRAMES_IN_FLIGHT as u32)
+            .add_pool_size(DescriptorType::UNIFORM_BUFFER, MAX_FRAMES_IN_FLIGHT as u32)
+            .build()?;
+
+        let scene_buffers = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| {
+                VRTBuffer::new(
+                    device.clone(),
+                    mem::size_of::<SceneUniform>() as DeviceSize,
+                    1,
+                    BufferUsageFlags::UNIFORM_BUFFER,
+                    MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+        let scene_sets = scene_buffers
+            .iter()
+            .map(|buffer| {
+                VRTDescriptorWriter::new(&scene_layout, &descriptor_pool)
+                    .write_buffer(0, buffer.get_buffer(), 0, WHOLE_SIZE)
+                    .build()
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.set_multisampling(msaa_samples, Some(MIN_SAMPLE_SHADING), false);
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_descriptor_set_layouts(&[
+            scene_layout.get_descriptor_set_layout(),
+            materials.get_descriptor_set_layout(),
+            environment.get_descriptor_set_layout(),
+        ]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            0,
+            mem::size_of::<PbrPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        config_info.set_depth_test(true, false, CompareOp::LESS);
+        let transparent_pipelines = BlendMode::TRANSPARENT
+            .iter()
+            .map(|&blend_mode| {
+                config_info.set_blend_mode(blend_mode);
+                let pipeline = VRTPipeline::new(
+                    device.clone(),
+                    VERTEX_SHADER,
+                    FRAGMENT_SHADER,
+                    &mut config_info,
+                    render_pass,
+                );
+                (blend_mode, pipeline)
+            })
+            .collect();
+        config_info.set_blend_mode(BlendMode::Opaque);
+        config_info.set_depth_test(true, true, CompareOp::LESS);
+
+        config_info.add_instance_input::<InstanceTransform>();
+        let instanced_pipeline = VRTPipeline::new(
+            device.clone(),
+            INSTANCED_VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        let render_system = Self {
+            pipeline,
+            instanced_pipeline,
+            transparent_pipelines,
+            scene_sets,
+            scene_buffers,
+            _descriptor_pool: descriptor_pool,
+            _scene_layout: scene_layout,
+            device,
+        };
+        for frame_index in 0..MAX_FRAMES_IN_FLIGHT {
+            render_system.update_scene(frame_index, &PbrScene::default(), environment);
+        }
+        Ok(render_system)
+    }
+
+    /// Uploads the scene of `frame_index`, before any model of the frame is rendered.
+    pub fn update_scene(&self, frame_index: usize, scene: &PbrScene, environment: &EnvironmentMap) {
+        let uniform = SceneUniform {
+            view_projection: scene.view_projection,
+            camera_position: scene.camera_position.extend(1.0).to_array(),
+            light_direction: scene
+                .light_direction
+                .normalize_or_zero()
+                .extend(scene.light_intensity)
+                .to_array(),
+            light_color: scene.light_color.extend(1.0).to_array(),
+            ambient_color: scene
+                .ambient_color
+                .extend(environment.max_reflection_lod())
+                .to_array(),
+        };
+
+        let buffer = &self.scene_buffers[frame_index];
+        let mapped = buffer.map(WHOLE_SIZE, 0);
+        buffer.write_to_buffer(&uniform as *const SceneUniform, mapped, 1, 0);
+        buffer.unmap();
+    }
+
+    pub fn render(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        model: &Model,
+        materials: &MaterialRegistry,
+        environment: &EnvironmentMap,
+        transform: Mat4,
+    ) {
+        self.render_material(
+            command_buffer,
+            frame_index,
+            model,
+            model.get_material(),
+            materials,
+            environment,
+            transform,
+        )
+    }
+
+    /// Draws `model` with `material` instead of its own, using the pipeline of the material's
+    /// blend mode. Transparent draws belong after all opaque ones, sorted back to front.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_material(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        model: &Model,
+        material: MaterialHandle,
+        materials: &MaterialRegistry,
+        environment: &EnvironmentMap,
+        transform: Mat4,
+    ) {
+        let blend_mode = materials.resolve(material).blend_mode;
+        let pipeline = self
+            .transparent_pipelines
+            .iter()
+            .find(|(mode, _)| *mode == blend_mode)
+            .map_or(&self.pipeline, |(_, pipeline)| pipeline);
+        self.bind(
+            pipeline,
+            command_buffer,
+            frame_index,
+            model,
+            material,
+            materials,
+            environment,
+            transform,
+        );
+        model.draw(self.device.clone(), command_buffer)
+    }
+
+    /// Draws a batch of the instances written for `frame_index`, each placed by its instance
+    /// transform and then by `transform`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_instanced(
+        &self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        model: &Model,
+        materials: &MaterialRegistry,
+        environment: &EnvironmentMap,
+        instances: &InstanceBuffer<InstanceTransform>,
+        batch: &InstanceBatch,
+        transform: Mat4,
+    ) {
+        self.bind(
+            &self.instanced_pipeline,
+            command_buffer,
+            frame_index,
+            model,
+            model.get_material(),
+            materials,
+            environment,
+            transform,
+        );
+        instances.bind(command_buffer, frame_index);
+        let end = batch.range.end.min(instances.len(frame_index));
+        model.draw_instanced(
+            self.device.clone(),
+            command_buffer,
+            batch.lod,
+            batch.range.start..end,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn bind(
+        &self,
+        pipeline: &VRTPipeline,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        model: &Model,
+        material: MaterialHandle,
+        materials: &MaterialRegistry,
+        environment: &EnvironmentMap,
+        transform: Mat4,
+    ) {
+        pipeline.bind(command_buffer);
+        pipeline.bind_descriptor_sets(
+            command_buffer,
+            0,
+            &[
+                self.scene_sets[frame_index],
+                materials.get_descriptor_set(material),
+                environment.get_descriptor_set(),
+            ],
+        );
+        pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::VERTEX | ShaderStageFlags::FRAGMENT,
+            &PbrPush {
+                model: transform,
+                factors: materials.resolve(material).factors(),
+            },
+        );
+        model.bind(self.device.clone(), command_buffer);
+    }
+}
@@ -4,37 +4,211 @@ use std::io::Read;
 use std::sync::Arc;
 
 use erupt::vk1_0::{
-    BlendFactor, BlendOp, ColorComponentFlags, CommandBuffer, CullModeFlags, DynamicState,
-    FrontFace, GraphicsPipelineCreateInfoBuilder, LogicOp, Pipeline, PipelineBindPoint,
-    PipelineCache, PipelineColorBlendAttachmentStateBuilder,
-    PipelineColorBlendStateCreateInfoBuilder, PipelineDynamicStateCreateFlags,
-    PipelineDynamicStateCreateInfoBuilder, PipelineInputAssemblyStateCreateInfoBuilder,
-    PipelineLayoutCreateInfoBuilder, PipelineMultisampleStateCreateInfoBuilder,
-    PipelineRasterizationStateCreateInfoBuilder, PipelineShaderStageCreateInfoBuilder,
-    PipelineVertexInputStateCreateInfoBuilder, PipelineViewportStateCreateInfoBuilder, PolygonMode,
-    PrimitiveTopology, RenderPass, SampleCountFlagBits, ShaderModule,
-    ShaderModuleCreateInfoBuilder, ShaderStageFlagBits, VertexInputAttributeDescriptionBuilder,
-    VertexInputBindingDescriptionBuilder,
+    BlendFactor, BlendOp, ColorComponentFlags, CommandBuffer, CompareOp, CullModeFlags,
+    DescriptorSet, DescriptorSetLayout, DynamicState, FrontFace, GraphicsPipelineCreateInfoBuilder,
+    LogicOp, Pipeline, PipelineBindPoint, PipelineCache, PipelineColorBlendAttachmentStateBuilder,
+    PipelineColorBlendStateCreateInfoBuilder, PipelineDepthStencilStateCreateInfoBuilder,
+    PipelineDynamicStateCreateFlags, PipelineDynamicStateCreateInfoBuilder,
+    PipelineInputAssemblyStateCreateInfoBuilder, PipelineLayout, PipelineLayoutCreateInfoBuilder,
+    PipelineMultisampleStateCreateInfoBuilder, PipelineRasterizationStateCreateInfoBuilder,
+    PipelineShaderStageCreateInfoBuilder, PipelineVertexInputStateCreateInfoBuilder,
+    PipelineViewportStateCreateInfoBuilder, PolygonMode, PrimitiveTopology,
+    PushConstantRangeBuilder, RenderPass, SampleCountFlagBits, ShaderModule,
+    ShaderModuleCreateInfoBuilder, ShaderStageFlagBits, ShaderStageFlags,
+    VertexInputAttributeDescriptionBuilder, VertexInputBindingDescriptionBuilder,
 };
 
 use crate::vrt::device::device::VRTDevice;
 use crate::vrt::utils::result::VkResult;
 
+use super::instance::InstanceData;
 use super::vertex::Vertex;
 
+/// Color blending presets. Transparent modes expect back-to-front drawing, usually with depth
+/// testing but without depth writes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub enum BlendMode {
+    #[default]
+    Opaque,
+    /// Straight alpha, `src * a + dst * (1 - a)`.
+    Alpha,
+    /// Color already multiplied by alpha, `src + dst * (1 - a)`.
+    Premultiplied,
+    /// `src * a + dst`, keeping the destination alpha.
+    Additive,
+    /// `src * dst`, keeping the destination alpha.
+    Multiply,
+}
+
+impl BlendMode {
+    pub const TRANSPARENT: [BlendMode; 4] = [
+        BlendMode::Alpha,
+        BlendMode::Premultiplied,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+    ];
+
+    pub fn is_transparent(self) -> bool {
+        self != BlendMode::Opaque
+    }
+
+    /// Source and destination factors of color, then of alpha.
+    fn factors(self) -> [BlendFactor; 4] {
+        match self {
+            BlendMode::Opaque => [
+                BlendFactor::ONE,
+                BlendFactor::ZERO,
+                BlendFactor::ONE,
+                BlendFactor::ZERO,
+            ],
+            BlendMode::Alpha => [
+                BlendFactor::SRC_ALPHA,
+                BlendFactor::ONE_MINUS_SRC_ALPHA,
+                BlendFactor::ONE,
+                BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ],
+            BlendMode::Premultiplied => [
+                BlendFactor::ONE,
+                BlendFactor::ONE_MINUS_SRC_ALPHA,
+                BlendFactor::ONE,
+                BlendFactor::ONE_MINUS_SRC_ALPHA,
+            ],
+            BlendMode::Additive => [
+                BlendFactor::SRC_ALPHA,
+                BlendFactor::ONE,
+                BlendFactor::ZERO,
+                BlendFactor::ONE,
+            ],
+            BlendMode::Multiply => [
+                BlendFactor::DST_COLOR,
+                BlendFactor::ZERO,
+                BlendFactor::ZERO,
+                BlendFactor::ONE,
+            ],
+        }
+    }
+}
+
 pub struct PipelineConfigInfo<'a> {
     input_assembly: PipelineInputAssemblyStateCreateInfoBuilder<'a>,
     rasterizer: PipelineRasterizationStateCreateInfoBuilder<'a>,
     multisampling: PipelineMultisampleStateCreateInfoBuilder<'a>,
-    pipeline_layout_info: PipelineLayoutCreateInfoBuilder<'a>,
+    descriptor_set_layouts: Vec<DescriptorSetLayout>,
+    push_constant_ranges: Vec<PushConstantRangeBuilder<'a>>,
     dynamic_state_info: PipelineDynamicStateCreateInfoBuilder<'a>,
     color_blend_attachment: PipelineColorBlendAttachmentStateBuilder<'a>,
-    binding_description: VertexInputBindingDescriptionBuilder<'a>,
+    color_attachment_count: usize,
+    depth_stencil_info: PipelineDepthStencilStateCreateInfoBuilder<'a>,
+    binding_descriptions: Vec<VertexInputBindingDescriptionBuilder<'a>>,
     attribute_descriptions: Vec<VertexInputAttributeDescriptionBuilder<'a>>,
 }
 
+impl<'a> PipelineConfigInfo<'a> {
+    pub fn set_depth_test(&mut self, test_enable: bool, write_enable: bool, compare_op: CompareOp) {
+        self.depth_stencil_info = self
+            .depth_stencil_info
+            .depth_test_enable(test_enable)
+            .depth_write_enable(write_enable)
+            .depth_compare_op(compare_op);
+    }
+
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        let [src_color, dst_color, src_alpha, dst_alpha] = blend_mode.factors();
+        self.color_blend_attachment = self
+            .color_blend_attachment
+            .blend_enable(blend_mode.is_transparent())
+            .src_color_blend_factor(src_color)
+            .dst_color_blend_factor(dst_color)
+            .color_blend_op(BlendOp::ADD)
+            .src_alpha_blend_factor(src_alpha)
+            .dst_alpha_blend_factor(dst_alpha)
+            .alpha_blend_op(BlendOp::ADD);
+    }
+
+    pub fn set_multisampling(
+        &mut self,
+        samples: SampleCountFlagBits,
+        min_sample_shading: Option<f32>,
+        alpha_to_coverage: bool,
+    ) {
+        self.multisampling = self
+            .multisampling
+            .rasterization_samples(samples)
+            .sample_shading_enable(min_sample_shading.is_some())
+            .min_sample_shading(min_sample_shading.unwrap_or(1.0))
+            .alpha_to_coverage_enable(alpha_to_coverage);
+    }
+
+    pub fn set_topology(&mut self, topology: PrimitiveTopology) {
+        self.input_assembly = self.input_assembly.topology(topology);
+    }
+
+    pub fn set_cull_mode(&mut self, cull_mode: CullModeFlags) {
+        self.rasterizer = self.rasterizer.cull_mode(cull_mode);
+    }
+
+    pub fn set_descriptor_set_layouts(&mut self, descriptor_set_layouts: &[DescriptorSetLayout]) {
+        self.descriptor_set_layouts = descriptor_set_layouts.to_vec();
+    }
+
+    pub fn add_push_constant_range(
+        &mut self,
+        stage_flags: ShaderStageFlags,
+        offset: u32,
+        size: u32,
+    ) {
+        self.push_constant_ranges.push(
+            PushConstantRangeBuilder::new()
+                .stage_flags(stage_flags)
+                .offset(offset)
+                .size(size),
+        );
+    }
+
+    /// Render passes with several color attachments, such as G-buffers, share one blend state.
+    pub fn set_color_attachment_count(&mut self, count: usize) {
+        self.color_attachment_count = count;
+    }
+
+    /// Depth bias is then set per pass with `cmd_set_depth_bias`, e.g. per shadow map.
+    pub fn set_dynamic_depth_bias(&mut self) {
+        self.rasterizer = self.rasterizer.depth_bias_enable(true);
+        self.dynamic_state_info = PipelineDynamicStateCreateInfoBuilder::new()
+            .dynamic_states(&[
+                DynamicState::VIEWPORT,
+                DynamicState::SCISSOR,
+                DynamicState::DEPTH_BIAS,
+            ])
+            .flags(PipelineDynamicStateCreateFlags::empty());
+    }
+
+    /// Adds the per-instance binding of `T` next to the `Vertex` binding.
+    pub fn add_instance_input<T: InstanceData>(&mut self) {
+        self.binding_descriptions.push(T::binding_description());
+        self.attribute_descriptions
+            .extend(T::attribute_descriptions());
+    }
+
+    /// Replaces the `Vertex` input with another per-vertex layout.
+    pub fn set_vertex_input(
+        &mut self,
+        binding_descriptions: Vec<VertexInputBindingDescriptionBuilder<'a>>,
+        attribute_descriptions: Vec<VertexInputAttributeDescriptionBuilder<'a>>,
+    ) {
+        self.binding_descriptions = binding_descriptions;
+        self.attribute_descriptions = attribute_descriptions;
+    }
+
+    /// For shaders that generate their vertices, such as fullscreen triangles.
+    pub fn clear_vertex_input(&mut self) {
+        self.binding_descriptions.clear();
+        self.attribute_descriptions.clear();
+    }
+}
+
 pub struct VRTPipeline {
     graphics_pipeline: Pipeline,
+    pipeline_layout: PipelineLayout,
     device: Arc<VRTDevice>,
 }
 
@@ -45,37 +219,78 @@ impl VRTPipeline {
         fragment_shader_path: &str,
         config_info: &mut PipelineConfigInfo,
         render_pass: RenderPass,
+    ) -> Self {
+        Self::create(
+            device,
+            vertex_shader_path,
+            Some(fragment_shader_path),
+            config_info,
+            render_pass,
+        )
+    }
+
+    /// Pipeline without a fragment stage or color attachments, for shadow maps and depth prepasses.
+    pub fn new_depth_only(
+        device: Arc<VRTDevice>,
+        vertex_shader_path: &str,
+        config_info: &mut PipelineConfigInfo,
+        render_pass: RenderPass,
+    ) -> Self {
+        config_info.color_attachment_count = 0;
+        Self::create(device, vertex_shader_path, None, config_info, render_pass)
+    }
+
+    fn create(
+        device: Arc<VRTDevice>,
+        vertex_shader_path: &str,
+        fragment_shader_path: Option<&str>,
+        config_info: &mut PipelineConfigInfo,
+        render_pass: RenderPass,
     ) -> Self {
         let vertex_shader_module =
-            Self::create_shader_module(device.clone(), &Self::read_file(&vertex_shader_path))
-                .unwrap();
-        let fragment_shader_module =
-            Self::create_shader_module(device.clone(), &Self::read_file(&fragment_shader_path))
+            Self::create_shader_module(device.clone(), &Self::read_file(vertex_shader_path))
                 .unwrap();
+        let fragment_shader_module = fragment_shader_path.map(|path| {
+            Self::create_shader_module(device.clone(), &Self::read_file(path)).unwrap()
+        });
         let name = CString::new("main").unwrap();
 
-        let vertex_shader_stage_info = PipelineShaderStageCreateInfoBuilder::new()
+        let mut shader_stages = vec![PipelineShaderStageCreateInfoBuilder::new()
             .stage(ShaderStageFlagBits::VERTEX)
             .module(vertex_shader_module)
-            .name(&name);
+            .name(&name)];
 
-        let fragment_shader_stage_info = PipelineShaderStageCreateInfoBuilder::new()
-            .stage(ShaderStageFlagBits::FRAGMENT)
-            .module(fragment_shader_module)
-            .name(&name);
+        if let Some(fragment_shader_module) = fragment_shader_module {
+            shader_stages.push(
+                PipelineShaderStageCreateInfoBuilder::new()
+                    .stage(ShaderStageFlagBits::FRAGMENT)
+                    .module(fragment_shader_module)
+                    .name(&name),
+            );
+        }
 
-        let _shader_stages = [vertex_shader_stage_info, fragment_shader_stage_info];
+        if !device.is_sample_rate_shading_enabled() {
+            config_info.multisampling = config_info.multisampling.sample_shading_enable(false);
+        }
+
+        let pipeline_layout_info = PipelineLayoutCreateInfoBuilder::new()
+            .set_layouts(&config_info.descriptor_set_layouts)
+            .push_constant_ranges(&config_info.push_constant_ranges);
 
         let pipeline_layout = unsafe {
             device
                 .get_device_ptr()
-                .create_pipeline_layout(&config_info.pipeline_layout_info, None)
-        };
+                .create_pipeline_layout(&pipeline_layout_info, None)
+        }
+        .result()
+        .unwrap();
 
+        let color_blend_attachments =
+            vec![config_info.color_blend_attachment; config_info.color_attachment_count];
         let color_blending = PipelineColorBlendStateCreateInfoBuilder::new()
             .logic_op_enable(false)
             .logic_op(LogicOp::COPY)
-            .attachments(std::slice::from_ref(&config_info.color_blend_attachment))
+            .attachments(&color_blend_attachments)
             .blend_constants([0.0, 0.0, 0.0, 0.0]);
 
         let viewport_state = PipelineViewportStateCreateInfoBuilder::new()
@@ -85,18 +300,19 @@ impl VRTPipeline {
             .scissor_count(1);
 
         let vertex_input_info = PipelineVertexInputStateCreateInfoBuilder::new()
-            .vertex_binding_descriptions(std::slice::from_ref(&config_info.binding_description))
+            .vertex_binding_descriptions(&config_info.binding_descriptions)
             .vertex_attribute_descriptions(&config_info.attribute_descriptions);
 
         let pipeline_info = GraphicsPipelineCreateInfoBuilder::new()
-            .stages(&_shader_stages)
+            .stages(&shader_stages)
             .vertex_input_state(&vertex_input_info)
             .input_assembly_state(&config_info.input_assembly)
             .viewport_state(&viewport_state)
             .rasterization_state(&config_info.rasterizer)
             .multisample_state(&config_info.multisampling)
             .color_blend_state(&color_blending)
-            .layout(pipeline_layout.unwrap())
+            .depth_stencil_state(&config_info.depth_stencil_info)
+            .layout(pipeline_layout)
             .dynamic_state(&config_info.dynamic_state_info)
             .render_pass(render_pass)
             .subpass(0)
@@ -112,11 +328,13 @@ impl VRTPipeline {
         .result()
         .unwrap()[0];
 
-        unsafe {
-            device
-                .get_device_ptr()
-                .destroy_shader_module(fragment_shader_module, None)
-        };
+        if let Some(fragment_shader_module) = fragment_shader_module {
+            unsafe {
+                device
+                    .get_device_ptr()
+                    .destroy_shader_module(fragment_shader_module, None)
+            };
+        }
         unsafe {
             device
                 .get_device_ptr()
@@ -125,6 +343,7 @@ impl VRTPipeline {
 
         Self {
             graphics_pipeline,
+            pipeline_layout,
             device,
         }
     }
@@ -142,7 +361,7 @@ impl VRTPipeline {
         .result()?)
     }
 
-    fn read_file(path: &str) -> Vec<u8> {
+    pub(super) fn read_file(path: &str) -> Vec<u8> {
         let mut file = File::open(path).unwrap();
         let meta = metadata(path).unwrap();
         let mut buffer = vec![0; meta.len() as usize];
@@ -186,7 +405,14 @@ impl VRTPipeline {
             .dst_alpha_blend_factor(BlendFactor::ZERO)
             .alpha_blend_op(BlendOp::ADD);
 
-        let pipeline_layout_info = PipelineLayoutCreateInfoBuilder::new();
+        let depth_stencil_info = PipelineDepthStencilStateCreateInfoBuilder::new()
+            .depth_test_enable(true)
+            .depth_write_enable(true)
+            .depth_compare_op(CompareOp::LESS)
+            .depth_bounds_test_enable(false)
+            .min_depth_bounds(0.0)
+            .max_depth_bounds(1.0)
+            .stencil_test_enable(false);
 
         let dynamic_state_info = PipelineDynamicStateCreateInfoBuilder::new()
             .dynamic_states(&[DynamicState::VIEWPORT, DynamicState::SCISSOR])
@@ -199,10 +425,13 @@ impl VRTPipeline {
             input_assembly,
             rasterizer,
             multisampling,
-            pipeline_layout_info,
+            descriptor_set_layouts: vec![],
+            push_constant_ranges: vec![],
             color_blend_attachment,
+            color_attachment_count: 1,
+            depth_stencil_info,
             dynamic_state_info,
-            binding_description,
+            binding_descriptions: vec![binding_description],
             attribute_descriptions: Vec::from(attribute_descriptions),
         }
     }
@@ -216,4 +445,53 @@ impl VRTPipeline {
             );
         }
     }
+    pub fn bind_descriptor_sets(
+        &self,
+        command_buffer: CommandBuffer,
+        first_set: u32,
+        descriptor_sets: &[DescriptorSet],
+    ) {
+        unsafe {
+            self.device.get_device_ptr().cmd_bind_descriptor_sets(
+                command_buffer,
+                PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                first_set,
+                descriptor_sets,
+                &[],
+            );
+        }
+    }
+
+    /// `T` has to match the `push_constant` block layout of the shaders.
+    pub fn push_constants<T: Copy>(
+        &self,
+        command_buffer: CommandBuffer,
+        stage_flags: ShaderStageFlags,
+        constants: &T,
+    ) {
+        unsafe {
+            self.device.get_device_ptr().cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                stage_flags,
+                0,
+                std::mem::size_of::<T>() as u32,
+                (constants as *const T).cast(),
+            );
+        }
+    }
+}
+
+impl Drop for VRTPipeline {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_pipeline(self.graphics_pipeline, None);
+            self.device
+                .get_device_ptr()
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+        }
+    }
 }
@@ -0,0 +1,206 @@
+use std::any::Any;
+use std::path::Path;
+
+use erupt::vk1_0::{
+    CommandBuffer, Extent2D, Extent3D, Format, ImageType, ImageView, ImageViewType,
+    SampleCountFlagBits,
+};
+
+use super::fullscreen::FullscreenPass;
+use super::{PostEffect, PostEffectContext};
+use crate::vrt::device::image::VRTImage;
+use crate::vrt::utils::result::{VkError, VkResult};
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/color_grading_frag.spirv";
+
+const IDENTITY_LUT_SIZE: u32 = 16;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ColorGradingParams {
+    intensity: f32,
+    lut_size: f32,
+}
+
+/// A 3D lookup table with red varying fastest, as stored in `.cube` files.
+pub struct CubeLut {
+    pub size: u32,
+    pub entries: Vec<[f32; 3]>,
+}
+
+impl CubeLut {
+    pub fn identity(size: u32) -> Self {
+        let scale = 1.0 / (size - 1) as f32;
+        let entries = (0..size * size * size)
+            .map(|index| {
+                [
+                    (index % size) as f32 * scale,
+                    (index / size % size) as f32 * scale,
+                    (index / (size * size)) as f32 * scale,
+                ]
+            })
+            .collect();
+        Self { size, entries }
+    }
+
+    pub fn load(path: &Path) -> VkResult<Self> {
+        let source = std::fs::read_to_string(path)?;
+        Self::parse(&source)
+            .map_err(|err| VkError::InvalidAsset(format!("{}: {}", path.display(), err)))
+    }
+
+    /// Parses the Adobe/Resolve `.cube` format, only 3D tables over the `[0, 1]` domain.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let mut size = None;
+        let mut entries = vec![];
+
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("LUT_3D_SIZE") => {
+                    let value = tokens.next().and_then(|value| value.parse::<u32>().ok());
+                    size = Some(
+                        value
+                            .filter(|size| *size >= 2)
+                            .ok_or("invalid LUT_3D_SIZE")?,
+                    );
+                }
+                Some("LUT_1D_SIZE") => return Err("1D tables are not supported".to_string()),
+                Some("DOMAIN_MIN") | Some("DOMAIN_MAX") => {
+                    let expected = if line.starts_with("DOMAIN_MIN") {
+                        0.0
+                    } else {
+                        1.0
+                    };
+                    if tokens.any(|value| value.parse::<f32>() != Ok(expected)) {
+                        return Err("only the [0, 1] domain is supported".to_string());
+                    }
+                }
+                Some("TITLE") => {}
+                Some(_) => {
+                    let entry = line
+                        .split_whitespace()
+                        .map(str::parse::<f32>)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| format!("invalid line `{}`", line))?;
+                    match entry.as_slice() {
+                        [r, g, b] => entries.push([*r, *g, *b]),
+                        _ => return Err(format!("invalid line `{}`", line)),
+                    }
+                }
+                None => {}
+            }
+        }
+
+        let size = size.ok_or("missing LUT_3D_SIZE")?;
+        if entries.len() != (size * size * size) as usize {
+            return Err(format!(
+                "expected {} entries, found {}",
+                size * size * size,
+                entries.len()
+            ));
+        }
+        Ok(Self { size, entries })
+    }
+
+    fn to_rgba8(&self) -> Vec<u8> {
+        self.entries
+            .iter()
+            .flat_map(|[r, g, b]| {
+                let quantize = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                [quantize(*r), quantize(*g), quantize(*b), 255]
+            })
+            .collect()
+    }
+}
+
+/// Remaps colors through a 3D lookup table, typically exported from grading tools.
+pub struct ColorGradingEffect {
+    pass: FullscreenPass,
+    _lut: VRTImage,
+    params: ColorGradingParams,
+    enabled: bool,
+}
+
+impl ColorGradingEffect {
+    /// Without `lut_path` an identity table is used, which leaves colors unchanged.
+    pub fn new(context: &PostEffectContext, lut_path: Option<&Path>) -> VkResult<Self> {
+        let lut = match lut_path {
+            Some(path) => CubeLut::load(path)?,
+            None => CubeLut::identity(IDENTITY_LUT_SIZE),
+        };
+
+        let lut_image = VRTImage::from_pixels(
+            context.device.clone(),
+            ImageType::_3D,
+            ImageViewType::_3D,
+            Extent3D {
+                width: lut.size,
+                height: lut.size,
+                depth: lut.size,
+            },
+            Format::R8G8B8A8_UNORM,
+            &lut.to_rgba8(),
+        )?;
+
+        let pass = FullscreenPass::new(
+            context.device.clone(),
+            context.render_pass,
+            SampleCountFlagBits::_1,
+            FRAGMENT_SHADER,
+            std::mem::size_of::<ColorGradingParams>() as u32,
+            context.sampler,
+            &[lut_image.get_image_view()],
+            context.inputs,
+        )?;
+
+        Ok(Self {
+            pass,
+            _lut: lut_image,
+            params: ColorGradingParams {
+                intensity: 1.0,
+                lut_size: lut.size as f32,
+            },
+            enabled: lut_path.is_some(),
+        })
+    }
+
+    pub fn get_intensity(&self) -> f32 {
+        self.params.intensity
+    }
+
+    /// Blend between the original and the graded color.
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.params.intensity = intensity.clamp(0.0, 1.0);
+    }
+}
+
+impl PostEffect for ColorGradingEffect {
+    fn name(&self) -> &str {
+        "color grading"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        self.pass.set_inputs(inputs)
+    }
+
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, _extent: Extent2D) {
+        self.pass.draw(command_buffer, input, &self.params);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
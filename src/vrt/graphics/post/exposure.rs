@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use erupt::vk1_0::{CommandBuffer, Extent2D, ImageView, SampleCountFlagBits};
+
+use super::fullscreen::FullscreenPass;
+use super::{PostEffect, PostEffectContext};
+use crate::vrt::utils::result::VkResult;
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/exposure_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ExposureParams {
+    exposure: f32,
+}
+
+/// Scales the HDR scene color by `2^exposure`.
+pub struct ExposureEffect {
+    pass: FullscreenPass,
+    params: ExposureParams,
+    enabled: bool,
+}
+
+impl ExposureEffect {
+    pub fn new(context: &PostEffectContext) -> VkResult<Self> {
+        let pass = FullscreenPass::new(
+            context.device.clone(),
+            context.render_pass,
+            SampleCountFlagBits::_1,
+            FRAGMENT_SHADER,
+            std::mem::size_of::<ExposureParams>() as u32,
+            context.sampler,
+            &[],
+            context.inputs,
+        )?;
+
+        Ok(Self {
+            pass,
+            params: ExposureParams { exposure: 0.0 },
+            enabled: true,
+        })
+    }
+
+    /// Exposure compensation in stops.
+    pub fn get_exposure(&self) -> f32 {
+        self.params.exposure
+    }
+
+    pub fn set_exposure(&mut self, exposure: f32) {
+        self.params.exposure = exposure;
+    }
+}
+
+impl PostEffect for ExposureEffect {
+    fn name(&self) -> &str {
+        "exposure"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        self.pass.set_inputs(inputs)
+    }
+
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, _extent: Extent2D) {
+        self.pass.draw(command_buffer, input, &self.params);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, CullModeFlags, DescriptorSet, DescriptorType, ImageLayout, ImageView,
+    RenderPass, SampleCountFlagBits, Sampler, ShaderStageFlags,
+};
+
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::pipeline::VRTPipeline;
+use crate::vrt::utils::result::VkResult;
+
+pub const FULLSCREEN_VERTEX_SHADER: &str = "./assets/shaders/fullscreen_vert.spirv";
+
+/// Upper bound on the images a pass can read from, one descriptor set each.
+const MAX_INPUTS: u32 = 4;
+
+/// A fullscreen triangle reading one of several input images.
+///
+/// Set 0 holds the input at binding 0, the sampler at binding 1 and any extra images from
+/// binding 2 onwards. Push constants are visible to the fragment shader.
+pub struct FullscreenPass {
+    pipeline: VRTPipeline,
+    descriptor_sets: Vec<DescriptorSet>,
+    descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    sampler: Sampler,
+    extra_images: Vec<ImageView>,
+}
+
+impl FullscreenPass {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        fragment_shader: &str,
+        push_constant_size: u32,
+        sampler: Sampler,
+        extra_images: &[ImageView],
+        inputs: &[ImageView],
+    ) -> VkResult<Self> {
+        let mut layout_builder = VRTDescriptorSetLayoutBuilder::new(device.clone());
+        layout_builder
+            .add_binding(
+                0,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .add_binding(1, DescriptorType::SAMPLER, ShaderStageFlags::FRAGMENT, None);
+        for binding in 0..extra_images.len() as u32 {
+            layout_builder.add_binding(
+                binding + 2,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            );
+        }
+        let descriptor_set_layout = layout_builder.build();
+
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(MAX_INPUTS)
+            .add_pool_size(
+                DescriptorType::SAMPLED_IMAGE,
+                MAX_INPUTS * (1 + extra_images.len() as u32),
+            )
+            .add_pool_size(DescriptorType::SAMPLER, MAX_INPUTS)
+            .build()?;
+
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_depth_test(false, false, erupt::vk1_0::CompareOp::ALWAYS);
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info
+            .set_descriptor_set_layouts(&[descriptor_set_layout.get_descriptor_set_layout()]);
+        if push_constant_size > 0 {
+            config_info.add_push_constant_range(ShaderStageFlags::FRAGMENT, 0, push_constant_size);
+        }
+
+        let pipeline = VRTPipeline::new(
+            device,
+            FULLSCREEN_VERTEX_SHADER,
+            fragment_shader,
+            &mut config_info,
+            render_pass,
+        );
+
+        let mut pass = Self {
+            pipeline,
+            descriptor_sets: vec![],
+            descriptor_pool,
+            descriptor_set_layout,
+            sampler,
+            extra_images: extra_images.to_vec(),
+        };
+        pass.set_inputs(inputs)?;
+        Ok(pass)
+    }
+
+    /// Rewrites every descriptor set, no frame using this pass may still be in flight.
+    pub fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        assert!(
+            inputs.len() as u32 <= MAX_INPUTS,
+            "too many fullscreen pass inputs"
+        );
+
+        self.descriptor_pool.reset_pool()?;
+        self.descriptor_sets = inputs
+            .iter()
+            .map(|input| {
+                let mut writer =
+                    VRTDescriptorWriter::new(&self.descriptor_set_layout, &self.descriptor_pool);
+                writer
+                    .write_image(0, *input, ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+                    .write_sampler(1, self.sampler);
+                for (binding, image) in self.extra_images.iter().enumerate() {
+                    writer.write_image(
+                        binding as u32 + 2,
+                        *image,
+                        ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    );
+                }
+                writer.build()
+            })
+            .collect::<VkResult<Vec<_>>>()?;
+        Ok(())
+    }
+
+    /// Draws with `params` as push constants, inside a render pass compatible with the one the
+    /// pass was created for.
+    pub fn draw<T: Copy>(&self, command_buffer: CommandBuffer, input: usize, params: &T) {
+        self.pipeline.bind(command_buffer);
+        self.pipeline.bind_descriptor_sets(
+            command_buffer,
+            0,
+            std::slice::from_ref(&self.descriptor_sets[input]),
+        );
+        if std::mem::size_of::<T>() > 0 {
+            self.pipeline
+                .push_constants(command_buffer, ShaderStageFlags::FRAGMENT, params);
+        }
+
+        unsafe {
+            self.descriptor_pool
+                .get_device()
+                .get_device_ptr()
+                .cmd_draw(command_buffer, 3, 1, 0, 0);
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::any::Any;
+
+use erupt::vk1_0::{CommandBuffer, Extent2D, ImageView, SampleCountFlagBits};
+
+use super::fullscreen::FullscreenPass;
+use super::{PostEffect, PostEffectContext};
+use crate::vrt::utils::result::VkResult;
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/fxaa_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct FxaaParams {
+    texel_size: [f32; 2],
+    span_max: f32,
+    reduce_mul: f32,
+    reduce_min: f32,
+}
+
+/// Fast approximate anti-aliasing, best placed after tonemapping.
+pub struct FxaaEffect {
+    pass: FullscreenPass,
+    span_max: f32,
+    reduce_mul: f32,
+    reduce_min: f32,
+    enabled: bool,
+}
+
+impl FxaaEffect {
+    pub fn new(context: &PostEffectContext) -> VkResult<Self> {
+        let pass = FullscreenPass::new(
+            context.device.clone(),
+            context.render_pass,
+            SampleCountFlagBits::_1,
+            FRAGMENT_SHADER,
+            std::mem::size_of::<FxaaParams>() as u32,
+            context.sampler,
+            &[],
+            context.inputs,
+        )?;
+
+        Ok(Self {
+            pass,
+            span_max: 8.0,
+            reduce_mul: 1.0 / 8.0,
+            reduce_min: 1.0 / 128.0,
+            enabled: true,
+        })
+    }
+
+    pub fn get_span_max(&self) -> f32 {
+        self.span_max
+    }
+
+    /// Longest edge search distance in texels.
+    pub fn set_span_max(&mut self, span_max: f32) {
+        self.span_max = span_max;
+    }
+}
+
+impl PostEffect for FxaaEffect {
+    fn name(&self) -> &str {
+        "fxaa"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        self.pass.set_inputs(inputs)
+    }
+
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, extent: Extent2D) {
+        let params = FxaaParams {
+            texel_size: [1.0 / extent.width as f32, 1.0 / extent.height as f32],
+            span_max: self.span_max,
+            reduce_mul: self.reduce_mul,
+            reduce_min: self.reduce_min,
+        };
+        self.pass.draw(command_buffer, input, &params);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,78 @@
+use std::any::Any;
+
+use erupt::vk1_0::{CommandBuffer, Extent2D, ImageView, SampleCountFlagBits};
+
+use super::fullscreen::FullscreenPass;
+use super::{PostEffect, PostEffectContext};
+use crate::vrt::utils::result::VkResult;
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/gamma_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct GammaParams {
+    gamma: f32,
+}
+
+/// Gamma encodes the color. Disabled by default since sRGB swapchain formats already encode on
+/// write, enable it for UNORM swapchains.
+pub struct GammaEffect {
+    pass: FullscreenPass,
+    params: GammaParams,
+    enabled: bool,
+}
+
+impl GammaEffect {
+    pub fn new(context: &PostEffectContext) -> VkResult<Self> {
+        let pass = FullscreenPass::new(
+            context.device.clone(),
+            context.render_pass,
+            SampleCountFlagBits::_1,
+            FRAGMENT_SHADER,
+            std::mem::size_of::<GammaParams>() as u32,
+            context.sampler,
+            &[],
+            context.inputs,
+        )?;
+
+        Ok(Self {
+            pass,
+            params: GammaParams { gamma: 2.2 },
+            enabled: false,
+        })
+    }
+
+    pub fn get_gamma(&self) -> f32 {
+        self.params.gamma
+    }
+
+    pub fn set_gamma(&mut self, gamma: f32) {
+        self.params.gamma = gamma.max(f32::EPSILON);
+    }
+}
+
+impl PostEffect for GammaEffect {
+    fn name(&self) -> &str {
+        "gamma"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        self.pass.set_inputs(inputs)
+    }
+
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, _extent: Extent2D) {
+        self.pass.draw(command_buffer, input, &self.params);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
@@ -0,0 +1,286 @@
+pub mod color_grading;
+pub mod exposure;
+pub mod fullscreen;
+pub mod fxaa;
+pub mod gamma;
+pub mod tonemap;
+pub mod vignette;
+
+use std::any::Any;
+use std::path::Path;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, Extent2D, Filter, Format, ImageView, RenderPass, SampleCountFlagBits, Sampler,
+    SamplerAddressMode,
+};
+
+use self::color_grading::ColorGradingEffect;
+use self::exposure::ExposureEffect;
+use self::fullscreen::FullscreenPass;
+use self::fxaa::FxaaEffect;
+use self::tonemap::TonemapEffect;
+use self::vignette::VignetteEffect;
+use super::render_target::{RenderTargetPass, VRTRenderTarget};
+use super::renderer::ClearValues;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::VRTSampler;
+use crate::vrt::device::swapchain::{OutputTransferFunction, RenderPassDescription};
+use crate::vrt::utils::result::VkResult;
+
+pub const HDR_FORMAT: Format = Format::R16G16B16A16_SFLOAT;
+
+const OUTPUT_FRAGMENT_SHADER: &str = "./assets/shaders/post_output_frag.spirv";
+/// Luminance in nits the chain output of 1.0 maps to on HDR swapchains, the BT.2408 reference
+/// white.
+const PAPER_WHITE_NITS: f32 = 203.0;
+
+/// Input index of the scene color, the intermediate targets follow it.
+const SCENE_INPUT: usize = 0;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct OutputParams {
+    transfer_function: OutputTransferFunction,
+    paper_white: f32,
+}
+
+/// What an effect needs to build its pipeline and descriptor sets.
+pub struct PostEffectContext<'a> {
+    pub device: Arc<VRTDevice>,
+    pub render_pass: RenderPass,
+    pub sampler: Sampler,
+    pub inputs: &'a [ImageView],
+}
+
+/// A fullscreen pass of the post-processing chain.
+pub trait PostEffect {
+    fn name(&self) -> &str;
+
+    fn is_enabled(&self) -> bool;
+
+    fn set_enabled(&mut self, enabled: bool);
+
+    /// Called when the chain recreated its targets, no frame is in flight at that point.
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()>;
+
+    /// Reads `input` of the chain inputs and writes the currently bound target.
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, extent: Extent2D);
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+/// Renders the scene into an HDR target and runs the enabled effects in order, ping-ponging
+/// between two intermediate targets before the result is copied into the swapchain image. The
+/// copy encodes the linear result for the output transfer function of the swapchain.
+pub struct PostProcessChain {
+    effects: Vec<Box<dyn PostEffect>>,
+    output_pass: FullscreenPass,
+    output_params: OutputParams,
+    scene_target: VRTRenderTarget,
+    targets: [VRTRenderTarget; 2],
+    sampler: VRTSampler,
+    inputs: Vec<ImageView>,
+    output_input: usize,
+    enabled: bool,
+    device: Arc<VRTDevice>,
+}
+
+impl PostProcessChain {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        extent: Extent2D,
+        output_render_pass: RenderPass,
+        output_samples: SampleCountFlagBits,
+        output_transfer_function: OutputTransferFunction,
+        color_lut: Option<&Path>,
+    ) -> VkResult<Self> {
+        let depth_format = VRTRenderTarget::find_sampled_depth_format(&device)?;
+        let scene_target = VRTRenderTarget::new(
+            device.clone(),
+            extent,
+            &[HDR_FORMAT],
+            Some(depth_format),
+            &RenderPassDescription::clear(),
+        )?;
+        let targets = [
+            VRTRenderTarget::new(
+                device.clone(),
+                extent,
+                &[HDR_FORMAT],
+                None,
+                &RenderPassDescription::clear(),
+            )?,
+            VRTRenderTarget::new(
+                device.clone(),
+                extent,
+                &[HDR_FORMAT],
+                None,
+                &RenderPassDescription::clear(),
+            )?,
+        ];
+        let sampler = VRTSampler::new(
+            device.clone(),
+            Filter::LINEAR,
+            SamplerAddressMode::CLAMP_TO_EDGE,
+        )?;
+        let inputs = Self::collect_inputs(&scene_target, &targets);
+
+        let output_pass = FullscreenPass::new(
+            device.clone(),
+            output_render_pass,
+            output_samples,
+            OUTPUT_FRAGMENT_SHADER,
+            std::mem::size_of::<OutputParams>() as u32,
+            sampler.get_sampler(),
+            &[],
+            &inputs,
+        )?;
+
+        let mut chain = Self {
+            effects: vec![],
+            output_pass,
+            output_params: OutputParams {
+                transfer_function: output_transfer_function,
+                paper_white: PAPER_WHITE_NITS,
+            },
+            scene_target,
+            targets,
+            sampler,
+            inputs,
+            output_input: SCENE_INPUT,
+            enabled: true,
+            device,
+        };
+
+        let context = chain.context();
+        let effects: Vec<Box<dyn PostEffect>> = vec![
+            Box::new(ExposureEffect::new(&context)?),
+            Box::new(TonemapEffect::new(&context)?),
+            Box::new(ColorGradingEffect::new(&context, color_lut)?),
+            Box::new(VignetteEffect::new(&context)?),
+            Box::new(FxaaEffect::new(&context)?),
+        ];
+        chain.effects = effects;
+        Ok(chain)
+    }
+
+    fn collect_inputs(
+        scene_target: &VRTRenderTarget,
+        targets: &[VRTRenderTarget],
+    ) -> Vec<ImageView> {
+        std::iter::once(scene_target)
+            .chain(targets)
+            .map(|target| target.get_color_view(0))
+            .collect()
+    }
+
+    /// For building effects outside of the chain, see `push_effect`.
+    pub fn context(&self) -> PostEffectContext<'_> {
+        PostEffectContext {
+            device: self.device.clone(),
+            render_pass: self.targets[0].get_render_pass(),
+            sampler: self.sampler.get_sampler(),
+            inputs: &self.inputs,
+        }
+    }
+
+    pub fn push_effect(&mut self, effect: Box<dyn PostEffect>) {
+        self.effects.push(effect);
+    }
+
+    pub fn effects_mut(&mut self) -> &mut [Box<dyn PostEffect>] {
+        &mut self.effects
+    }
+
+    pub fn effect_mut<T: PostEffect + 'static>(&mut self) -> Option<&mut T> {
+        self.effects
+            .iter_mut()
+            .find_map(|effect| effect.as_any_mut().downcast_mut::<T>())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn get_extent(&self) -> Extent2D {
+        self.scene_target.get_extent()
+    }
+
+    /// Scene pipelines have to be built against this render pass.
+    pub fn get_scene_render_pass(&self) -> RenderPass {
+        self.scene_target.get_render_pass()
+    }
+
+    /// Recreates the targets, no frame using the chain may still be in flight.
+    pub fn resize(&mut self, extent: Extent2D) -> VkResult<()> {
+        if extent == self.get_extent() {
+            return Ok(());
+        }
+
+        self.scene_target.resize(extent)?;
+        for target in &mut self.targets {
+            target.resize(extent)?;
+        }
+        self.inputs = Self::collect_inputs(&self.scene_target, &self.targets);
+
+        for effect in &mut self.effects {
+            effect.set_inputs(&self.inputs)?;
+        }
+        self.output_pass.set_inputs(&self.inputs)
+    }
+
+    /// Rebuilds the copy into the swapchain after its render pass changed.
+    pub fn set_output_render_pass(
+        &mut self,
+        render_pass: RenderPass,
+        samples: SampleCountFlagBits,
+    ) -> VkResult<()> {
+        self.output_pass = FullscreenPass::new(
+            self.device.clone(),
+            render_pass,
+            samples,
+            OUTPUT_FRAGMENT_SHADER,
+            std::mem::size_of::<OutputParams>() as u32,
+            self.sampler.get_sampler(),
+            &[],
+            &self.inputs,
+        )?;
+        Ok(())
+    }
+
+    pub fn begin_scene(
+        &self,
+        command_buffer: CommandBuffer,
+        clear_values: &ClearValues,
+    ) -> RenderTargetPass<'_> {
+        self.scene_target.begin(command_buffer, clear_values)
+    }
+
+    /// Records the enabled effects, outside of any render pass.
+    pub fn apply(&mut self, command_buffer: CommandBuffer) {
+        let mut input = SCENE_INPUT;
+        for effect in self.effects.iter().filter(|effect| effect.is_enabled()) {
+            // Never write the target that is being read.
+            let target_index = if input == 1 { 1 } else { 0 };
+            let target = &self.targets[target_index];
+
+            let pass = target.begin(command_buffer, &ClearValues::default());
+            effect.draw(pass.command_buffer(), input, target.get_extent());
+            drop(pass);
+
+            input = target_index + 1;
+        }
+        self.output_input = input;
+    }
+
+    /// Copies the chain result, inside the swapchain render pass.
+    pub fn draw_output(&self, command_buffer: CommandBuffer) {
+        self.output_pass
+            .draw(command_buffer, self.output_input, &self.output_params);
+    }
+}
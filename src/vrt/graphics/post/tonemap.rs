@@ -0,0 +1,98 @@
+use std::any::Any;
+
+use erupt::vk1_0::{CommandBuffer, Extent2D, ImageView, SampleCountFlagBits};
+
+use super::fullscreen::FullscreenPass;
+use super::{PostEffect, PostEffectContext};
+use crate::vrt::utils::result::VkResult;
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/tonemap_frag.spirv";
+
+/// Matches the operator constants in `tonemap.frag`.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TonemapOperator {
+    Reinhard = 0,
+    Aces = 1,
+}
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct TonemapParams {
+    operator: TonemapOperator,
+    white_point: f32,
+}
+
+/// Maps HDR color into the displayable range.
+pub struct TonemapEffect {
+    pass: FullscreenPass,
+    params: TonemapParams,
+    enabled: bool,
+}
+
+impl TonemapEffect {
+    pub fn new(context: &PostEffectContext) -> VkResult<Self> {
+        let pass = FullscreenPass::new(
+            context.device.clone(),
+            context.render_pass,
+            SampleCountFlagBits::_1,
+            FRAGMENT_SHADER,
+            std::mem::size_of::<TonemapParams>() as u32,
+            context.sampler,
+            &[],
+            context.inputs,
+        )?;
+
+        Ok(Self {
+            pass,
+            params: TonemapParams {
+                operator: TonemapOperator::Aces,
+                white_point: 4.0,
+            },
+            enabled: true,
+        })
+    }
+
+    pub fn get_operator(&self) -> TonemapOperator {
+        self.params.operator
+    }
+
+    pub fn set_operator(&mut self, operator: TonemapOperator) {
+        self.params.operator = operator;
+    }
+
+    pub fn get_white_point(&self) -> f32 {
+        self.params.white_point
+    }
+
+    /// Luminance that maps to white with the Reinhard operator.
+    pub fn set_white_point(&mut self, white_point: f32) {
+        self.params.white_point = white_point.max(f32::EPSILON);
+    }
+}
+
+impl PostEffect for TonemapEffect {
+    fn name(&self) -> &str {
+        "tonemap"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        self.pass.set_inputs(inputs)
+    }
+
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, _extent: Extent2D) {
+        self.pass.draw(command_buffer, input, &self.params);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
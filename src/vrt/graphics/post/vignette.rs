@@ -0,0 +1,93 @@
+use std::any::Any;
+
+use erupt::vk1_0::{CommandBuffer, Extent2D, ImageView, SampleCountFlagBits};
+
+use super::fullscreen::FullscreenPass;
+use super::{PostEffect, PostEffectContext};
+use crate::vrt::utils::result::VkResult;
+
+const FRAGMENT_SHADER: &str = "./assets/shaders/vignette_frag.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct VignetteParams {
+    intensity: f32,
+    radius: f32,
+    softness: f32,
+}
+
+/// Darkens the image towards the corners.
+pub struct VignetteEffect {
+    pass: FullscreenPass,
+    params: VignetteParams,
+    enabled: bool,
+}
+
+impl VignetteEffect {
+    pub fn new(context: &PostEffectContext) -> VkResult<Self> {
+        let pass = FullscreenPass::new(
+            context.device.clone(),
+            context.render_pass,
+            SampleCountFlagBits::_1,
+            FRAGMENT_SHADER,
+            std::mem::size_of::<VignetteParams>() as u32,
+            context.sampler,
+            &[],
+            context.inputs,
+        )?;
+
+        Ok(Self {
+            pass,
+            params: VignetteParams {
+                intensity: 0.8,
+                radius: 1.0,
+                softness: 0.6,
+            },
+            enabled: false,
+        })
+    }
+
+    /// Intensity, radius and softness.
+    pub fn get_params(&self) -> (f32, f32, f32) {
+        (
+            self.params.intensity,
+            self.params.radius,
+            self.params.softness,
+        )
+    }
+
+    /// `radius` and `softness` are relative to the distance from the center to a corner.
+    pub fn set_params(&mut self, intensity: f32, radius: f32, softness: f32) {
+        self.params = VignetteParams {
+            intensity: intensity.clamp(0.0, 1.0),
+            radius,
+            softness,
+        };
+    }
+}
+
+impl PostEffect for VignetteEffect {
+    fn name(&self) -> &str {
+        "vignette"
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    fn set_inputs(&mut self, inputs: &[ImageView]) -> VkResult<()> {
+        self.pass.set_inputs(inputs)
+    }
+
+    fn draw(&self, command_buffer: CommandBuffer, input: usize, _extent: Extent2D) {
+        self.pass.draw(command_buffer, input, &self.params);
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
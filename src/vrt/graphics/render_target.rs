@@ -0,0 +1,388 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    AccessFlags, AttachmentDescriptionBuilder, AttachmentLoadOp, AttachmentReferenceBuilder,
+    AttachmentStoreOp, ClearColorValue, ClearDepthStencilValue, ClearValue, CommandBuffer,
+    Extent2D, Format, FormatFeatureFlags, Framebuffer, FramebufferCreateInfoBuilder,
+    ImageAspectFlags, ImageLayout, ImageTiling, ImageUsageFlags, ImageView, Offset2DBuilder,
+    PipelineBindPoint, PipelineStageFlags, Rect2DBuilder, RenderPass, RenderPassBeginInfoBuilder,
+    RenderPassCreateInfoBuilder, SampleCountFlagBits, SubpassContents, SubpassDependencyBuilder,
+    SubpassDescriptionBuilder, ViewportBuilder, SUBPASS_EXTERNAL,
+};
+
+use super::renderer::ClearValues;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::VRTImage;
+use crate::vrt::device::swapchain::RenderPassDescription;
+use crate::vrt::utils::result::VkResult;
+
+/// Offscreen color and depth attachments that later passes sample from.
+pub struct VRTRenderTarget {
+    device: Arc<VRTDevice>,
+    render_pass: RenderPass,
+    framebuffer: Framebuffer,
+    color_images: Vec<VRTImage>,
+    depth_image: Option<VRTImage>,
+    color_formats: Vec<Format>,
+    depth_format: Option<Format>,
+    extent: Extent2D,
+}
+
+/// A render pass on a render target, ended when dropped.
+pub struct RenderTargetPass<'t> {
+    device: &'t VRTDevice,
+    command_buffer: CommandBuffer,
+}
+
+impl VRTRenderTarget {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        extent: Extent2D,
+        color_formats: &[Format],
+        depth_format: Option<Format>,
+        description: &RenderPassDescription,
+    ) -> VkResult<Self> {
+        let render_pass =
+            Self::create_render_pass(&device, color_formats, depth_format, description)?;
+
+        let mut target = Self {
+            device,
+            render_pass,
+            framebuffer: Framebuffer::null(),
+            color_images: vec![],
+            depth_image: None,
+            color_formats: color_formats.to_vec(),
+            depth_format,
+            extent,
+        };
+        target.create_attachments()?;
+        Ok(target)
+    }
+
+    /// A depth format without stencil that can be both rendered to and sampled.
+    pub fn find_sampled_depth_format(device: &VRTDevice) -> VkResult<Format> {
+        device.find_supported_format(
+            &[Format::D32_SFLOAT, Format::D16_UNORM],
+            ImageTiling::OPTIMAL,
+            FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT | FormatFeatureFlags::SAMPLED_IMAGE,
+        )
+    }
+
+    /// Recreates the attachments, the render pass and pipelines built for it stay valid.
+    pub fn resize(&mut self, extent: Extent2D) -> VkResult<()> {
+        if extent == self.extent {
+            return Ok(());
+        }
+
+        self.destroy_attachments();
+        self.extent = extent;
+        self.create_attachments()
+    }
+
+    fn create_attachments(&mut self) -> VkResult<()> {
+        for format in &self.color_formats {
+            self.color_images.push(VRTImage::new_attachment(
+                self.device.clone(),
+                self.extent,
+                *format,
+                ImageUsageFlags::COLOR_ATTACHMENT | ImageUsageFlags::SAMPLED,
+                ImageAspectFlags::COLOR,
+            )?);
+        }
+
+        if let Some(depth_format) = self.depth_format {
+            self.depth_image = Some(VRTImage::new_attachment(
+                self.device.clone(),
+                self.extent,
+                depth_format,
+                ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | ImageUsageFlags::SAMPLED,
+                ImageAspectFlags::DEPTH,
+            )?);
+        }
+
+        let attachments = self
+            .color_images
+            .iter()
+            .chain(self.depth_image.iter())
+            .map(|image| image.get_image_view())
+            .collect::<Vec<_>>();
+
+        let framebuffer_info = FramebufferCreateInfoBuilder::new()
+            .render_pass(self.render_pass)
+            .attachments(&attachments)
+            .width(self.extent.width)
+            .height(self.extent.height)
+            .layers(1);
+
+        self.framebuffer = unsafe {
+            self.device
+                .get_device_ptr()
+                .create_framebuffer(&framebuffer_info, None)
+        }
+        .result()?;
+        Ok(())
+    }
+
+    fn destroy_attachments(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_framebuffer(self.framebuffer, None);
+        }
+        self.framebuffer = Framebuffer::null();
+        self.color_images.clear();
+        self.depth_image = None;
+    }
+
+    pub(super) fn create_render_pass(
+        device: &VRTDevice,
+        color_formats: &[Format],
+        depth_format: Option<Format>,
+        description: &RenderPassDescription,
+    ) -> VkResult<RenderPass> {
+        let initial_layout = |load_op: AttachmentLoadOp, final_layout: ImageLayout| {
+            if load_op == AttachmentLoadOp::LOAD {
+                final_layout
+            } else {
+                ImageLayout::UNDEFINED
+            }
+        };
+
+        // Attachments end up read-only so that later passes can sample them right away.
+        let mut attachments = color_formats
+            .iter()
+            .map(|format| {
+                AttachmentDescriptionBuilder::new()
+                    .format(*format)
+                    .samples(SampleCountFlagBits::_1)
+                    .load_op(description.color.load_op)
+                    .store_op(description.color.store_op)
+                    .stencil_load_op(AttachmentLoadOp::DONT_CARE)
+                    .stencil_store_op(AttachmentStoreOp::DONT_CARE)
+                    .initial_layout(initial_layout(
+                        description.color.load_op,
+                        ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    ))
+                    .final_layout(ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            })
+            .collect::<Vec<_>>();
+
+        if let Some(depth_format) = depth_format {
+            attachments.push(
+                AttachmentDescriptionBuilder::new()
+                    .format(depth_format)
+                    .samples(SampleCountFlagBits::_1)
+                    .load_op(description.depth.load_op)
+                    .store_op(description.depth.store_op)
+                    .stencil_load_op(description.stencil.load_op)
+                    .stencil_store_op(description.stencil.store_op)
+                    .initial_layout(initial_layout(
+                        description.depth.load_op,
+                        ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                    ))
+                    .final_layout(ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL),
+            );
+        }
+
+        let color_attachment_refs = (0..color_formats.len() as u32)
+            .map(|attachment| {
+                AttachmentReferenceBuilder::new()
+                    .attachment(attachment)
+                    .layout(ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            })
+            .collect::<Vec<_>>();
+
+        let depth_attachment_ref = AttachmentReferenceBuilder::new()
+            .attachment(color_formats.len() as u32)
+            .layout(ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+        let mut subpass = SubpassDescriptionBuilder::new()
+            .pipeline_bind_point(PipelineBindPoint::GRAPHICS)
+            .color_attachments(&color_attachment_refs);
+        if depth_format.is_some() {
+            subpass = subpass.depth_stencil_attachment(&depth_attachment_ref);
+        }
+
+        let attachment_stages = PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+            | PipelineStageFlags::EARLY_FRAGMENT_TESTS
+            | PipelineStageFlags::LATE_FRAGMENT_TESTS;
+        let attachment_access =
+            AccessFlags::COLOR_ATTACHMENT_WRITE | AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE;
+        let shader_stages =
+            PipelineStageFlags::FRAGMENT_SHADER | PipelineStageFlags::COMPUTE_SHADER;
+
+        // Wait for earlier readers before writing, and make the writes visible to later readers.
+        let dependencies = [
+            SubpassDependencyBuilder::new()
+                .src_subpass(SUBPASS_EXTERNAL)
+                .dst_subpass(0)
+                .src_stage_mask(shader_stages | attachment_stages)
+                .src_access_mask(attachment_access)
+                .dst_stage_mask(attachment_stages)
+                .dst_access_mask(
+                    attachment_access
+                        | AccessFlags::COLOR_ATTACHMENT_READ
+                        | AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ,
+                ),
+            SubpassDependencyBuilder::new()
+                .src_subpass(0)
+                .dst_subpass(SUBPASS_EXTERNAL)
+                .src_stage_mask(attachment_stages)
+                .src_access_mask(attachment_access)
+                .dst_stage_mask(shader_stages)
+                .dst_access_mask(AccessFlags::SHADER_READ),
+        ];
+
+        let render_pass_info = RenderPassCreateInfoBuilder::new()
+            .attachments(&attachments)
+            .subpasses(std::slice::from_ref(&subpass))
+            .dependencies(&dependencies);
+
+        Ok(unsafe {
+            device
+                .get_device_ptr()
+                .create_render_pass(&render_pass_info, None)
+        }
+        .result()?)
+    }
+
+    pub fn begin(
+        &self,
+        command_buffer: CommandBuffer,
+        clear_values: &ClearValues,
+    ) -> RenderTargetPass<'_> {
+        let mut clear = vec![
+            ClearValue {
+                color: ClearColorValue {
+                    float32: clear_values.color,
+                },
+            };
+            self.color_images.len()
+        ];
+        if self.depth_image.is_some() {
+            clear.push(ClearValue {
+                depth_stencil: ClearDepthStencilValue {
+                    depth: clear_values.depth,
+                    stencil: clear_values.stencil,
+                },
+            });
+        }
+
+        RenderTargetPass::begin(
+            &self.device,
+            command_buffer,
+            self.render_pass,
+            self.framebuffer,
+            self.extent,
+            &clear,
+        )
+    }
+
+    pub fn get_render_pass(&self) -> RenderPass {
+        self.render_pass
+    }
+
+    pub fn get_extent(&self) -> Extent2D {
+        self.extent
+    }
+
+    pub fn get_color_view(&self, index: usize) -> ImageView {
+        self.color_images[index].get_image_view()
+    }
+
+    pub fn get_depth_view(&self) -> Option<ImageView> {
+        self.depth_image
+            .as_ref()
+            .map(|image| image.get_image_view())
+    }
+}
+
+impl Drop for VRTRenderTarget {
+    fn drop(&mut self) {
+        self.destroy_attachments();
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
+impl<'t> RenderTargetPass<'t> {
+    pub(super) fn begin(
+        device: &'t VRTDevice,
+        command_buffer: CommandBuffer,
+        render_pass: RenderPass,
+        framebuffer: Framebuffer,
+        extent: Extent2D,
+        clear_values: &[ClearValue],
+    ) -> Self {
+        let render_pass_info = RenderPassBeginInfoBuilder::new()
+            .render_pass(render_pass)
+            .framebuffer(framebuffer)
+            .render_area(
+                *Rect2DBuilder::new()
+                    .offset(*Offset2DBuilder::new().x(0).y(0))
+                    .extent(extent),
+            )
+            .clear_values(clear_values);
+
+        unsafe {
+            device.get_device_ptr().cmd_begin_render_pass(
+                command_buffer,
+                &render_pass_info,
+                SubpassContents::INLINE,
+            );
+        }
+        set_viewport_and_scissor(device, command_buffer, extent);
+
+        Self {
+            device,
+            command_buffer,
+        }
+    }
+
+    pub fn command_buffer(&self) -> CommandBuffer {
+        self.command_buffer
+    }
+}
+
+impl Drop for RenderTargetPass<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .cmd_end_render_pass(self.command_buffer);
+        }
+    }
+}
+
+/// Covers the whole `extent`, pipelines keep viewport and scissor dynamic.
+pub fn set_viewport_and_scissor(
+    device: &VRTDevice,
+    command_buffer: CommandBuffer,
+    extent: Extent2D,
+) {
+    let viewport = ViewportBuilder::new()
+        .x(0.0)
+        .y(0.0)
+        .width(extent.width as f32)
+        .height(extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    let scissor = Rect2DBuilder::new()
+        .offset(*Offset2DBuilder::new().x(0).y(0))
+        .extent(extent);
+
+    unsafe {
+        device.get_device_ptr().cmd_set_viewport(
+            command_buffer,
+            0,
+            std::slice::from_ref(&viewport),
+        );
+        device
+            .get_device_ptr()
+            .cmd_set_scissor(command_buffer, 0, std::slice::from_ref(&scissor));
+    }
+}
@@ -1,13 +1,17 @@
 use crate::vrt::device::device::VRTDevice;
-use crate::vrt::device::swapchain::Swapchain;
-use crate::vrt::device::swapchain::MAX_FRAMES_IN_FLIGHT;
+use crate::vrt::device::surface::VRTSurface;
+use crate::vrt::device::swapchain::{
+    OutputTransferFunction, Swapchain, SwapchainConfig, MAX_FRAMES_IN_FLIGHT,
+};
 use crate::vrt::utils::result::VkError;
 use crate::vrt::utils::result::{VkError::SwapChainExpired, VkResult};
 use crate::VRTWindow;
 use erupt::vk;
 use erupt::vk::ClearColorValue;
+use erupt::vk::ClearDepthStencilValue;
 use erupt::vk::ClearValue;
 use erupt::vk::CommandBuffer;
+use erupt::vk::Extent2D;
 use erupt::vk::Offset2DBuilder;
 use erupt::vk::Rect2DBuilder;
 use erupt::vk::RenderPassBeginInfoBuilder;
@@ -15,59 +19,132 @@ use erupt::vk::{
     CommandBufferAllocateInfoBuilder, CommandBufferBeginInfoBuilder, CommandBufferLevel,
 };
 use erupt::vk1_0::RenderPass;
+use erupt::vk1_0::SampleCountFlagBits;
 use erupt::vk1_0::SubpassContents;
-use erupt::vk1_0::ViewportBuilder;
 use erupt::SmallVec;
-use std::convert::TryFrom;
 use std::sync::Arc;
+use std::time::Instant;
+
+use super::frame::Frame;
+use super::frame_stats::FrameStats;
+use super::render_target::set_viewport_and_scissor;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ClearValues {
+    pub color: [f32; 4],
+    pub depth: f32,
+    pub stencil: u32,
+}
+
+impl Default for ClearValues {
+    fn default() -> Self {
+        Self {
+            color: [0.0, 0.0, 0.0, 1.0],
+            depth: 1.0,
+            stencil: 0,
+        }
+    }
+}
 
 pub struct VRTRenderer {
     swapchain: Swapchain,
+    surface: VRTSurface,
+    swapchain_config: SwapchainConfig,
     command_buffers: SmallVec<CommandBuffer>,
     device: Arc<VRTDevice>,
     current_frame_index: usize,
-    is_frame_started: bool,
+    is_swapchain_dirty: bool,
     image_index: u32,
+    frame_stats: FrameStats,
+    clear_values: ClearValues,
+    render_pass_generation: u64,
 }
 
 impl VRTRenderer {
-    pub fn new(device: Arc<VRTDevice>, window: &VRTWindow) -> VkResult<Self> {
-        let swapchain = Swapchain::new(&device, window.get_extent(), None)?;
-
-        let command_buffers = Self::create_command_buffers(&device)?;
+    pub fn new(
+        device: Arc<VRTDevice>,
+        window: &VRTWindow,
+        swapchain_config: SwapchainConfig,
+    ) -> VkResult<Self> {
+        let surface = VRTSurface::new(device.clone(), window)?;
+        let swapchain = Swapchain::new(
+            &device,
+            &surface,
+            window.get_extent(),
+            None,
+            &swapchain_config,
+        )?;
+
+        let command_buffers =
+            Self::create_command_buffers(&device, swapchain.get_frames_in_flight())?;
         Ok(Self {
             swapchain,
+            surface,
+            swapchain_config,
             device,
             command_buffers,
             current_frame_index: 0,
-            is_frame_started: false,
+            is_swapchain_dirty: false,
             image_index: 0,
+            frame_stats: FrameStats::new(),
+            clear_values: ClearValues::default(),
+            render_pass_generation: 0,
         })
     }
 
     fn recreate_swapchain(&mut self, window: &VRTWindow) -> VkResult<()> {
-        let mut extent = window.get_extent();
-        while extent.width == 0 || extent.height == 0 {
-            extent = window.get_extent();
+        // A minimized window has no drawable area, so defer until it is restored.
+        if window.is_minimized() {
+            self.is_swapchain_dirty = true;
+            return Ok(());
         }
 
-        unsafe {
-            self.device.get_device_ptr().device_wait_idle().unwrap();
-        }
+        // Only this swapchain's frames have to retire, other queue work can keep running. Its
+        // presents may still wait on the render finished semaphores though, so the present queue
+        // drains before the old swapchain and its semaphores are destroyed.
+        self.swapchain.wait_for_frames_in_flight()?;
+        let present_queue = self.device.get_queues().present;
+        unsafe { self.device.get_device_ptr().queue_wait_idle(present_queue) }.result()?;
+        let old_surface_format = self.swapchain.get_surface_format();
+        let old_depth_format = self.swapchain.get_depth_format();
+        let old_msaa_samples = self.swapchain.get_msaa_samples();
         self.swapchain = Swapchain::new(
             &self.device,
-            extent,
+            &self.surface,
+            window.get_extent(),
             Some(self.swapchain.get_swapchain_khr()),
-        )
-        .unwrap();
+            &self.swapchain_config,
+        )?;
+        self.is_swapchain_dirty = false;
+
+        // Pipelines stay valid with a compatible render pass, same attachment formats and samples.
+        let surface_format = self.swapchain.get_surface_format();
+        if surface_format.format != old_surface_format.format
+            || surface_format.color_space != old_surface_format.color_space
+            || self.swapchain.get_depth_format() != old_depth_format
+            || self.swapchain.get_msaa_samples() != old_msaa_samples
+        {
+            self.render_pass_generation += 1;
+        }
+
+        // The new swapchain starts its sync objects at frame 0, keep the command buffers in step.
+        self.current_frame_index = 0;
+        if self.command_buffers.len() != self.swapchain.get_frames_in_flight() {
+            self.free_command_buffers();
+            self.command_buffers =
+                Self::create_command_buffers(&self.device, self.swapchain.get_frames_in_flight())?;
+        }
         Ok(())
     }
 
-    fn create_command_buffers(device: &VRTDevice) -> VkResult<SmallVec<CommandBuffer>> {
+    fn create_command_buffers(
+        device: &VRTDevice,
+        frames_in_flight: usize,
+    ) -> VkResult<SmallVec<CommandBuffer>> {
         let alloc_info = CommandBufferAllocateInfoBuilder::new()
             .command_pool(device.get_command_pool())
             .level(CommandBufferLevel::PRIMARY)
-            .command_buffer_count(MAX_FRAMES_IN_FLIGHT as u32);
+            .command_buffer_count(frames_in_flight as u32);
 
         let command_buffers = unsafe {
             device
@@ -89,12 +166,27 @@ impl VRTRenderer {
         }
     }
 
-    pub fn begin_frame(&mut self, window: &VRTWindow) -> VkResult<CommandBuffer> {
-        if self.is_frame_started == true {
-            return Err(VkError::FrameAlreadyStarted);
+    pub fn begin_frame<'a>(&'a mut self, window: &'a mut VRTWindow) -> VkResult<Frame<'a>> {
+        if window.is_minimized() {
+            self.is_swapchain_dirty = true;
+            return Err(SwapChainExpired);
         }
 
+        if self.is_swapchain_dirty {
+            let render_pass_generation = self.render_pass_generation;
+            self.recreate_swapchain(window)?;
+            // Skip the frame, so pipelines can be rebuilt against the new render passes first.
+            if render_pass_generation != self.render_pass_generation {
+                return Err(SwapChainExpired);
+            }
+        }
+
+        let acquire_start = Instant::now();
+        self.frame_stats.begin_frame(acquire_start);
+
         let image_index_result = self.swapchain.acquire_next_image();
+        self.frame_stats
+            .record_acquire_wait(acquire_start.elapsed());
 
         let image_index = match image_index_result {
             Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
@@ -102,10 +194,8 @@ impl VRTRenderer {
                 return Err(SwapChainExpired);
             }
             result => result,
-        }
-        .unwrap();
+        }?;
 
-        self.is_frame_started = true;
         self.image_index = image_index;
 
         let begin_info = CommandBufferBeginInfoBuilder::new();
@@ -119,69 +209,98 @@ impl VRTRenderer {
         }
         .result()?;
 
-        Ok(command_buffer)
+        Ok(Frame::new(self, window, command_buffer))
     }
 
-    pub fn end_frame(&mut self, window: &mut VRTWindow, command_buffer: CommandBuffer) {
-        if !self.is_frame_started {
-            println!("cannot end frame if it's not started");
-            return;
-        }
+    pub(super) fn end_frame(
+        &mut self,
+        window: &mut VRTWindow,
+        command_buffer: CommandBuffer,
+    ) -> VkResult<()> {
         unsafe {
             self.device
                 .get_device_ptr()
                 .end_command_buffer(command_buffer)
-                .unwrap();
         }
+        .result()?;
 
-        let present_result = self
-            .swapchain
-            .submit_command_buffer(
-                &self.device,
-                &command_buffer,
-                &u32::try_from(self.image_index).unwrap(),
-            )
-            .unwrap();
+        let submit_start = Instant::now();
+        self.swapchain
+            .submit_command_buffer(&self.device, &command_buffer, &self.image_index)?;
+        self.frame_stats.record_submit(submit_start.elapsed());
+
+        let present_start = Instant::now();
+        let present_result = self.swapchain.present(&self.device, &self.image_index);
+        self.frame_stats.record_present(present_start.elapsed());
+
+        self.current_frame_index = (self.current_frame_index + 1) % self.command_buffers.len();
 
         if present_result.raw == vk::Result::ERROR_OUT_OF_DATE_KHR
             || present_result.raw == vk::Result::SUBOPTIMAL_KHR
             || window.was_window_resized()
         {
             window.reset_resized_flag();
-            self.recreate_swapchain(window).unwrap();
+            self.recreate_swapchain(window)
         } else {
-            present_result.result().unwrap();
+            present_result.result().map_err(VkError::Vk)
         }
-
-        self.is_frame_started = false;
-        self.current_frame_index = (self.current_frame_index + 1) % MAX_FRAMES_IN_FLIGHT;
     }
 
     fn get_current_command_buffer(&self) -> CommandBuffer {
         self.command_buffers[self.current_frame_index as usize]
     }
 
-    pub fn begin_swapchain_render_pass(&self, command_buffer: CommandBuffer) {
-        if self.is_frame_started == false {
-            println!("cannot begin swapchain render pass");
-            return;
-        }
+    /// Waits until no submitted frame of this renderer is executing anymore.
+    pub fn wait_for_frames_in_flight(&self) -> VkResult<()> {
+        self.swapchain.wait_for_frames_in_flight()
+    }
 
-        let clear_color = ClearValue {
+    pub fn get_current_frame_index(&self) -> usize {
+        self.current_frame_index
+    }
+
+    pub fn get_extent(&self) -> Extent2D {
+        self.swapchain.get_extent()
+    }
+
+    /// Begins one of the configured swapchain render passes, fails if there is no pass at
+    /// `index`.
+    pub(super) fn begin_swapchain_render_pass(
+        &self,
+        command_buffer: CommandBuffer,
+        index: usize,
+    ) -> VkResult<()> {
+        let render_pass = self
+            .swapchain
+            .get_render_pass_at(index)
+            .ok_or(VkError::MissingRenderPass(index))?;
+
+        // Indexed by attachment, the MSAA resolve target ignores its entry.
+        let color = ClearValue {
             color: ClearColorValue {
-                float32: [0.0, 0.0, 0.0, 1.0],
+                float32: self.clear_values.color,
             },
         };
+        let clear_values = [
+            color,
+            ClearValue {
+                depth_stencil: ClearDepthStencilValue {
+                    depth: self.clear_values.depth,
+                    stencil: self.clear_values.stencil,
+                },
+            },
+            color,
+        ];
 
         let render_pass_info = RenderPassBeginInfoBuilder::new()
-            .render_pass(self.swapchain.get_render_pass())
+            .render_pass(render_pass)
             .framebuffer(self.swapchain.get_frame_buffer()[self.image_index as usize])
             .render_area(
                 *Rect2DBuilder::new()
                     .offset(*Offset2DBuilder::new().x(0).y(0))
                     .extent(self.swapchain.get_extent()),
             )
-            .clear_values(std::slice::from_ref(&clear_color));
+            .clear_values(&clear_values);
 
         unsafe {
             self.device.get_device_ptr().cmd_begin_render_pass(
@@ -189,38 +308,12 @@ impl VRTRenderer {
                 &render_pass_info,
                 SubpassContents::INLINE,
             );
-
-            let viewport = ViewportBuilder::new()
-                .x(0.0)
-                .y(0.0)
-                .width(self.swapchain.get_extent().width as f32)
-                .height(self.swapchain.get_extent().height as f32)
-                .min_depth(0.0)
-                .max_depth(1.0);
-
-            let scissor = Rect2DBuilder::new()
-                .offset(*Offset2DBuilder::new().x(0).y(0))
-                .extent(self.swapchain.get_extent());
-
-            self.device.get_device_ptr().cmd_set_viewport(
-                command_buffer,
-                0,
-                &std::slice::from_ref(&viewport),
-            );
-
-            self.device.get_device_ptr().cmd_set_scissor(
-                command_buffer,
-                0,
-                &std::slice::from_ref(&scissor),
-            );
         }
+        set_viewport_and_scissor(&self.device, command_buffer, self.swapchain.get_extent());
+        Ok(())
     }
 
-    pub fn end_swapchain_render_pass(&self, command_buffer: CommandBuffer) {
-        if self.is_frame_started == false {
-            println!("cannot end swapchain render pass ");
-            return;
-        }
+    pub(super) fn end_swapchain_render_pass(&self, command_buffer: CommandBuffer) {
         unsafe {
             self.device
                 .get_device_ptr()
@@ -231,10 +324,90 @@ impl VRTRenderer {
     pub fn get_swapchain_render_pass(&self) -> RenderPass {
         self.swapchain.get_render_pass()
     }
+
+    pub fn get_swapchain_render_pass_at(&self, index: usize) -> Option<RenderPass> {
+        self.swapchain.get_render_pass_at(index)
+    }
+
+    pub fn get_clear_values(&self) -> ClearValues {
+        self.clear_values
+    }
+
+    pub fn set_clear_values(&mut self, clear_values: ClearValues) {
+        self.clear_values = clear_values;
+    }
+
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.set_clear_values(ClearValues {
+            color,
+            ..self.clear_values
+        });
+    }
+
+    pub fn get_frame_stats(&self) -> &FrameStats {
+        &self.frame_stats
+    }
+
+    pub(super) fn get_frame_stats_mut(&mut self) -> &mut FrameStats {
+        &mut self.frame_stats
+    }
+
+    pub fn get_msaa_samples(&self) -> SampleCountFlagBits {
+        self.swapchain.get_msaa_samples()
+    }
+
+    /// Pipelines built against the swapchain render passes are recreated, see
+    /// `get_render_pass_generation`.
+    pub fn set_msaa_samples(
+        &mut self,
+        window: &VRTWindow,
+        samples: SampleCountFlagBits,
+    ) -> VkResult<()> {
+        let samples = self.device.clamp_sample_count(samples);
+        if samples == self.swapchain_config.msaa_samples {
+            return Ok(());
+        }
+
+        self.swapchain_config.msaa_samples = samples;
+        self.recreate_swapchain(window)
+    }
+
+    /// Changes whenever the swapchain render passes stop being compatible with the previous
+    /// ones, pipelines built against them must be recreated before the next frame.
+    pub fn get_render_pass_generation(&self) -> u64 {
+        self.render_pass_generation
+    }
+
+    pub fn get_frames_in_flight(&self) -> usize {
+        self.swapchain.get_frames_in_flight()
+    }
+
+    pub fn set_frames_in_flight(
+        &mut self,
+        window: &VRTWindow,
+        frames_in_flight: usize,
+    ) -> VkResult<()> {
+        let frames_in_flight = frames_in_flight.clamp(1, MAX_FRAMES_IN_FLIGHT);
+        if frames_in_flight == self.swapchain_config.frames_in_flight {
+            return Ok(());
+        }
+
+        self.swapchain_config.frames_in_flight = frames_in_flight;
+        self.recreate_swapchain(window)
+    }
+
+    pub fn get_image_count(&self) -> usize {
+        self.swapchain.get_image_count()
+    }
+
+    pub fn get_output_transfer_function(&self) -> OutputTransferFunction {
+        self.swapchain.get_output_transfer_function()
+    }
 }
 
 impl Drop for VRTRenderer {
     fn drop(&mut self) {
+        let _ = self.swapchain.wait_for_frames_in_flight();
         self.free_command_buffers();
     }
 }
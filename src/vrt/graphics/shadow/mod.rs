@@ -0,0 +1,549 @@
+pub mod shadow_render_system;
+
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    BufferUsageFlags, ClearDepthStencilValue, ClearValue, CommandBuffer, DescriptorSet,
+    DescriptorSetLayout, DescriptorType, DeviceSize, Extent2D, Extent3D, Format, Framebuffer,
+    FramebufferCreateInfoBuilder, ImageAspectFlags, ImageCreateInfoBuilder, ImageLayout,
+    ImageTiling, ImageType, ImageUsageFlags, ImageView, ImageViewType, MemoryPropertyFlags,
+    RenderPass, SampleCountFlagBits, ShaderStageFlags, SharingMode, WHOLE_SIZE,
+};
+use glam::{Mat4, Vec3};
+
+use super::light::{GpuLight, Light, LightKind};
+use super::render_target::{RenderTargetPass, VRTRenderTarget};
+use crate::vrt::device::buffer::VRTBuffer;
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::device::swapchain::{RenderPassDescription, MAX_FRAMES_IN_FLIGHT};
+use crate::vrt::utils::result::VkResult;
+
+/// Size of the layer matrix array in the lighting shaders.
+pub const MAX_SHADOW_LAYERS: usize = 8;
+pub const MAX_CASCADES: u32 = 4;
+
+/// How far the depth range of a cascade reaches towards the light, in cascade radii, so that
+/// casters outside the view frustum still throw shadows into it.
+const CASTER_EXTENT: f32 = 4.0;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ShadowSettings {
+    pub enabled: bool,
+    /// Width and height of every shadow map layer.
+    pub resolution: u32,
+    /// Cascades of the first shadowed directional light, at most `MAX_CASCADES`.
+    pub cascade_count: u32,
+    /// Blends cascade splits from uniform (0.0) to logarithmic (1.0).
+    pub cascade_split_lambda: f32,
+    /// Distance from the near plane after which directional shadows end.
+    pub max_distance: f32,
+    pub depth_bias_constant: f32,
+    pub depth_bias_slope: f32,
+    /// World space offset along the surface normal before the shadow lookup.
+    pub normal_bias: f32,
+    /// Samples a (2r + 1)² texel kernel, 0 takes a single filtered sample.
+    pub pcf_radius: u32,
+    pub max_spot_shadows: u32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            resolution: 2048,
+            cascade_count: MAX_CASCADES,
+            cascade_split_lambda: 0.75,
+            max_distance: 100.0,
+            depth_bias_constant: 1.25,
+            depth_bias_slope: 1.75,
+            normal_bias: 0.01,
+            pcf_radius: 1,
+            max_spot_shadows: 2,
+        }
+    }
+}
+
+impl ShadowSettings {
+    fn clamped(mut self) -> Self {
+        self.resolution = self.resolution.max(1);
+        self.cascade_count = self.cascade_count.clamp(1, MAX_CASCADES);
+        self.cascade_split_lambda = self.cascade_split_lambda.clamp(0.0, 1.0);
+        self.max_spot_shadows = self
+            .max_spot_shadows
+            .min(MAX_SHADOW_LAYERS as u32 - self.cascade_count);
+        self
+    }
+
+    pub fn layer_count(&self) -> u32 {
+        self.cascade_count + self.max_spot_shadows
+    }
+}
+
+/// Matches the `ShadowData` uniform block of the lighting shaders.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ShadowUniform {
+    layer_matrices: [Mat4; MAX_SHADOW_LAYERS],
+    texel_size: f32,
+    pcf_radius: f32,
+    normal_bias: f32,
+    _padding: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct ShadowAssignment {
+    light_index: usize,
+    first_layer: u32,
+    layer_count: u32,
+}
+
+/// Depth maps rendered from the views of shadow casting lights, stored as layers of one array
+/// image: the cascades of a directional light followed by spot lights.
+///
+/// The lighting shaders bind the maps, a comparison sampler and the layer matrices as one set.
+pub struct ShadowMaps {
+    framebuffers: Vec<Framebuffer>,
+    layer_views: Vec<ImageView>,
+    depth_image: VRTImage,
+    render_pass: RenderPass,
+    sampler: VRTSampler,
+    descriptor_sets: Vec<DescriptorSet>,
+    uniform_buffers: Vec<VRTBuffer>,
+    descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    layer_matrices: Vec<Mat4>,
+    assignments: Vec<ShadowAssignment>,
+    settings: ShadowSettings,
+    depth_format: Format,
+    device: Arc<VRTDevice>,
+}
+
+impl ShadowMaps {
+    pub fn new(device: Arc<VRTDevice>, settings: ShadowSettings) -> VkResult<Self> {
+        let settings = settings.clamped();
+        let depth_format = VRTRenderTarget::find_sampled_depth_format(&device)?;
+        let render_pass = VRTRenderTarget::create_render_pass(
+            &device,
+            &[],
+            Some(depth_format),
+            &RenderPassDescription::clear(),
+        )?;
+        let sampler = VRTSampler::new_shadow(device.clone())?;
+
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .add_binding(1, DescriptorType::SAMPLER, ShaderStageFlags::FRAGMENT, None)
+            .add_binding(
+                2,
+                DescriptorType::UNIFORM_BUFFER,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .build();
+
+        let frame_count = MAX_FRAMES_IN_FLIGHT as u32;
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(frame_count)
+            .add_pool_size(DescriptorType::SAMPLED_IMAGE, frame_count)
+            .add_pool_size(DescriptorType::SAMPLER, frame_count)
+            .add_pool_size(DescriptorType::UNIFORM_BUFFER, frame_count)
+            .build()?;
+
+        let uniform_buffers = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| {
+                VRTBuffer::new(
+                    device.clone(),
+                    mem::size_of::<ShadowUniform>() as DeviceSize,
+                    1,
+                    BufferUsageFlags::UNIFORM_BUFFER,
+                    MemoryPropertyFlags::HOST_VISIBLE | MemoryPropertyFlags::HOST_COHERENT,
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+        let descriptor_sets = (0..MAX_FRAMES_IN_FLIGHT)
+            .map(|_| descriptor_pool.allocate_descriptor(&descriptor_set_layout))
+            .collect::<VkResult<Vec<_>>>()?;
+
+        let depth_image = Self::create_depth_image(&device, depth_format, &settings)?;
+
+        let mut shadow_maps = Self {
+            framebuffers: vec![],
+            layer_views: vec![],
+            depth_image,
+            render_pass,
+            sampler,
+            descriptor_sets,
+            uniform_buffers,
+            descriptor_pool,
+            descriptor_set_layout,
+            layer_matrices: vec![],
+            assignments: vec![],
+            settings,
+            depth_format,
+            device,
+        };
+        shadow_maps.create_layer_framebuffers()?;
+        shadow_maps.write_descriptors();
+        for frame_index in 0..MAX_FRAMES_IN_FLIGHT {
+            shadow_maps.upload(frame_index);
+        }
+        Ok(shadow_maps)
+    }
+
+    fn create_depth_image(
+        device: &Arc<VRTDevice>,
+        depth_format: Format,
+        settings: &ShadowSettings,
+    ) -> VkResult<VRTImage> {
+        let image_info = ImageCreateInfoBuilder::new()
+            .image_type(ImageType::_2D)
+            .extent(Extent3D {
+                width: settings.resolution,
+                height: settings.resolution,
+                depth: 1,
+            })
+            .mip_levels(1)
+            .array_layers(settings.layer_count())
+            .format(depth_format)
+            .tiling(ImageTiling::OPTIMAL)
+            .initial_layout(ImageLayout::UNDEFINED)
+            .usage(ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | ImageUsageFlags::SAMPLED)
+            .samples(SampleCountFlagBits::_1)
+            .sharing_mode(SharingMode::EXCLUSIVE);
+        let depth_image = VRTImage::new(
+            device.clone(),
+            &image_info,
+            ImageViewType::_2D_ARRAY,
+            ImageAspectFlags::DEPTH,
+        )?;
+
+        // Layers without a caster this frame are still bound, so they need a readable layout.
+        let command_buffer = device.begin_single_time_commands()?;
+        device.transition_image_layout(
+            command_buffer,
+            depth_image.get_image(),
+            depth_image
+                .subresource_range()
+                .layer_count(settings.layer_count()),
+            ImageLayout::UNDEFINED,
+            ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+        )?;
+        device.end_single_time_commands(command_buffer)?;
+
+        Ok(depth_image)
+    }
+
+    fn create_layer_framebuffers(&mut self) -> VkResult<()> {
+        for layer in 0..self.settings.layer_count() {
+            let layer_view = self.depth_image.create_layer_view(layer)?;
+            self.layer_views.push(layer_view);
+
+            let framebuffer_info = FramebufferCreateInfoBuilder::new()
+                .render_pass(self.render_pass)
+                .attachments(std::slice::from_ref(&layer_view))
+                .width(self.settings.resolution)
+                .height(self.settings.resolution)
+                .layers(1);
+            self.framebuffers.push(
+                unsafe {
+                    self.device
+                        .get_device_ptr()
+                        .create_framebuffer(&framebuffer_info, None)
+                }
+                .result()?,
+            );
+        }
+        Ok(())
+    }
+
+    fn destroy_layer_framebuffers(&mut self) {
+        unsafe {
+            for framebuffer in self.framebuffers.drain(..) {
+                self.device
+                    .get_device_ptr()
+                    .destroy_framebuffer(framebuffer, None);
+            }
+            for layer_view in self.layer_views.drain(..) {
+                self.device
+                    .get_device_ptr()
+                    .destroy_image_view(layer_view, None);
+            }
+        }
+    }
+
+    fn write_descriptors(&self) {
+        for (descriptor_set, buffer) in self.descriptor_sets.iter().zip(&self.uniform_buffers) {
+            VRTDescriptorWriter::new(&self.descriptor_set_layout, &self.descriptor_pool)
+                .write_image(
+                    0,
+                    self.depth_image.get_image_view(),
+                    ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                )
+                .write_sampler(1, self.sampler.get_sampler())
+                .write_buffer(2, buffer.get_buffer(), 0, WHOLE_SIZE)
+                .overwrite(*descriptor_set);
+        }
+    }
+
+    pub fn get_settings(&self) -> ShadowSettings {
+        self.settings
+    }
+
+    /// Recreates the maps if their size or layer count changes, no frame using them may still be
+    /// in flight.
+    pub fn set_settings(&mut self, settings: ShadowSettings) -> VkResult<()> {
+        let settings = settings.clamped();
+        let recreate = settings.resolution != self.settings.resolution
+            || settings.layer_count() != self.settings.layer_count();
+        self.settings = settings;
+
+        if recreate {
+            self.destroy_layer_framebuffers();
+            self.depth_image =
+                Self::create_depth_image(&self.device, self.depth_format, &self.settings)?;
+            self.create_layer_framebuffers()?;
+            self.write_descriptors();
+        }
+        Ok(())
+    }
+
+    /// Depth-only pipelines that render into the maps have to be built against this render pass.
+    pub fn get_render_pass(&self) -> RenderPass {
+        self.render_pass
+    }
+
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self, frame_index: usize) -> DescriptorSet {
+        self.descriptor_sets[frame_index]
+    }
+
+    /// Number of layers that have to be rendered this frame.
+    pub fn layer_count(&self) -> usize {
+        self.layer_matrices.len()
+    }
+
+    /// View projection of the light that renders into `layer`.
+    pub fn layer_matrix(&self, layer: usize) -> Mat4 {
+        self.layer_matrices[layer]
+    }
+
+    /// Assigns layers to the shadow casting `lights` and uploads their matrices for
+    /// `frame_index`. Cascades cover the camera frustum between `near` and `far`.
+    pub fn update(
+        &mut self,
+        frame_index: usize,
+        lights: &[Light],
+        inverse_view_projection: Mat4,
+        near: f32,
+        far: f32,
+    ) {
+        self.layer_matrices.clear();
+        self.assignments.clear();
+
+        let mut cascades_assigned = false;
+        let mut spot_shadows = 0;
+        for (light_index, light) in lights.iter().enumerate() {
+            if !self.settings.enabled || !light.cast_shadows || light.direction == Vec3::ZERO {
+                continue;
+            }
+
+            let first_layer = self.layer_matrices.len() as u32;
+            match light.kind {
+                LightKind::Directional if !cascades_assigned => {
+                    cascades_assigned = true;
+                    let cascades =
+                        self.cascade_matrices(light.direction, inverse_view_projection, near, far);
+                    self.layer_matrices.extend(cascades);
+                }
+                LightKind::Spot if spot_shadows < self.settings.max_spot_shadows => {
+                    spot_shadows += 1;
+                    self.layer_matrices.push(Self::spot_matrix(light));
+                }
+                _ => continue,
+            }
+
+            self.assignments.push(ShadowAssignment {
+                light_index,
+                first_layer,
+                layer_count: self.layer_matrices.len() as u32 - first_layer,
+            });
+        }
+
+        self.upload(frame_index);
+    }
+
+    fn upload(&self, frame_index: usize) {
+        let mut layer_matrices = [Mat4::IDENTITY; MAX_SHADOW_LAYERS];
+        layer_matrices[..self.layer_matrices.len()].copy_from_slice(&self.layer_matrices);
+        let uniform = ShadowUniform {
+            layer_matrices,
+            texel_size: 1.0 / self.settings.resolution as f32,
+            pcf_radius: self.settings.pcf_radius as f32,
+            normal_bias: self.settings.normal_bias,
+            _padding: 0.0,
+        };
+
+        let buffer = &self.uniform_buffers[frame_index];
+        let mapped = buffer.map(WHOLE_SIZE, 0);
+        buffer.write_to_buffer(&uniform as *const ShadowUniform, mapped, 1, 0);
+        buffer.unmap();
+    }
+
+    /// Stores the assigned layers in the lights uploaded for the same frame.
+    pub fn apply_to(&self, gpu_lights: &mut [GpuLight]) {
+        for assignment in &self.assignments {
+            if let Some(gpu_light) = gpu_lights.get_mut(assignment.light_index) {
+                gpu_light.cone[2] = assignment.first_layer as f32;
+                gpu_light.cone[3] = assignment.layer_count as f32;
+            }
+        }
+    }
+
+    /// Clears `layer` and sets the depth bias, depth-only render systems draw inside the
+    /// returned pass with `layer_matrix(layer)`.
+    pub fn begin_layer(&self, command_buffer: CommandBuffer, layer: usize) -> RenderTargetPass<'_> {
+        let clear_value = ClearValue {
+            depth_stencil: ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        };
+        let extent = Extent2D {
+            width: self.settings.resolution,
+            height: self.settings.resolution,
+        };
+
+        let pass = RenderTargetPass::begin(
+            &self.device,
+            command_buffer,
+            self.render_pass,
+            self.framebuffers[layer],
+            extent,
+            std::slice::from_ref(&clear_value),
+        );
+        unsafe {
+            self.device.get_device_ptr().cmd_set_depth_bias(
+                command_buffer,
+                self.settings.depth_bias_constant,
+                0.0,
+                self.settings.depth_bias_slope,
+            );
+        }
+        pass
+    }
+
+    fn cascade_matrices(
+        &self,
+        direction: Vec3,
+        inverse_view_projection: Mat4,
+        near: f32,
+        far: f32,
+    ) -> Vec<Mat4> {
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)];
+        let near_corners =
+            corners.map(|(x, y)| inverse_view_projection.project_point3(Vec3::new(x, y, 0.0)));
+        let far_corners =
+            corners.map(|(x, y)| inverse_view_projection.project_point3(Vec3::new(x, y, 1.0)));
+
+        let count = self.settings.cascade_count;
+        let lambda = self.settings.cascade_split_lambda;
+        let shadow_far = far.min(near + self.settings.max_distance);
+        let split = |cascade: u32| {
+            let fraction = cascade as f32 / count as f32;
+            let logarithmic = near * (shadow_far / near).powf(fraction);
+            let uniform = near + (shadow_far - near) * fraction;
+            let distance = lambda * logarithmic + (1.0 - lambda) * uniform;
+            (distance - near) / (far - near)
+        };
+
+        (0..count)
+            .map(|cascade| {
+                let (start, end) = (split(cascade), split(cascade + 1));
+                let slice = near_corners
+                    .iter()
+                    .zip(&far_corners)
+                    .flat_map(|(near, far)| [near.lerp(*far, start), near.lerp(*far, end)])
+                    .collect::<Vec<_>>();
+
+                let center =
+                    slice.iter().fold(Vec3::ZERO, |sum, corner| sum + *corner) / slice.len() as f32;
+                let radius = slice
+                    .iter()
+                    .map(|corner| corner.distance(center))
+                    .fold(0.0, f32::max);
+                // A bounding sphere keeps the map size constant while the camera rotates.
+                let radius = (radius * 16.0).ceil() / 16.0;
+
+                self.directional_matrix(direction, center, radius)
+            })
+            .collect()
+    }
+
+    fn directional_matrix(&self, direction: Vec3, center: Vec3, radius: f32) -> Mat4 {
+        let view = Mat4::look_at_rh(center - direction * radius, center, up_vector(direction));
+        let mut projection = Mat4::orthographic_rh(
+            -radius,
+            radius,
+            -radius,
+            radius,
+            -radius * CASTER_EXTENT,
+            radius * 2.0,
+        );
+
+        // Snap the origin to whole texels so that shadow edges do not shimmer as the camera moves.
+        let half_resolution = self.settings.resolution as f32 / 2.0;
+        let origin = (projection * view).transform_point3(Vec3::ZERO) * half_resolution;
+        let offset = (origin.round() - origin) / half_resolution;
+        projection.w_axis.x += offset.x;
+        projection.w_axis.y += offset.y;
+
+        projection * view
+    }
+
+    fn spot_matrix(light: &Light) -> Mat4 {
+        let view = Mat4::look_at_rh(
+            light.position,
+            light.position + light.direction,
+            up_vector(light.direction),
+        );
+        let field_of_view = (light.outer_angle * 2.0).clamp(0.01, 3.1);
+        let far = light.range.min(f32::MAX);
+        let projection = Mat4::perspective_rh(field_of_view, 1.0, far * 0.01, far);
+        projection * view
+    }
+}
+
+fn up_vector(direction: Vec3) -> Vec3 {
+    if direction.normalize().y.abs() > 0.99 {
+        Vec3::Z
+    } else {
+        Vec3::Y
+    }
+}
+
+impl Drop for ShadowMaps {
+    fn drop(&mut self) {
+        self.destroy_layer_framebuffers();
+        unsafe {
+            self.device
+                .get_device_ptr()
+                .destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
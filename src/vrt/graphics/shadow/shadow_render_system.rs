@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{CommandBuffer, CullModeFlags, RenderPass, ShaderStageFlags};
+use glam::Mat4;
+
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::model::Model;
+use crate::vrt::graphics::pipeline::VRTPipeline;
+
+const VERTEX_SHADER: &str = "./assets/shaders/shadow_vert.spirv";
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct ShadowPush {
+    transform: Mat4,
+}
+
+/// Renders model depth into a layer of `ShadowMaps`.
+pub struct ShadowRenderSystem {
+    pipeline: VRTPipeline,
+    device: Arc<VRTDevice>,
+}
+
+impl ShadowRenderSystem {
+    pub fn new(device: Arc<VRTDevice>, render_pass: RenderPass) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        // Flat geometry has no back side to cast from, so both faces write depth.
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_dynamic_depth_bias();
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX,
+            0,
+            std::mem::size_of::<ShadowPush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new_depth_only(
+            device.clone(),
+            VERTEX_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+
+        Self { pipeline, device }
+    }
+
+    /// `transform` takes model space to the clip space of the shadow map layer.
+    pub fn render(&self, command_buffer: CommandBuffer, model: &Model, transform: Mat4) {
+        self.pipeline.bind(command_buffer);
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::VERTEX,
+            &ShadowPush { transform },
+        );
+        model.bind(self.device.clone(), command_buffer);
+        model.draw(self.device.clone(), command_buffer)
+    }
+}
@@ -0,0 +1,362 @@
+pub mod sprite_render_system;
+
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    DescriptorSet, DescriptorSetLayout, DescriptorType, Extent2D, Extent3D, Filter, Format,
+    ImageLayout, ImageType, ImageViewType, SamplerAddressMode, ShaderStageFlags,
+    VertexInputAttributeDescriptionBuilder,
+};
+use glam::{Mat4, Vec2, Vec4};
+
+use super::instance::{InstanceData, FIRST_INSTANCE_LOCATION, INSTANCE_BINDING};
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::utils::result::VkResult;
+
+const MAX_ATLASES: u32 = 64;
+
+/// One sprite of a batch, read per instance by `sprite.vert`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpriteQuad {
+    /// Center in world units in xy, rotation in radians in z.
+    pub position_rotation: Vec4,
+    /// Size in world units in xy, the pivot the sprite rotates around in zw, from 0 to 1.
+    pub size_pivot: Vec4,
+    /// Atlas texture coordinates of the corners, min in xy and max in zw.
+    pub uv_rect: Vec4,
+    pub color: Vec4,
+}
+
+impl InstanceData for SpriteQuad {
+    fn attribute_descriptions() -> Vec<VertexInputAttributeDescriptionBuilder<'static>> {
+        let size = mem::size_of::<Vec4>() as u32;
+        (0..4)
+            .map(|index| {
+                VertexInputAttributeDescriptionBuilder::new()
+                    .binding(INSTANCE_BINDING)
+                    .location(FIRST_INSTANCE_LOCATION + index)
+                    .format(Format::R32G32B32A32_SFLOAT)
+                    .offset(index * size)
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
+pub struct SpriteAtlasHandle(usize);
+
+/// A sub-rectangle of an atlas to texture a sprite with.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpriteRegion {
+    pub atlas: SpriteAtlasHandle,
+    /// Texture coordinates of the corners, min in xy and max in zw.
+    pub uv_rect: Vec4,
+    /// Size in texels.
+    pub size: Vec2,
+}
+
+struct SpriteAtlasEntry {
+    _image: VRTImage,
+    extent: Extent2D,
+    regions: HashMap<String, SpriteRegion>,
+    descriptor_set: DescriptorSet,
+}
+
+/// Owns the textures sprites are drawn with and the named regions within them.
+///
+/// A texture without regions is an atlas of one sprite, see `full_region`.
+pub struct SpriteAtlasRegistry {
+    entries: Vec<SpriteAtlasEntry>,
+    sampler: VRTSampler,
+    descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+    device: Arc<VRTDevice>,
+}
+
+impl SpriteAtlasRegistry {
+    /// `filter` applies to all atlases, `Filter::NEAREST` keeps pixel art crisp.
+    pub fn new(device: Arc<VRTDevice>, filter: Filter) -> VkResult<Self> {
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .add_binding(1, DescriptorType::SAMPLER, ShaderStageFlags::FRAGMENT, None)
+            .build();
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device.clone())
+            .set_max_sets(MAX_ATLASES)
+            .add_pool_size(DescriptorType::SAMPLED_IMAGE, MAX_ATLASES)
+            .add_pool_size(DescriptorType::SAMPLER, MAX_ATLASES)
+            .build()?;
+        let sampler = VRTSampler::new(device.clone(), filter, SamplerAddressMode::CLAMP_TO_EDGE)?;
+
+        Ok(Self {
+            entries: vec![],
+            sampler,
+            descriptor_pool,
+            descriptor_set_layout,
+            device,
+        })
+    }
+
+    /// Uploads tightly packed texels, color atlases should use an `*_SRGB` format.
+    pub fn add(
+        &mut self,
+        extent: Extent2D,
+        format: Format,
+        pixels: &[u8],
+    ) -> VkResult<SpriteAtlasHandle> {
+        let image = VRTImage::from_pixels(
+            self.device.clone(),
+            ImageType::_2D,
+            ImageViewType::_2D,
+            Extent3D {
+                width: extent.width,
+                height: extent.height,
+                depth: 1,
+            },
+            format,
+            pixels,
+        )?;
+        let descriptor_set =
+            VRTDescriptorWriter::new(&self.descriptor_set_layout, &self.descriptor_pool)
+                .write_image(
+                    0,
+                    image.get_image_view(),
+                    ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                )
+                .write_sampler(1, self.sampler.get_sampler())
+                .build()?;
+
+        let handle = SpriteAtlasHandle(self.entries.len());
+        self.entries.push(SpriteAtlasEntry {
+            _image: image,
+            extent,
+            regions: HashMap::new(),
+            descriptor_set,
+        });
+        Ok(handle)
+    }
+
+    /// Names the rectangle of `size` texels at `min`, with y pointing down from the top left of
+    /// the atlas. Replaces an existing region of the same name.
+    pub fn add_region(
+        &mut self,
+        atlas: SpriteAtlasHandle,
+        name: &str,
+        min: Vec2,
+        size: Vec2,
+    ) -> Option<SpriteRegion> {
+        let entry = self.entries.get_mut(atlas.0)?;
+        let extent = Vec2::new(entry.extent.width as f32, entry.extent.height as f32);
+        let (uv_min, uv_max) = (min / extent, (min + size) / extent);
+        let region = SpriteRegion {
+            atlas,
+            uv_rect: Vec4::new(uv_min.x, uv_min.y, uv_max.x, uv_max.y),
+            size,
+        };
+        entry.regions.insert(name.to_string(), region);
+        Some(region)
+    }
+
+    /// Names the cells of a sheet of equally sized sprites row by row, `names` in reading order.
+    pub fn add_grid<'a>(
+        &mut self,
+        atlas: SpriteAtlasHandle,
+        cell_size: Vec2,
+        names: impl IntoIterator<Item = &'a str>,
+    ) {
+        let columns = match self.entries.get(atlas.0) {
+            Some(entry) => ((entry.extent.width as f32 / cell_size.x) as usize).max(1),
+            None => return,
+        };
+        for (index, name) in names.into_iter().enumerate() {
+            let cell = Vec2::new((index % columns) as f32, (index / columns) as f32);
+            self.add_region(atlas, name, cell * cell_size, cell_size);
+        }
+    }
+
+    pub fn region(&self, atlas: SpriteAtlasHandle, name: &str) -> Option<SpriteRegion> {
+        self.entries.get(atlas.0)?.regions.get(name).copied()
+    }
+
+    /// The whole texture of `atlas`.
+    pub fn full_region(&self, atlas: SpriteAtlasHandle) -> Option<SpriteRegion> {
+        self.entries.get(atlas.0).map(|entry| SpriteRegion {
+            atlas,
+            uv_rect: Vec4::new(0.0, 0.0, 1.0, 1.0),
+            size: Vec2::new(entry.extent.width as f32, entry.extent.height as f32),
+        })
+    }
+
+    /// Sprite pipelines bind atlas descriptor sets as set 0.
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self, atlas: SpriteAtlasHandle) -> Option<DescriptorSet> {
+        self.entries.get(atlas.0).map(|entry| entry.descriptor_set)
+    }
+}
+
+/// A textured quad in world units. Sprites of lower layers are drawn first, within a layer the
+/// order of sprites sharing an atlas is kept.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Sprite {
+    pub region: SpriteRegion,
+    /// Position of the pivot.
+    pub position: Vec2,
+    pub size: Vec2,
+    /// Counterclockwise in radians, around the pivot.
+    pub rotation: f32,
+    /// Point of the sprite at `position`, (0, 0) is the bottom left and (1, 1) the top right.
+    pub pivot: Vec2,
+    /// Multiplied with the texture.
+    pub color: Vec4,
+    pub layer: i32,
+}
+
+impl Sprite {
+    pub fn new(region: SpriteRegion, position: Vec2, size: Vec2) -> Self {
+        Self {
+            region,
+            position,
+            size,
+            rotation: 0.0,
+            pivot: Vec2::splat(0.5),
+            color: Vec4::ONE,
+            layer: 0,
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    pub fn with_pivot(mut self, pivot: Vec2) -> Self {
+        self.pivot = pivot;
+        self
+    }
+
+    pub fn with_color(mut self, color: Vec4) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_layer(mut self, layer: i32) -> Self {
+        self.layer = layer;
+        self
+    }
+
+    fn quad(&self) -> SpriteQuad {
+        SpriteQuad {
+            position_rotation: Vec4::new(self.position.x, self.position.y, self.rotation, 0.0),
+            size_pivot: Vec4::new(self.size.x, self.size.y, self.pivot.x, self.pivot.y),
+            uv_rect: self.region.uv_rect,
+            color: self.color,
+        }
+    }
+}
+
+/// Consecutive quads of a batch sharing an atlas, drawn with one instanced draw.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpriteDraw {
+    pub atlas: SpriteAtlasHandle,
+    pub instances: Range<u32>,
+}
+
+/// Sprites collected over a frame.
+#[derive(Debug, Clone, Default)]
+pub struct SpriteBatch {
+    sprites: Vec<Sprite>,
+}
+
+impl SpriteBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, sprite: Sprite) {
+        self.sprites.push(sprite);
+    }
+
+    pub fn clear(&mut self) {
+        self.sprites.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// The quads of all sprites ordered by layer and then by atlas, with the draws covering
+    /// them. A layer using a single atlas, or layers sharing one, merge into one draw.
+    pub fn quads(&self) -> (Vec<SpriteQuad>, Vec<SpriteDraw>) {
+        let mut order = (0..self.sprites.len()).collect::<Vec<_>>();
+        // Stable, sprites with equal keys stay in the order they were pushed.
+        order.sort_by_key(|&index| {
+            let sprite = &self.sprites[index];
+            (sprite.layer, sprite.region.atlas)
+        });
+
+        let mut quads = Vec::with_capacity(order.len());
+        let mut draws: Vec<SpriteDraw> = vec![];
+        for index in order {
+            let sprite = &self.sprites[index];
+            let instance = quads.len() as u32;
+            match draws.last_mut() {
+                Some(draw) if draw.atlas == sprite.region.atlas => draw.instances.end += 1,
+                _ => draws.push(SpriteDraw {
+                    atlas: sprite.region.atlas,
+                    instances: instance..instance + 1,
+                }),
+            }
+            quads.push(sprite.quad());
+        }
+        (quads, draws)
+    }
+}
+
+/// An orthographic camera looking down on the xy plane, with y pointing up on screen.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera2D {
+    pub center: Vec2,
+    /// World units visible from the bottom to the top of the viewport.
+    pub view_height: f32,
+}
+
+impl Camera2D {
+    pub fn new(center: Vec2, view_height: f32) -> Self {
+        Self {
+            center,
+            view_height,
+        }
+    }
+
+    /// The width follows the aspect ratio of `extent`, so sprites keep their proportions.
+    pub fn view_projection(&self, extent: Extent2D) -> Mat4 {
+        let aspect = extent.width as f32 / extent.height.max(1) as f32;
+        let half_size = Vec2::new(self.view_height * aspect, self.view_height) * 0.5;
+        // Vulkan clip space points y down, so the top edge maps to -1.
+        Mat4::orthographic_rh(
+            self.center.x - half_size.x,
+            self.center.x + half_size.x,
+            self.center.y + half_size.y,
+            self.center.y - half_size.y,
+            -1.0,
+            1.0,
+        )
+    }
+}
@@ -0,0 +1,111 @@
+use std::mem;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, CompareOp, CullModeFlags, RenderPass, SampleCountFlagBits, ShaderStageFlags,
+};
+use glam::Mat4;
+
+use super::{SpriteAtlasRegistry, SpriteBatch, SpriteQuad};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::instance::InstanceBuffer;
+use crate::vrt::graphics::pipeline::{BlendMode, VRTPipeline};
+
+const VERTEX_SHADER: &str = "./assets/shaders/sprite_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/sprite_frag.spirv";
+const INITIAL_SPRITE_CAPACITY: u32 = 4096;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+struct SpritePush {
+    view_projection: Mat4,
+}
+
+/// Draws sprite batches without depth testing, so layers alone decide what is on top. Every
+/// sprite is an instance of a six vertex quad and each run of sprites sharing an atlas is one
+/// draw.
+pub struct SpriteRenderSystem {
+    pipeline: VRTPipeline,
+    sprites: InstanceBuffer<SpriteQuad>,
+    device: Arc<VRTDevice>,
+}
+
+impl SpriteRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        atlases: &SpriteAtlasRegistry,
+        blend_mode: BlendMode,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.add_instance_input::<SpriteQuad>();
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_depth_test(false, false, CompareOp::ALWAYS);
+        config_info.set_blend_mode(blend_mode);
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info.set_descriptor_set_layouts(&[atlases.get_descriptor_set_layout()]);
+        config_info.add_push_constant_range(
+            ShaderStageFlags::VERTEX,
+            0,
+            mem::size_of::<SpritePush>() as u32,
+        );
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+        let sprites = InstanceBuffer::new(device.clone(), INITIAL_SPRITE_CAPACITY);
+
+        Self {
+            pipeline,
+            sprites,
+            device,
+        }
+    }
+
+    /// Draws `batch` seen through `view_projection`, usually from a `Camera2D`.
+    pub fn render(
+        &mut self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        view_projection: Mat4,
+        atlases: &SpriteAtlasRegistry,
+        batch: &SpriteBatch,
+    ) {
+        if batch.is_empty() {
+            return;
+        }
+        let (quads, draws) = batch.quads();
+        self.sprites.update(frame_index, &quads);
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline.push_constants(
+            command_buffer,
+            ShaderStageFlags::VERTEX,
+            &SpritePush { view_projection },
+        );
+        self.sprites.bind(command_buffer, frame_index);
+        for draw in draws {
+            let descriptor_set = match atlases.get_descriptor_set(draw.atlas) {
+                Some(descriptor_set) => descriptor_set,
+                None => continue,
+            };
+            self.pipeline
+                .bind_descriptor_sets(command_buffer, 0, &[descriptor_set]);
+            unsafe {
+                self.device.get_device_ptr().cmd_draw(
+                    command_buffer,
+                    6,
+                    draw.instances.end - draw.instances.start,
+                    0,
+                    draw.instances.start,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,410 @@
+pub mod text_render_system;
+
+use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    DescriptorSet, DescriptorSetLayout, DescriptorType, Extent2D, Extent3D, Filter, Format,
+    ImageLayout, ImageType, ImageViewType, SamplerAddressMode, ShaderStageFlags,
+    VertexInputAttributeDescriptionBuilder,
+};
+use fontdue::{Font, FontSettings};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+
+use super::instance::{InstanceData, FIRST_INSTANCE_LOCATION, INSTANCE_BINDING};
+use crate::vrt::device::descriptors::layout::{
+    VRTDescriptorSetLayout, VRTDescriptorSetLayoutBuilder,
+};
+use crate::vrt::device::descriptors::pool::{VRTDescriptorPool, VRTDescriptorPoolBuilder};
+use crate::vrt::device::descriptors::writer::VRTDescriptorWriter;
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::device::image::{VRTImage, VRTSampler};
+use crate::vrt::utils::result::{VkError, VkResult};
+
+const ATLAS_WIDTH: u32 = 512;
+/// Empty texels around each glyph, so filtering never picks up a neighbour.
+const GLYPH_PADDING: u32 = 1;
+/// Drawn for characters the atlas does not contain.
+const FALLBACK_CHARACTER: char = '?';
+
+/// Printable ASCII and Latin-1, the characters rasterized into every atlas.
+fn atlas_characters() -> impl Iterator<Item = char> {
+    (' '..='~').chain('\u{a0}'..='\u{ff}')
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// One glyph of a text batch, read per instance by `text.vert`.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct GlyphQuad {
+    /// Corners in normalized device coordinates, min in xy and max in zw.
+    pub rect: Vec4,
+    /// Atlas texture coordinates of the corners.
+    pub uv_rect: Vec4,
+    pub color: Vec4,
+}
+
+impl InstanceData for GlyphQuad {
+    fn attribute_descriptions() -> Vec<VertexInputAttributeDescriptionBuilder<'static>> {
+        let size = mem::size_of::<Vec4>() as u32;
+        (0..3)
+            .map(|index| {
+                VertexInputAttributeDescriptionBuilder::new()
+                    .binding(INSTANCE_BINDING)
+                    .location(FIRST_INSTANCE_LOCATION + index)
+                    .format(Format::R32G32B32A32_SFLOAT)
+                    .offset(index * size)
+            })
+            .collect()
+    }
+}
+
+/// Placement of a rasterized glyph, in pixels of the atlas size with y pointing down.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Glyph {
+    uv_min: Vec2,
+    uv_max: Vec2,
+    /// Top left corner relative to the pen on the baseline.
+    offset: Vec2,
+    size: Vec2,
+    advance: f32,
+}
+
+/// A TTF or OTF font rasterized into a single channel coverage atlas at load time.
+///
+/// Glyphs are rasterized once at `pixel_size` and scaled when drawn, so text much larger than
+/// that gets blurry.
+pub struct FontAtlas {
+    font: Font,
+    glyphs: HashMap<char, Glyph>,
+    pixel_size: f32,
+    ascent: f32,
+    line_height: f32,
+    _image: VRTImage,
+    _sampler: VRTSampler,
+    descriptor_set: DescriptorSet,
+    _descriptor_pool: VRTDescriptorPool,
+    descriptor_set_layout: VRTDescriptorSetLayout,
+}
+
+impl FontAtlas {
+    pub fn load(device: Arc<VRTDevice>, path: impl AsRef<Path>, pixel_size: f32) -> VkResult<Self> {
+        Self::from_bytes(device, &fs::read(path)?, pixel_size)
+    }
+
+    pub fn from_bytes(device: Arc<VRTDevice>, bytes: &[u8], pixel_size: f32) -> VkResult<Self> {
+        let pixel_size = pixel_size.max(1.0);
+        let font = Font::from_bytes(
+            bytes,
+            FontSettings {
+                scale: pixel_size,
+                ..FontSettings::default()
+            },
+        )
+        .map_err(|err| VkError::InvalidAsset(format!("font: {}", err)))?;
+        let line_metrics = font
+            .horizontal_line_metrics(pixel_size)
+            .ok_or_else(|| VkError::InvalidAsset("font without horizontal metrics".into()))?;
+
+        // Shelf packing, rows of glyphs as tall as the tallest glyph in them.
+        let mut rasterized = vec![];
+        let (mut x, mut y, mut row_height) = (GLYPH_PADDING, GLYPH_PADDING, 0);
+        for character in atlas_characters() {
+            if font.lookup_glyph_index(character) == 0 && character != FALLBACK_CHARACTER {
+                continue;
+            }
+            let (metrics, coverage) = font.rasterize(character, pixel_size);
+            let (width, height) = (metrics.width as u32, metrics.height as u32);
+            if x + width + GLYPH_PADDING > ATLAS_WIDTH {
+                x = GLYPH_PADDING;
+                y += row_height + GLYPH_PADDING;
+                row_height = 0;
+            }
+            rasterized.push((character, metrics, coverage, x, y));
+            x += width + GLYPH_PADDING;
+            row_height = row_height.max(height);
+        }
+        let atlas_height = (y + row_height + GLYPH_PADDING).next_power_of_two();
+
+        let atlas_size = Vec2::new(ATLAS_WIDTH as f32, atlas_height as f32);
+        let mut pixels = vec![0u8; (ATLAS_WIDTH * atlas_height) as usize];
+        let mut glyphs = HashMap::new();
+        for (character, metrics, coverage, x, y) in rasterized {
+            for (row, texels) in coverage.chunks(metrics.width.max(1)).enumerate() {
+                let start = ((y as usize + row) * ATLAS_WIDTH as usize) + x as usize;
+                pixels[start..start + texels.len()].copy_from_slice(texels);
+            }
+            let size = Vec2::new(metrics.width as f32, metrics.height as f32);
+            let corner = Vec2::new(x as f32, y as f32);
+            glyphs.insert(
+                character,
+                Glyph {
+                    uv_min: corner / atlas_size,
+                    uv_max: (corner + size) / atlas_size,
+                    offset: Vec2::new(
+                        metrics.xmin as f32,
+                        -(metrics.ymin as f32 + metrics.height as f32),
+                    ),
+                    size,
+                    advance: metrics.advance_width,
+                },
+            );
+        }
+
+        let image = VRTImage::from_pixels(
+            device.clone(),
+            ImageType::_2D,
+            ImageViewType::_2D,
+            Extent3D {
+                width: ATLAS_WIDTH,
+                height: atlas_height,
+                depth: 1,
+            },
+            Format::R8_UNORM,
+            &pixels,
+        )?;
+        let sampler = VRTSampler::new(
+            device.clone(),
+            Filter::LINEAR,
+            SamplerAddressMode::CLAMP_TO_EDGE,
+        )?;
+
+        let descriptor_set_layout = VRTDescriptorSetLayoutBuilder::new(device.clone())
+            .add_binding(
+                0,
+                DescriptorType::SAMPLED_IMAGE,
+                ShaderStageFlags::FRAGMENT,
+                None,
+            )
+            .add_binding(1, DescriptorType::SAMPLER, ShaderStageFlags::FRAGMENT, None)
+            .build();
+        let descriptor_pool = VRTDescriptorPoolBuilder::new(device)
+            .set_max_sets(1)
+            .add_pool_size(DescriptorType::SAMPLED_IMAGE, 1)
+            .add_pool_size(DescriptorType::SAMPLER, 1)
+            .build()?;
+        let descriptor_set = VRTDescriptorWriter::new(&descriptor_set_layout, &descriptor_pool)
+            .write_image(
+                0,
+                image.get_image_view(),
+                ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            )
+            .write_sampler(1, sampler.get_sampler())
+            .build()?;
+
+        Ok(Self {
+            font,
+            glyphs,
+            pixel_size,
+            ascent: line_metrics.ascent,
+            line_height: line_metrics.new_line_size,
+            _image: image,
+            _sampler: sampler,
+            descriptor_set,
+            _descriptor_pool: descriptor_pool,
+            descriptor_set_layout,
+        })
+    }
+
+    pub fn get_pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    pub fn get_descriptor_set_layout(&self) -> DescriptorSetLayout {
+        self.descriptor_set_layout.get_descriptor_set_layout()
+    }
+
+    pub fn get_descriptor_set(&self) -> DescriptorSet {
+        self.descriptor_set
+    }
+
+    fn glyph(&self, character: char) -> Option<(char, &Glyph)> {
+        self.glyphs
+            .get(&character)
+            .map(|glyph| (character, glyph))
+            .or_else(|| {
+                self.glyphs
+                    .get(&FALLBACK_CHARACTER)
+                    .map(|glyph| (FALLBACK_CHARACTER, glyph))
+            })
+    }
+
+    fn line_width(&self, line: &str, size: f32) -> f32 {
+        let scale = size / self.pixel_size;
+        let mut width = 0.0;
+        let mut previous = None;
+        for (character, glyph) in line.chars().filter_map(|character| self.glyph(character)) {
+            width += (glyph.advance + self.kerning(previous, character)) * scale;
+            previous = Some(character);
+        }
+        width
+    }
+
+    fn kerning(&self, previous: Option<char>, character: char) -> f32 {
+        previous
+            .and_then(|previous| {
+                self.font
+                    .horizontal_kern(previous, character, self.pixel_size)
+            })
+            .unwrap_or(0.0)
+    }
+
+    /// Size in pixels of `text` drawn `size` pixels high, lines split at `\n`.
+    pub fn measure(&self, text: &str, size: f32) -> Vec2 {
+        let scale = size / self.pixel_size;
+        let width = text
+            .lines()
+            .map(|line| self.line_width(line, size))
+            .fold(0.0, f32::max);
+        Vec2::new(
+            width,
+            text.lines().count() as f32 * self.line_height * scale,
+        )
+    }
+
+    /// Appends the glyphs of `text` to `quads`, placed in a viewport of `extent` pixels.
+    fn layout(&self, text: &Text, extent: Extent2D, quads: &mut Vec<GlyphQuad>) {
+        let scale = text.size / self.pixel_size;
+        let viewport = Vec2::new(extent.width.max(1) as f32, extent.height.max(1) as f32);
+        let to_ndc = |pixel: Vec2| pixel / viewport * 2.0 - Vec2::ONE;
+
+        for (index, line) in text.content.lines().enumerate() {
+            let width = self.line_width(line, text.size);
+            let mut pen = Vec2::new(
+                match text.align {
+                    TextAlign::Left => text.position.x,
+                    TextAlign::Center => text.position.x - width * 0.5,
+                    TextAlign::Right => text.position.x - width,
+                },
+                text.position.y + (self.ascent + index as f32 * self.line_height) * scale,
+            );
+
+            let mut previous = None;
+            for (character, glyph) in line.chars().filter_map(|character| self.glyph(character)) {
+                pen.x += self.kerning(previous, character) * scale;
+                previous = Some(character);
+                if glyph.size.x > 0.0 && glyph.size.y > 0.0 {
+                    // Snap to whole pixels, glyphs are rasterized on the pixel grid.
+                    let min = (pen + glyph.offset * scale).round();
+                    let (min, max) = (to_ndc(min), to_ndc(min + glyph.size * scale));
+                    quads.push(GlyphQuad {
+                        rect: Vec4::new(min.x, min.y, max.x, max.y),
+                        uv_rect: Vec4::new(
+                            glyph.uv_min.x,
+                            glyph.uv_min.y,
+                            glyph.uv_max.x,
+                            glyph.uv_max.y,
+                        ),
+                        color: text.color,
+                    });
+                }
+                pen.x += glyph.advance * scale;
+            }
+        }
+    }
+}
+
+/// A string to draw, positioned by the top of its first line in pixels from the top left of the
+/// viewport. The alignment decides whether `position` is the left end, center or right end of
+/// each line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text {
+    pub content: String,
+    pub position: Vec2,
+    pub color: Vec4,
+    /// Line height in pixels.
+    pub size: f32,
+    pub align: TextAlign,
+}
+
+impl Text {
+    pub fn new(content: impl Into<String>, position: Vec2) -> Self {
+        Self {
+            content: content.into(),
+            position,
+            color: Vec4::ONE,
+            size: 16.0,
+            align: TextAlign::Left,
+        }
+    }
+
+    /// A label centered on the screen position of `position`, `None` when the point is behind
+    /// the camera or outside the view.
+    pub fn world(
+        content: impl Into<String>,
+        position: Vec3,
+        view_projection: Mat4,
+        extent: Extent2D,
+    ) -> Option<Self> {
+        let clip = view_projection.mul_vec4(position.extend(1.0));
+        if clip.w <= 0.0 {
+            return None;
+        }
+        let ndc = clip.truncate() / clip.w;
+        if ndc.x.abs() > 1.0 || ndc.y.abs() > 1.0 || !(0.0..=1.0).contains(&ndc.z) {
+            return None;
+        }
+
+        let viewport = Vec2::new(extent.width as f32, extent.height as f32);
+        let pixel = (ndc.truncate() + Vec2::ONE) * 0.5 * viewport;
+        Some(Self::new(content, pixel).with_align(TextAlign::Center))
+    }
+
+    pub fn with_color(mut self, color: Vec4) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_size(mut self, size: f32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_align(mut self, align: TextAlign) -> Self {
+        self.align = align;
+        self
+    }
+}
+
+/// Texts collected over a frame, drawn together with a single draw of their atlas.
+#[derive(Debug, Clone, Default)]
+pub struct TextBatch {
+    texts: Vec<Text>,
+}
+
+impl TextBatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, text: Text) {
+        self.texts.push(text);
+    }
+
+    pub fn clear(&mut self) {
+        self.texts.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.texts.is_empty()
+    }
+
+    /// The glyphs of all texts laid out with `atlas`, in the order the texts were pushed.
+    pub fn quads(&self, atlas: &FontAtlas, extent: Extent2D) -> Vec<GlyphQuad> {
+        let mut quads = vec![];
+        for text in &self.texts {
+            atlas.layout(text, extent, &mut quads);
+        }
+        quads
+    }
+}
@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{
+    CommandBuffer, CompareOp, CullModeFlags, Extent2D, RenderPass, SampleCountFlagBits,
+};
+
+use super::{FontAtlas, GlyphQuad, TextBatch};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::instance::InstanceBuffer;
+use crate::vrt::graphics::pipeline::{BlendMode, VRTPipeline};
+
+const VERTEX_SHADER: &str = "./assets/shaders/text_vert.spirv";
+const FRAGMENT_SHADER: &str = "./assets/shaders/text_frag.spirv";
+const INITIAL_GLYPH_CAPACITY: u32 = 1024;
+
+/// Draws text batches over the frame, alpha blended and without depth testing. Every glyph is an
+/// instance of a six vertex quad, so a batch takes a single draw.
+pub struct TextRenderSystem {
+    pipeline: VRTPipeline,
+    glyphs: InstanceBuffer<GlyphQuad>,
+    device: Arc<VRTDevice>,
+}
+
+impl TextRenderSystem {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        render_pass: RenderPass,
+        msaa_samples: SampleCountFlagBits,
+        atlas: &FontAtlas,
+    ) -> Self {
+        let mut config_info = VRTPipeline::default_pipeline_config_info();
+        config_info.clear_vertex_input();
+        config_info.add_instance_input::<GlyphQuad>();
+        config_info.set_cull_mode(CullModeFlags::NONE);
+        config_info.set_depth_test(false, false, CompareOp::ALWAYS);
+        config_info.set_blend_mode(BlendMode::Alpha);
+        config_info.set_multisampling(msaa_samples, None, false);
+        config_info.set_descriptor_set_layouts(&[atlas.get_descriptor_set_layout()]);
+
+        let pipeline = VRTPipeline::new(
+            device.clone(),
+            VERTEX_SHADER,
+            FRAGMENT_SHADER,
+            &mut config_info,
+            render_pass,
+        );
+        let glyphs = InstanceBuffer::new(device.clone(), INITIAL_GLYPH_CAPACITY);
+
+        Self {
+            pipeline,
+            glyphs,
+            device,
+        }
+    }
+
+    /// Lays out `batch` for a viewport of `extent` and draws it with the atlas it was laid out
+    /// with.
+    pub fn render(
+        &mut self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        extent: Extent2D,
+        atlas: &FontAtlas,
+        batch: &TextBatch,
+    ) {
+        self.glyphs.update(frame_index, &batch.quads(atlas, extent));
+        if self.glyphs.is_empty(frame_index) {
+            return;
+        }
+
+        self.pipeline.bind(command_buffer);
+        self.pipeline
+            .bind_descriptor_sets(command_buffer, 0, &[atlas.get_descriptor_set()]);
+        self.glyphs.bind(command_buffer, frame_index);
+        unsafe {
+            self.device.get_device_ptr().cmd_draw(
+                command_buffer,
+                6,
+                self.glyphs.len(frame_index),
+                0,
+                0,
+            );
+        }
+    }
+}
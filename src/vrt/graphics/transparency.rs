@@ -0,0 +1,109 @@
+use std::cmp::Ordering;
+
+use glam::{Mat4, Vec3};
+
+/// Depth of `position` after `view_projection`, 0 at the near and 1 at the far plane.
+pub fn view_depth(view_projection: Mat4, position: Vec3) -> f32 {
+    let clip = view_projection.mul_vec4(position.extend(1.0));
+    clip.z / clip.w.abs().max(f32::EPSILON)
+}
+
+/// Transparent draws of a frame, collected while opaque geometry is recorded and then drawn
+/// farthest first so each blends over what is behind it.
+#[derive(Debug, Clone)]
+pub struct TransparentQueue<T> {
+    draws: Vec<(f32, T)>,
+}
+
+impl<T> Default for TransparentQueue<T> {
+    fn default() -> Self {
+        Self { draws: vec![] }
+    }
+}
+
+impl<T> TransparentQueue<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues `draw` at its view depth, see `view_depth`.
+    pub fn push(&mut self, depth: f32, draw: T) {
+        self.draws.push((depth, draw));
+    }
+
+    /// Stable, so draws at the same depth keep the order they were pushed in.
+    pub fn sort_back_to_front(&mut self) {
+        self.draws
+            .sort_by(|(a, _), (b, _)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.draws.iter().map(|(_, draw)| draw)
+    }
+
+    pub fn clear(&mut self) {
+        self.draws.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.draws.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.draws.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(draws: &[(f32, char)]) -> String {
+        let mut queue = TransparentQueue::new();
+        for (depth, draw) in draws {
+            queue.push(*depth, *draw);
+        }
+        queue.sort_back_to_front();
+        queue.iter().collect()
+    }
+
+    #[test]
+    fn sorts_farthest_first() {
+        assert_eq!(sorted(&[(0.2, 'a'), (0.9, 'b'), (0.5, 'c')]), "bca");
+    }
+
+    #[test]
+    fn keeps_the_push_order_at_equal_depth() {
+        assert_eq!(
+            sorted(&[(0.5, 'a'), (0.7, 'b'), (0.5, 'c'), (0.5, 'd')]),
+            "bacd"
+        );
+    }
+
+    #[test]
+    fn tolerates_nan_depths() {
+        assert_eq!(sorted(&[(f32::NAN, 'a'), (0.5, 'b')]).len(), 2);
+    }
+
+    #[test]
+    fn clear_empties_the_queue() {
+        let mut queue = TransparentQueue::new();
+        queue.push(0.5, ());
+        assert!(!queue.is_empty());
+
+        queue.clear();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn view_depth_grows_from_near_to_far() {
+        let view_projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 0.1, 10.0)
+            * Mat4::look_at_rh(Vec3::ZERO, Vec3::NEG_Z, Vec3::Y);
+        let depth = |distance: f32| view_depth(view_projection, Vec3::new(0.0, 0.0, -distance));
+
+        assert!(depth(0.1).abs() < 1e-5);
+        assert!((depth(10.0) - 1.0).abs() < 1e-5);
+        assert!(depth(1.0) < depth(2.0));
+        assert!(depth(2.0) < depth(5.0));
+    }
+}
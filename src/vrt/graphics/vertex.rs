@@ -23,6 +23,8 @@ macro_rules! offset_of {
 pub struct Vertex {
     position: Vec2,
     color: Vec3,
+    normal: Vec3,
+    uv: Vec2,
 }
 
 impl Vertex {
@@ -30,19 +32,40 @@ impl Vertex {
         Vertex::new(
             Vec2::from_array([0.0, -0.5]),
             Vec3::from_array([1.0, 0.0, 0.0]),
-        ),
+        )
+        .with_uv(Vec2::from_array([0.5, 0.0])),
         Vertex::new(
             Vec2::from_array([0.5, 0.5]),
             Vec3::from_array([0.0, 1.0, 0.0]),
-        ),
+        )
+        .with_uv(Vec2::from_array([1.0, 1.0])),
         Vertex::new(
             Vec2::from_array([-0.5, 0.5]),
             Vec3::from_array([0.0, 1.0, 1.0]),
-        ),
+        )
+        .with_uv(Vec2::from_array([0.0, 1.0])),
     ];
 
+    pub const INDICES: [u32; 3] = [0, 1, 2];
+
+    /// Vertices face the viewer (-z) unless given another normal.
     pub const fn new(position: Vec2, color: Vec3) -> Self {
-        Self { position, color }
+        Self {
+            position,
+            color,
+            normal: Vec3::from_array([0.0, 0.0, -1.0]),
+            uv: Vec2::ZERO,
+        }
+    }
+
+    pub const fn with_uv(mut self, uv: Vec2) -> Self {
+        self.uv = uv;
+        self
+    }
+
+    /// The position in model space, vertices lie in the z = 0 plane.
+    pub fn get_position(&self) -> Vec3 {
+        self.position.extend(0.0)
     }
 
     pub fn binding_description() -> VertexInputBindingDescriptionBuilder<'static> {
@@ -52,7 +75,7 @@ impl Vertex {
             .input_rate(VertexInputRate::VERTEX)
     }
 
-    pub fn attribute_descriptions() -> [VertexInputAttributeDescriptionBuilder<'static>; 2] {
+    pub fn attribute_descriptions() -> [VertexInputAttributeDescriptionBuilder<'static>; 4] {
         [
             VertexInputAttributeDescriptionBuilder::new()
                 .binding(0)
@@ -64,6 +87,16 @@ impl Vertex {
                 .location(1)
                 .format(Format::R32G32B32_SFLOAT)
                 .offset(offset_of!(Self, color)),
+            VertexInputAttributeDescriptionBuilder::new()
+                .binding(0)
+                .location(2)
+                .format(Format::R32G32B32_SFLOAT)
+                .offset(offset_of!(Self, normal)),
+            VertexInputAttributeDescriptionBuilder::new()
+                .binding(0)
+                .location(3)
+                .format(Format::R32G32_SFLOAT)
+                .offset(offset_of!(Self, uv)),
         ]
     }
 }
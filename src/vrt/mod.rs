@@ -3,3 +3,4 @@ pub mod device;
 pub mod utils;
 pub mod window;
 pub mod graphics;
+pub mod scenes;
@@ -0,0 +1,146 @@
+use std::sync::Arc;
+
+use glam::{Mat4, Vec3};
+use imgui::{TreeNodeFlags, Ui};
+use winit::event::VirtualKeyCode;
+
+use super::{create_marker_lights, Scene, SceneAssets, SceneContext};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::bounds::{BoundingSphere, Frustum};
+use crate::vrt::graphics::clustered::clustered_render_system::ClusteredRenderSystem;
+use crate::vrt::graphics::clustered::ClusteredLighting;
+use crate::vrt::graphics::debug_draw::debug_render_system::DebugRenderSystem;
+use crate::vrt::graphics::debug_draw::{DebugDraw, DebugStyle};
+use crate::vrt::graphics::frame::Frame;
+use crate::vrt::graphics::light::LightKind;
+use crate::vrt::graphics::renderer::VRTRenderer;
+use crate::vrt::utils::result::VkResult;
+
+/// The model lit by the marker lights in a single forward pass, with the lights culled into
+/// clusters by a compute pass first.
+pub struct ClusteredScene {
+    clustered_lighting: ClusteredLighting,
+    clustered_render_system: ClusteredRenderSystem,
+    debug_render_system: DebugRenderSystem,
+    device: Arc<VRTDevice>,
+}
+
+impl ClusteredScene {
+    pub fn new(device: Arc<VRTDevice>, renderer: &VRTRenderer) -> VkResult<Self> {
+        let mut clustered_lighting = ClusteredLighting::new(device.clone())?;
+        clustered_lighting.set_camera(
+            Mat4::from_translation(Vec3::new(0.0, 0.0, 0.5)),
+            Mat4::IDENTITY,
+        );
+        // The identity projection keeps view depth as it is, the near plane stays off zero for
+        // the logarithmic depth slices.
+        clustered_lighting.set_depth_range(0.01, 1.0);
+        *clustered_lighting.lights_mut() = create_marker_lights();
+        let clustered_render_system =
+            Self::create_render_system(&device, renderer, &clustered_lighting);
+        let debug_render_system = Self::create_debug_render_system(&device, renderer);
+
+        Ok(Self {
+            clustered_lighting,
+            clustered_render_system,
+            debug_render_system,
+            device,
+        })
+    }
+
+    fn create_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        clustered_lighting: &ClusteredLighting,
+    ) -> ClusteredRenderSystem {
+        ClusteredRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            clustered_lighting.get_descriptor_set_layout(),
+        )
+    }
+
+    fn create_debug_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+    ) -> DebugRenderSystem {
+        DebugRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+        )
+    }
+}
+
+impl Scene for ClusteredScene {
+    fn name(&self) -> &'static str {
+        "Clustered forward shading"
+    }
+
+    fn key(&self) -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::L)
+    }
+
+    fn rebuild(&mut self, renderer: &VRTRenderer, _assets: &SceneAssets) -> VkResult<()> {
+        self.clustered_render_system =
+            Self::create_render_system(&self.device, renderer, &self.clustered_lighting);
+        self.debug_render_system = Self::create_debug_render_system(&self.device, renderer);
+        Ok(())
+    }
+
+    /// The range of every point light the camera sees, in the color of the light.
+    fn queue_debug_shapes(&self, debug_draw: &DebugDraw, _assets: &SceneAssets) {
+        let frustum = Frustum::from_view_projection(self.clustered_lighting.get_view_projection());
+        for light in self.clustered_lighting.lights() {
+            let sphere = BoundingSphere::new(light.position, light.range);
+            if light.kind == LightKind::Point && frustum.intersects_sphere(&sphere) {
+                debug_draw.sphere(&sphere, DebugStyle::new(light.color.extend(0.5)));
+            }
+        }
+    }
+
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()> {
+        let frame_index = frame.frame_index();
+        self.clustered_lighting
+            .cull_lights(frame.command_buffer(), frame_index, frame.extent());
+        let cluster_set = self.clustered_lighting.get_descriptor_set(frame_index);
+
+        let render_pass = frame.begin_swapchain_render_pass()?;
+        self.clustered_render_system.render(
+            render_pass.command_buffer(),
+            cluster_set,
+            &context.assets.model,
+            Mat4::IDENTITY,
+            0.5,
+            0.0,
+        );
+        if let Some(debug_frame) = context.debug_frame {
+            self.debug_render_system.render(
+                render_pass.command_buffer(),
+                frame_index,
+                self.clustered_lighting.get_view_projection(),
+                debug_frame,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn build_gui(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Clustered shading", TreeNodeFlags::DEFAULT_OPEN) {
+            let mut ambient_color = self.clustered_lighting.get_ambient_color().to_array();
+            if ui.color_edit3("Ambient", &mut ambient_color) {
+                self.clustered_lighting
+                    .set_ambient_color(Vec3::from(ambient_color));
+            }
+        }
+    }
+
+    fn activate(&mut self) {
+        log::info!(
+            "clustered forward shading, {} lights",
+            self.clustered_lighting.lights().len()
+        );
+    }
+}
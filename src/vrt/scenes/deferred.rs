@@ -0,0 +1,149 @@
+use std::sync::Arc;
+
+use glam::{Mat4, Vec3};
+use imgui::{TreeNodeFlags, Ui};
+use winit::event::VirtualKeyCode;
+
+use super::{create_marker_lights, Scene, SceneAssets, SceneContext};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::deferred::geometry_render_system::GeometryRenderSystem;
+use crate::vrt::graphics::deferred::DeferredRenderer;
+use crate::vrt::graphics::frame::Frame;
+use crate::vrt::graphics::renderer::VRTRenderer;
+use crate::vrt::graphics::shadow::shadow_render_system::ShadowRenderSystem;
+use crate::vrt::utils::result::VkResult;
+
+/// The model lit by the marker lights through the G-buffer, with shadow maps for the lights
+/// that cast them.
+pub struct DeferredScene {
+    deferred_renderer: DeferredRenderer,
+    geometry_render_system: GeometryRenderSystem,
+    shadow_render_system: ShadowRenderSystem,
+}
+
+impl DeferredScene {
+    pub fn new(device: Arc<VRTDevice>, renderer: &VRTRenderer) -> VkResult<Self> {
+        let mut deferred_renderer = DeferredRenderer::new(
+            device.clone(),
+            renderer.get_extent(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+        )?;
+        deferred_renderer.set_camera(
+            Mat4::from_translation(Vec3::new(0.0, 0.0, 0.5)),
+            Mat4::IDENTITY,
+        );
+        // The identity projection keeps view depth as it is, the near plane stays off zero so the
+        // logarithmic cascade splits remain finite.
+        deferred_renderer.set_camera_depth_range(0.01, 1.0);
+        *deferred_renderer.lights_mut() = create_marker_lights();
+        let geometry_render_system =
+            GeometryRenderSystem::new(device.clone(), deferred_renderer.get_geometry_render_pass());
+        let shadow_render_system =
+            ShadowRenderSystem::new(device, deferred_renderer.shadow_maps().get_render_pass());
+
+        Ok(Self {
+            deferred_renderer,
+            geometry_render_system,
+            shadow_render_system,
+        })
+    }
+
+    fn toggle_shadows(&mut self) -> VkResult<()> {
+        let mut settings = self.deferred_renderer.get_shadow_settings();
+        settings.enabled = !settings.enabled;
+        self.deferred_renderer.set_shadow_settings(settings)?;
+        log::info!("shadows {}", settings.enabled);
+        Ok(())
+    }
+}
+
+impl Scene for DeferredScene {
+    fn name(&self) -> &'static str {
+        "Deferred shading"
+    }
+
+    fn key(&self) -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::G)
+    }
+
+    fn rebuild(&mut self, renderer: &VRTRenderer, _assets: &SceneAssets) -> VkResult<()> {
+        self.deferred_renderer.set_output_render_pass(
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+        )
+    }
+
+    fn resize(&mut self, renderer: &VRTRenderer) -> VkResult<()> {
+        let extent = renderer.get_extent();
+        if self.deferred_renderer.get_extent() != extent {
+            renderer.wait_for_frames_in_flight()?;
+            self.deferred_renderer.resize(extent)?;
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()> {
+        let model = &context.assets.model;
+        self.deferred_renderer.update_shadows(frame.frame_index());
+        let shadow_maps = self.deferred_renderer.shadow_maps();
+        for layer in 0..shadow_maps.layer_count() {
+            let shadow_pass = shadow_maps.begin_layer(frame.command_buffer(), layer);
+            self.shadow_render_system.render(
+                shadow_pass.command_buffer(),
+                model,
+                shadow_maps.layer_matrix(layer),
+            );
+        }
+
+        let geometry_pass = self
+            .deferred_renderer
+            .begin_geometry(frame.command_buffer());
+        self.geometry_render_system.render(
+            geometry_pass.command_buffer(),
+            model,
+            self.deferred_renderer.get_view_projection(),
+            0.5,
+            0.0,
+        );
+        drop(geometry_pass);
+
+        self.deferred_renderer
+            .render_lighting(frame.command_buffer(), frame.frame_index());
+
+        let render_pass = frame.begin_swapchain_render_pass()?;
+        self.deferred_renderer
+            .draw_composition(render_pass.command_buffer());
+
+        Ok(())
+    }
+
+    fn process_key(&mut self, key: VirtualKeyCode, _renderer: &VRTRenderer) -> VkResult<bool> {
+        match key {
+            VirtualKeyCode::H => self.toggle_shadows()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn build_gui(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Deferred shading", TreeNodeFlags::DEFAULT_OPEN) {
+            let mut ambient_color = self.deferred_renderer.get_ambient_color().to_array();
+            if ui.color_edit3("Ambient", &mut ambient_color) {
+                self.deferred_renderer
+                    .set_ambient_color(Vec3::from(ambient_color));
+            }
+            let mut exposure = self.deferred_renderer.get_exposure();
+            if ui.slider("Exposure (EV)", -6.0, 6.0, &mut exposure) {
+                self.deferred_renderer.set_exposure(exposure);
+            }
+        }
+    }
+
+    fn activate(&mut self) {
+        log::info!(
+            "deferred shading, {} lights",
+            self.deferred_renderer.lights().len()
+        );
+    }
+}
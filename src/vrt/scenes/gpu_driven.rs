@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use glam::{Mat4, Vec3};
+use winit::event::VirtualKeyCode;
+
+use super::{Scene, SceneAssets, SceneContext};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::frame::Frame;
+use crate::vrt::graphics::indirect::indirect_render_system::IndirectRenderSystem;
+use crate::vrt::graphics::indirect::{IndirectMesh, IndirectObject, IndirectScene};
+use crate::vrt::graphics::model::Model;
+use crate::vrt::graphics::renderer::VRTRenderer;
+use crate::vrt::utils::result::VkResult;
+
+/// Objects along each axis of the lattice.
+const LATTICE_SIZE: u32 = 34;
+
+/// A lattice of small copies of the model around the origin, culled and drawn by the GPU
+/// with indirect draws. The camera orbits inside the lattice, so most objects are culled at
+/// any time.
+pub struct GpuDrivenScene {
+    scene: IndirectScene,
+    render_system: IndirectRenderSystem,
+    device: Arc<VRTDevice>,
+}
+
+impl GpuDrivenScene {
+    pub fn new(device: Arc<VRTDevice>, renderer: &VRTRenderer, model: &Model) -> VkResult<Self> {
+        let size = LATTICE_SIZE;
+        let mut scene = IndirectScene::new(device.clone(), size * size * size)?;
+        let mesh = scene
+            .add_mesh(IndirectMesh::from_model(model))
+            .expect("Cannot add lattice mesh");
+
+        let spacing = 40.0 / size as f32;
+        let offset = (size - 1) as f32 * 0.5;
+        *scene.objects_mut() = (0..size * size * size)
+            .map(|index| {
+                let cell = Vec3::new(
+                    (index % size) as f32,
+                    (index / size % size) as f32,
+                    (index / (size * size)) as f32,
+                );
+                let transform = Mat4::from_translation((cell - offset) * spacing)
+                    * Mat4::from_rotation_y(index as f32 * 0.7)
+                    * Mat4::from_scale(Vec3::splat(spacing * 0.5));
+                IndirectObject::new(mesh, transform).with_color((cell / size as f32).extend(1.0))
+            })
+            .collect();
+        let render_system = Self::create_render_system(&device, renderer, &scene);
+
+        Ok(Self {
+            scene,
+            render_system,
+            device,
+        })
+    }
+
+    fn create_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        scene: &IndirectScene,
+    ) -> IndirectRenderSystem {
+        IndirectRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            scene,
+        )
+    }
+}
+
+impl Scene for GpuDrivenScene {
+    fn name(&self) -> &'static str {
+        "GPU-driven drawing"
+    }
+
+    fn key(&self) -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::D)
+    }
+
+    fn rebuild(&mut self, renderer: &VRTRenderer, _assets: &SceneAssets) -> VkResult<()> {
+        self.render_system = Self::create_render_system(&self.device, renderer, &self.scene);
+        Ok(())
+    }
+
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()> {
+        let frame_index = frame.frame_index();
+        let extent = frame.extent();
+        let angle = context.elapsed * 0.2;
+        let aspect = extent.width as f32 / extent.height.max(1) as f32;
+        let forward = Vec3::new(angle.sin(), 0.0, angle.cos());
+        let view = Mat4::look_at_rh(Vec3::ZERO, forward, Vec3::NEG_Y);
+        self.scene.set_camera(
+            Mat4::perspective_rh(std::f32::consts::FRAC_PI_3, aspect, 0.1, 100.0) * view,
+        );
+        // A headlight, so the faces the camera sees are the lit ones.
+        self.scene.set_light(forward, 0.2);
+        self.scene.cull(frame.command_buffer(), frame_index);
+
+        let render_pass = frame.begin_swapchain_render_pass()?;
+        self.render_system.render(
+            render_pass.command_buffer(),
+            frame_index,
+            &self.scene,
+            &context.assets.model,
+        );
+
+        Ok(())
+    }
+
+    fn activate(&mut self) {
+        log::info!(
+            "GPU-driven drawing, {} of {} objects, compacted draws {}",
+            self.scene.objects().len(),
+            self.scene.get_max_objects(),
+            self.scene.is_compacting()
+        );
+    }
+}
@@ -0,0 +1,634 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use erupt::vk1_0::{CommandBuffer, Extent2D, RenderPass, SampleCountFlagBits};
+use glam::{Mat4, Vec2, Vec3, Vec4};
+use imgui::{TreeNodeFlags, Ui};
+use winit::event::VirtualKeyCode;
+
+use super::{Scene, SceneAssets, SceneContext};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::bounds::{CullingStats, Frustum};
+use crate::vrt::graphics::debug_draw::debug_render_system::DebugRenderSystem;
+use crate::vrt::graphics::debug_draw::{DebugDraw, DebugStyle};
+use crate::vrt::graphics::frame::Frame;
+use crate::vrt::graphics::ibl::skybox_render_system::SkyboxRenderSystem;
+use crate::vrt::graphics::instance::{InstanceBatch, InstanceBuffer, InstanceTransform};
+use crate::vrt::graphics::lod::{self, LodSelector};
+use crate::vrt::graphics::material::{MaterialHandle, MaterialRegistry};
+use crate::vrt::graphics::model::Model;
+use crate::vrt::graphics::pbr_render_system::{PbrRenderSystem, PbrScene};
+use crate::vrt::graphics::post::color_grading::ColorGradingEffect;
+use crate::vrt::graphics::post::exposure::ExposureEffect;
+use crate::vrt::graphics::post::fxaa::FxaaEffect;
+use crate::vrt::graphics::post::gamma::GammaEffect;
+use crate::vrt::graphics::post::tonemap::{TonemapEffect, TonemapOperator};
+use crate::vrt::graphics::post::vignette::VignetteEffect;
+use crate::vrt::graphics::post::PostProcessChain;
+use crate::vrt::graphics::renderer::VRTRenderer;
+use crate::vrt::graphics::text::Text;
+use crate::vrt::graphics::transparency::{self, TransparentQueue};
+use crate::vrt::graphics::vertex::Vertex;
+use crate::vrt::utils::result::VkResult;
+
+const INSTANCE_GRID_SIZE: u32 = 100;
+
+/// Render systems drawing the main scene into one render pass.
+struct SceneRenderSystems {
+    pbr: PbrRenderSystem,
+    skybox: SkyboxRenderSystem,
+    debug: DebugRenderSystem,
+}
+
+impl SceneRenderSystems {
+    fn new(
+        device: &Arc<VRTDevice>,
+        render_pass: RenderPass,
+        samples: SampleCountFlagBits,
+        assets: &SceneAssets,
+    ) -> VkResult<Self> {
+        Ok(Self {
+            pbr: PbrRenderSystem::new(
+                device.clone(),
+                render_pass,
+                samples,
+                &assets.materials,
+                &assets.environment,
+            )?,
+            skybox: SkyboxRenderSystem::new(
+                device.clone(),
+                render_pass,
+                samples,
+                assets.environment.get_descriptor_set_layout(),
+            ),
+            debug: DebugRenderSystem::new(device.clone(), render_pass, samples),
+        })
+    }
+}
+
+/// The camera and the visible instances of a frame, see `SceneContent::prepare`.
+struct SceneView {
+    scene: PbrScene,
+    batches: Vec<InstanceBatch>,
+    culling: CullingStats,
+}
+
+/// What the main scene draws, whichever render pass it goes into.
+struct SceneContent {
+    lod_model: Model,
+    lod_selector: LodSelector,
+    instances: InstanceBuffer<InstanceTransform>,
+    instancing_enabled: bool,
+    transparent_queue: TransparentQueue<(Mat4, MaterialHandle)>,
+    transparency_enabled: bool,
+    skybox_enabled: bool,
+}
+
+/// The model, or a field of its LOD copies, lit by the environment map. The scene is drawn
+/// straight into the swapchain, or into the HDR target of the post-processing chain while the
+/// chain is enabled.
+pub struct MainScene {
+    content: SceneContent,
+    forward_render_systems: SceneRenderSystems,
+    post_render_systems: SceneRenderSystems,
+    post_chain: PostProcessChain,
+    device: Arc<VRTDevice>,
+}
+
+impl MainScene {
+    pub fn new(
+        device: Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        assets: &SceneAssets,
+        color_lut: Option<&Path>,
+    ) -> VkResult<Self> {
+        let mut post_chain = PostProcessChain::new(
+            device.clone(),
+            renderer.get_extent(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            renderer.get_output_transfer_function(),
+            color_lut,
+        )?;
+        let gamma = GammaEffect::new(&post_chain.context())?;
+        post_chain.push_effect(Box::new(gamma));
+        let forward_render_systems = SceneRenderSystems::new(
+            &device,
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            assets,
+        )?;
+        let post_render_systems = SceneRenderSystems::new(
+            &device,
+            post_chain.get_scene_render_pass(),
+            SampleCountFlagBits::_1,
+            assets,
+        )?;
+
+        let mut lod_model = Self::create_lod_model(device.clone())?;
+        lod_model.set_material(assets.model.get_material());
+        let lod_selector = LodSelector::new(vec![0.04, 0.015, 0.006], 0.15);
+        log::info!(
+            "LOD model levels {}, selector levels {}",
+            lod_model.get_lod_count(),
+            lod_selector.get_level_count()
+        );
+        let content = SceneContent {
+            lod_model,
+            lod_selector,
+            instances: InstanceBuffer::new(device.clone(), INSTANCE_GRID_SIZE * INSTANCE_GRID_SIZE),
+            instancing_enabled: false,
+            transparent_queue: TransparentQueue::new(),
+            transparency_enabled: false,
+            skybox_enabled: true,
+        };
+
+        Ok(Self {
+            content,
+            forward_render_systems,
+            post_render_systems,
+            post_chain,
+            device,
+        })
+    }
+
+    /// A finely tessellated disc with simplified levels of detail.
+    fn create_lod_model(device: Arc<VRTDevice>) -> VkResult<Model> {
+        const RINGS: u32 = 8;
+        const SEGMENTS: u32 = 48;
+
+        let mut vertices = vec![Vertex::new(Vec2::ZERO, Vec3::ONE).with_uv(Vec2::splat(0.5))];
+        for ring in 1..=RINGS {
+            let radius = 0.5 * ring as f32 / RINGS as f32;
+            for segment in 0..SEGMENTS {
+                let angle = std::f32::consts::TAU * segment as f32 / SEGMENTS as f32;
+                let position = Vec2::new(angle.cos(), angle.sin()) * radius;
+                let shade = 1.0 - 0.6 * ring as f32 / RINGS as f32;
+                vertices.push(
+                    Vertex::new(position, Vec3::new(shade, shade, 1.0))
+                        .with_uv(position + Vec2::splat(0.5)),
+                );
+            }
+        }
+
+        let vertex = |ring: u32, segment: u32| 1 + (ring - 1) * SEGMENTS + segment % SEGMENTS;
+        let mut indices = vec![];
+        for segment in 0..SEGMENTS {
+            indices.extend([0, vertex(1, segment), vertex(1, segment + 1)]);
+        }
+        for ring in 1..RINGS {
+            for segment in 0..SEGMENTS {
+                let (inner, outer) = (vertex(ring, segment), vertex(ring + 1, segment));
+                let (inner_next, outer_next) =
+                    (vertex(ring, segment + 1), vertex(ring + 1, segment + 1));
+                indices.extend([inner, outer, outer_next, inner, outer_next, inner_next]);
+            }
+        }
+
+        let lods = lod::generate_lods(&vertices, &indices, 4);
+        log::info!(
+            "LOD model triangles {:?}",
+            lods.iter().map(|lod| lod.len() / 3).collect::<Vec<_>>()
+        );
+        Model::from_mesh(device, &vertices, &lods)
+    }
+
+    fn toggle_post_effect(&mut self, index: usize) {
+        if let Some(effect) = self.post_chain.effects_mut().get_mut(index) {
+            effect.set_enabled(!effect.is_enabled());
+            log::info!("{} {}", effect.name(), effect.is_enabled());
+        }
+    }
+
+    fn cycle_tonemap_operator(&mut self) {
+        if let Some(tonemap) = self.post_chain.effect_mut::<TonemapEffect>() {
+            let operator = match tonemap.get_operator() {
+                TonemapOperator::Reinhard => TonemapOperator::Aces,
+                TonemapOperator::Aces => TonemapOperator::Reinhard,
+            };
+            tonemap.set_operator(operator);
+            log::info!("tonemap operator {:?}", operator);
+        }
+    }
+
+    fn adjust_exposure(&mut self, stops: f32) {
+        if let Some(exposure) = self.post_chain.effect_mut::<ExposureEffect>() {
+            exposure.set_exposure(exposure.get_exposure() + stops);
+            log::info!("exposure {:+.1} EV", exposure.get_exposure());
+        }
+    }
+}
+
+impl SceneContent {
+    /// Seen through a perspective camera above the instance field while instancing is
+    /// enabled. Culls the model, or every instance of the field, and writes the visible
+    /// instances grouped into one batch per level of detail.
+    fn prepare(
+        &mut self,
+        model: &Model,
+        frame_index: usize,
+        extent: Extent2D,
+        elapsed: f32,
+    ) -> SceneView {
+        let mut culling = CullingStats::default();
+        if !self.instancing_enabled {
+            let scene = PbrScene::default();
+            let frustum = Frustum::from_view_projection(scene.view_projection);
+            culling.record(model.is_visible(&frustum, Mat4::IDENTITY));
+            return SceneView {
+                scene,
+                batches: vec![],
+                culling,
+            };
+        }
+
+        let eye = Vec3::new(0.0, -1.0, -1.0);
+        let view = Mat4::look_at_rh(eye, Vec3::new(0.0, 0.5, 12.0), Vec3::NEG_Y);
+        let aspect = extent.width as f32 / extent.height.max(1) as f32;
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_3, aspect, 0.1, 100.0);
+        let scene = PbrScene {
+            view_projection: projection * view,
+            camera_position: eye,
+            ..PbrScene::default()
+        };
+        let frustum = Frustum::from_view_projection(scene.view_projection);
+
+        let mut levels = vec![vec![]; self.lod_selector.get_level_count()];
+        for (index, instance) in Self::instance_field(elapsed).enumerate() {
+            let visible = self.lod_model.is_visible(&frustum, instance.model);
+            culling.record(visible);
+            if visible {
+                let sphere = self.lod_model.world_bounding_sphere(instance.model);
+                let lod = self
+                    .lod_selector
+                    .select(index, lod::projected_size(&sphere, view, projection));
+                levels[lod].push(instance);
+            }
+        }
+
+        let mut batches = vec![];
+        let mut first = 0;
+        for (lod, level) in levels
+            .iter()
+            .enumerate()
+            .filter(|(_, level)| !level.is_empty())
+        {
+            let end = first + level.len() as u32;
+            batches.push(InstanceBatch::new(first..end).with_lod(lod));
+            first = end;
+        }
+        let capacity = self.instances.get_capacity(frame_index);
+        self.instances.update(frame_index, &levels.concat());
+        if self.instances.get_capacity(frame_index) != capacity {
+            log::info!(
+                "instance buffer of frame {} grown to {}",
+                frame_index,
+                self.instances.get_capacity(frame_index)
+            );
+        }
+        SceneView {
+            scene,
+            batches,
+            culling,
+        }
+    }
+
+    /// Spinning copies of the LOD model on a plane stretching away from the camera, tinted by
+    /// their position. The field sways sideways, so its outer columns leave the screen.
+    fn instance_field(elapsed: f32) -> impl Iterator<Item = InstanceTransform> {
+        let size = INSTANCE_GRID_SIZE;
+        let sway = (elapsed * 0.5).sin() * 2.0;
+        (0..size * size).map(move |index| {
+            let (x, z) = ((index % size) as f32, (index / size) as f32);
+            let position = Vec3::new((x - size as f32 * 0.5) * 0.2 + sway, 0.5, 1.0 + z * 0.4);
+            let angle = elapsed * 2.0 + (x + z) * 0.1;
+            let model = Mat4::from_translation(position)
+                * Mat4::from_rotation_z(angle)
+                * Mat4::from_scale(Vec3::splat(0.15));
+            let color = Vec4::new(x / size as f32, z / size as f32, 1.0 - x / size as f32, 1.0);
+            InstanceTransform::new(model, color)
+        })
+    }
+
+    /// One pane of the LOD model per transparent material, drifting through each other in depth
+    /// so the back to front order keeps changing.
+    fn queue_transparent(&mut self, materials: &MaterialRegistry, scene: &PbrScene, elapsed: f32) {
+        let queue = &mut self.transparent_queue;
+        queue.clear();
+        let panes = materials
+            .handles()
+            .filter(|&material| materials.resolve(material).blend_mode.is_transparent())
+            .collect::<Vec<_>>();
+        let offset = (panes.len() as f32 - 1.0) * 0.5;
+        for (index, material) in panes.into_iter().enumerate() {
+            let phase = elapsed * 0.7 + index as f32 * 1.9;
+            let position = Vec3::new(
+                (index as f32 - offset) * 0.25,
+                phase.cos() * 0.15,
+                0.5 + phase.sin() * 0.4,
+            );
+            let transform = Mat4::from_translation(position) * Mat4::from_scale(Vec3::splat(0.8));
+            queue.push(
+                transparency::view_depth(scene.view_projection, position),
+                (transform, material),
+            );
+        }
+        queue.sort_back_to_front();
+    }
+
+    /// A fixed camera for the skybox, the scene itself is drawn without a view transform.
+    fn skybox_view_projection(extent: Extent2D) -> Mat4 {
+        let aspect = extent.width.max(1) as f32 / extent.height.max(1) as f32;
+        Mat4::perspective_rh(std::f32::consts::FRAC_PI_3, aspect, 0.1, 10.0)
+            * Mat4::look_at_rh(Vec3::ZERO, Vec3::Z, Vec3::NEG_Y)
+    }
+
+    /// Draws the scene with `render_systems`, inside a render pass they were built for.
+    fn record_scene(
+        &mut self,
+        command_buffer: CommandBuffer,
+        frame_index: usize,
+        extent: Extent2D,
+        render_systems: &mut SceneRenderSystems,
+        view: &SceneView,
+        context: &mut SceneContext,
+    ) {
+        let assets = context.assets;
+        render_systems
+            .pbr
+            .update_scene(frame_index, &view.scene, &assets.environment);
+        for batch in &view.batches {
+            render_systems.pbr.render_instanced(
+                command_buffer,
+                frame_index,
+                &self.lod_model,
+                &assets.materials,
+                &assets.environment,
+                &self.instances,
+                batch,
+                Mat4::IDENTITY,
+            );
+        }
+        if !self.instancing_enabled && view.culling.visible > 0 {
+            render_systems.pbr.render(
+                command_buffer,
+                frame_index,
+                &assets.model,
+                &assets.materials,
+                &assets.environment,
+                Mat4::IDENTITY,
+            );
+        }
+        if self.skybox_enabled {
+            render_systems.skybox.render(
+                command_buffer,
+                &assets.environment,
+                Self::skybox_view_projection(extent),
+                0.0,
+                1.0,
+            );
+        }
+        if self.transparency_enabled {
+            self.queue_transparent(&assets.materials, &view.scene, context.elapsed);
+            for &(transform, material) in self.transparent_queue.iter() {
+                render_systems.pbr.render_material(
+                    command_buffer,
+                    frame_index,
+                    &self.lod_model,
+                    material,
+                    &assets.materials,
+                    &assets.environment,
+                    transform,
+                );
+                let name = assets.materials.get_name(material).unwrap_or_default();
+                let position = transform.w_axis.truncate();
+                if let Some(label) = Text::world(name, position, view.scene.view_projection, extent)
+                {
+                    context.text_batch.push(label);
+                }
+            }
+        }
+        if let Some(debug_frame) = context.debug_frame {
+            render_systems.debug.render(
+                command_buffer,
+                frame_index,
+                view.scene.view_projection,
+                debug_frame,
+            );
+        }
+    }
+}
+
+impl Scene for MainScene {
+    fn name(&self) -> &'static str {
+        "Main scene"
+    }
+
+    fn key(&self) -> Option<VirtualKeyCode> {
+        None
+    }
+
+    fn rebuild(&mut self, renderer: &VRTRenderer, assets: &SceneAssets) -> VkResult<()> {
+        self.forward_render_systems = SceneRenderSystems::new(
+            &self.device,
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            assets,
+        )?;
+        self.post_chain.set_output_render_pass(
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+        )
+    }
+
+    fn resize(&mut self, renderer: &VRTRenderer) -> VkResult<()> {
+        let extent = renderer.get_extent();
+        if self.post_chain.is_enabled() && self.post_chain.get_extent() != extent {
+            renderer.wait_for_frames_in_flight()?;
+            self.post_chain.resize(extent)?;
+        }
+        Ok(())
+    }
+
+    /// Shapes describing the scene: world axes, the bounds of the model, the light direction
+    /// and a grid on the plane of the instance field.
+    fn queue_debug_shapes(&self, debug_draw: &DebugDraw, assets: &SceneAssets) {
+        debug_draw.axes(Mat4::IDENTITY, 0.25, DebugStyle::default());
+        debug_draw.aabb(
+            &assets.model.get_aabb(),
+            DebugStyle::new(Vec4::new(1.0, 1.0, 0.0, 1.0)),
+        );
+        debug_draw.sphere(
+            &assets.model.world_bounding_sphere(Mat4::IDENTITY),
+            DebugStyle::new(Vec4::new(0.0, 1.0, 1.0, 0.6)),
+        );
+        let light_direction = PbrScene::default().light_direction;
+        debug_draw.arrow(
+            -light_direction * 0.4,
+            Vec3::ZERO,
+            DebugStyle::new(Vec4::new(1.0, 0.8, 0.3, 1.0)).with_depth_test(false),
+        );
+        debug_draw.grid(
+            Vec3::new(0.0, 0.5, 10.0),
+            Vec3::X,
+            Vec3::Z,
+            20,
+            1.0,
+            DebugStyle::new(Vec4::new(0.5, 0.5, 0.5, 0.5)),
+        );
+        if self.content.instancing_enabled {
+            // What the model camera sees, drawn while looking over the instance field instead.
+            let camera = PbrScene::default();
+            let style = DebugStyle::new(Vec4::new(1.0, 0.5, 0.2, 1.0));
+            debug_draw.frustum(camera.view_projection, style);
+            debug_draw.line(camera.camera_position, Vec3::ZERO, style);
+        }
+    }
+
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()> {
+        let frame_index = frame.frame_index();
+        let extent = frame.extent();
+        let view =
+            self.content
+                .prepare(&context.assets.model, frame_index, extent, context.elapsed);
+        frame.record_culling(view.culling);
+
+        if self.post_chain.is_enabled() {
+            let clear_values = frame.renderer().get_clear_values();
+            let scene_pass = self
+                .post_chain
+                .begin_scene(frame.command_buffer(), &clear_values);
+            self.content.record_scene(
+                scene_pass.command_buffer(),
+                frame_index,
+                extent,
+                &mut self.post_render_systems,
+                &view,
+                context,
+            );
+            drop(scene_pass);
+
+            self.post_chain.apply(frame.command_buffer());
+
+            let render_pass = frame.begin_swapchain_render_pass()?;
+            self.post_chain.draw_output(render_pass.command_buffer());
+        } else {
+            let render_pass = frame.begin_swapchain_render_pass()?;
+            self.content.record_scene(
+                render_pass.command_buffer(),
+                frame_index,
+                extent,
+                &mut self.forward_render_systems,
+                &view,
+                context,
+            );
+        }
+
+        Ok(())
+    }
+
+    fn process_key(&mut self, key: VirtualKeyCode, _renderer: &VRTRenderer) -> VkResult<bool> {
+        match key {
+            VirtualKeyCode::K => {
+                self.content.skybox_enabled = !self.content.skybox_enabled;
+                log::info!("skybox {}", self.content.skybox_enabled);
+            }
+            VirtualKeyCode::I => {
+                self.content.instancing_enabled = !self.content.instancing_enabled;
+                log::info!("instanced grid {}", self.content.instancing_enabled);
+            }
+            VirtualKeyCode::O => {
+                self.content.transparency_enabled = !self.content.transparency_enabled;
+                log::info!("transparent panes {}", self.content.transparency_enabled);
+            }
+            VirtualKeyCode::P => {
+                let enabled = !self.post_chain.is_enabled();
+                self.post_chain.set_enabled(enabled);
+                log::info!("post-processing {}", enabled);
+            }
+            key if (VirtualKeyCode::Key1..=VirtualKeyCode::Key9).contains(&key) => {
+                self.toggle_post_effect(key as usize - VirtualKeyCode::Key1 as usize);
+            }
+            VirtualKeyCode::T => self.cycle_tonemap_operator(),
+            VirtualKeyCode::Equals => self.adjust_exposure(0.5),
+            VirtualKeyCode::Minus => self.adjust_exposure(-0.5),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn build_gui(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Scene", TreeNodeFlags::DEFAULT_OPEN) {
+            ui.checkbox("Skybox", &mut self.content.skybox_enabled);
+            if ui.checkbox("Instanced grid", &mut self.content.instancing_enabled) {
+                // The grid and the single model are different objects to the selector.
+                self.content.lod_selector.reset();
+            }
+            let mut hysteresis = self.content.lod_selector.get_hysteresis();
+            if ui.slider("LOD hysteresis", 0.0, 0.9, &mut hysteresis) {
+                self.content.lod_selector.set_hysteresis(hysteresis);
+            }
+            ui.checkbox("Transparent panes", &mut self.content.transparency_enabled);
+            let queue = &self.content.transparent_queue;
+            if self.content.transparency_enabled && !queue.is_empty() {
+                ui.text(format!("{} panes sorted back to front", queue.len()));
+            }
+        }
+
+        if ui.collapsing_header("Post-processing", TreeNodeFlags::DEFAULT_OPEN) {
+            let mut enabled = self.post_chain.is_enabled();
+            if ui.checkbox("Enabled", &mut enabled) {
+                self.post_chain.set_enabled(enabled);
+            }
+            for effect in self.post_chain.effects_mut() {
+                let mut enabled = effect.is_enabled();
+                if ui.checkbox(effect.name(), &mut enabled) {
+                    effect.set_enabled(enabled);
+                }
+            }
+            if let Some(exposure) = self.post_chain.effect_mut::<ExposureEffect>() {
+                let mut stops = exposure.get_exposure();
+                if ui.slider("Exposure (EV)", -6.0, 6.0, &mut stops) {
+                    exposure.set_exposure(stops);
+                }
+            }
+            if let Some(tonemap) = self.post_chain.effect_mut::<TonemapEffect>() {
+                let mut white_point = tonemap.get_white_point();
+                if ui.slider("White point", 1.0, 16.0, &mut white_point) {
+                    tonemap.set_white_point(white_point);
+                }
+            }
+            if let Some(color_grading) = self.post_chain.effect_mut::<ColorGradingEffect>() {
+                let mut intensity = color_grading.get_intensity();
+                if ui.slider("Grading intensity", 0.0, 1.0, &mut intensity) {
+                    color_grading.set_intensity(intensity);
+                }
+            }
+            if let Some(vignette) = self.post_chain.effect_mut::<VignetteEffect>() {
+                let (mut intensity, mut radius, mut softness) = vignette.get_params();
+                let mut changed = ui.slider("Vignette intensity", 0.0, 1.0, &mut intensity);
+                changed |= ui.slider("Vignette radius", 0.2, 1.5, &mut radius);
+                changed |= ui.slider("Vignette softness", 0.05, 1.0, &mut softness);
+                if changed {
+                    vignette.set_params(intensity, radius, softness);
+                }
+            }
+            if let Some(fxaa) = self.post_chain.effect_mut::<FxaaEffect>() {
+                let mut span_max = fxaa.get_span_max();
+                if ui.slider("FXAA span", 2.0, 16.0, &mut span_max) {
+                    fxaa.set_span_max(span_max);
+                }
+            }
+            if let Some(gamma) = self.post_chain.effect_mut::<GammaEffect>() {
+                let mut value = gamma.get_gamma();
+                if ui.slider("Gamma", 1.0, 3.0, &mut value) {
+                    gamma.set_gamma(value);
+                }
+            }
+        }
+    }
+
+    fn activate(&mut self) {
+        log::info!("main scene");
+    }
+}
@@ -0,0 +1,110 @@
+pub mod clustered;
+pub mod deferred;
+pub mod gpu_driven;
+pub mod main_scene;
+pub mod particles;
+pub mod sprites;
+
+use glam::Vec3;
+use imgui::Ui;
+use winit::event::VirtualKeyCode;
+
+use super::graphics::debug_draw::{DebugDraw, DebugFrame};
+use super::graphics::frame::Frame;
+use super::graphics::ibl::EnvironmentMap;
+use super::graphics::light::Light;
+use super::graphics::material::MaterialRegistry;
+use super::graphics::model::Model;
+use super::graphics::renderer::VRTRenderer;
+use super::graphics::text::TextBatch;
+use super::utils::result::VkResult;
+
+/// Assets shared by every scene and the inspector windows.
+pub struct SceneAssets {
+    pub model: Model,
+    pub materials: MaterialRegistry,
+    pub environment: EnvironmentMap,
+}
+
+/// What a scene records a frame with.
+pub struct SceneContext<'a> {
+    pub assets: &'a SceneAssets,
+    /// Seconds since the app started.
+    pub elapsed: f32,
+    /// Shapes queued for this frame, `None` while debug drawing is disabled.
+    pub debug_frame: Option<&'a DebugFrame>,
+    /// Text drawn over the frame, e.g. labels of world positions.
+    pub text_batch: &'a mut TextBatch,
+}
+
+/// What the app draws. The main scene shows unless a feature demo was toggled on with its key,
+/// every scene owns the render systems it draws with.
+pub trait Scene {
+    fn name(&self) -> &'static str;
+
+    /// Toggles the scene, `None` for the main scene.
+    fn key(&self) -> Option<VirtualKeyCode>;
+
+    /// Recreates the pipelines built against the swapchain render passes of `renderer`, after
+    /// they changed. No frame is in flight at that point.
+    fn rebuild(&mut self, renderer: &VRTRenderer, assets: &SceneAssets) -> VkResult<()>;
+
+    /// Called before the frame begins, so offscreen targets can follow the swapchain extent.
+    fn resize(&mut self, _renderer: &VRTRenderer) -> VkResult<()> {
+        Ok(())
+    }
+
+    /// Called before the frame takes the queued shapes.
+    fn queue_debug_shapes(&self, _debug_draw: &DebugDraw, _assets: &SceneAssets) {}
+
+    /// Records the frame up to and including the main swapchain render pass.
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()>;
+
+    /// A key released while the scene shows, returns whether the scene used it.
+    fn process_key(&mut self, _key: VirtualKeyCode, _renderer: &VRTRenderer) -> VkResult<bool> {
+        Ok(false)
+    }
+
+    /// Settings of the scene in the GUI panel.
+    fn build_gui(&mut self, _ui: &Ui) {}
+
+    /// Called when the scene shows again.
+    fn activate(&mut self) {}
+}
+
+/// A grid of small colored lights standing in for sensors and markers, plus a dim sun and
+/// a spot light.
+fn create_marker_lights() -> Vec<Light> {
+    const GRID_SIZE: usize = 16;
+
+    let mut lights = vec![
+        Light::directional(Vec3::new(0.3, 0.5, 1.0), Vec3::ONE, 0.5).with_shadows(),
+        Light::spot(
+            Vec3::new(0.0, 0.0, -0.4),
+            Vec3::new(0.0, 0.0, 1.0),
+            Vec3::new(1.0, 0.9, 0.7),
+            4.0,
+            1.0,
+            0.3,
+            0.5,
+        )
+        .with_shadows(),
+    ];
+    for y in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            let position = Vec3::new(
+                (x as f32 + 0.5) / GRID_SIZE as f32 * 2.0 - 1.0,
+                (y as f32 + 0.5) / GRID_SIZE as f32 * 2.0 - 1.0,
+                -0.05,
+            );
+            let hue = (x + y * GRID_SIZE) as f32 / (GRID_SIZE * GRID_SIZE) as f32;
+            let color = Vec3::new(
+                (hue * std::f32::consts::TAU).cos() * 0.5 + 0.5,
+                ((hue + 1.0 / 3.0) * std::f32::consts::TAU).cos() * 0.5 + 0.5,
+                ((hue + 2.0 / 3.0) * std::f32::consts::TAU).cos() * 0.5 + 0.5,
+            );
+            lights.push(Light::point(position, color, 2.0, 0.2));
+        }
+    }
+    lights
+}
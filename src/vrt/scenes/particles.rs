@@ -0,0 +1,243 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use glam::{Mat4, Vec3, Vec4};
+use imgui::{TreeNodeFlags, Ui};
+use winit::event::VirtualKeyCode;
+
+use super::{Scene, SceneAssets, SceneContext};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::bounds::BoundingSphere;
+use crate::vrt::graphics::debug_draw::debug_render_system::DebugRenderSystem;
+use crate::vrt::graphics::debug_draw::{DebugDraw, DebugStyle};
+use crate::vrt::graphics::frame::Frame;
+use crate::vrt::graphics::particles::particle_render_system::ParticleRenderSystem;
+use crate::vrt::graphics::particles::{
+    ParticleCollider, ParticleEmitter, ParticleForces, ParticleStyle, ParticleSystem,
+};
+use crate::vrt::graphics::pipeline::BlendMode;
+use crate::vrt::graphics::renderer::VRTRenderer;
+use crate::vrt::utils::result::VkResult;
+
+/// A fountain raining onto the ground and a sphere, stirred by curl noise, seen from an
+/// orbiting camera. Debug shapes outline the colliders.
+pub struct ParticleScene {
+    particles: ParticleSystem,
+    render_system: ParticleRenderSystem,
+    debug_render_system: DebugRenderSystem,
+    clock: Instant,
+    device: Arc<VRTDevice>,
+}
+
+impl ParticleScene {
+    pub fn new(device: Arc<VRTDevice>, renderer: &VRTRenderer, capacity: u32) -> VkResult<Self> {
+        let particles = Self::create_particles(device.clone(), capacity)?;
+        let render_system = Self::create_render_system(
+            &device,
+            renderer,
+            &particles,
+            ParticleStyle::Billboard,
+            BlendMode::Additive,
+        );
+        let debug_render_system = Self::create_debug_render_system(&device, renderer);
+
+        Ok(Self {
+            particles,
+            render_system,
+            debug_render_system,
+            clock: Instant::now(),
+            device,
+        })
+    }
+
+    fn create_particles(device: Arc<VRTDevice>, capacity: u32) -> VkResult<ParticleSystem> {
+        let mut particles = ParticleSystem::new(device, capacity)?;
+        let lifetime = (1.5, 2.5);
+        *particles.emitter_mut() = ParticleEmitter {
+            position: Vec3::new(0.0, -0.2, 0.0),
+            radius: 0.1,
+            velocity: Vec3::new(0.0, -6.0, 0.0),
+            velocity_spread: 1.5,
+            // Just below what the capacity sustains, so the fountain never stutters.
+            rate: capacity as f32 / (lifetime.0 + lifetime.1) * 2.0 * 0.9,
+            lifetime,
+            color_start: Vec4::new(1.0, 0.6, 0.2, 1.0),
+            color_end: Vec4::new(0.2, 0.3, 1.0, 0.0),
+            size_start: 0.03,
+            size_end: 0.01,
+        };
+        *particles.forces_mut() = ParticleForces {
+            gravity: Vec3::new(0.0, 9.81, 0.0),
+            drag: 0.1,
+            curl_strength: 2.0,
+            curl_scale: 0.5,
+            restitution: 0.4,
+            friction: 0.2,
+        };
+        *particles.colliders_mut() = vec![
+            ParticleCollider::Plane {
+                normal: Vec3::NEG_Y,
+                distance: 0.0,
+            },
+            ParticleCollider::Sphere {
+                center: Vec3::new(1.5, -1.5, 0.0),
+                radius: 1.0,
+            },
+        ];
+        Ok(particles)
+    }
+
+    fn create_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        particles: &ParticleSystem,
+        style: ParticleStyle,
+        blend_mode: BlendMode,
+    ) -> ParticleRenderSystem {
+        ParticleRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            particles,
+            style,
+            blend_mode,
+        )
+    }
+
+    fn create_debug_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+    ) -> DebugRenderSystem {
+        DebugRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+        )
+    }
+
+    fn cycle_style(&mut self, renderer: &VRTRenderer) {
+        let (style, blend_mode) = match (
+            self.render_system.get_style(),
+            self.render_system.get_blend_mode(),
+        ) {
+            (ParticleStyle::Billboard, BlendMode::Additive) => {
+                (ParticleStyle::Billboard, BlendMode::Alpha)
+            }
+            // Alpha to coverage, which needs MSAA to look smooth.
+            (ParticleStyle::Billboard, BlendMode::Alpha) => {
+                (ParticleStyle::Billboard, BlendMode::Opaque)
+            }
+            (ParticleStyle::Billboard, _) => (ParticleStyle::Point, BlendMode::Additive),
+            (ParticleStyle::Point, _) => (ParticleStyle::Billboard, BlendMode::Additive),
+        };
+        self.render_system =
+            Self::create_render_system(&self.device, renderer, &self.particles, style, blend_mode);
+        log::info!("particles as {:?} with {:?} blending", style, blend_mode);
+    }
+}
+
+impl Scene for ParticleScene {
+    fn name(&self) -> &'static str {
+        "Particles"
+    }
+
+    fn key(&self) -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::J)
+    }
+
+    fn rebuild(&mut self, renderer: &VRTRenderer, _assets: &SceneAssets) -> VkResult<()> {
+        self.render_system = Self::create_render_system(
+            &self.device,
+            renderer,
+            &self.particles,
+            self.render_system.get_style(),
+            self.render_system.get_blend_mode(),
+        );
+        self.debug_render_system = Self::create_debug_render_system(&self.device, renderer);
+        Ok(())
+    }
+
+    /// Outlines of the particle colliders.
+    fn queue_debug_shapes(&self, debug_draw: &DebugDraw, _assets: &SceneAssets) {
+        let style = DebugStyle::new(Vec4::new(0.2, 1.0, 0.4, 1.0));
+        for collider in self.particles.colliders() {
+            match *collider {
+                ParticleCollider::Plane { normal, distance } => {
+                    let (u, v) = normal.any_orthonormal_pair();
+                    debug_draw.grid(normal * distance, u, v, 16, 0.5, style);
+                }
+                ParticleCollider::Sphere { center, radius } => {
+                    debug_draw.sphere(&BoundingSphere::new(center, radius), style);
+                }
+            }
+        }
+    }
+
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()> {
+        let frame_index = frame.frame_index();
+        let extent = frame.extent();
+        let now = Instant::now();
+        let dt = now.duration_since(self.clock).as_secs_f32();
+        self.clock = now;
+        self.particles
+            .update(frame.command_buffer(), frame_index, dt);
+
+        // Orbit the fountain from above the ground, world up is -Y.
+        let angle = context.elapsed * 0.15;
+        let aspect = extent.width as f32 / extent.height.max(1) as f32;
+        let view = Mat4::look_at_rh(
+            Vec3::new(angle.sin() * 8.0, -3.5, angle.cos() * 8.0),
+            Vec3::new(0.0, -1.5, 0.0),
+            Vec3::NEG_Y,
+        );
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_3, aspect, 0.1, 100.0);
+
+        let render_pass = frame.begin_swapchain_render_pass()?;
+        if let Some(debug_frame) = context.debug_frame {
+            self.debug_render_system.render(
+                render_pass.command_buffer(),
+                frame_index,
+                projection * view,
+                debug_frame,
+            );
+        }
+        self.render_system.render(
+            render_pass.command_buffer(),
+            frame_index,
+            &self.particles,
+            view,
+            projection,
+            extent,
+        );
+
+        Ok(())
+    }
+
+    fn process_key(&mut self, key: VirtualKeyCode, renderer: &VRTRenderer) -> VkResult<bool> {
+        match key {
+            VirtualKeyCode::A => self.cycle_style(renderer),
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn build_gui(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Particles", TreeNodeFlags::DEFAULT_OPEN) {
+            let forces = self.particles.forces_mut();
+            ui.slider("Curl strength", 0.0, 10.0, &mut forces.curl_strength);
+            ui.slider("Curl scale", 0.05, 4.0, &mut forces.curl_scale);
+            ui.slider("Drag", 0.0, 2.0, &mut forces.drag);
+            ui.slider("Restitution", 0.0, 1.0, &mut forces.restitution);
+            ui.slider("Friction", 0.0, 1.0, &mut forces.friction);
+            if ui.button("Reset") {
+                self.particles.reset();
+            }
+        }
+    }
+
+    fn activate(&mut self) {
+        // Resume where the simulation stopped instead of catching up on the time hidden.
+        self.clock = Instant::now();
+        log::info!("particles, {} max", self.particles.get_capacity());
+    }
+}
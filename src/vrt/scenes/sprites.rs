@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use erupt::vk1_0::{Extent2D, Filter, Format};
+use glam::{Vec2, Vec4};
+use winit::event::VirtualKeyCode;
+
+use super::{Scene, SceneAssets, SceneContext};
+use crate::vrt::device::device::VRTDevice;
+use crate::vrt::graphics::frame::Frame;
+use crate::vrt::graphics::pipeline::BlendMode;
+use crate::vrt::graphics::renderer::VRTRenderer;
+use crate::vrt::graphics::sprite::sprite_render_system::SpriteRenderSystem;
+use crate::vrt::graphics::sprite::{
+    Camera2D, Sprite, SpriteAtlasHandle, SpriteAtlasRegistry, SpriteBatch,
+};
+use crate::vrt::utils::result::VkResult;
+
+/// Agents circling in the view.
+const AGENT_COUNT: u32 = 2000;
+/// Ground tiles along each axis.
+const GROUND_SIZE: i32 = 32;
+
+/// A 2D view of sprites drawn from atlases in a single batch.
+pub struct SpriteScene {
+    atlases: SpriteAtlasRegistry,
+    sheet: SpriteAtlasHandle,
+    ground_tile: SpriteAtlasHandle,
+    batch: SpriteBatch,
+    render_system: SpriteRenderSystem,
+    device: Arc<VRTDevice>,
+}
+
+impl SpriteScene {
+    pub fn new(device: Arc<VRTDevice>, renderer: &VRTRenderer) -> VkResult<Self> {
+        let (atlases, sheet, ground_tile) = Self::create_sprite_atlases(device.clone())?;
+        let render_system = Self::create_render_system(&device, renderer, &atlases);
+
+        Ok(Self {
+            atlases,
+            sheet,
+            ground_tile,
+            batch: SpriteBatch::new(),
+            render_system,
+            device,
+        })
+    }
+
+    fn create_render_system(
+        device: &Arc<VRTDevice>,
+        renderer: &VRTRenderer,
+        atlases: &SpriteAtlasRegistry,
+    ) -> SpriteRenderSystem {
+        SpriteRenderSystem::new(
+            device.clone(),
+            renderer.get_swapchain_render_pass(),
+            renderer.get_msaa_samples(),
+            atlases,
+            BlendMode::Alpha,
+        )
+    }
+
+    /// A sheet of an agent arrow and a marker ring, and a ground tile drawn from an atlas of its
+    /// own.
+    fn create_sprite_atlases(
+        device: Arc<VRTDevice>,
+    ) -> VkResult<(SpriteAtlasRegistry, SpriteAtlasHandle, SpriteAtlasHandle)> {
+        const CELL_SIZE: u32 = 32;
+        const TILE_SIZE: u32 = 16;
+
+        let mut atlases = SpriteAtlasRegistry::new(device, Filter::LINEAR)?;
+        let sheet_pixels = (0..CELL_SIZE * 2 * CELL_SIZE)
+            .flat_map(|texel| {
+                let (x, y) = (texel % (CELL_SIZE * 2), texel / (CELL_SIZE * 2));
+                // Centered cell coordinates from -1 to 1, y pointing up.
+                let cell = Vec2::new(
+                    ((x % CELL_SIZE) as f32 + 0.5) / CELL_SIZE as f32 * 2.0 - 1.0,
+                    1.0 - (y as f32 + 0.5) / CELL_SIZE as f32 * 2.0,
+                );
+                let inside = if x < CELL_SIZE {
+                    // An arrow pointing along +x.
+                    cell.x > -0.8 && cell.y.abs() < (0.8 - cell.x) * 0.5
+                } else {
+                    (0.6..0.9).contains(&cell.length())
+                };
+                if inside {
+                    [255, 255, 255, 255]
+                } else {
+                    [0, 0, 0, 0]
+                }
+            })
+            .collect::<Vec<u8>>();
+        let sprite_sheet = atlases.add(
+            Extent2D {
+                width: CELL_SIZE * 2,
+                height: CELL_SIZE,
+            },
+            Format::R8G8B8A8_SRGB,
+            &sheet_pixels,
+        )?;
+        atlases.add_grid(
+            sprite_sheet,
+            Vec2::splat(CELL_SIZE as f32),
+            ["agent", "ring"],
+        );
+
+        let tile_pixels = (0..TILE_SIZE * TILE_SIZE)
+            .flat_map(|texel| {
+                let (x, y) = (texel % TILE_SIZE, texel / TILE_SIZE);
+                if x == 0 || y == 0 {
+                    [70, 80, 70, 255]
+                } else {
+                    [40, 48, 40, 255]
+                }
+            })
+            .collect::<Vec<u8>>();
+        let ground_tile = atlases.add(
+            Extent2D {
+                width: TILE_SIZE,
+                height: TILE_SIZE,
+            },
+            Format::R8G8B8A8_SRGB,
+            &tile_pixels,
+        )?;
+
+        Ok((atlases, sprite_sheet, ground_tile))
+    }
+
+    /// A top-down view of agents circling over tiled ground, with rings marking the centers
+    /// of their orbits. Pushed ring, agent, ground to show that layers and not push order
+    /// decide what is on top.
+    fn queue_sprites(&mut self, elapsed: f32) {
+        self.batch.clear();
+        let (agent, ring, ground) = match (
+            self.atlases.region(self.sheet, "agent"),
+            self.atlases.region(self.sheet, "ring"),
+            self.atlases.full_region(self.ground_tile),
+        ) {
+            (Some(agent), Some(ring), Some(ground)) => (agent, ring, ground),
+            _ => return,
+        };
+
+        for index in 0..AGENT_COUNT {
+            // Orbit centers on a golden angle spiral.
+            let spiral_angle = index as f32 * 2.399_963;
+            let spiral_radius = (index as f32 / AGENT_COUNT as f32).sqrt() * 14.0;
+            let center = Vec2::new(spiral_angle.cos(), spiral_angle.sin()) * spiral_radius;
+            let orbit_radius = 0.3 + (index % 7) as f32 * 0.1;
+            let direction = if index % 2 == 0 { 1.0 } else { -1.0 };
+            let angle = direction * elapsed * (1.5 - orbit_radius) + spiral_angle;
+            let position = center + Vec2::new(angle.cos(), angle.sin()) * orbit_radius;
+            let heading = angle + direction * std::f32::consts::FRAC_PI_2;
+            let hue = index as f32 / AGENT_COUNT as f32 * std::f32::consts::TAU;
+            let color = Vec4::new(
+                hue.cos() * 0.4 + 0.6,
+                (hue + 2.1).cos() * 0.4 + 0.6,
+                (hue + 4.2).cos() * 0.4 + 0.6,
+                1.0,
+            );
+
+            if index % 50 == 0 {
+                self.batch.push(
+                    Sprite::new(ring, center, Vec2::splat(orbit_radius * 2.0))
+                        .with_color(color * Vec4::new(1.0, 1.0, 1.0, 0.5))
+                        .with_layer(1),
+                );
+            }
+            self.batch.push(
+                Sprite::new(agent, position, Vec2::splat(0.35))
+                    .with_rotation(heading)
+                    .with_color(color)
+                    .with_layer(2),
+            );
+        }
+
+        let half = GROUND_SIZE / 2;
+        for y in -half..half {
+            for x in -half..half {
+                self.batch.push(
+                    Sprite::new(ground, Vec2::new(x as f32, y as f32), Vec2::ONE)
+                        .with_pivot(Vec2::ZERO),
+                );
+            }
+        }
+    }
+}
+
+impl Scene for SpriteScene {
+    fn name(&self) -> &'static str {
+        "2D sprite view"
+    }
+
+    fn key(&self) -> Option<VirtualKeyCode> {
+        Some(VirtualKeyCode::Q)
+    }
+
+    fn rebuild(&mut self, renderer: &VRTRenderer, _assets: &SceneAssets) -> VkResult<()> {
+        self.render_system = Self::create_render_system(&self.device, renderer, &self.atlases);
+        Ok(())
+    }
+
+    fn record(&mut self, frame: &mut Frame, context: &mut SceneContext) -> VkResult<()> {
+        self.queue_sprites(context.elapsed);
+
+        let frame_index = frame.frame_index();
+        let extent = frame.extent();
+        let render_pass = frame.begin_swapchain_render_pass()?;
+        self.render_system.render(
+            render_pass.command_buffer(),
+            frame_index,
+            Camera2D::new(Vec2::ZERO, 30.0).view_projection(extent),
+            &self.atlases,
+            &self.batch,
+        );
+
+        Ok(())
+    }
+}
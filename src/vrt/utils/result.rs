@@ -1,8 +1,10 @@
 use std::error::Error;
 use std::fmt;
+use std::io;
 
 use erupt::utils::loading::EntryLoaderError;
 use erupt::{vk, LoaderError};
+use winit::error::OsError;
 // use image::ImageError;
 // use tobj::LoadError;
 
@@ -23,7 +25,13 @@ pub enum VkError {
     UnsupportedLayoutTransition,
     UnsupportedLinearBlitting,
     SwapChainExpired,
-    FrameAlreadyStarted
+    UnsupportedSurface,
+    NoRenderPass,
+    MissingRenderPass(usize),
+    Io(io::Error),
+    InvalidAsset(String),
+    UnsupportedFeature(&'static str),
+    Window(OsError),
 }
 
 impl From<EntryLoaderError> for VkError {
@@ -44,6 +52,18 @@ impl From<vk::Result> for VkError {
     }
 }
 
+impl From<io::Error> for VkError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<OsError> for VkError {
+    fn from(err: OsError) -> Self {
+        Self::Window(err)
+    }
+}
+
 // impl From<ImageError> for VkError {
 //     fn from(err: ImageError) -> Self {
 //         Self::Image(err)
@@ -78,9 +98,19 @@ impl fmt::Display for VkError {
             VkError::SwapChainExpired => {
                 f.write_str("Swap chain out of date ERROR_OUT_OF_DATE_KHR")
             }
-            VkError::FrameAlreadyStarted => {
-                f.write_str("Frame already started")
+            VkError::UnsupportedSurface => {
+                f.write_str("surface is not supported by the present queue family")
+            }
+            VkError::NoRenderPass => f.write_str("swapchain config has no render pass"),
+            VkError::MissingRenderPass(index) => {
+                write!(f, "swapchain has no render pass at index {}", index)
+            }
+            VkError::Io(_) => f.write_str("io error"),
+            VkError::InvalidAsset(reason) => write!(f, "invalid asset: {}", reason),
+            VkError::UnsupportedFeature(feature) => {
+                write!(f, "device feature {} is not supported", feature)
             }
+            VkError::Window(_) => f.write_str("window error"),
         }
     }
 }
@@ -91,6 +121,8 @@ impl Error for VkError {
             VkError::EntryLoader(err) => Some(err),
             VkError::Loader(err) => Some(err),
             VkError::Vk(err) => Some(err),
+            VkError::Io(err) => Some(err),
+            VkError::Window(err) => Some(err),
             // VkError::Image(err) => Some(err),
             // VkError::ObjLoad(err) => Some(err),
             VkError::ValidationLayerUnavailable
@@ -100,7 +132,11 @@ impl Error for VkError {
             | VkError::NoSuitableMemoryType
             | VkError::NoSupportedFormat
             | VkError::UnsupportedLayoutTransition
-            | VkError::FrameAlreadyStarted
+            | VkError::UnsupportedSurface
+            | VkError::NoRenderPass
+            | VkError::MissingRenderPass(_)
+            | VkError::InvalidAsset(_)
+            | VkError::UnsupportedFeature(_)
             | VkError::UnsupportedLinearBlitting => None,
         }
     }
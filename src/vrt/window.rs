@@ -1,8 +1,8 @@
 use erupt::vk::Extent2D;
 use winit::dpi::{LogicalPosition, LogicalSize};
 use winit::error::OsError;
-use winit::event_loop::EventLoop;
-use winit::window::{Window, WindowBuilder};
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::{Window, WindowBuilder, WindowId};
 
 pub struct VRTWindow {
     resized: bool,
@@ -13,7 +13,7 @@ pub struct VRTWindow {
 
 impl VRTWindow {
     pub fn build_window(
-        event_loop: &EventLoop<()>,
+        event_loop: &EventLoopWindowTarget<()>,
         app_name: &str,
         width: u32,
         height: u32,
@@ -48,6 +48,14 @@ impl VRTWindow {
         }
     }
 
+    pub fn is_minimized(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
     pub fn get_window_ptr(&self) -> &Window {
         &self.window
     }